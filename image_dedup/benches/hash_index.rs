@@ -0,0 +1,116 @@
+//! 近傍探索インデックスのベンチマーク
+//!
+//! 10万エントリに対する「閾値以内の全ハッシュ」検索を、
+//! Multi-Index Hashingと総当たり（線形走査）で比較
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image_dedup::services::{BinaryHash, MultiIndexHash};
+use std::time::Duration;
+
+const ENTRY_COUNT: usize = 100_000;
+const QUERY_COUNT: usize = 100;
+const THRESHOLD: u32 = 5;
+
+/// 決定的な擬似乱数（SplitMix64）
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// 実データに近い分布のハッシュを生成
+///
+/// 約2割を既存ハッシュから数ビットだけ変えた「重複候補」にする
+fn generate_hashes(count: usize) -> Vec<u64> {
+    let mut rng = SplitMix64(42);
+    let mut hashes: Vec<u64> = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let hash = if i > 0 && rng.next().is_multiple_of(5) {
+            let base = hashes[(rng.next() % i as u64) as usize];
+            let flips = rng.next() % 4;
+            (0..flips).fold(base, |hash, _| hash ^ (1 << (rng.next() % 64)))
+        } else {
+            rng.next()
+        };
+        hashes.push(hash);
+    }
+
+    hashes
+}
+
+fn linear_find_within(hashes: &[u64], query: &u64, threshold: u32) -> Vec<(usize, u32)> {
+    hashes
+        .iter()
+        .enumerate()
+        .map(|(id, hash)| (id, hash.hamming_distance(query)))
+        .filter(|&(_, distance)| distance <= threshold)
+        .collect()
+}
+
+fn build_index(hashes: &[u64]) -> MultiIndexHash<u64> {
+    let mut index = MultiIndexHash::new(u64::BITS, THRESHOLD);
+    for (id, hash) in hashes.iter().enumerate() {
+        if let Err(e) = index.insert(*hash, id) {
+            panic!("Failed to build index: {e}");
+        }
+    }
+    index
+}
+
+/// インデックス構築のベンチマーク
+fn benchmark_index_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Hash Index Build");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+
+    let hashes = generate_hashes(ENTRY_COUNT);
+
+    group.bench_function(BenchmarkId::new("MultiIndexHash", ENTRY_COUNT), |b| {
+        b.iter(|| std::hint::black_box(build_index(&hashes)))
+    });
+
+    group.finish();
+}
+
+/// 閾値検索のベンチマーク（QUERY_COUNT件のクエリ）
+fn benchmark_find_within(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Hash Index Query");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+
+    let hashes = generate_hashes(ENTRY_COUNT);
+    let index = build_index(&hashes);
+    let queries: Vec<u64> = hashes
+        .iter()
+        .step_by(ENTRY_COUNT / QUERY_COUNT)
+        .copied()
+        .collect();
+
+    group.bench_function(BenchmarkId::new("Linear", ENTRY_COUNT), |b| {
+        b.iter(|| {
+            for query in &queries {
+                std::hint::black_box(linear_find_within(&hashes, query, THRESHOLD));
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("MultiIndexHash", ENTRY_COUNT), |b| {
+        b.iter(|| {
+            for query in &queries {
+                std::hint::black_box(index.find_within(query, THRESHOLD));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_index_build, benchmark_find_within);
+criterion_main!(benches);
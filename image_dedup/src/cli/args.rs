@@ -1,5 +1,10 @@
+use crate::core::{BackgroundColor, ContentHashAlgorithm};
+use crate::services::{DatabaseFormat, KeepPolicy};
 use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Parser)]
 #[command(name = "image_dedup")]
@@ -21,7 +26,7 @@ pub enum Commands {
         #[arg(short, long, default_value = "hashes.json")]
         output: PathBuf,
 
-        /// Number of threads to use for parallel processing
+        /// Number of images hashed in parallel (defaults to twice the CPU count)
         #[arg(short, long)]
         threads: Option<usize>,
 
@@ -29,7 +34,18 @@ pub enum Commands {
         #[arg(short, long)]
         force: bool,
 
-        /// Hash algorithm to use
+        /// Update an existing database, hashing only new or modified files
+        #[arg(short, long, conflicts_with = "force")]
+        update: bool,
+
+        /// Continue an interrupted scan from its checkpoint (<OUTPUT>.checkpoint),
+        /// hashing only the files it had not persisted yet
+        #[arg(long, conflicts_with_all = ["force", "update", "config_preset"])]
+        resume: bool,
+
+        /// Hash algorithm to use (dct, average, difference, wavelet, block_mean, crop_resistant).
+        /// A comma-separated list (e.g. dct,difference,average) records every algorithm's hash
+        /// for ensemble voting in find-dups; the first one is the main hash
         #[arg(short = 'a', long, default_value = "dct")]
         algorithm: String,
 
@@ -37,18 +53,58 @@ pub enum Commands {
         #[arg(long, default_value = "8")]
         hash_size: u32,
 
-        /// Configuration preset (default, high_performance, testing)
-        #[arg(short = 'p', long)]
+        /// Fixed configuration preset (default, high_performance, testing), ignoring --algorithm and --hash-size
+        #[arg(
+            short = 'p',
+            long,
+            conflicts_with_all = [
+                "threads", "content_hash", "exact_only", "rotation_invariant", "color_signature",
+                "flatten_alpha", "trim_borders", "normalize_contrast"
+            ]
+        )]
         config_preset: Option<String>,
 
-        /// Configuration file path
+        /// Configuration file with "algorithm", "parameters" and optional "threads" keys
         #[arg(short = 'c', long)]
         config: Option<PathBuf>,
+
+        /// Also record a content digest (blake3, sha256) so find-dups can group byte-identical files
+        #[arg(long)]
+        content_hash: Option<ContentHashAlgorithm>,
+
+        /// Record only content digests without decoding images (finds exact duplicates only)
+        #[arg(long)]
+        exact_only: bool,
+
+        /// Also record hashes of the 8 rotated and mirrored variants so find-dups matches rotated or flipped copies
+        #[arg(long, conflicts_with = "exact_only")]
+        rotation_invariant: bool,
+
+        /// Also record a coarse colour histogram so find-dups can tell recoloured variants apart
+        #[arg(long, conflicts_with = "exact_only")]
+        color_signature: bool,
+
+        /// Composite transparent pixels onto this background (white, black or #rrggbb) before hashing
+        #[arg(long, conflicts_with = "exact_only")]
+        flatten_alpha: Option<BackgroundColor>,
+
+        /// Crop uniform borders and letterboxing before hashing
+        #[arg(long, conflicts_with = "exact_only")]
+        trim_borders: bool,
+
+        /// Stretch brightness to the full range before hashing
+        #[arg(long, conflicts_with = "exact_only")]
+        normalize_contrast: bool,
+
+        /// Database format (json, sqlite, binary, jsonl); defaults to sqlite for .sqlite/.db
+        /// outputs, binary for .bin, jsonl for .jsonl/.ndjson and json otherwise
+        #[arg(long, conflicts_with = "config_preset")]
+        format: Option<DatabaseFormat>,
     },
 
     /// Find duplicate images using hash database
     FindDups {
-        /// Hash database file
+        /// Hash database file (JSON, SQLite, binary or JSON Lines)
         #[arg(default_value = "hashes.json")]
         hash_database: PathBuf,
 
@@ -56,9 +112,38 @@ pub enum Commands {
         #[arg(short, long, default_value = "duplicates.json")]
         output: PathBuf,
 
-        /// Maximum Hamming distance for duplicates
+        /// Maximum Hamming distance for duplicates (per 64 hash bits)
         #[arg(short, long, default_value = "5")]
         threshold: u32,
+
+        /// How similar images are grouped
+        #[arg(short, long, value_enum, default_value = "star")]
+        grouping: GroupingMode,
+
+        /// Number of matching regions that makes a cropped copy a duplicate
+        /// (only for hashes recorded with the crop_resistant algorithm)
+        #[arg(long, default_value = "2")]
+        min_segment_matches: usize,
+
+        /// How many algorithms must agree on a pair
+        /// (only for databases scanned with several algorithms)
+        #[arg(long, value_enum, default_value = "majority")]
+        vote: VoteMode,
+
+        /// Per-algorithm thresholds for ensemble voting (e.g. dct=6,difference=10);
+        /// unlisted algorithms use --threshold
+        #[arg(long, value_delimiter = ',')]
+        algorithm_threshold: Vec<AlgorithmThreshold>,
+
+        /// Never group images whose colour signatures differ by more than this (0.0-1.0);
+        /// requires a database scanned with --color-signature
+        #[arg(long)]
+        max_color_distance: Option<f64>,
+
+        /// Policies for choosing the file to keep, in tie-break order
+        /// (largest, resolution, newest, oldest, shortest-path, dir:<prefix>, format:<ext>)
+        #[arg(short, long, value_delimiter = ',')]
+        keep: Vec<KeepPolicy>,
     },
 
     /// Filter duplicate groups by minimum hash distance
@@ -78,7 +163,7 @@ pub enum Commands {
         #[arg(default_value = "duplicates.json")]
         duplicate_list: PathBuf,
 
-        /// Action to perform: move or delete
+        /// Action to perform: move, delete, trash, hardlink, symlink or reflink
         #[arg(short, long, default_value = "move")]
         action: ProcessAction,
 
@@ -89,6 +174,67 @@ pub enum Commands {
         /// Skip confirmation prompt
         #[arg(long)]
         no_confirm: bool,
+
+        /// Hash database used for file metadata (defaults to the one recorded by find-dups)
+        #[arg(long)]
+        scan_db: Option<PathBuf>,
+
+        /// Policies for choosing the file to keep, overriding the find-dups representative
+        /// (largest, resolution, newest, oldest, shortest-path, dir:<prefix>, format:<ext>)
+        #[arg(short, long, value_delimiter = ',')]
+        keep: Vec<KeepPolicy>,
+
+        /// Write absolute paths into symlinks instead of paths relative to the link
+        #[arg(long)]
+        absolute_symlinks: bool,
+
+        /// Also replace files whose contents differ from the kept file with links
+        /// (their original contents are lost and cannot be restored)
+        #[arg(long)]
+        allow_lossy_links: bool,
+
+        /// Trash directory for --action trash (defaults to $XDG_DATA_HOME/Trash or ~/.local/share/Trash)
+        #[arg(long)]
+        trash_dir: Option<PathBuf>,
+
+        /// Undo journal path (defaults to process_journal_<timestamp>.jsonl next to the duplicate list)
+        #[arg(short, long)]
+        journal: Option<PathBuf>,
+
+        /// Show the full plan as a table and write it as JSON without touching any file
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Execute a plan written by --dry-run verbatim instead of reading the duplicate list
+        #[arg(long, conflicts_with_all = ["scan_db", "keep", "action", "dest"])]
+        plan: Option<PathBuf>,
+
+        /// Output path for the --dry-run plan (defaults to process_plan.json next to the duplicate list)
+        #[arg(long, requires = "dry_run")]
+        plan_output: Option<PathBuf>,
+    },
+
+    /// Restore files moved or replaced with links by process using its undo journal
+    Restore {
+        /// Undo journal written by the process command
+        journal: PathBuf,
+    },
+
+    /// Convert a hash database between the JSON, SQLite, binary and JSON Lines formats
+    Convert {
+        /// Hash database to read (format detected from its content)
+        input: PathBuf,
+
+        /// Output file path for the converted database
+        output: PathBuf,
+
+        /// Output format (json, sqlite, binary, jsonl); defaults to the one matching the output extension
+        #[arg(long)]
+        format: Option<DatabaseFormat>,
+
+        /// Overwrite an existing output file
+        #[arg(short, long)]
+        force: bool,
     },
 }
 
@@ -96,4 +242,70 @@ pub enum Commands {
 pub enum ProcessAction {
     Move,
     Delete,
+    /// Move to the FreeDesktop trash so files can be restored from the file manager
+    Trash,
+    /// Replace with a hard link to the kept file (byte-identical files only)
+    Hardlink,
+    /// Replace with a symbolic link to the kept file
+    Symlink,
+    /// Replace with a copy-on-write reflink of the kept file
+    Reflink,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupingMode {
+    /// First unprocessed image is the centre; members are within threshold of it
+    Star,
+    /// Transitive grouping: chains of similar images form one group
+    Connected,
+    /// Every pair of images in a group is within threshold
+    Complete,
+}
+
+/// How the algorithms of an ensemble decide whether a pair is a duplicate
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteMode {
+    /// Every algorithm is within its threshold
+    All,
+    /// More than half of the algorithms are within their thresholds
+    Majority,
+    /// At least one algorithm is within its threshold
+    Any,
+}
+
+/// Threshold for one algorithm of an ensemble, written as `<algorithm>=<threshold>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlgorithmThreshold {
+    pub algorithm: String,
+    /// Maximum Hamming distance per 64 hash bits
+    pub threshold: u32,
+}
+
+impl FromStr for AlgorithmThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, threshold) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected <algorithm>=<threshold>, got '{s}'"))?;
+        let algorithm = algorithm.trim();
+        if algorithm.is_empty() {
+            return Err(format!("Missing algorithm name in '{s}'"));
+        }
+        let threshold = threshold
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid threshold in '{s}'"))?;
+        Ok(Self {
+            algorithm: algorithm.to_string(),
+            threshold,
+        })
+    }
+}
+
+impl fmt::Display for AlgorithmThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.algorithm, self.threshold)
+    }
 }
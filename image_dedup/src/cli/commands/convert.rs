@@ -0,0 +1,175 @@
+use crate::services::persistence::incremental::{load_scan_result, write_scan_result};
+use crate::services::DatabaseFormat;
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// Convert a hash database between the JSON, SQLite, binary and JSON Lines formats
+///
+/// The input format is detected from its content; the output format comes from `format`
+/// or, when omitted, from the output extension.
+pub async fn execute_convert(
+    input: PathBuf,
+    output: PathBuf,
+    format: Option<DatabaseFormat>,
+    force: bool,
+) -> Result<()> {
+    if !input.exists() {
+        anyhow::bail!("Input database does not exist: {}", input.display());
+    }
+    if output.exists() {
+        if std::fs::canonicalize(&input)? == std::fs::canonicalize(&output)? {
+            anyhow::bail!("Input and output are the same file: {}", input.display());
+        }
+        if !force {
+            anyhow::bail!(
+                "Output file already exists: {}. Use --force to overwrite.",
+                output.display()
+            );
+        }
+    }
+
+    let input_format = DatabaseFormat::detect(&input)?;
+    let output_format = DatabaseFormat::for_output(&output, format);
+
+    println!("🔁 ハッシュデータベース変換");
+    println!("📄 入力: {} ({})", input.display(), input_format);
+    println!("📄 出力: {} ({})", output.display(), output_format);
+
+    let scan_result = load_scan_result(&input)?;
+    write_scan_result(&output, &scan_result, output_format)?;
+
+    println!("✅ 変換完了!");
+    println!("   - エントリ数: {}", scan_result.images.len());
+    println!(
+        "   - ファイルサイズ: {} → {} バイト",
+        std::fs::metadata(&input)?.len(),
+        std::fs::metadata(&output)?.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ProcessingMetadata;
+    use crate::services::persistence::implementations::{HashEntry, ScanInfo, ScanResult};
+    use tempfile::TempDir;
+
+    fn write_json_database(path: &std::path::Path) -> ScanResult {
+        let images = (0..3)
+            .map(|i| HashEntry {
+                file_path: format!("/photos/{i}.jpg"),
+                hash: format!("00000000000000f{i}"),
+                hash_bits: 0xf0 + i,
+                metadata: ProcessingMetadata {
+                    file_size: 100 + i,
+                    processing_time_ms: 1,
+                    image_dimensions: (8, 8),
+                    was_resized: false,
+                    hash_size_bits: 64,
+                    modified_time_ms: Some(1_700_000_000_000),
+                    content_hash: Some(format!("blake3:{i:02x}")),
+                    dihedral_hashes: None,
+                    segment_hashes: None,
+                    ensemble_hashes: None,
+                    ensemble_dihedral_hashes: None,
+                    color_signature: None,
+                },
+            })
+            .collect();
+        let scan_result = ScanResult {
+            scan_info: ScanInfo {
+                algorithm: "scan".to_string(),
+                parameters: serde_json::json!({ "algorithm": "dct" }),
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+                total_files: 3,
+            },
+            images,
+        };
+        write_scan_result(path, &scan_result, DatabaseFormat::Json).unwrap();
+        scan_result
+    }
+
+    #[tokio::test]
+    async fn test_convert_json_to_binary_and_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let json = temp_dir.path().join("hashes.json");
+        let binary = temp_dir.path().join("hashes.bin");
+        let round_trip = temp_dir.path().join("round_trip.json");
+        let original = write_json_database(&json);
+
+        execute_convert(json.clone(), binary.clone(), None, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            DatabaseFormat::detect(&binary).unwrap(),
+            DatabaseFormat::Binary
+        );
+        assert!(
+            std::fs::metadata(&binary).unwrap().len() < std::fs::metadata(&json).unwrap().len()
+        );
+
+        execute_convert(binary, round_trip.clone(), None, false)
+            .await
+            .unwrap();
+        let converted = load_scan_result(&round_trip).unwrap();
+
+        assert_eq!(
+            converted.scan_info.parameters,
+            original.scan_info.parameters
+        );
+        assert_eq!(converted.images.len(), original.images.len());
+        for (converted, original) in converted.images.iter().zip(&original.images) {
+            assert_eq!(converted.file_path, original.file_path);
+            assert_eq!(converted.hash, original.hash);
+            assert_eq!(converted.metadata, original.metadata);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_convert_with_explicit_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let json = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("hashes.out");
+        write_json_database(&json);
+
+        execute_convert(json, output.clone(), Some(DatabaseFormat::Sqlite), false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            DatabaseFormat::detect(&output).unwrap(),
+            DatabaseFormat::Sqlite
+        );
+        assert_eq!(load_scan_result(&output).unwrap().images.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_convert_refuses_to_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let json = temp_dir.path().join("hashes.json");
+        let binary = temp_dir.path().join("hashes.bin");
+        write_json_database(&json);
+        std::fs::write(&binary, "existing").unwrap();
+
+        let error = execute_convert(json.clone(), binary.clone(), None, false)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+        assert_eq!(std::fs::read_to_string(&binary).unwrap(), "existing");
+
+        let error = execute_convert(json.clone(), json.clone(), None, true)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("same file"));
+
+        execute_convert(json, binary.clone(), None, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            DatabaseFormat::detect(&binary).unwrap(),
+            DatabaseFormat::Binary
+        );
+    }
+}
@@ -1,6 +1,20 @@
+use crate::cli::{AlgorithmThreshold, GroupingMode, VoteMode};
+use crate::perceptual_hash::DihedralTransform;
+use crate::services::persistence::incremental::load_scan_result;
+use crate::services::persistence::RecordView;
+use crate::services::{
+    complete_linkage_clusters, connected_clusters, max_intra_distance, select_keeper,
+    star_clusters, BinaryHash, BitVector, ColorSignature, KeepCandidate, KeepPolicy,
+    MultiIndexHash, SimilarityGraph,
+};
+use crate::services::{BinaryHashDatabase, DatabaseFormat};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 閾値の基準となるハッシュのビット数（8×8）
+const REFERENCE_HASH_BITS: u32 = 64;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct HashEntry {
@@ -10,6 +24,366 @@ struct HashEntry {
     metadata: Option<serde_json::Value>, // メタデータはオプショナル（旧フォーマット互換のため）
 }
 
+impl HashEntry {
+    /// 比較に使うハッシュのビット列を取得
+    ///
+    /// `hash` の16進文字列から全ビットを復元する。
+    /// 16進として解釈できない旧データは `hash_bits`（先頭64ビット）を使う
+    fn bit_vector(&self) -> BitVector {
+        BitVector::from_hex(&self.hash, self.hash_size_bits())
+            .unwrap_or_else(|_| BitVector::from_u64(self.hash_bits))
+    }
+
+    /// メタデータに記録されたハッシュのビット数（旧フォーマットでは未記録）
+    fn hash_size_bits(&self) -> Option<u32> {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("hash_size_bits"))
+            .and_then(|bits| bits.as_u64())
+            .filter(|&bits| bits > 0)
+            .and_then(|bits| u32::try_from(bits).ok())
+    }
+
+    /// 回転・反転のバリアントと部分領域のハッシュを含む比較用のハッシュを取得
+    ///
+    /// バリアントは `scan --rotation-invariant` で記録した場合のみ。
+    /// 8件揃っていない、または元のハッシュと長さが異なる場合は使わない。
+    /// 部分領域のハッシュは切り抜きに強いアルゴリズムで記録した場合のみ。
+    /// アンサンブルのハッシュは複数のアルゴリズムでスキャンした場合のみ。
+    /// アンサンブルのバリアントは、8件揃っていてそのアルゴリズムのハッシュと長さが同じ場合のみ使う。
+    /// 色の署名は `scan --color-signature` で記録した場合のみ
+    fn hash_variants(&self) -> HashVariants {
+        let hash = self.bit_vector();
+        let dihedral = self
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("dihedral_hashes"))
+            .and_then(|hashes| hashes.as_array())
+            .filter(|hashes| hashes.len() == DihedralTransform::ALL.len())
+            .and_then(|hashes| {
+                hashes
+                    .iter()
+                    .map(|hex| BitVector::from_hex(hex.as_str()?, self.hash_size_bits()).ok())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .filter(|variants| {
+                variants
+                    .iter()
+                    .all(|variant| variant.bit_len() == hash.bit_len())
+            });
+        let segments = self
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("segment_hashes"))
+            .and_then(|hashes| hashes.as_array())
+            .map(|hashes| {
+                hashes
+                    .iter()
+                    .filter_map(|hex| {
+                        BitVector::from_hex(hex.as_str()?, self.hash_size_bits()).ok()
+                    })
+                    .filter(|segment| segment.bit_len() == hash.bit_len())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let ensemble: BTreeMap<String, BitVector> = self
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("ensemble_hashes"))
+            .and_then(|hashes| hashes.as_object())
+            .map(|hashes| {
+                hashes
+                    .iter()
+                    .filter_map(|(algorithm, hex)| {
+                        let hex = hex.as_str()?;
+                        // メインと同じ長さのハッシュは記録されたビット数を使う
+                        let bits = if hex.len() == self.hash.len() {
+                            self.hash_size_bits()
+                        } else {
+                            None
+                        };
+                        let hash = BitVector::from_hex(hex, bits).ok()?;
+                        Some((algorithm.clone(), hash))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let ensemble_dihedral = self
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("ensemble_dihedral_hashes"))
+            .and_then(|hashes| hashes.as_object())
+            .map(|hashes| {
+                hashes
+                    .iter()
+                    .filter_map(|(algorithm, variants)| {
+                        let bits = ensemble.get(algorithm)?.bit_len();
+                        let variants = variants
+                            .as_array()
+                            .filter(|variants| variants.len() == DihedralTransform::ALL.len())?
+                            .iter()
+                            .map(|hex| BitVector::from_hex(hex.as_str()?, Some(bits)).ok())
+                            .collect::<Option<Vec<_>>>()?;
+                        Some((algorithm.clone(), variants))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let color = self
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("color_signature"))
+            .and_then(|signature| signature.as_str())
+            .and_then(|signature| ColorSignature::from_hex(signature).ok());
+        HashVariants {
+            hash,
+            dihedral,
+            segments,
+            ensemble,
+            ensemble_dihedral,
+            color,
+        }
+    }
+
+    /// オリジナル選択の候補情報を取得
+    fn keep_candidate(&self) -> KeepCandidate {
+        KeepCandidate::from_metadata(self.file_path.clone(), self.metadata.as_ref())
+    }
+
+    /// ファイル内容のハッシュ（`scan --content-hash` で記録した場合のみ）
+    fn content_hash(&self) -> Option<&str> {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("content_hash"))
+            .and_then(|hash| hash.as_str())
+    }
+
+    /// 知覚ハッシュを持っているか（`scan --exact-only` のエントリは持たない）
+    fn has_perceptual_hash(&self) -> bool {
+        !self.hash.is_empty()
+    }
+}
+
+impl From<RecordView<'_>> for HashEntry {
+    /// バイナリ形式のレコードから、比較とオリジナル選択に使う項目だけでメタデータを組み立てる
+    fn from(record: RecordView<'_>) -> Self {
+        let mut metadata = record.extras.unwrap_or_default();
+        metadata.insert("hash_size_bits".to_string(), record.hash_size_bits.into());
+        metadata.insert("file_size".to_string(), record.file_size.into());
+        metadata.insert(
+            "image_dimensions".to_string(),
+            serde_json::json!([record.image_dimensions.0, record.image_dimensions.1]),
+        );
+        if let Some(modified_time_ms) = record.modified_time_ms {
+            metadata.insert("modified_time_ms".to_string(), modified_time_ms.into());
+        }
+        Self {
+            file_path: record.file_path.to_string(),
+            hash: record.hash,
+            hash_bits: record.hash_bits,
+            metadata: Some(serde_json::Value::Object(metadata)),
+        }
+    }
+}
+
+/// 比較に使うハッシュと、回転・反転した画像のハッシュ
+struct HashVariants {
+    hash: BitVector,
+    /// `DihedralTransform::ALL` の順のバリアント（記録されていない場合はNone）
+    dihedral: Option<Vec<BitVector>>,
+    /// 部分領域ごとのハッシュ（記録されていない場合は空）
+    segments: Vec<BitVector>,
+    /// アンサンブルのアルゴリズムごとのハッシュ（記録されていない場合は空）
+    ensemble: BTreeMap<String, BitVector>,
+    /// アンサンブルのアルゴリズムごとの `DihedralTransform::ALL` の順のバリアント（記録されていない場合は空）
+    ensemble_dihedral: BTreeMap<String, Vec<BitVector>>,
+    /// 色の署名（記録されていない場合はNone）
+    color: Option<ColorSignature>,
+}
+
+impl HashVariants {
+    fn bit_len(&self) -> u32 {
+        self.hash.bit_len()
+    }
+
+    /// インデックスへの登録と検索に使うハッシュ（バリアントがなければ元のハッシュのみ）
+    fn keys(&self) -> &[BitVector] {
+        self.dihedral
+            .as_deref()
+            .unwrap_or(std::slice::from_ref(&self.hash))
+    }
+
+    /// `other` がこの画像をどう回転・反転したものに最も近いかを（距離, 変換）で取得
+    ///
+    /// どちらか一方にバリアントがあれば使う。距離が同じなら変換なしを優先する
+    fn closest_transform(&self, other: &Self) -> (u32, DihedralTransform) {
+        let mut best = (
+            self.hash.hamming_distance(&other.hash),
+            DihedralTransform::Identity,
+        );
+        // other ≈ transform(self)
+        for (variant, transform) in self.dihedral.iter().flatten().zip(DihedralTransform::ALL) {
+            let distance = variant.hamming_distance(&other.hash);
+            if distance < best.0 {
+                best = (distance, transform);
+            }
+        }
+        // self ≈ transform(other)、つまり other ≈ transform⁻¹(self)
+        for (variant, transform) in other.dihedral.iter().flatten().zip(DihedralTransform::ALL) {
+            let distance = self.hash.hamming_distance(variant);
+            if distance < best.0 {
+                best = (distance, transform.inverse());
+            }
+        }
+        best
+    }
+
+    /// アンサンブルのアルゴリズムのインデックスへの登録と検索に使うハッシュ（バリアントがなければ元のハッシュのみ）
+    fn ensemble_keys<'a>(&'a self, algorithm: &str, hash: &'a BitVector) -> &'a [BitVector] {
+        self.ensemble_dihedral
+            .get(algorithm)
+            .map_or(std::slice::from_ref(hash), Vec::as_slice)
+    }
+
+    /// 画像を `transform` で回転・反転したときのアンサンブルのアルゴリズムのハッシュ
+    fn ensemble_hash(&self, algorithm: &str, transform: DihedralTransform) -> Option<&BitVector> {
+        if transform == DihedralTransform::Identity {
+            return self.ensemble.get(algorithm);
+        }
+        let position = DihedralTransform::ALL
+            .iter()
+            .position(|&candidate| candidate == transform)?;
+        self.ensemble_dihedral.get(algorithm)?.get(position)
+    }
+
+    /// 閾値以内で一致する部分領域の数
+    ///
+    /// 相手のいずれかの領域に一致する領域を両方向で数え、少ない方を返す
+    fn segment_matches(&self, other: &Self, threshold: u32) -> usize {
+        let count = |from: &[BitVector], to: &[BitVector]| {
+            from.iter()
+                .filter(|segment| {
+                    to.iter()
+                        .any(|candidate| segment.hamming_distance(candidate) <= threshold)
+                })
+                .count()
+        };
+        count(&self.segments, &other.segments).min(count(&other.segments, &self.segments))
+    }
+
+    /// 色の署名の距離（どちらかに記録されていなければNone）
+    fn color_distance(&self, other: &Self) -> Option<f64> {
+        Some(self.color.as_ref()?.distance(other.color.as_ref()?))
+    }
+}
+
+/// アンサンブルの投票設定
+struct Voting {
+    mode: VoteMode,
+    /// 閾値を指定しなかったアルゴリズムの閾値（64ビットあたり）
+    threshold: u32,
+    /// アルゴリズムごとの閾値（64ビットあたり）
+    thresholds: HashMap<String, u32>,
+}
+
+/// 2つのエントリに共通するアルゴリズムの投票結果
+struct Tally<'a> {
+    /// 閾値以内だったアルゴリズム
+    agreeing: Vec<&'a str>,
+    /// 投票したアルゴリズムの数
+    voters: usize,
+}
+
+impl Voting {
+    fn new(mode: VoteMode, threshold: u32, overrides: &[AlgorithmThreshold]) -> Self {
+        Self {
+            mode,
+            threshold,
+            thresholds: overrides
+                .iter()
+                .map(|entry| (entry.algorithm.clone(), entry.threshold))
+                .collect(),
+        }
+    }
+
+    /// アルゴリズムの閾値をハッシュのビット数に合わせて取得
+    fn threshold_for(&self, algorithm: &str, hash_size_bits: u32) -> u32 {
+        let threshold = self
+            .thresholds
+            .get(algorithm)
+            .copied()
+            .unwrap_or(self.threshold);
+        scaled_threshold(threshold, hash_size_bits)
+    }
+
+    /// 両方に同じ長さで記録されているアルゴリズムで投票（共通するアルゴリズムがなければNone）
+    ///
+    /// 回転・反転のバリアントがあれば変換ごとに投票し、最も多くのアルゴリズムが一致した変換の
+    /// 結果を使う（同数なら変換なしを優先）。変換なしと投票するアルゴリズムの数が異なる変換は使わない
+    fn tally<'a>(&self, a: &'a HashVariants, b: &HashVariants) -> Option<Tally<'a>> {
+        let identity = DihedralTransform::Identity;
+        let mut best = self.tally_transformed(a, b, identity, identity);
+        if best.voters == 0 {
+            return None;
+        }
+        if a.ensemble_dihedral.is_empty() && b.ensemble_dihedral.is_empty() {
+            return Some(best);
+        }
+        for transform in DihedralTransform::ALL.into_iter().skip(1) {
+            // b ≈ transform(a)、または a ≈ transform(b)
+            for tally in [
+                self.tally_transformed(a, b, transform, identity),
+                self.tally_transformed(a, b, identity, transform),
+            ] {
+                if tally.voters == best.voters && tally.agreeing.len() > best.agreeing.len() {
+                    best = tally;
+                }
+            }
+        }
+        Some(best)
+    }
+
+    /// `a` と `b` をそれぞれ回転・反転したハッシュで投票
+    fn tally_transformed<'a>(
+        &self,
+        a: &'a HashVariants,
+        b: &HashVariants,
+        a_transform: DihedralTransform,
+        b_transform: DihedralTransform,
+    ) -> Tally<'a> {
+        let mut tally = Tally {
+            agreeing: Vec::new(),
+            voters: 0,
+        };
+        for algorithm in a.ensemble.keys() {
+            let (Some(hash), Some(other)) = (
+                a.ensemble_hash(algorithm, a_transform),
+                b.ensemble_hash(algorithm, b_transform),
+            ) else {
+                continue;
+            };
+            if hash.bit_len() != other.bit_len() {
+                continue;
+            }
+            tally.voters += 1;
+            if hash.hamming_distance(other) <= self.threshold_for(algorithm, hash.bit_len()) {
+                tally.agreeing.push(algorithm);
+            }
+        }
+        tally
+    }
+
+    /// 投票結果が重複の条件を満たすか
+    fn passes(&self, tally: &Tally) -> bool {
+        let votes = tally.agreeing.len();
+        match self.mode {
+            VoteMode::All => votes == tally.voters,
+            VoteMode::Majority => votes * 2 > tally.voters,
+            VoteMode::Any => votes > 0,
+        }
+    }
+}
+
 // 新しいフォーマット用の構造体
 #[derive(Debug, Deserialize)]
 struct ScanResult {
@@ -33,11 +407,69 @@ enum HashDatabase {
     OldFormat(Vec<HashEntry>),
 }
 
+impl HashDatabase {
+    /// ハッシュデータベースを読み込む
+    ///
+    /// 形式はファイルの内容から判定する。SQLiteはテーブルから、バイナリはmmapしたレコードから直接読む
+    fn load(path: &Path) -> Result<Self> {
+        let format = DatabaseFormat::detect(path)?;
+        if format == DatabaseFormat::Binary {
+            let database = BinaryHashDatabase::open(path)?;
+            let images = (0..database.len())
+                .map(|index| database.view(index).map(HashEntry::from))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Self::NewFormat(ScanResult {
+                images,
+                scan_info: serde_json::to_value(database.scan_info())?,
+            }));
+        }
+        if format != DatabaseFormat::Json {
+            let scan_result = load_scan_result(path)?;
+            let images = scan_result
+                .images
+                .into_iter()
+                .map(|entry| {
+                    Ok(HashEntry {
+                        file_path: entry.file_path,
+                        hash: entry.hash,
+                        hash_bits: entry.hash_bits,
+                        metadata: Some(serde_json::to_value(entry.metadata)?),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Self::NewFormat(ScanResult {
+                images,
+                scan_info: serde_json::to_value(scan_result.scan_info)?,
+            }));
+        }
+
+        let json_content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json_content)?)
+    }
+}
+
+/// 重複グループの種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GroupKind {
+    /// 内容がバイト単位で一致するファイル
+    Exact,
+    /// 知覚ハッシュが類似しているファイル
+    #[default]
+    Perceptual,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct DuplicateGroup {
     group_id: usize,
+    /// グループの種類（旧フォーマットでは知覚ハッシュによるグループのみ）
+    #[serde(default)]
+    kind: GroupKind,
     representative_file: String,
     files: Vec<DuplicateFile>,
+    /// グループ内の全ペアの最大ハミング距離
+    #[serde(default)]
+    max_distance: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,6 +477,31 @@ struct DuplicateFile {
     path: String,
     hash: String,
     distance_from_representative: u32,
+    /// 64ビットあたりに正規化した距離（ハッシュサイズ間で比較可能）
+    #[serde(default)]
+    normalized_distance: f64,
+    /// 代表ファイルをこの変換で回転・反転するとこのファイルに一致する（回転・反転を考慮した場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transform: Option<DihedralTransform>,
+    /// 代表ファイルとの関係（部分領域のハッシュがある場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    relation: Option<MatchRelation>,
+    /// 代表ファイルとの距離が閾値以内だったアルゴリズム（アンサンブルの場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    agreeing_algorithms: Option<Vec<String>>,
+    /// 代表ファイルとの色の署名の距離（両方に記録されている場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_distance: Option<f64>,
+}
+
+/// 代表ファイルとどのように一致したか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MatchRelation {
+    /// 画像全体のハッシュが閾値以内
+    Similar,
+    /// 画像全体は一致しないが、部分領域が一致する（切り抜き・余白の追加など）
+    Cropped,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,19 +510,211 @@ struct DuplicatesReport {
     total_duplicates: usize,
     threshold: u32,
     groups: Vec<DuplicateGroup>,
+    /// 代表ファイルの選択に使った基準（未指定の場合は空）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keep_policy: Vec<String>,
+    /// 読み込んだハッシュデータベースのパス（processコマンドが参照する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scan_database: Option<PathBuf>,
+    /// アンサンブルの投票方式（アンサンブルのハッシュがある場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vote: Option<VoteMode>,
+    /// 色の署名の距離の上限（指定した場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_color_distance: Option<f64>,
+}
+
+/// ハッシュサイズごとのインデックスによる類似度グラフ
+///
+/// 回転・反転のバリアントがあるエントリは全バリアントをインデックスに登録し、
+/// 距離はバリアント間の最小値とする。
+/// 部分領域のハッシュが `min_segment_matches` 個以上一致するペアも類似とみなす。
+/// アンサンブルのハッシュが共通するペアは、画像全体の一致を投票で判定する。
+/// 色の署名の距離が `max_color_distance` を超えるペアはハッシュによらず類似としない
+struct HashGraph<'a> {
+    hashes: &'a [HashVariants],
+    indexes: &'a HashMap<u32, MultiIndexHash<BitVector>>,
+    segment_indexes: &'a HashMap<u32, MultiIndexHash<BitVector>>,
+    min_segment_matches: usize,
+    /// アルゴリズム名とビット数ごとのアンサンブルのインデックス
+    ensemble_indexes: &'a HashMap<(String, u32), MultiIndexHash<BitVector>>,
+    voting: &'a Voting,
+    max_color_distance: Option<f64>,
+}
+
+impl HashGraph<'_> {
+    /// 色の署名で `i` と `j` が別の配色と判定されないか（署名がなければ判定しない）
+    fn colors_compatible(&self, i: usize, j: usize) -> bool {
+        match (
+            self.max_color_distance,
+            self.hashes[i].color_distance(&self.hashes[j]),
+        ) {
+            (Some(limit), Some(distance)) => distance <= limit,
+            _ => true,
+        }
+    }
+
+    /// 画像全体で `i` と `j` が一致するか（アンサンブルなら投票、それ以外は距離で判定）
+    fn whole_image_matches(&self, i: usize, j: usize) -> bool {
+        match self.voting.tally(&self.hashes[i], &self.hashes[j]) {
+            Some(tally) => self.voting.passes(&tally),
+            None => self.distance(i, j) <= self.indexes[&self.hashes[i].bit_len()].threshold(),
+        }
+    }
+
+    /// 部分領域のハッシュで `i` と `j` が一致するか
+    fn segments_match(&self, i: usize, j: usize) -> bool {
+        let bits = self.hashes[i].bit_len();
+        let Some(index) = self.segment_indexes.get(&bits) else {
+            return false;
+        };
+        bits == self.hashes[j].bit_len()
+            && self.hashes[i].segment_matches(&self.hashes[j], index.threshold())
+                >= self.min_segment_matches
+    }
+}
+
+impl SimilarityGraph for HashGraph<'_> {
+    fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    fn neighbours(&self, i: usize) -> Vec<(usize, u32)> {
+        let hash = &self.hashes[i];
+        let index = &self.indexes[&hash.bit_len()];
+        // 1つのエントリが複数のバリアントで見つかるため、候補ごとに最小距離を求め直す
+        let mut candidates: Vec<usize> = hash
+            .keys()
+            .iter()
+            .flat_map(|key| index.find_within(key, index.threshold()))
+            .map(|(j, _)| j)
+            .collect();
+        if let Some(segment_index) = self.segment_indexes.get(&hash.bit_len()) {
+            candidates.extend(
+                hash.segments
+                    .iter()
+                    .flat_map(|segment| {
+                        segment_index.find_within(segment, segment_index.threshold())
+                    })
+                    .map(|(j, _)| j),
+            );
+        }
+        for (algorithm, key) in &hash.ensemble {
+            if let Some(index) = self
+                .ensemble_indexes
+                .get(&(algorithm.clone(), key.bit_len()))
+            {
+                candidates.extend(
+                    hash.ensemble_keys(algorithm, key)
+                        .iter()
+                        .flat_map(|key| index.find_within(key, index.threshold()))
+                        .map(|(j, _)| j),
+                );
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        candidates
+            .into_iter()
+            .filter(|&j| self.is_similar(i, j))
+            .map(|j| (j, self.distance(i, j)))
+            .collect()
+    }
+
+    fn distance(&self, i: usize, j: usize) -> u32 {
+        self.hashes[i].closest_transform(&self.hashes[j]).0
+    }
+
+    fn is_similar(&self, i: usize, j: usize) -> bool {
+        let bits = self.hashes[i].bit_len();
+        bits == self.hashes[j].bit_len()
+            && self.colors_compatible(i, j)
+            && (self.whole_image_matches(i, j) || self.segments_match(i, j))
+    }
+}
+
+/// グルーピング結果の1グループ（要素はエントリのインデックス）
+struct Cluster {
+    kind: GroupKind,
+    members: Vec<usize>,
+    max_distance: u32,
+}
+
+/// 内容ハッシュが一致するエントリをまとめる（2件以上のグループのみ、出現順）
+fn exact_clusters(entries: &[HashEntry]) -> Vec<Vec<usize>> {
+    let mut group_of: HashMap<&str, usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(content_hash) = entry.content_hash() else {
+            continue;
+        };
+        let group = *group_of.entry(content_hash).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(i);
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// 選択基準を表示用の文字列に変換
+fn format_policies(policies: &[KeepPolicy]) -> String {
+    policies
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// 64ビット基準の閾値をハッシュのビット数に合わせてスケーリング
+fn scaled_threshold(threshold: u32, hash_size_bits: u32) -> u32 {
+    let scaled = u64::from(threshold) * u64::from(hash_size_bits) / u64::from(REFERENCE_HASH_BITS);
+    u32::try_from(scaled).unwrap_or(u32::MAX)
+}
+
+/// ハミング距離を64ビットあたりに正規化
+fn normalized_distance(distance: u32, hash_size_bits: u32) -> f64 {
+    if hash_size_bits == 0 {
+        return 0.0;
+    }
+    f64::from(distance) * f64::from(REFERENCE_HASH_BITS) / f64::from(hash_size_bits)
 }
 
-/// Calculate Hamming distance between two hash values
-fn hamming_distance(hash1: u64, hash2: u64) -> u32 {
-    (hash1 ^ hash2).count_ones()
+/// Options for the find-dups command
+pub struct FindDupsOptions {
+    pub hash_database: PathBuf,
+    pub output: PathBuf,
+    /// Hamming distance threshold per 64 bits
+    pub threshold: u32,
+    pub grouping: GroupingMode,
+    /// Matching regions needed to report a cropped duplicate
+    pub min_segment_matches: usize,
+    /// How ensemble algorithms vote on a whole-image match
+    pub vote: VoteMode,
+    /// Per-algorithm thresholds overriding `threshold` in ensemble votes
+    pub algorithm_thresholds: Vec<AlgorithmThreshold>,
+    /// Upper bound on the colour signature distance; `None` disables the check
+    pub max_color_distance: Option<f64>,
+    /// Policies choosing the file to keep, tried in order
+    pub keep: Vec<KeepPolicy>,
 }
 
 /// Find duplicate images using hash database
-pub async fn execute_find_dups(
-    hash_database: PathBuf,
-    output: PathBuf,
-    threshold: u32,
-) -> Result<()> {
+pub async fn execute_find_dups(options: FindDupsOptions) -> Result<()> {
+    let FindDupsOptions {
+        hash_database,
+        output,
+        threshold,
+        grouping,
+        min_segment_matches,
+        vote,
+        algorithm_thresholds,
+        max_color_distance,
+        keep,
+    } = options;
+
     // Validate input file
     if !hash_database.exists() {
         anyhow::bail!(
@@ -73,15 +722,26 @@ pub async fn execute_find_dups(
             hash_database.display()
         );
     }
+    if min_segment_matches == 0 {
+        anyhow::bail!("Minimum segment matches must be at least 1");
+    }
+    if let Some(limit) = max_color_distance {
+        if !(0.0..=1.0).contains(&limit) {
+            anyhow::bail!("Maximum color distance must be between 0 and 1");
+        }
+    }
 
     println!("🔍 画像重複検出ツール - find-dupsコマンド");
     println!("📄 ハッシュデータベース: {}", hash_database.display());
     println!("📄 出力ファイル: {}", output.display());
-    println!("🎯 類似度閾値: {threshold} (ハミング距離)");
+    println!("🎯 類似度閾値: {threshold} (64ビットあたりのハミング距離)");
+    println!("🧩 グルーピング: {grouping:?}");
+    if !keep.is_empty() {
+        println!("📌 オリジナル選択基準: {}", format_policies(&keep));
+    }
 
-    // Read hash entries from JSON file (supporting both old and new formats)
-    let json_content = std::fs::read_to_string(&hash_database)?;
-    let database: HashDatabase = serde_json::from_str(&json_content)?;
+    // Read hash entries from a SQLite or binary database, or a JSON file (old and new formats)
+    let database = HashDatabase::load(&hash_database)?;
 
     let hash_entries = match database {
         HashDatabase::NewFormat(scan_result) => {
@@ -107,58 +767,280 @@ pub async fn execute_find_dups(
         hash_entries.len()
     );
 
-    // Group similar images
-    let mut groups = Vec::new();
-    let mut group_id = 0;
+    // Byte-identical files form exact groups first; they carry no risk of false positives
+    let exact = exact_clusters(&hash_entries);
+    if !exact.is_empty() {
+        println!("🟰 完全一致グループ: {}個", exact.len());
+    }
 
-    let mut processed = std::collections::HashSet::new();
+    // Files with a perceptual hash are grouped by similarity. Only the first file of
+    // each exact group takes part; its byte-identical twins join the group it ends up in
+    let exact_group_of: HashMap<usize, usize> = exact
+        .iter()
+        .enumerate()
+        .map(|(group, members)| (members[0], group))
+        .collect();
+    let twins: HashSet<usize> = exact
+        .iter()
+        .flat_map(|members| &members[1..])
+        .copied()
+        .collect();
+    let perceptual: Vec<usize> = (0..hash_entries.len())
+        .filter(|i| !twins.contains(i) && hash_entries[*i].has_perceptual_hash())
+        .collect();
+
+    // Decode full-width hashes and build one nearest-neighbour index per hash size
+    // (hashes of different sizes are never compared with each other)
+    let hashes: Vec<HashVariants> = perceptual
+        .iter()
+        .map(|&i| hash_entries[i].hash_variants())
+        .collect();
+
+    let rotation_invariant = hashes.iter().filter(|hash| hash.dihedral.is_some()).count();
+    if rotation_invariant > 0 {
+        println!("🔄 回転・反転を考慮して比較: {rotation_invariant}個のエントリ");
+    }
+    let segmented = hashes
+        .iter()
+        .filter(|hash| !hash.segments.is_empty())
+        .count();
+    if segmented > 0 {
+        println!(
+            "✂️  部分領域を比較: {segmented}個のエントリ ({min_segment_matches}領域以上の一致で重複)"
+        );
+    }
 
-    for i in 0..hash_entries.len() {
-        if processed.contains(&i) {
-            continue;
+    let voting = Voting::new(vote, threshold, &algorithm_thresholds);
+    let ensemble_entries = hashes
+        .iter()
+        .filter(|hash| !hash.ensemble.is_empty())
+        .count();
+    if ensemble_entries > 0 {
+        let algorithms: HashSet<&str> = hashes
+            .iter()
+            .flat_map(|hash| hash.ensemble.keys().map(String::as_str))
+            .collect();
+        let mut algorithms: Vec<&str> = algorithms.into_iter().collect();
+        algorithms.sort_unstable();
+        println!(
+            "🗳️  アンサンブル投票 ({vote:?}): {ensemble_entries}個のエントリ, アルゴリズム: {}",
+            algorithms.join(", ")
+        );
+        for entry in &algorithm_thresholds {
+            if !algorithms.contains(&entry.algorithm.as_str()) {
+                println!(
+                    "⚠️  閾値を指定したアルゴリズム {} はデータベースに記録されていません",
+                    entry.algorithm
+                );
+            }
         }
+    }
 
-        let base_entry = &hash_entries[i];
-        let base_hash = base_entry.hash_bits;
+    if let Some(limit) = max_color_distance {
+        let colored = hashes.iter().filter(|hash| hash.color.is_some()).count();
+        if colored > 0 {
+            println!("🎨 色の距離の上限: {limit} ({colored}個のエントリに色の署名)");
+        } else {
+            println!("⚠️  色の署名が記録されていません（scan --color-signature で記録できます）");
+        }
+    }
 
-        let mut group_files = vec![DuplicateFile {
-            path: base_entry.file_path.clone(),
-            hash: base_entry.hash.clone(),
-            distance_from_representative: 0,
-        }];
+    let mut indexes: HashMap<u32, MultiIndexHash<BitVector>> = HashMap::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        let bits = hash.bit_len();
+        let index = indexes
+            .entry(bits)
+            .or_insert_with(|| MultiIndexHash::new(bits, scaled_threshold(threshold, bits)));
+        for key in hash.keys() {
+            index.insert(key.clone(), i)?;
+        }
+    }
 
-        processed.insert(i);
+    // Segment hashes go into separate indexes so that a matching region never
+    // counts as a match of the whole image
+    let mut segment_indexes: HashMap<u32, MultiIndexHash<BitVector>> = HashMap::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        for segment in &hash.segments {
+            let bits = segment.bit_len();
+            segment_indexes
+                .entry(bits)
+                .or_insert_with(|| MultiIndexHash::new(bits, scaled_threshold(threshold, bits)))
+                .insert(segment.clone(), i)?;
+        }
+    }
 
-        // Find all similar images in remaining entries
-        for (j, entry) in hash_entries.iter().enumerate() {
-            if processed.contains(&j) {
-                continue;
-            }
+    if indexes.len() > 1 {
+        let mut sizes: Vec<u32> = indexes.keys().copied().collect();
+        sizes.sort_unstable();
+        println!("⚠️  異なるハッシュサイズが混在しています（同じサイズ同士のみ比較します）: {sizes:?}ビット");
+    }
 
-            let distance = hamming_distance(base_hash, entry.hash_bits);
-            if distance <= threshold {
-                group_files.push(DuplicateFile {
-                    path: entry.file_path.clone(),
-                    hash: entry.hash.clone(),
-                    distance_from_representative: distance,
+    // Each algorithm of an ensemble gets its own index with its own threshold
+    let mut ensemble_indexes: HashMap<(String, u32), MultiIndexHash<BitVector>> = HashMap::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        for (algorithm, key) in &hash.ensemble {
+            let bits = key.bit_len();
+            let index = ensemble_indexes
+                .entry((algorithm.clone(), bits))
+                .or_insert_with(|| {
+                    MultiIndexHash::new(bits, voting.threshold_for(algorithm, bits))
                 });
-                processed.insert(j);
+            for variant in hash.ensemble_keys(algorithm, key) {
+                index.insert(variant.clone(), i)?;
             }
         }
+    }
 
-        // Only process groups with duplicates
-        if group_files.len() > 1 {
-            let group = DuplicateGroup {
-                group_id,
-                representative_file: base_entry.file_path.clone(),
-                files: group_files,
-            };
+    // Group similar images
+    let graph = HashGraph {
+        hashes: &hashes,
+        indexes: &indexes,
+        segment_indexes: &segment_indexes,
+        min_segment_matches,
+        ensemble_indexes: &ensemble_indexes,
+        voting: &voting,
+        max_color_distance,
+    };
+    let similar = match grouping {
+        GroupingMode::Star => star_clusters(&graph),
+        GroupingMode::Connected => connected_clusters(&graph),
+        GroupingMode::Complete => complete_linkage_clusters(&graph),
+    };
 
-            groups.push(group);
-            group_id += 1;
+    // An exact group whose first file is similar to other files is merged into that group
+    let mut merged = vec![false; exact.len()];
+    let similar: Vec<Cluster> = similar
+        .iter()
+        .map(|cluster| {
+            let mut members = Vec::with_capacity(cluster.len());
+            for &j in cluster {
+                let i = perceptual[j];
+                match exact_group_of.get(&i) {
+                    Some(&group) => {
+                        merged[group] = true;
+                        members.extend_from_slice(&exact[group]);
+                    }
+                    None => members.push(i),
+                }
+            }
+            Cluster {
+                kind: GroupKind::Perceptual,
+                members,
+                max_distance: max_intra_distance(&graph, cluster),
+            }
+        })
+        .collect();
+
+    let mut clusters: Vec<Cluster> = exact
+        .into_iter()
+        .zip(merged)
+        .filter(|(_, merged)| !merged)
+        .map(|(members, _)| Cluster {
+            kind: GroupKind::Exact,
+            members,
+            max_distance: 0,
+        })
+        .chain(similar)
+        .collect();
+
+    // Move the file chosen by the keep policy to the front of each cluster
+    if !keep.is_empty() {
+        let fill_from_filesystem = keep.iter().any(KeepPolicy::needs_file_info);
+        for cluster in &mut clusters {
+            let candidates: Vec<KeepCandidate> = cluster
+                .members
+                .iter()
+                .map(|&j| {
+                    let candidate = hash_entries[j].keep_candidate();
+                    if fill_from_filesystem {
+                        candidate.fill_from_filesystem()
+                    } else {
+                        candidate
+                    }
+                })
+                .collect();
+
+            if let Some(keeper) = select_keeper(&candidates, &keep) {
+                let representative = cluster.members.remove(keeper);
+                cluster.members.insert(0, representative);
+            }
         }
     }
 
+    let groups: Vec<DuplicateGroup> = clusters
+        .iter()
+        .enumerate()
+        .map(|(group_id, cluster)| {
+            // The first member of each cluster is the representative
+            let representative = &hash_entries[cluster.members[0]];
+            let representative_hash = representative.hash_variants();
+            let bits = representative_hash.bit_len();
+            let bit_threshold = scaled_threshold(threshold, bits);
+
+            let files = cluster
+                .members
+                .iter()
+                .map(|&j| {
+                    let entry = &hash_entries[j];
+                    match cluster.kind {
+                        GroupKind::Exact => DuplicateFile {
+                            path: entry.file_path.clone(),
+                            hash: entry.content_hash().unwrap_or_default().to_string(),
+                            distance_from_representative: 0,
+                            normalized_distance: 0.0,
+                            transform: None,
+                            relation: None,
+                            agreeing_algorithms: None,
+                            color_distance: None,
+                        },
+                        GroupKind::Perceptual => {
+                            let hash = entry.hash_variants();
+                            let (distance, transform) =
+                                representative_hash.closest_transform(&hash);
+                            let has_variants =
+                                representative_hash.dihedral.is_some() || hash.dihedral.is_some();
+                            let has_segments = !representative_hash.segments.is_empty()
+                                || !hash.segments.is_empty();
+                            let tally = voting.tally(&representative_hash, &hash);
+                            let whole_image_matches = match &tally {
+                                Some(tally) => voting.passes(tally),
+                                None => distance <= bit_threshold,
+                            };
+                            // The whole image differs but enough regions match
+                            let cropped = !whole_image_matches
+                                && representative_hash.segment_matches(&hash, bit_threshold)
+                                    >= min_segment_matches;
+                            DuplicateFile {
+                                path: entry.file_path.clone(),
+                                hash: entry.hash.clone(),
+                                distance_from_representative: distance,
+                                normalized_distance: normalized_distance(distance, bits),
+                                transform: has_variants.then_some(transform),
+                                relation: has_segments.then_some(if cropped {
+                                    MatchRelation::Cropped
+                                } else {
+                                    MatchRelation::Similar
+                                }),
+                                agreeing_algorithms: tally.map(|tally| {
+                                    tally.agreeing.iter().map(ToString::to_string).collect()
+                                }),
+                                color_distance: representative_hash.color_distance(&hash),
+                            }
+                        }
+                    }
+                })
+                .collect();
+
+            DuplicateGroup {
+                group_id,
+                kind: cluster.kind,
+                representative_file: representative.file_path.clone(),
+                files,
+                max_distance: cluster.max_distance,
+            }
+        })
+        .collect();
+
     // Create report
     let total_duplicates: usize = groups.iter().map(|g| g.files.len() - 1).sum();
     let report = DuplicatesReport {
@@ -166,6 +1048,11 @@ pub async fn execute_find_dups(
         total_duplicates,
         threshold,
         groups,
+        keep_policy: keep.iter().map(ToString::to_string).collect(),
+        // processは別の作業ディレクトリから実行されることもあるため絶対パスで記録
+        scan_database: Some(std::fs::canonicalize(&hash_database).unwrap_or(hash_database)),
+        vote: (ensemble_entries > 0).then_some(vote),
+        max_color_distance,
     };
 
     // Create output directory if it doesn't exist
@@ -187,9 +1074,34 @@ pub async fn execute_find_dups(
     if report.total_groups > 0 {
         println!("\n📌 重複例 (最初の3グループ):");
         for (idx, group) in report.groups.iter().take(3).enumerate() {
-            println!("\n  グループ {} ({} ファイル):", idx + 1, group.files.len());
+            let kind = match group.kind {
+                GroupKind::Exact => "完全一致",
+                GroupKind::Perceptual => "類似",
+            };
+            println!(
+                "\n  グループ {} ({} ファイル, {}):",
+                idx + 1,
+                group.files.len(),
+                kind
+            );
             for file in &group.files {
-                println!("    - {} (距離: {})", file.path, file.distance_from_representative);
+                let mut details = vec![format!("距離: {}", file.distance_from_representative)];
+                if let Some(transform) = file
+                    .transform
+                    .filter(|&transform| transform != DihedralTransform::Identity)
+                {
+                    details.push(format!("変換: {transform}"));
+                }
+                if file.relation == Some(MatchRelation::Cropped) {
+                    details.push("切り抜き".to_string());
+                }
+                if let Some(agreeing) = &file.agreeing_algorithms {
+                    details.push(format!("一致: {}", agreeing.join(", ")));
+                }
+                if let Some(color_distance) = file.color_distance {
+                    details.push(format!("色の距離: {color_distance:.2}"));
+                }
+                println!("    - {} ({})", file.path, details.join(", "));
             }
         }
     }
@@ -203,6 +1115,25 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    /// CLIの既定値（スター型、2領域、多数決、閾値の上書きなし）でのfind-dupsのオプション
+    fn find_dups_options(
+        hash_database: impl Into<PathBuf>,
+        output: impl Into<PathBuf>,
+        threshold: u32,
+    ) -> FindDupsOptions {
+        FindDupsOptions {
+            hash_database: hash_database.into(),
+            output: output.into(),
+            threshold,
+            grouping: GroupingMode::Star,
+            min_segment_matches: 2,
+            vote: VoteMode::Majority,
+            algorithm_thresholds: Vec::new(),
+            max_color_distance: None,
+            keep: Vec::new(),
+        }
+    }
+
     fn create_test_hash_entry(file_path: &str, hash: &str, hash_bits: u64) -> HashEntry {
         HashEntry {
             file_path: file_path.to_string(),
@@ -242,7 +1173,9 @@ mod tests {
         }"#;
         fs::write(&hash_db, new_format).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 3).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 3))
+            .await
+            .unwrap();
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -253,11 +1186,11 @@ mod tests {
 
     #[test]
     fn test_hamming_distance() {
-        assert_eq!(hamming_distance(0b0000, 0b0000), 0);
-        assert_eq!(hamming_distance(0b1111, 0b0000), 4);
-        assert_eq!(hamming_distance(0b1010, 0b0101), 4);
-        assert_eq!(hamming_distance(0b1100, 0b1010), 2);
-        assert_eq!(hamming_distance(0b11111111, 0b00000000), 8);
+        assert_eq!(0b0000u64.hamming_distance(&0b0000), 0);
+        assert_eq!(0b1111u64.hamming_distance(&0b0000), 4);
+        assert_eq!(0b1010u64.hamming_distance(&0b0101), 4);
+        assert_eq!(0b1100u64.hamming_distance(&0b1010), 2);
+        assert_eq!(0b11111111u64.hamming_distance(&0b00000000), 8);
     }
 
     #[tokio::test]
@@ -265,7 +1198,7 @@ mod tests {
         let nonexistent = PathBuf::from("nonexistent.json");
         let output = PathBuf::from("output.json");
 
-        let result = execute_find_dups(nonexistent, output, 5).await;
+        let result = execute_find_dups(find_dups_options(nonexistent, output, 5)).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("does not exist"));
     }
@@ -279,7 +1212,9 @@ mod tests {
         // Create empty database
         fs::write(&hash_db, "[]").unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 5).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 5))
+            .await
+            .unwrap();
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -305,7 +1240,9 @@ mod tests {
         let json = serde_json::to_string_pretty(&entries).unwrap();
         fs::write(&hash_db, json).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 3).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 3))
+            .await
+            .unwrap();
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -321,6 +1258,7 @@ mod tests {
         assert_eq!(group.files[0].distance_from_representative, 0);
         assert_eq!(group.files[1].distance_from_representative, 1);
         assert_eq!(group.files[2].distance_from_representative, 2);
+        assert_eq!(group.files[1].transform, None);
     }
 
     #[tokio::test]
@@ -339,7 +1277,9 @@ mod tests {
         let json = serde_json::to_string_pretty(&entries).unwrap();
         fs::write(&hash_db, json).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 2).await.unwrap(); // strict threshold
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 2))
+            .await
+            .unwrap(); // strict threshold
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -371,7 +1311,9 @@ mod tests {
         let json = serde_json::to_string_pretty(&entries).unwrap();
         fs::write(&hash_db, json).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 2).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 2))
+            .await
+            .unwrap();
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -400,7 +1342,7 @@ mod tests {
         // Create invalid JSON
         fs::write(&hash_db, "invalid json content").unwrap();
 
-        let result = execute_find_dups(hash_db, output, 5).await;
+        let result = execute_find_dups(find_dups_options(hash_db, output, 5)).await;
         assert!(result.is_err());
     }
 
@@ -415,7 +1357,9 @@ mod tests {
         let json = serde_json::to_string_pretty(&entries).unwrap();
         fs::write(&hash_db, json).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 5).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 5))
+            .await
+            .unwrap();
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -438,7 +1382,7 @@ mod tests {
         // Create empty database
         fs::write(&hash_db, "[]").unwrap();
 
-        let result = execute_find_dups(hash_db, nested_output.clone(), 5).await;
+        let result = execute_find_dups(find_dups_options(hash_db, nested_output.clone(), 5)).await;
         assert!(result.is_ok());
         assert!(nested_output.exists());
     }
@@ -449,12 +1393,19 @@ mod tests {
             path: "test.jpg".to_string(),
             hash: "abcd1234".to_string(),
             distance_from_representative: 5,
+            normalized_distance: 5.0,
+            transform: None,
+            relation: None,
+            agreeing_algorithms: None,
+            color_distance: None,
         };
 
         let group = DuplicateGroup {
             group_id: 0,
+            kind: GroupKind::Perceptual,
             representative_file: "test.jpg".to_string(),
             files: vec![file],
+            max_distance: 0,
         };
 
         let report = DuplicatesReport {
@@ -462,6 +1413,10 @@ mod tests {
             total_duplicates: 0,
             threshold: 5,
             groups: vec![group],
+            keep_policy: vec![],
+            scan_database: None,
+            vote: None,
+            max_color_distance: None,
         };
 
         // Test that structures can be serialized and deserialized
@@ -472,6 +1427,7 @@ mod tests {
         assert_eq!(deserialized.threshold, 5);
         assert_eq!(deserialized.groups[0].group_id, 0);
         assert_eq!(deserialized.groups[0].files[0].path, "test.jpg");
+        assert_eq!(deserialized.groups[0].kind, GroupKind::Perceptual);
     }
 
     #[test]
@@ -527,7 +1483,9 @@ mod tests {
         let json = serde_json::to_string_pretty(&entries).unwrap();
         fs::write(&hash_db, json).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 3).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 3))
+            .await
+            .unwrap();
 
         // Check output file
         let content = fs::read_to_string(&output).unwrap();
@@ -567,7 +1525,9 @@ mod tests {
         let json = serde_json::to_string_pretty(&entries).unwrap();
         fs::write(&hash_db, json).unwrap();
 
-        execute_find_dups(hash_db, output.clone(), 3).await.unwrap();
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 3))
+            .await
+            .unwrap();
 
         let content = fs::read_to_string(&output).unwrap();
         let report: DuplicatesReport = serde_json::from_str(&content).unwrap();
@@ -577,4 +1537,907 @@ mod tests {
         assert_eq!(report.groups[0].files[0].path, "first.jpg");
         assert_eq!(report.groups[0].files[0].distance_from_representative, 0);
     }
+
+    #[tokio::test]
+    async fn test_find_dups_keep_policy_selects_representative() {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+
+        let entries = vec![
+            HashEntry {
+                file_path: "/backup/small.jpg".to_string(),
+                hash: "hash1".to_string(),
+                hash_bits: 0b0000_0000,
+                metadata: Some(serde_json::json!({"file_size": 1000})),
+            },
+            HashEntry {
+                file_path: "/photos/large.jpg".to_string(),
+                hash: "hash2".to_string(),
+                hash_bits: 0b0000_0001,
+                metadata: Some(serde_json::json!({"file_size": 5000})),
+            },
+            HashEntry {
+                file_path: "/photos/same_size.png".to_string(),
+                hash: "hash3".to_string(),
+                hash_bits: 0b0000_0011,
+                metadata: Some(serde_json::json!({"file_size": 5000})),
+            },
+        ];
+
+        let json = serde_json::to_string_pretty(&entries).unwrap();
+        fs::write(&hash_db, json).unwrap();
+
+        let keep: Vec<KeepPolicy> = vec!["largest".parse().unwrap(), "format:png".parse().unwrap()];
+        execute_find_dups(FindDupsOptions {
+            keep,
+            ..find_dups_options(hash_db, output.clone(), 3)
+        })
+        .await
+        .unwrap();
+
+        let content = fs::read_to_string(&output).unwrap();
+        let report: DuplicatesReport = serde_json::from_str(&content).unwrap();
+
+        // Largest size is tied, so the PNG tie-breaker decides the representative
+        let group = &report.groups[0];
+        assert_eq!(group.representative_file, "/photos/same_size.png");
+        assert_eq!(group.files[0].path, "/photos/same_size.png");
+        assert_eq!(group.files[0].distance_from_representative, 0);
+        assert_eq!(group.files.len(), 3);
+        assert_eq!(report.keep_policy, vec!["largest", "format:png"]);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_records_scan_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+
+        let entries = vec![create_test_hash_entry("a.jpg", "hash1", 0)];
+        fs::write(&hash_db, serde_json::to_string(&entries).unwrap()).unwrap();
+
+        execute_find_dups(find_dups_options(hash_db.clone(), output.clone(), 3))
+            .await
+            .unwrap();
+
+        let report: DuplicatesReport =
+            serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        let recorded = report.scan_database.unwrap();
+        assert!(recorded.is_absolute());
+        assert_eq!(recorded, fs::canonicalize(&hash_db).unwrap());
+    }
+
+    fn create_full_width_entry(file_path: &str, hash: String, hash_size_bits: u32) -> HashEntry {
+        HashEntry {
+            file_path: file_path.to_string(),
+            hash_bits: 0, // 先頭64ビットだけでは区別できない状況を再現
+            hash,
+            metadata: Some(serde_json::json!({"hash_size_bits": hash_size_bits})),
+        }
+    }
+
+    #[test]
+    fn test_bit_vector_from_entry() {
+        let entry = create_full_width_entry("a.jpg", "ff".repeat(32), 256);
+        assert_eq!(entry.bit_vector().bit_len(), 256);
+
+        // 16進として解釈できない旧データはhash_bitsにフォールバック
+        let legacy = create_test_hash_entry("b.jpg", "hash1", 0b1010);
+        assert_eq!(legacy.bit_vector(), BitVector::from_u64(0b1010));
+    }
+
+    #[test]
+    fn test_scaled_threshold_and_normalized_distance() {
+        assert_eq!(scaled_threshold(5, 64), 5);
+        assert_eq!(scaled_threshold(5, 256), 20);
+        assert_eq!(scaled_threshold(5, 1024), 80);
+        assert_eq!(scaled_threshold(5, 25), 1);
+
+        assert_eq!(normalized_distance(20, 256), 5.0);
+        assert_eq!(normalized_distance(3, 64), 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_compares_full_width_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+
+        // 16×16 (256ビット) のハッシュ。先頭64ビットはすべて同じ
+        let base = "00".repeat(32);
+        let near = format!("{}{}", "00".repeat(30), "0fff"); // 距離12 = 64ビットあたり3
+        let far = format!("{}{}", "00".repeat(8), "ff".repeat(24)); // 距離192
+
+        let entries = vec![
+            create_full_width_entry("base.jpg", base, 256),
+            create_full_width_entry("near.jpg", near, 256),
+            create_full_width_entry("far.jpg", far, 256),
+        ];
+        fs::write(&hash_db, serde_json::to_string_pretty(&entries).unwrap()).unwrap();
+
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 5))
+            .await
+            .unwrap();
+
+        let content = fs::read_to_string(&output).unwrap();
+        let report: DuplicatesReport = serde_json::from_str(&content).unwrap();
+
+        assert_eq!(report.total_groups, 1);
+        assert_eq!(report.groups[0].files.len(), 2);
+        assert_eq!(report.groups[0].files[1].path, "near.jpg");
+        assert_eq!(report.groups[0].files[1].distance_from_representative, 12);
+        assert_eq!(report.groups[0].files[1].normalized_distance, 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_does_not_compare_different_hash_sizes() {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+
+        let entries = vec![
+            create_full_width_entry("small.jpg", "00".repeat(8), 64),
+            create_full_width_entry("large.jpg", "00".repeat(32), 256),
+        ];
+        fs::write(&hash_db, serde_json::to_string_pretty(&entries).unwrap()).unwrap();
+
+        execute_find_dups(find_dups_options(hash_db, output.clone(), 5))
+            .await
+            .unwrap();
+
+        let content = fs::read_to_string(&output).unwrap();
+        let report: DuplicatesReport = serde_json::from_str(&content).unwrap();
+        assert_eq!(report.total_groups, 0);
+    }
+
+    fn write_chain_database(hash_db: &std::path::Path) {
+        // A~B (距離2), B~C (距離2) だが A~C は距離4
+        let entries = vec![
+            create_test_hash_entry("a.jpg", "hash1", 0b0000),
+            create_test_hash_entry("b.jpg", "hash2", 0b0011),
+            create_test_hash_entry("c.jpg", "hash3", 0b1111),
+        ];
+        fs::write(hash_db, serde_json::to_string_pretty(&entries).unwrap()).unwrap();
+    }
+
+    async fn run_grouping(grouping: GroupingMode) -> DuplicatesReport {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+        write_chain_database(&hash_db);
+
+        execute_find_dups(FindDupsOptions {
+            grouping,
+            ..find_dups_options(hash_db, output.clone(), 2)
+        })
+        .await
+        .unwrap();
+
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_star_grouping_splits_chain() {
+        let report = run_grouping(GroupingMode::Star).await;
+
+        assert_eq!(report.total_groups, 1);
+        assert_eq!(report.groups[0].files.len(), 2);
+        assert_eq!(report.groups[0].max_distance, 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_connected_grouping_follows_chain() {
+        let report = run_grouping(GroupingMode::Connected).await;
+
+        assert_eq!(report.total_groups, 1);
+        assert_eq!(report.total_duplicates, 2);
+        let group = &report.groups[0];
+        assert_eq!(group.representative_file, "a.jpg");
+        assert_eq!(group.files[2].path, "c.jpg");
+        assert_eq!(group.files[2].distance_from_representative, 4);
+        assert_eq!(group.max_distance, 4);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_complete_grouping_bounds_max_distance() {
+        let report = run_grouping(GroupingMode::Complete).await;
+
+        assert_eq!(report.total_groups, 1);
+        assert!(report.groups.iter().all(|group| group.max_distance <= 2));
+    }
+
+    fn create_content_hashed_entry(
+        file_path: &str,
+        hash: &str,
+        hash_bits: u64,
+        content_hash: &str,
+    ) -> HashEntry {
+        HashEntry {
+            file_path: file_path.to_string(),
+            hash: hash.to_string(),
+            hash_bits,
+            metadata: Some(serde_json::json!({"content_hash": content_hash})),
+        }
+    }
+
+    async fn run_find_dups(entries: &[HashEntry], keep: &[KeepPolicy]) -> DuplicatesReport {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+        fs::write(&hash_db, serde_json::to_string(entries).unwrap()).unwrap();
+
+        execute_find_dups(FindDupsOptions {
+            keep: keep.to_vec(),
+            ..find_dups_options(hash_db, output.clone(), 3)
+        })
+        .await
+        .unwrap();
+
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_reads_sqlite_and_binary_databases() {
+        use crate::core::ProcessingMetadata;
+        use crate::services::persistence::implementations::{
+            HashEntry as StoredEntry, ScanInfo, ScanResult as StoredScanResult,
+        };
+        use crate::services::persistence::incremental::write_scan_result;
+
+        let entry = |path: &str, hash: &str| StoredEntry {
+            file_path: path.to_string(),
+            hash: hash.to_string(),
+            hash_bits: u64::from_str_radix(hash, 16).unwrap(),
+            metadata: ProcessingMetadata {
+                file_size: 100,
+                processing_time_ms: 1,
+                image_dimensions: (8, 8),
+                was_resized: false,
+                hash_size_bits: 64,
+                modified_time_ms: None,
+                content_hash: None,
+                dihedral_hashes: None,
+                segment_hashes: None,
+                ensemble_hashes: None,
+                ensemble_dihedral_hashes: None,
+                color_signature: None,
+            },
+        };
+        let database = StoredScanResult {
+            scan_info: ScanInfo {
+                algorithm: "scan".to_string(),
+                parameters: serde_json::json!({}),
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+                total_files: 3,
+            },
+            images: vec![
+                entry("a.jpg", "00000000000000ff"),
+                entry("b.jpg", "00000000000000fe"),
+                entry("c.jpg", "ffffffffffff0000"),
+            ],
+        };
+
+        for format in [DatabaseFormat::Sqlite, DatabaseFormat::Binary] {
+            let temp_dir = TempDir::new().unwrap();
+            // 拡張子ではなくファイルの内容から形式を判定する
+            let hash_db = temp_dir.path().join("hashes.data");
+            let output = temp_dir.path().join("duplicates.json");
+            write_scan_result(&hash_db, &database, format).unwrap();
+
+            execute_find_dups(find_dups_options(hash_db, output.clone(), 3))
+                .await
+                .unwrap();
+
+            let report: DuplicatesReport =
+                serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+            assert_eq!(report.total_groups, 1, "{format}");
+            let paths: Vec<&str> = report.groups[0]
+                .files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect();
+            assert_eq!(paths, vec!["a.jpg", "b.jpg"]);
+            assert_eq!(report.groups[0].files[1].distance_from_representative, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_groups_exact_duplicates_first() {
+        let entries = vec![
+            create_test_hash_entry("unrelated.jpg", "hash0", 0b0011_1100_0000),
+            create_content_hashed_entry("a.jpg", "hash1", 0b0000_0000, "blake3:aa"),
+            create_content_hashed_entry("other.jpg", "hash2", 0b1111_0000, "blake3:bb"),
+            create_content_hashed_entry("a_copy.jpg", "hash3", 0b0000_0000, "blake3:aa"),
+            create_content_hashed_entry("resized.jpg", "hash4", 0b1111_0001, "blake3:cc"),
+        ];
+
+        let report = run_find_dups(&entries, &[]).await;
+
+        assert_eq!(report.total_groups, 2);
+        assert_eq!(report.total_duplicates, 2);
+
+        let exact = &report.groups[0];
+        assert_eq!(exact.kind, GroupKind::Exact);
+        let paths: Vec<&str> = exact.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.jpg", "a_copy.jpg"]);
+        assert_eq!(exact.files[1].hash, "blake3:aa");
+        assert_eq!(exact.max_distance, 0);
+
+        let similar = &report.groups[1];
+        assert_eq!(similar.kind, GroupKind::Perceptual);
+        let paths: Vec<&str> = similar.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["other.jpg", "resized.jpg"]);
+        assert_eq!(similar.files[1].distance_from_representative, 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_merges_exact_group_with_similar_files() {
+        let entries = vec![
+            create_test_hash_entry("similar.jpg", "hash0", 0b0000_0001),
+            create_content_hashed_entry("a.jpg", "hash1", 0b0000_0000, "blake3:aa"),
+            create_content_hashed_entry("a_copy.jpg", "hash2", 0b0000_0000, "blake3:aa"),
+            create_content_hashed_entry("b.jpg", "hash3", 0b1111_0000, "blake3:bb"),
+            create_content_hashed_entry("b_copy.jpg", "hash4", 0b1111_0000, "blake3:bb"),
+        ];
+
+        let report = run_find_dups(&entries, &[]).await;
+
+        assert_eq!(report.total_groups, 2);
+        assert_eq!(report.total_duplicates, 3);
+
+        let exact = &report.groups[0];
+        assert_eq!(exact.kind, GroupKind::Exact);
+        let paths: Vec<&str> = exact.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.jpg", "b_copy.jpg"]);
+
+        // 完全一致グループに類似するファイルは、完全一致のファイルごと1つのグループにまとめる
+        let similar = &report.groups[1];
+        assert_eq!(similar.kind, GroupKind::Perceptual);
+        let paths: Vec<&str> = similar.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["similar.jpg", "a.jpg", "a_copy.jpg"]);
+        assert_eq!(similar.files[2].distance_from_representative, 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_exact_group_uses_keep_policy() {
+        let mut small = create_content_hashed_entry("small.jpg", "", 0, "sha256:aa");
+        small.metadata = Some(serde_json::json!({"content_hash": "sha256:aa", "file_size": 10}));
+        let mut large = create_content_hashed_entry("large.jpg", "", 0, "sha256:aa");
+        large.metadata = Some(serde_json::json!({"content_hash": "sha256:aa", "file_size": 20}));
+
+        let report = run_find_dups(&[small, large], &[KeepPolicy::Largest]).await;
+
+        assert_eq!(report.total_groups, 1);
+        assert_eq!(report.groups[0].representative_file, "large.jpg");
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_keep_resolution_on_exact_only_database() {
+        let image_dir = TempDir::new().unwrap();
+        let small_path = image_dir.path().join("small.png");
+        let large_path = image_dir.path().join("large.png");
+        image::RgbImage::new(10, 10).save(&small_path).unwrap();
+        image::RgbImage::new(40, 30).save(&large_path).unwrap();
+
+        // scan --exact-only は寸法を (0, 0) で記録する
+        let exact_only_entry = |path: &std::path::Path| HashEntry {
+            file_path: path.to_string_lossy().to_string(),
+            hash: String::new(),
+            hash_bits: 0,
+            metadata: Some(serde_json::json!({
+                "content_hash": "blake3:aa",
+                "image_dimensions": [0, 0],
+            })),
+        };
+        let entries = vec![exact_only_entry(&small_path), exact_only_entry(&large_path)];
+
+        let report = run_find_dups(&entries, &[KeepPolicy::HighestResolution]).await;
+
+        assert_eq!(report.total_groups, 1);
+        assert_eq!(
+            report.groups[0].representative_file,
+            large_path.to_string_lossy()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_exact_only_entries_are_not_compared_perceptually() {
+        // scan --exact-only のエントリは知覚ハッシュを持たない
+        let entries = vec![
+            create_content_hashed_entry("a.jpg", "", 0, "blake3:aa"),
+            create_content_hashed_entry("b.jpg", "", 0, "blake3:bb"),
+            create_content_hashed_entry("b_copy.jpg", "", 0, "blake3:bb"),
+        ];
+
+        let report = run_find_dups(&entries, &[]).await;
+
+        assert_eq!(report.total_groups, 1);
+        assert_eq!(report.groups[0].kind, GroupKind::Exact);
+        assert_eq!(report.groups[0].representative_file, "b.jpg");
+    }
+
+    /// 回転・反転のバリアントは互いに十分離れた値にする（`DihedralTransform::ALL` の順）
+    const VARIANTS: [&str; 8] = [
+        "00000000000000ff",
+        "ff00000000000000",
+        "0000ff0000000000",
+        "000000ff00000000",
+        "00000000ff000000",
+        "0000000000ff0000",
+        "000000000000ff00",
+        "00ff000000000000",
+    ];
+
+    fn create_dihedral_entry(file_path: &str, variants: [&str; 8]) -> HashEntry {
+        HashEntry {
+            file_path: file_path.to_string(),
+            hash: variants[0].to_string(),
+            hash_bits: 0,
+            metadata: Some(serde_json::json!({
+                "hash_size_bits": 64,
+                "dihedral_hashes": variants,
+            })),
+        }
+    }
+
+    #[test]
+    fn test_closest_transform() {
+        let original = create_dihedral_entry("original.jpg", VARIANTS).hash_variants();
+        let rotated = create_test_hash_entry("rotated.jpg", "ff00000000000001", 0).hash_variants();
+
+        // rotated ≈ rotate90(original)
+        assert_eq!(
+            original.closest_transform(&rotated),
+            (1, DihedralTransform::Rotate90)
+        );
+        // original ≈ rotate270(rotated)（バリアントは片方にあればよい）
+        assert_eq!(
+            rotated.closest_transform(&original),
+            (1, DihedralTransform::Rotate270)
+        );
+        // 同じ距離なら変換なしを優先
+        assert_eq!(
+            original.closest_transform(&original),
+            (0, DihedralTransform::Identity)
+        );
+    }
+
+    #[test]
+    fn test_incomplete_dihedral_hashes_are_ignored() {
+        let mut entry = create_dihedral_entry("a.jpg", VARIANTS);
+        entry.metadata = Some(serde_json::json!({
+            "hash_size_bits": 64,
+            "dihedral_hashes": &VARIANTS[..4],
+        }));
+        assert!(entry.hash_variants().dihedral.is_none());
+        assert!(create_dihedral_entry("a.jpg", VARIANTS)
+            .hash_variants()
+            .dihedral
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_matches_rotated_copies() {
+        let entries = vec![
+            create_dihedral_entry("original.jpg", VARIANTS),
+            create_test_hash_entry("unrelated.jpg", "f0f0f0f0f0f0f0f0", 0),
+            // 90度回転したコピー（元のハッシュとは距離15）
+            create_test_hash_entry("rotated.jpg", "ff00000000000001", 0),
+            // 左右反転したコピー
+            create_test_hash_entry("mirrored.jpg", "00000000ff000000", 0),
+        ];
+
+        let report = run_find_dups(&entries, &[]).await;
+
+        assert_eq!(report.total_groups, 1);
+        let group = &report.groups[0];
+        assert_eq!(group.representative_file, "original.jpg");
+        let files: Vec<(&str, u32, Option<DihedralTransform>)> = group
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.distance_from_representative, f.transform))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("original.jpg", 0, Some(DihedralTransform::Identity)),
+                ("rotated.jpg", 1, Some(DihedralTransform::Rotate90)),
+                ("mirrored.jpg", 0, Some(DihedralTransform::FlipHorizontal)),
+            ]
+        );
+        // バリアントを持たない rotated と mirrored の間は回転・反転を考慮できない
+        assert_eq!(group.max_distance, 17);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_rotated_representative_without_variants() {
+        // バリアントを持たないエントリが代表でも、相手のバリアントで一致を見つける
+        let entries = vec![
+            create_test_hash_entry("rotated.jpg", "ff00000000000000", 0),
+            create_dihedral_entry("original.jpg", VARIANTS),
+        ];
+
+        let report = run_find_dups(&entries, &[]).await;
+
+        assert_eq!(report.total_groups, 1);
+        let original = &report.groups[0].files[1];
+        assert_eq!(original.path, "original.jpg");
+        assert_eq!(original.transform, Some(DihedralTransform::Rotate270));
+    }
+
+    fn create_segmented_entry(file_path: &str, hash: &str, segments: &[&str]) -> HashEntry {
+        HashEntry {
+            file_path: file_path.to_string(),
+            hash: hash.to_string(),
+            hash_bits: 0,
+            metadata: Some(serde_json::json!({
+                "hash_size_bits": 64,
+                "segment_hashes": segments,
+            })),
+        }
+    }
+
+    #[test]
+    fn test_segment_matches() {
+        let original = create_segmented_entry(
+            "original.jpg",
+            "0000000000000000",
+            &["00000000000000ff", "ff00000000000000", "0000ff0000000000"],
+        )
+        .hash_variants();
+        let cropped = create_segmented_entry(
+            "cropped.jpg",
+            "ffffffffffffffff",
+            &["00000000000000fe", "ff00000000000000", "f0f0f0f0f0f0f0f0"],
+        )
+        .hash_variants();
+
+        assert_eq!(original.segment_matches(&cropped, 1), 2);
+        assert_eq!(cropped.segment_matches(&original, 1), 2);
+        assert_eq!(original.segment_matches(&cropped, 0), 1);
+        // 部分領域を持たないエントリとは一致しない
+        let plain = create_test_hash_entry("plain.jpg", "0000000000000000", 0).hash_variants();
+        assert_eq!(original.segment_matches(&plain, 64), 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_matches_cropped_copies() {
+        let segments = ["00000000000000ff", "ff00000000000000", "0000ff0000000000"];
+        let entries = vec![
+            create_segmented_entry("original.jpg", "0000000000000000", &segments),
+            // 画像全体のハッシュは大きく異なるが、2つの領域が一致する
+            create_segmented_entry(
+                "cropped.jpg",
+                "ffffffffffffffff",
+                &["ff00000000000001", "00000000000000ff", "f0f0f0f0f0f0f0f0"],
+            ),
+            // 一致する領域が1つだけ
+            create_segmented_entry(
+                "unrelated.jpg",
+                "0f0f0f0f0f0f0f0f",
+                &["0000ff0000000000", "ffff000000000000"],
+            ),
+            create_segmented_entry("resized.jpg", "0000000000000001", &segments),
+        ];
+
+        let report = run_find_dups(&entries, &[]).await;
+
+        assert_eq!(report.total_groups, 1);
+        let files: Vec<(&str, Option<MatchRelation>)> = report.groups[0]
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.relation))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("original.jpg", Some(MatchRelation::Similar)),
+                ("cropped.jpg", Some(MatchRelation::Cropped)),
+                ("resized.jpg", Some(MatchRelation::Similar)),
+            ]
+        );
+        assert_eq!(report.groups[0].files[1].distance_from_representative, 64);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_min_segment_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+        let entries = vec![
+            create_segmented_entry("a.jpg", "0000000000000000", &["00000000000000ff"]),
+            create_segmented_entry("b.jpg", "ffffffffffffffff", &["00000000000000ff"]),
+        ];
+        fs::write(&hash_db, serde_json::to_string(&entries).unwrap()).unwrap();
+
+        execute_find_dups(FindDupsOptions {
+            min_segment_matches: 1,
+            ..find_dups_options(hash_db.clone(), output.clone(), 3)
+        })
+        .await
+        .unwrap();
+        let report: DuplicatesReport =
+            serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(report.total_groups, 1);
+
+        execute_find_dups(find_dups_options(hash_db.clone(), output.clone(), 3))
+            .await
+            .unwrap();
+        let report: DuplicatesReport =
+            serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(report.total_groups, 0);
+
+        let result = execute_find_dups(FindDupsOptions {
+            min_segment_matches: 0,
+            ..find_dups_options(hash_db, output, 3)
+        })
+        .await;
+        assert!(result.is_err());
+    }
+
+    fn create_ensemble_entry(file_path: &str, hashes: [(&str, &str); 3]) -> HashEntry {
+        let ensemble: serde_json::Map<String, serde_json::Value> = hashes
+            .iter()
+            .map(|(algorithm, hex)| (algorithm.to_string(), serde_json::json!(hex)))
+            .collect();
+        HashEntry {
+            file_path: file_path.to_string(),
+            hash: hashes[0].1.to_string(),
+            hash_bits: 0,
+            metadata: Some(serde_json::json!({
+                "hash_size_bits": 64,
+                "ensemble_hashes": ensemble,
+            })),
+        }
+    }
+
+    /// a~b は dct と difference のみ、a~c は average のみが閾値3以内
+    fn ensemble_entries() -> Vec<HashEntry> {
+        vec![
+            create_ensemble_entry(
+                "a.png",
+                [
+                    ("dct", "0000000000000000"),
+                    ("difference", "0000000000000000"),
+                    ("average", "0000000000000000"),
+                ],
+            ),
+            create_ensemble_entry(
+                "b.png",
+                [
+                    ("dct", "0000000000000000"),
+                    ("difference", "0000000000000003"),
+                    ("average", "00000000000fffff"),
+                ],
+            ),
+            create_ensemble_entry(
+                "c.png",
+                [
+                    ("dct", "ffffffff00000000"),
+                    ("difference", "ffffffff00000000"),
+                    ("average", "0000000000000001"),
+                ],
+            ),
+        ]
+    }
+
+    async fn run_voting(
+        vote: VoteMode,
+        algorithm_thresholds: &[AlgorithmThreshold],
+    ) -> DuplicatesReport {
+        run_voting_on(&ensemble_entries(), vote, algorithm_thresholds).await
+    }
+
+    async fn run_voting_on(
+        entries: &[HashEntry],
+        vote: VoteMode,
+        algorithm_thresholds: &[AlgorithmThreshold],
+    ) -> DuplicatesReport {
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+        fs::write(&hash_db, serde_json::to_string(entries).unwrap()).unwrap();
+
+        execute_find_dups(FindDupsOptions {
+            vote,
+            algorithm_thresholds: algorithm_thresholds.to_vec(),
+            ..find_dups_options(hash_db, output.clone(), 3)
+        })
+        .await
+        .unwrap();
+
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap()
+    }
+
+    fn group_paths(report: &DuplicatesReport) -> Vec<Vec<&str>> {
+        report
+            .groups
+            .iter()
+            .map(|group| group.files.iter().map(|f| f.path.as_str()).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_ensemble_vote_modes() {
+        let report = run_voting(VoteMode::Majority, &[]).await;
+        assert_eq!(group_paths(&report), vec![vec!["a.png", "b.png"]]);
+        assert_eq!(report.vote, Some(VoteMode::Majority));
+        let agreeing = report.groups[0].files[1]
+            .agreeing_algorithms
+            .as_ref()
+            .unwrap();
+        assert_eq!(agreeing, &vec!["dct".to_string(), "difference".to_string()]);
+
+        let report = run_voting(VoteMode::Any, &[]).await;
+        assert_eq!(group_paths(&report), vec![vec!["a.png", "b.png", "c.png"]]);
+
+        let report = run_voting(VoteMode::All, &[]).await;
+        assert_eq!(report.total_groups, 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_ensemble_algorithm_thresholds() {
+        let thresholds: Vec<AlgorithmThreshold> = vec!["average=20".parse().unwrap()];
+
+        let report = run_voting(VoteMode::All, &thresholds).await;
+
+        assert_eq!(group_paths(&report), vec![vec!["a.png", "b.png"]]);
+        let agreeing = report.groups[0].files[1]
+            .agreeing_algorithms
+            .as_ref()
+            .unwrap();
+        assert_eq!(agreeing.len(), 3);
+    }
+
+    /// dct と average のバリアントを記録した画像と、それを `DihedralTransform::ALL[1]` で変換した画像
+    fn rotated_ensemble_entries(with_variants: bool) -> Vec<HashEntry> {
+        let variants = |rotated: &'static str| {
+            let mut variants = [
+                "0000000000000000",
+                "",
+                "0f0f0f0f0f0f0f0f",
+                "f0f0f0f0f0f0f0f0",
+                "3333333333333333",
+                "cccccccccccccccc",
+                "5555555555555555",
+                "aaaaaaaaaaaaaaaa",
+            ];
+            variants[1] = rotated;
+            variants
+        };
+        let mut original = create_ensemble_entry(
+            "original.png",
+            [
+                ("dct", "0000000000000000"),
+                ("difference", "0000000000000000"),
+                ("average", "0000000000000000"),
+            ],
+        );
+        if with_variants {
+            original.metadata.as_mut().unwrap()["ensemble_dihedral_hashes"] = serde_json::json!({
+                "dct": variants("ffffffff00000000"),
+                "average": variants("00000000ffffffff"),
+            });
+        }
+        let mut rotated = create_ensemble_entry(
+            "rotated.png",
+            [
+                ("dct", "ffffffff00000000"),
+                ("difference", "0000000000000000"),
+                ("average", "00000000ffffffff"),
+            ],
+        );
+        // difference はバリアントがないため、変換したときに投票できるアルゴリズムに揃える
+        rotated.metadata.as_mut().unwrap()["ensemble_hashes"]
+            .as_object_mut()
+            .unwrap()
+            .remove("difference");
+        vec![original, rotated]
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_ensemble_votes_per_dihedral_variant() {
+        let report = run_voting_on(&rotated_ensemble_entries(true), VoteMode::All, &[]).await;
+        assert_eq!(
+            group_paths(&report),
+            vec![vec!["original.png", "rotated.png"]]
+        );
+        let agreeing = report.groups[0].files[1]
+            .agreeing_algorithms
+            .as_ref()
+            .unwrap();
+        assert_eq!(agreeing, &vec!["average".to_string(), "dct".to_string()]);
+
+        // バリアントがなければ変換なしでしか投票できない
+        let report = run_voting_on(&rotated_ensemble_entries(false), VoteMode::All, &[]).await;
+        assert_eq!(report.total_groups, 0);
+    }
+
+    #[test]
+    fn test_parse_algorithm_threshold() {
+        let parsed: AlgorithmThreshold = "dct=6".parse().unwrap();
+        assert_eq!(parsed.algorithm, "dct");
+        assert_eq!(parsed.threshold, 6);
+        assert_eq!(parsed.to_string(), "dct=6");
+
+        assert!("dct".parse::<AlgorithmThreshold>().is_err());
+        assert!("=6".parse::<AlgorithmThreshold>().is_err());
+        assert!("dct=-1".parse::<AlgorithmThreshold>().is_err());
+    }
+
+    #[test]
+    fn test_entries_without_common_algorithms_use_main_hash() {
+        let voting = Voting::new(VoteMode::All, 3, &[]);
+        let ensemble = ensemble_entries()[0].hash_variants();
+        let plain = create_test_hash_entry("plain.png", "0000000000000000", 0).hash_variants();
+
+        assert!(voting.tally(&ensemble, &plain).is_none());
+        let tally = voting.tally(&ensemble, &ensemble).unwrap();
+        assert_eq!(tally.voters, 3);
+        assert!(voting.passes(&tally));
+    }
+
+    fn create_colored_entry(file_path: &str, hash_bits: u64, color: [u8; 3]) -> HashEntry {
+        let image =
+            image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(16, 16, image::Rgb(color)));
+        HashEntry {
+            file_path: file_path.to_string(),
+            hash: format!("{hash_bits:016x}"),
+            hash_bits,
+            metadata: Some(serde_json::json!({
+                "color_signature": ColorSignature::from_image(&image).to_hex()
+            })),
+        }
+    }
+
+    async fn run_color_check(max_color_distance: Option<f64>) -> Result<DuplicatesReport> {
+        let entries = vec![
+            create_colored_entry("red.png", 0, [220, 30, 30]),
+            create_colored_entry("red_copy.png", 1, [215, 35, 30]),
+            create_colored_entry("blue.png", 0, [30, 60, 220]),
+            create_test_hash_entry("plain.png", "0000000000000000", 0),
+        ];
+        let temp_dir = TempDir::new().unwrap();
+        let hash_db = temp_dir.path().join("hashes.json");
+        let output = temp_dir.path().join("duplicates.json");
+        fs::write(&hash_db, serde_json::to_string(&entries).unwrap()).unwrap();
+
+        execute_find_dups(FindDupsOptions {
+            max_color_distance,
+            ..find_dups_options(hash_db, output.clone(), 3)
+        })
+        .await?;
+
+        Ok(serde_json::from_str(&fs::read_to_string(&output)?)?)
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_max_color_distance_splits_recoloured_variants() {
+        let report = run_color_check(None).await.unwrap();
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].files.len(), 4);
+        assert!(report.max_color_distance.is_none());
+
+        let report = run_color_check(Some(0.2)).await.unwrap();
+        assert_eq!(report.max_color_distance, Some(0.2));
+        let groups = group_paths(&report);
+        assert_eq!(groups.len(), 1);
+        assert!(!groups[0].contains(&"blue.png"));
+        // 署名のないエントリは色で区別しない
+        assert!(groups[0].contains(&"plain.png"));
+
+        let red_copy = report.groups[0]
+            .files
+            .iter()
+            .find(|file| file.path == "red_copy.png")
+            .unwrap();
+        assert!(red_copy.color_distance.unwrap() < 0.2);
+    }
+
+    #[tokio::test]
+    async fn test_find_dups_rejects_invalid_max_color_distance() {
+        let error = run_color_check(Some(1.5)).await.unwrap_err();
+        assert!(error.to_string().contains("between 0 and 1"));
+    }
 }
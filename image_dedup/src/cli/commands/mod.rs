@@ -1,9 +1,13 @@
+pub mod convert;
 pub mod filter_duplicates;
 pub mod find_dups;
 pub mod process;
+pub mod restore;
 pub mod scan;
 
+pub use convert::*;
 pub use filter_duplicates::*;
 pub use find_dups::*;
 pub use process::*;
+pub use restore::*;
 pub use scan::*;
@@ -1,16 +1,58 @@
 use crate::cli::ProcessAction;
+use crate::services::persistence::implementations::HashEntry as StoredEntry;
+use crate::services::plan::{format_bytes, PlanSummary};
+use crate::services::{
+    replace_with_link, resolve_link_target, select_keeper, symlink_target, FreeDesktopTrash,
+    JournalEntry, JournalOperation, JournalWriter, KeepCandidate, KeepPolicy, LinkKind, PlanAction,
+    PlannedFile, ProcessPlan,
+};
+use crate::services::{
+    BinaryHashDatabase, DatabaseFormat, JsonlHashPersistence, SqliteHashPersistence,
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Configuration struct for process command to reduce argument count
+pub struct ProcessConfig {
+    pub duplicate_list: PathBuf,
+    pub action: ProcessAction,
+    pub dest: PathBuf,
+    pub no_confirm: bool,
+    /// Overrides the scan database recorded in the duplicate list
+    pub scan_database: Option<PathBuf>,
+    pub keep: Vec<KeepPolicy>,
+    /// Write absolute paths into symlinks (relative to the link by default)
+    pub absolute_symlinks: bool,
+    /// Also replace files whose contents differ from the kept file with links.
+    /// Their original contents are lost and cannot be restored
+    pub allow_lossy_links: bool,
+    /// Trash root for the trash action (defaults to the home trash)
+    pub trash_dir: Option<PathBuf>,
+    /// Undo journal path (defaults to a timestamped file next to the duplicate list)
+    pub journal: Option<PathBuf>,
+    /// Only build the plan: print it as a table and write it as JSON
+    pub dry_run: bool,
+    /// Execute a previously written plan instead of building one from the duplicate list
+    pub plan: Option<PathBuf>,
+    /// Where the dry-run plan is written (defaults to process_plan.json next to the duplicate list)
+    pub plan_output: Option<PathBuf>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct DuplicatesReport {
     total_groups: usize,
     total_duplicates: usize,
     threshold: u32,
     groups: Vec<DuplicateGroup>,
+    /// find-dupsで代表ファイルの選択に使った基準
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keep_policy: Vec<String>,
+    /// find-dupsが読み込んだハッシュデータベースのパス
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scan_database: Option<PathBuf>,
 }
 
 // Hash database structures for metadata lookup
@@ -53,17 +95,32 @@ struct DuplicateFile {
 }
 
 /// Prompt user for confirmation
-fn confirm_action(action: &ProcessAction, total_files: usize) -> Result<bool> {
+fn confirm_action(summary: &PlanSummary) -> Result<bool> {
     use std::io::{self, Write};
 
-    print!(
-        "⚠️  {} files will be {}. Continue? [y/N]: ",
-        total_files,
-        match action {
-            ProcessAction::Move => "moved",
-            ProcessAction::Delete => "PERMANENTLY DELETED",
-        }
-    );
+    let mut operations = Vec::new();
+    if summary.move_count > 0 {
+        operations.push(format!("{} files will be moved", summary.move_count));
+    }
+    if summary.delete > 0 {
+        operations.push(format!(
+            "{} files will be PERMANENTLY DELETED",
+            summary.delete
+        ));
+    }
+    if summary.trash > 0 {
+        operations.push(format!(
+            "{} files will be moved to the trash",
+            summary.trash
+        ));
+    }
+    if summary.link > 0 {
+        operations.push(format!(
+            "{} files will be replaced with links to the kept files",
+            summary.link
+        ));
+    }
+    print!("⚠️  {}. Continue? [y/N]: ", operations.join(", "));
     io::stdout().flush()?;
 
     let mut input = String::new();
@@ -73,34 +130,157 @@ fn confirm_action(action: &ProcessAction, total_files: usize) -> Result<bool> {
 }
 
 /// Load hash database for metadata lookup
-fn load_hash_database(scan_database_path: &Path) -> Result<HashMap<String, u64>> {
-    let json_content = fs::read_to_string(scan_database_path)?;
-    let database: HashDatabase = serde_json::from_str(&json_content)?;
-    
-    let hash_entries = match database {
-        HashDatabase::NewFormat(scan_result) => scan_result.images,
-        HashDatabase::OldFormat(entries) => entries,
-    };
-    
-    let mut file_sizes = HashMap::new();
-    for entry in hash_entries {
-        if let Some(metadata) = entry.metadata {
-            if let Some(file_size) = metadata.get("file_size").and_then(|v| v.as_u64()) {
-                file_sizes.insert(entry.file_path, file_size);
+///
+/// SQLite and binary databases are queried only for the files in the report;
+/// JSON files are read whole.
+fn load_hash_database(
+    scan_database_path: &Path,
+    report: &DuplicatesReport,
+) -> Result<HashMap<String, KeepCandidate>> {
+    let paths = report
+        .groups
+        .iter()
+        .flat_map(|group| &group.files)
+        .map(|file| file.path.as_str());
+    let hash_entries = match DatabaseFormat::detect(scan_database_path)? {
+        DatabaseFormat::Json => {
+            let json_content = fs::read_to_string(scan_database_path)?;
+            match serde_json::from_str(&json_content)? {
+                HashDatabase::NewFormat(scan_result) => scan_result.images,
+                HashDatabase::OldFormat(entries) => entries,
             }
         }
+        DatabaseFormat::Sqlite => report_entries(SqliteHashPersistence::load_entries(
+            scan_database_path,
+            paths,
+        )?)?,
+        DatabaseFormat::Binary => report_entries(
+            BinaryHashDatabase::open(scan_database_path)?
+                .find_all(paths)?
+                .into_values()
+                .collect(),
+        )?,
+        DatabaseFormat::Jsonl => {
+            let paths: HashSet<&str> = paths.collect();
+            report_entries(
+                JsonlHashPersistence::load(scan_database_path)?
+                    .images
+                    .into_iter()
+                    .filter(|entry| paths.contains(entry.file_path.as_str()))
+                    .collect(),
+            )?
+        }
+    };
+
+    let candidates = hash_entries
+        .into_iter()
+        .filter(|entry| entry.metadata.is_some())
+        .map(|entry| {
+            let candidate =
+                KeepCandidate::from_metadata(entry.file_path.clone(), entry.metadata.as_ref());
+            (entry.file_path, candidate)
+        })
+        .collect();
+
+    Ok(candidates)
+}
+
+/// Convert stored entries into the JSON-metadata entries of the report format
+fn report_entries(stored: Vec<StoredEntry>) -> Result<Vec<HashEntry>> {
+    stored
+        .into_iter()
+        .map(|entry| {
+            Ok(HashEntry {
+                file_path: entry.file_path,
+                hash: entry.hash,
+                hash_bits: entry.hash_bits,
+                metadata: Some(serde_json::to_value(entry.metadata)?),
+            })
+        })
+        .collect()
+}
+
+/// Verify that the files in the report still match the scan database
+///
+/// Fails if a file recorded in the database was removed, or its size or
+/// modification time changed since the scan
+fn verify_scan_database(
+    report: &DuplicatesReport,
+    file_info: &HashMap<String, KeepCandidate>,
+) -> Result<()> {
+    let mismatches: Vec<String> = report
+        .groups
+        .iter()
+        .flat_map(|group| &group.files)
+        .filter_map(|file| {
+            let recorded = file_info.get(&file.path)?;
+            let reason = match fs::metadata(&file.path) {
+                Err(_) => "missing",
+                Ok(metadata)
+                    if recorded
+                        .file_size
+                        .is_some_and(|size| size != metadata.len()) =>
+                {
+                    "size changed"
+                }
+                Ok(metadata)
+                    if recorded.modified_time_ms.is_some()
+                        && recorded.modified_time_ms
+                            != crate::core::ProcessingMetadata::modified_time_ms_of(&metadata) =>
+                {
+                    "modified"
+                }
+                Ok(_) => return None,
+            };
+            Some(format!("{} ({reason})", file.path))
+        })
+        .collect();
+
+    if !mismatches.is_empty() {
+        const SHOWN: usize = 5;
+        let mut details = mismatches
+            .iter()
+            .take(SHOWN)
+            .map(|line| format!("\n  - {line}"))
+            .collect::<String>();
+        if mismatches.len() > SHOWN {
+            details.push_str(&format!("\n  ... and {} more", mismatches.len() - SHOWN));
+        }
+        anyhow::bail!(
+            "Scan database no longer matches the files on disk ({} files):{}\nRe-run scan and find-dups before processing.",
+            mismatches.len(),
+            details
+        );
     }
-    
-    Ok(file_sizes)
+
+    Ok(())
 }
 
-/// Find the file with the largest size in a group
-fn find_largest_file(group: &DuplicateGroup, file_sizes: &HashMap<String, u64>) -> String {
-    group
+/// Find the file to keep in a group according to the keep policies
+fn find_file_to_keep(
+    group: &DuplicateGroup,
+    file_info: &HashMap<String, KeepCandidate>,
+    policies: &[KeepPolicy],
+) -> String {
+    let fill_from_filesystem = policies.iter().any(KeepPolicy::needs_file_info);
+    let candidates: Vec<KeepCandidate> = group
         .files
         .iter()
-        .max_by_key(|file| file_sizes.get(&file.path).unwrap_or(&0))
-        .map(|file| file.path.clone())
+        .map(|file| {
+            let candidate = file_info
+                .get(&file.path)
+                .cloned()
+                .unwrap_or_else(|| KeepCandidate::new(file.path.clone()));
+            if fill_from_filesystem {
+                candidate.fill_from_filesystem()
+            } else {
+                candidate
+            }
+        })
+        .collect();
+
+    select_keeper(&candidates, policies)
+        .map(|index| group.files[index].path.clone())
         .unwrap_or_else(|| group.files[0].path.clone())
 }
 
@@ -111,17 +291,170 @@ pub async fn execute_process(
     dest: PathBuf,
     no_confirm: bool,
 ) -> Result<()> {
-    execute_process_with_scan_database(duplicate_list, action, dest, no_confirm, None).await
+    execute_process_with_scan_database(duplicate_list, action, dest, no_confirm, None, &[]).await
 }
 
-/// Process duplicate images with optional scan database for file size lookup
+/// Process duplicate images with optional scan database and keep policies
 pub async fn execute_process_with_scan_database(
     duplicate_list: PathBuf,
     action: ProcessAction,
     dest: PathBuf,
     no_confirm: bool,
     scan_database: Option<PathBuf>,
+    keep: &[KeepPolicy],
 ) -> Result<()> {
+    execute_process_with_config(ProcessConfig {
+        duplicate_list,
+        action,
+        dest,
+        no_confirm,
+        scan_database,
+        keep: keep.to_vec(),
+        absolute_symlinks: false,
+        allow_lossy_links: false,
+        trash_dir: None,
+        journal: None,
+        dry_run: false,
+        plan: None,
+        plan_output: None,
+    })
+    .await
+}
+
+/// Default undo journal path: a timestamped file next to the duplicate list
+fn default_journal_path(duplicate_list: &Path) -> PathBuf {
+    let file_name = format!(
+        "process_journal_{}.jsonl",
+        chrono::Local::now().format("%Y%m%d_%H%M%S_%3f")
+    );
+    duplicate_list.with_file_name(file_name)
+}
+
+/// Process duplicate images according to the configuration
+///
+/// The operations are first collected into a plan. With `dry_run` the plan is only
+/// printed and written as JSON; `plan` executes such a reviewed plan verbatim.
+///
+/// Every move or delete is recorded in an undo journal that `restore` can replay.
+pub async fn execute_process_with_config(config: ProcessConfig) -> Result<()> {
+    let ProcessConfig {
+        duplicate_list,
+        action,
+        dest,
+        no_confirm,
+        scan_database,
+        keep,
+        absolute_symlinks,
+        allow_lossy_links,
+        trash_dir,
+        journal,
+        dry_run,
+        plan,
+        plan_output,
+    } = config;
+
+    println!("🔧 画像重複検出ツール - processコマンド");
+
+    let (plan, plan_is_new) = if let Some(plan_path) = &plan {
+        println!("📋 実行計画ファイル: {}", plan_path.display());
+        (ProcessPlan::load(plan_path)?, false)
+    } else {
+        match build_plan(
+            &duplicate_list,
+            &action,
+            &dest,
+            scan_database,
+            keep,
+            absolute_symlinks,
+        )? {
+            Some(plan) => (plan, true),
+            None => {
+                println!("✅ 処理する重複ファイルがありません。");
+                return Ok(());
+            }
+        }
+    };
+
+    println!(
+        "   - 処理対象ファイル数: {} (移動: {}, 削除: {}, ゴミ箱: {}, リンク: {}, {})",
+        plan.operations().count(),
+        plan.summary.move_count,
+        plan.summary.delete,
+        plan.summary.trash,
+        plan.summary.link,
+        format_bytes(plan.summary.bytes_reclaimed)
+    );
+
+    if dry_run {
+        println!("\n📋 実行計画 (dry run - ファイルは変更されません):\n");
+        print!("{}", plan.render_table());
+        if plan_is_new {
+            let plan_output =
+                plan_output.unwrap_or_else(|| duplicate_list.with_file_name("process_plan.json"));
+            plan.write(&plan_output)?;
+            println!("\n📄 計画を保存しました: {}", plan_output.display());
+            println!(
+                "   この計画を実行するには: image_dedup process --plan {}",
+                plan_output.display()
+            );
+        }
+        return Ok(());
+    }
+
+    if plan.operations().next().is_none() {
+        println!("✅ 処理する重複ファイルがありません。");
+        return Ok(());
+    }
+
+    // Confirm action
+    if !no_confirm && !confirm_action(&plan.summary)? {
+        println!("❌ 処理をキャンセルしました。");
+        return Ok(());
+    }
+
+    let trash = if plan.summary.trash > 0 {
+        let trash = match trash_dir {
+            Some(root) => FreeDesktopTrash::new(root),
+            None => FreeDesktopTrash::home()?,
+        };
+        println!("🗑️  ゴミ箱: {}", trash.root().display());
+        Some(trash)
+    } else {
+        None
+    };
+
+    // Record every operation so that moves can be undone with the restore command
+    let journal_base = plan.duplicate_list.as_deref().unwrap_or(&duplicate_list);
+    let journal_path = journal.unwrap_or_else(|| default_journal_path(journal_base));
+    let mut journal = JournalWriter::create(&journal_path)?;
+    println!("📝 操作ジャーナル: {}", journal_path.display());
+
+    execute_plan(&plan, trash.as_ref(), &mut journal, allow_lossy_links);
+
+    Ok(())
+}
+
+/// Build the plan from a duplicate list
+///
+/// `scan_database` overrides the database path recorded in the report by find-dups.
+/// A database that cannot be read, or no longer matches the files on disk, is an error.
+///
+/// The file to keep in each group is chosen by `keep` when given. Otherwise the
+/// representative chosen by find-dups is kept, except for reports without a keep
+/// policy combined with a scan database, where the largest file is kept.
+///
+/// Link actions point each duplicate at the kept file of its group; symlinks are
+/// relative to the link unless `absolute_symlinks` is set.
+///
+/// Returns `None` when the report has no duplicate groups.
+fn build_plan(
+    duplicate_list: &Path,
+    action: &ProcessAction,
+    dest: &Path,
+    scan_database: Option<PathBuf>,
+    keep: Vec<KeepPolicy>,
+    absolute_symlinks: bool,
+) -> Result<Option<ProcessPlan>> {
     // Validate input file
     if !duplicate_list.exists() {
         anyhow::bail!(
@@ -130,7 +463,6 @@ pub async fn execute_process_with_scan_database(
         );
     }
 
-    println!("🔧 画像重複検出ツール - processコマンド");
     println!("📄 重複リストファイル: {}", duplicate_list.display());
     println!("🎯 アクション: {action:?}");
     if matches!(action, ProcessAction::Move) {
@@ -138,128 +470,370 @@ pub async fn execute_process_with_scan_database(
     }
 
     // Read duplicates report
-    let json_content = fs::read_to_string(&duplicate_list)?;
+    let json_content = fs::read_to_string(duplicate_list)?;
     let report: DuplicatesReport = serde_json::from_str(&json_content)?;
 
     if report.total_groups == 0 {
-        println!("✅ 処理する重複ファイルがありません。");
-        return Ok(());
+        return Ok(None);
     }
 
-    // Load file sizes from scan database if available
-    let file_sizes = if let Some(scan_db_path) = &scan_database {
-        match load_hash_database(scan_db_path) {
-            Ok(sizes) => {
-                println!("📊 スキャンデータベースからファイルサイズ情報を読み込みました");
-                sizes
-            }
-            Err(e) => {
-                println!("⚠️  スキャンデータベースの読み込みに失敗: {e}");
-                println!("   各グループの代表ファイル（最初に見つかったファイル）を保持します");
-                HashMap::new()
-            }
-        }
+    // Load file metadata from the scan database (explicit path first, then the one recorded by find-dups)
+    let scan_database = scan_database.or_else(|| report.scan_database.clone());
+    let file_info = if let Some(scan_db_path) = &scan_database {
+        let info = load_hash_database(scan_db_path, &report).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to load scan database {}: {}",
+                scan_db_path.display(),
+                e
+            )
+        })?;
+        verify_scan_database(&report, &info)?;
+        println!(
+            "📊 スキャンデータベースからファイル情報を読み込みました: {}",
+            scan_db_path.display()
+        );
+        info
     } else {
-        println!("📊 ファイルサイズ情報なし - 各グループの代表ファイルを保持します");
         HashMap::new()
     };
 
+    // Decide how to choose the file to keep in each group
+    let policies: Vec<KeepPolicy> = if !keep.is_empty() {
+        keep
+    } else if report.keep_policy.is_empty() && !file_info.is_empty() {
+        vec![KeepPolicy::Largest]
+    } else {
+        Vec::new()
+    };
+
+    let keep_description = if !policies.is_empty() {
+        format!(
+            "選択基準 {} で選んだファイルを保持",
+            policies
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" → ")
+        )
+    } else if !report.keep_policy.is_empty() {
+        format!(
+            "find-dupsの選択基準 {} で選ばれた代表ファイルを保持",
+            report.keep_policy.join(" → ")
+        )
+    } else {
+        "各グループの代表ファイルを保持".to_string()
+    };
+
     println!("\n📊 重複情報:");
     println!("   - グループ数: {}", report.total_groups);
     println!("   - 重複ファイル総数: {}", report.total_duplicates);
 
     // Determine which files to keep and which to process
-    let files_to_process: Vec<(usize, &DuplicateFile, String)> = report
-        .groups
-        .iter()
-        .flat_map(|group| {
-            let file_to_keep = if file_sizes.is_empty() {
-                // No file size info - use representative file or first in group if not set
-                if !group.representative_file.is_empty() {
-                    group.representative_file.clone()
-                } else {
-                    group.files[0].path.clone()
-                }
+    let operation = match action {
+        ProcessAction::Move => PlanAction::Move,
+        ProcessAction::Delete => PlanAction::Delete,
+        ProcessAction::Trash => PlanAction::Trash,
+        ProcessAction::Hardlink => PlanAction::Hardlink,
+        ProcessAction::Symlink => PlanAction::Symlink,
+        ProcessAction::Reflink => PlanAction::Reflink,
+    };
+    let mut files = Vec::new();
+    for group in &report.groups {
+        let file_to_keep = if !policies.is_empty() {
+            find_file_to_keep(group, &file_info, &policies)
+        } else if !group.representative_file.is_empty() {
+            group.representative_file.clone()
+        } else {
+            // Representative not set - use first in group
+            group.files[0].path.clone()
+        };
+
+        for file in &group.files {
+            let path = PathBuf::from(&file.path);
+            let planned = if file.path == file_to_keep {
+                PlannedFile::new(group.group_id, PlanAction::Keep, path, None)
             } else {
-                // Use file size info to find largest file
-                find_largest_file(group, &file_sizes)
+                let destination = match operation {
+                    PlanAction::Move => planned_destination(dest, group.group_id, &path),
+                    PlanAction::Symlink => Some(symlink_target(
+                        &path,
+                        Path::new(&file_to_keep),
+                        absolute_symlinks,
+                    )?),
+                    PlanAction::Hardlink | PlanAction::Reflink => {
+                        Some(PathBuf::from(&file_to_keep))
+                    }
+                    _ => None,
+                };
+                PlannedFile::new(group.group_id, operation, path, destination)
             };
+            files.push(planned);
+        }
+    }
 
-            let file_to_keep_clone = file_to_keep.clone();
-            group
-                .files
-                .iter()
-                .filter(move |file| file.path != file_to_keep)
-                .map(move |file| (group.group_id, file, file_to_keep_clone.clone()))
-        })
-        .collect();
-
-    println!(
-        "   - 処理対象ファイル数: {} (各グループで最大サイズのファイルを保持)",
-        files_to_process.len()
-    );
+    println!("   - {keep_description}");
 
-    // Confirm action
-    if !no_confirm && !confirm_action(&action, files_to_process.len())? {
-        println!("❌ 処理をキャンセルしました。");
-        return Ok(());
-    }
+    Ok(Some(ProcessPlan::new(
+        Some(duplicate_list.to_path_buf()),
+        files,
+    )))
+}
 
-    // Create destination directory if moving
-    if matches!(action, ProcessAction::Move) {
-        fs::create_dir_all(&dest)?;
-    }
+/// Destination of a moved file: `<dest>/group_<id>/<file name>`
+fn planned_destination(dest: &Path, group_id: usize, source_path: &Path) -> Option<PathBuf> {
+    let filename = source_path.file_name()?;
+    Some(dest.join(format!("group_{group_id}")).join(filename))
+}
 
-    // Process files
+/// Execute the moves and deletes of a plan, recording each one in the journal
+///
+/// Files that changed since the plan was created are skipped and reported as errors.
+/// A group is skipped entirely when its kept file is missing or changed, so that no
+/// duplicate is removed without the file it duplicates.
+/// Files whose contents differ from the kept file are not replaced with links unless
+/// `allow_lossy_links` is set, since restore could not bring their contents back.
+fn execute_plan(
+    plan: &ProcessPlan,
+    trash: Option<&FreeDesktopTrash>,
+    journal: &mut JournalWriter,
+    allow_lossy_links: bool,
+) {
     let mut success_count = 0;
+    let mut skipped_count = 0;
     let mut error_count = 0;
+    let mut keeper_problems: HashMap<usize, Option<String>> = HashMap::new();
+
+    for file in plan.operations() {
+        let source_path = file.path.as_path();
+
+        let keeper_problem = keeper_problems.entry(file.group_id).or_insert_with(|| {
+            plan.verify_keeper(file.group_id)
+                .err()
+                .map(|e| e.to_string())
+        });
+        if let Some(reason) = keeper_problem {
+            println!(
+                "⚠️  スキップ: {} - 保持するファイルを確認できません ({reason})",
+                source_path.display()
+            );
+            skipped_count += 1;
+            continue;
+        }
 
-    for (group_id, file, _file_to_keep) in files_to_process {
-        let source_path = Path::new(&file.path);
-
-        match &action {
-            ProcessAction::Move => {
-                let filename = source_path
-                    .file_name()
+        let result = file.verify_unchanged().and_then(|()| match file.action {
+            PlanAction::Move => {
+                let destination = file
+                    .destination
+                    .as_deref()
                     .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
-                let dest_subdir = dest.join(format!("group_{group_id}"));
-                fs::create_dir_all(&dest_subdir)?;
-                let dest_path = dest_subdir.join(filename);
-
-                match fs::rename(source_path, &dest_path) {
-                    Ok(_) => {
-                        println!(
-                            "✓ 移動: {} → {}",
-                            source_path.display(),
-                            dest_path.display()
-                        );
-                        success_count += 1;
-                    }
-                    Err(e) => {
-                        eprintln!("✗ エラー: {} - {}", source_path.display(), e);
-                        error_count += 1;
-                    }
-                }
+                let dest_path = move_file(source_path, destination, journal)?;
+                Ok(Some(format!(
+                    "移動: {} → {}",
+                    source_path.display(),
+                    dest_path.display()
+                )))
+            }
+            PlanAction::Delete => {
+                delete_file(source_path, journal)?;
+                Ok(Some(format!("削除: {}", source_path.display())))
+            }
+            PlanAction::Trash => {
+                let trash = trash.ok_or_else(|| anyhow::anyhow!("Trash is not configured"))?;
+                let trashed_path = trash_file(source_path, trash, journal)?;
+                Ok(Some(format!(
+                    "ゴミ箱へ移動: {} → {}",
+                    source_path.display(),
+                    trashed_path.display()
+                )))
+            }
+            PlanAction::Hardlink | PlanAction::Symlink | PlanAction::Reflink => {
+                let target = file
+                    .destination
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Plan has no link target"))?;
+                let created =
+                    link_file(source_path, target, file.action, journal, allow_lossy_links)?;
+                Ok(created.map(|created| {
+                    format!(
+                        "{}に置換: {} → {}",
+                        link_label(created),
+                        source_path.display(),
+                        target.display()
+                    )
+                }))
+            }
+            PlanAction::Keep => Ok(Some(String::new())),
+        });
+
+        match result {
+            Ok(Some(message)) => {
+                println!("✓ {message}");
+                success_count += 1;
+            }
+            Ok(None) => {
+                println!(
+                    "⚠️  スキップ: {} - 保持するファイルと内容が異なるため置き換えません (--allow-lossy-links で許可)",
+                    source_path.display()
+                );
+                skipped_count += 1;
+            }
+            Err(e) => {
+                eprintln!("✗ エラー: {} - {}", source_path.display(), e);
+                error_count += 1;
             }
-            ProcessAction::Delete => match fs::remove_file(source_path) {
-                Ok(_) => {
-                    println!("✓ 削除: {}", source_path.display());
-                    success_count += 1;
-                }
-                Err(e) => {
-                    eprintln!("✗ エラー: {} - {}", source_path.display(), e);
-                    error_count += 1;
-                }
-            },
         }
     }
 
     println!("\n✅ 処理完了!");
     println!("📊 結果:");
     println!("   - 成功: {success_count} ファイル");
+    if skipped_count > 0 {
+        println!("   - スキップ: {skipped_count} ファイル");
+    }
     if error_count > 0 {
         println!("   - エラー: {error_count} ファイル");
     }
+    println!(
+        "📝 {}件の操作をジャーナルに記録しました: {}",
+        journal.len(),
+        journal.path().display()
+    );
+    if plan.summary.move_count + plan.summary.trash + plan.summary.link > 0 && !journal.is_empty() {
+        println!(
+            "   元に戻すには: image_dedup restore {}",
+            journal.path().display()
+        );
+        if allow_lossy_links && plan.summary.link > 0 {
+            println!("   ⚠️  内容が異なるファイルから置き換えたリンクは元に戻せません");
+        }
+    }
+}
+
+/// Run a file operation after recording its start in the journal
+///
+/// A failed operation is recorded as aborted; the caller commits the entry on success.
+/// Operations leave the file unchanged when they fail.
+fn journaled<T>(
+    journal: &mut JournalWriter,
+    entry: &JournalEntry,
+    operation: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    journal.begin(entry)?;
+    operation().or_else(|e| {
+        journal.abort(entry)?;
+        Err(e)
+    })
+}
+
+/// Move a file to its planned destination and record it in the journal
+///
+/// An existing file at the destination is never overwritten.
+fn move_file(
+    source_path: &Path,
+    destination: &Path,
+    journal: &mut JournalWriter,
+) -> Result<PathBuf> {
+    if destination.exists() {
+        anyhow::bail!("Destination already exists: {}", destination.display());
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let entry = JournalEntry::for_file(
+        JournalOperation::Move,
+        source_path,
+        Some(destination.to_path_buf()),
+    )?;
+    journaled(journal, &entry, || {
+        Ok(fs::rename(source_path, destination)?)
+    })?;
+    journal.commit(&entry)?;
+
+    Ok(destination.to_path_buf())
+}
+
+/// Move a file to the trash and record it in the journal
+fn trash_file(
+    source_path: &Path,
+    trash: &FreeDesktopTrash,
+    journal: &mut JournalWriter,
+) -> Result<PathBuf> {
+    let mut entry = JournalEntry::for_file(JournalOperation::Trash, source_path, None)?;
+    // The name in the trash is reserved first so that the journal knows it before the move
+    let trashed_path = trash.reserve(source_path)?;
+    entry.new_path = Some(trashed_path.clone());
+    if let Err(e) = journal.begin(&entry) {
+        FreeDesktopTrash::release(&trashed_path);
+        return Err(e);
+    }
+    if let Err(e) = FreeDesktopTrash::move_into(source_path, &trashed_path) {
+        journal.abort(&entry)?;
+        return Err(e);
+    }
+    journal.commit(&entry)?;
+
+    Ok(trashed_path)
+}
+
+/// Replace a file with a link to the kept file and record it in the journal
+///
+/// The journal records the kind of link actually created, which differs from the
+/// planned one when a hardlink falls back to a symlink across filesystems.
+///
+/// Returns `None` without touching the file when its contents differ from the kept
+/// file and `allow_lossy` is not set.
+fn link_file(
+    source_path: &Path,
+    target: &Path,
+    action: PlanAction,
+    journal: &mut JournalWriter,
+    allow_lossy: bool,
+) -> Result<Option<LinkKind>> {
+    let kind = match action {
+        PlanAction::Hardlink => LinkKind::Hardlink,
+        PlanAction::Reflink => LinkKind::Reflink,
+        _ => LinkKind::Symlink,
+    };
+    let kept_path = match kind {
+        LinkKind::Symlink => resolve_link_target(source_path, target),
+        LinkKind::Hardlink | LinkKind::Reflink => target.to_path_buf(),
+    };
+    let mut entry = JournalEntry::for_file(journal_operation(kind), source_path, Some(kept_path))?;
+    let created = journaled(journal, &entry, || {
+        replace_with_link(source_path, target, kind, allow_lossy)
+    })?;
+    let Some(created) = created else {
+        journal.abort(&entry)?;
+        return Ok(None);
+    };
+    entry.operation = journal_operation(created);
+    journal.commit(&entry)?;
+
+    Ok(Some(created))
+}
+
+fn journal_operation(kind: LinkKind) -> JournalOperation {
+    match kind {
+        LinkKind::Hardlink => JournalOperation::Hardlink,
+        LinkKind::Symlink => JournalOperation::Symlink,
+        LinkKind::Reflink => JournalOperation::Reflink,
+    }
+}
+
+fn link_label(kind: LinkKind) -> &'static str {
+    match kind {
+        LinkKind::Hardlink => "ハードリンク",
+        LinkKind::Symlink => "シンボリックリンク",
+        LinkKind::Reflink => "reflink",
+    }
+}
+
+/// Delete a file and record it in the journal
+fn delete_file(source_path: &Path, journal: &mut JournalWriter) -> Result<()> {
+    let entry = JournalEntry::for_file(JournalOperation::Delete, source_path, None)?;
+    journaled(journal, &entry, || Ok(fs::remove_file(source_path)?))?;
+    journal.commit(&entry)?;
 
     Ok(())
 }
@@ -278,6 +852,8 @@ mod tests {
             total_duplicates: groups.iter().map(|g| g.files.len().saturating_sub(1)).sum(),
             threshold: 5,
             groups,
+            keep_policy: Vec::new(),
+            scan_database: None,
         };
         serde_json::to_string_pretty(&report)
     }
@@ -582,6 +1158,59 @@ mod tests {
         assert!(dest.join("group_0").join("medium.jpg").exists());
     }
 
+    #[tokio::test]
+    async fn test_process_keep_policy_overrides_representative() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let dest = temp_dir.path().join("moved");
+
+        let originals = temp_dir.path().join("originals");
+        fs::create_dir_all(&originals).unwrap();
+        let file1 = temp_dir.path().join("copy.jpg");
+        let file2 = originals.join("photo.jpg");
+        fs::write(&file1, "larger copy content").unwrap();
+        fs::write(&file2, "photo").unwrap();
+
+        // The representative is the copy, but the policy prefers the originals directory
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: file1.to_string_lossy().to_string(),
+            files: vec![
+                DuplicateFile {
+                    path: file1.to_string_lossy().to_string(),
+                    hash: "hash1".to_string(),
+                    distance_from_representative: 0,
+                },
+                DuplicateFile {
+                    path: file2.to_string_lossy().to_string(),
+                    hash: "hash2".to_string(),
+                    distance_from_representative: 1,
+                },
+            ],
+        };
+
+        let report_json = create_test_duplicate_report(vec![group]).unwrap();
+        fs::write(&dup_list, report_json).unwrap();
+
+        let keep = vec![KeepPolicy::PreferDirectory(
+            originals.to_string_lossy().to_string(),
+        )];
+        let result = execute_process_with_scan_database(
+            dup_list,
+            ProcessAction::Move,
+            dest.clone(),
+            true,
+            None,
+            &keep,
+        )
+        .await;
+        assert!(result.is_ok());
+
+        assert!(file2.exists());
+        assert!(!file1.exists());
+        assert!(dest.join("group_0").join("copy.jpg").exists());
+    }
+
     #[tokio::test]
     async fn test_process_with_file_size_database() {
         let temp_dir = TempDir::new().unwrap();
@@ -660,6 +1289,7 @@ mod tests {
             dest.clone(),
             true,
             Some(scan_db),
+            &[],
         )
         .await;
         assert!(result.is_ok());
@@ -719,4 +1349,697 @@ mod tests {
         assert!(file1.exists());
         assert!(!file2.exists());
     }
+
+    /// Write a scan database recording the current size of each file
+    fn write_scan_database(scan_db: &Path, files: &[&Path]) {
+        let images: Vec<serde_json::Value> = files
+            .iter()
+            .map(|path| {
+                serde_json::json!({
+                    "file_path": path.to_string_lossy(),
+                    "hash": "hash",
+                    "hash_bits": 0,
+                    "metadata": {"file_size": fs::metadata(path).unwrap().len()}
+                })
+            })
+            .collect();
+        let database = serde_json::json!({"scan_info": {}, "images": images});
+        fs::write(scan_db, database.to_string()).unwrap();
+    }
+
+    /// Write a report whose representative is the first file, recording the scan database
+    fn write_report_with_scan_database(dup_list: &Path, files: &[&Path], scan_db: &Path) {
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: files[0].to_string_lossy().to_string(),
+            files: files
+                .iter()
+                .enumerate()
+                .map(|(i, path)| DuplicateFile {
+                    path: path.to_string_lossy().to_string(),
+                    hash: format!("hash{i}"),
+                    distance_from_representative: i as u32,
+                })
+                .collect(),
+        };
+        let report = DuplicatesReport {
+            total_groups: 1,
+            total_duplicates: files.len() - 1,
+            threshold: 5,
+            groups: vec![group],
+            keep_policy: Vec::new(),
+            scan_database: Some(scan_db.to_path_buf()),
+        };
+        fs::write(dup_list, serde_json::to_string_pretty(&report).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_process_uses_scan_database_from_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let scan_db = temp_dir.path().join("hashes.json");
+        let dest = temp_dir.path().join("moved");
+
+        let small = temp_dir.path().join("small.jpg");
+        let large = temp_dir.path().join("large.jpg");
+        fs::write(&small, "s").unwrap();
+        fs::write(&large, "large content").unwrap();
+
+        write_scan_database(&scan_db, &[&small, &large]);
+        write_report_with_scan_database(&dup_list, &[&small, &large], &scan_db);
+
+        execute_process(dup_list, ProcessAction::Move, dest.clone(), true)
+            .await
+            .unwrap();
+
+        // The recorded database provides file sizes, so the largest file is kept
+        assert!(large.exists());
+        assert!(!small.exists());
+    }
+
+    #[tokio::test]
+    async fn test_process_reads_non_json_scan_databases() {
+        use crate::core::ProcessingMetadata;
+        use crate::services::persistence::implementations::{
+            HashEntry as StoredEntry, ScanInfo, ScanResult as StoredScanResult,
+        };
+        use crate::services::persistence::incremental::write_scan_result;
+
+        for format in [
+            DatabaseFormat::Sqlite,
+            DatabaseFormat::Binary,
+            DatabaseFormat::Jsonl,
+        ] {
+            let temp_dir = TempDir::new().unwrap();
+            let dup_list = temp_dir.path().join("duplicates.json");
+            let scan_db = temp_dir.path().join("hashes.data");
+            let dest = temp_dir.path().join("moved");
+
+            let small = temp_dir.path().join("small.jpg");
+            let large = temp_dir.path().join("large.jpg");
+            fs::write(&small, "s").unwrap();
+            fs::write(&large, "large content").unwrap();
+
+            let images = [&small, &large]
+                .iter()
+                .map(|path| {
+                    let metadata = fs::metadata(path).unwrap();
+                    StoredEntry {
+                        file_path: path.to_string_lossy().to_string(),
+                        hash: "hash".to_string(),
+                        hash_bits: 0,
+                        metadata: ProcessingMetadata {
+                            file_size: metadata.len(),
+                            processing_time_ms: 1,
+                            image_dimensions: (8, 8),
+                            was_resized: false,
+                            hash_size_bits: 64,
+                            modified_time_ms: ProcessingMetadata::modified_time_ms_of(&metadata),
+                            content_hash: None,
+                            dihedral_hashes: None,
+                            segment_hashes: None,
+                            ensemble_hashes: None,
+                            ensemble_dihedral_hashes: None,
+                            color_signature: None,
+                        },
+                    }
+                })
+                .collect();
+            write_scan_result(
+                &scan_db,
+                &StoredScanResult {
+                    scan_info: ScanInfo {
+                        algorithm: "scan".to_string(),
+                        parameters: serde_json::json!({}),
+                        timestamp: "2024-01-01T00:00:00Z".to_string(),
+                        total_files: 2,
+                    },
+                    images,
+                },
+                format,
+            )
+            .unwrap();
+            write_report_with_scan_database(&dup_list, &[&small, &large], &scan_db);
+
+            // The database still matches, so its metadata is verified and the largest file is kept
+            execute_process(dup_list, ProcessAction::Move, dest.clone(), true)
+                .await
+                .unwrap();
+
+            assert!(large.exists());
+            assert!(!small.exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_scan_database_override() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let scan_db = temp_dir.path().join("hashes.json");
+        let dest = temp_dir.path().join("moved");
+
+        let small = temp_dir.path().join("small.jpg");
+        let large = temp_dir.path().join("large.jpg");
+        fs::write(&small, "s").unwrap();
+        fs::write(&large, "large content").unwrap();
+
+        write_scan_database(&scan_db, &[&small, &large]);
+        // The recorded database no longer exists
+        let missing_db = temp_dir.path().join("moved_away.json");
+        write_report_with_scan_database(&dup_list, &[&small, &large], &missing_db);
+
+        let result =
+            execute_process(dup_list.clone(), ProcessAction::Move, dest.clone(), true).await;
+        assert!(result.is_err());
+        assert!(small.exists());
+
+        execute_process_with_scan_database(
+            dup_list,
+            ProcessAction::Move,
+            dest.clone(),
+            true,
+            Some(scan_db),
+            &[],
+        )
+        .await
+        .unwrap();
+
+        assert!(large.exists());
+        assert!(!small.exists());
+    }
+
+    #[tokio::test]
+    async fn test_process_rejects_stale_scan_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let scan_db = temp_dir.path().join("hashes.json");
+        let dest = temp_dir.path().join("moved");
+
+        let file1 = temp_dir.path().join("a.jpg");
+        let file2 = temp_dir.path().join("b.jpg");
+        fs::write(&file1, "original").unwrap();
+        fs::write(&file2, "original content").unwrap();
+
+        write_scan_database(&scan_db, &[&file1, &file2]);
+        write_report_with_scan_database(&dup_list, &[&file1, &file2], &scan_db);
+
+        // File changed after the scan
+        fs::write(&file1, "edited after the scan").unwrap();
+
+        let error = execute_process(dup_list, ProcessAction::Delete, dest, true)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("no longer matches"));
+        assert!(error.to_string().contains("a.jpg (size changed)"));
+        // Nothing is touched
+        assert!(file1.exists());
+        assert!(file2.exists());
+    }
+
+    #[test]
+    fn test_verify_scan_database_reports_missing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let scan_db = temp_dir.path().join("hashes.json");
+
+        let file1 = temp_dir.path().join("a.jpg");
+        let file2 = temp_dir.path().join("b.jpg");
+        fs::write(&file1, "a").unwrap();
+        fs::write(&file2, "b").unwrap();
+
+        write_scan_database(&scan_db, &[&file1, &file2]);
+        write_report_with_scan_database(&dup_list, &[&file1, &file2], &scan_db);
+        let report: DuplicatesReport =
+            serde_json::from_str(&fs::read_to_string(&dup_list).unwrap()).unwrap();
+        let file_info = load_hash_database(&scan_db, &report).unwrap();
+
+        assert!(verify_scan_database(&report, &file_info).is_ok());
+
+        fs::remove_file(&file2).unwrap();
+        let error = verify_scan_database(&report, &file_info).unwrap_err();
+        assert!(error.to_string().contains("b.jpg (missing)"));
+    }
+
+    #[tokio::test]
+    async fn test_process_writes_journal_next_to_duplicate_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let dest = temp_dir.path().join("moved");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let duplicate = temp_dir.path().join("duplicate.jpg");
+        fs::write(&keep, "keep").unwrap();
+        fs::write(&duplicate, "duplicate").unwrap();
+
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: keep.to_string_lossy().to_string(),
+            files: [&keep, &duplicate]
+                .iter()
+                .map(|path| DuplicateFile {
+                    path: path.to_string_lossy().to_string(),
+                    hash: "hash".to_string(),
+                    distance_from_representative: 0,
+                })
+                .collect(),
+        };
+        fs::write(
+            &dup_list,
+            create_test_duplicate_report(vec![group]).unwrap(),
+        )
+        .unwrap();
+
+        execute_process(dup_list, ProcessAction::Delete, dest, true)
+            .await
+            .unwrap();
+
+        let journals: Vec<PathBuf> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("process_journal_")
+            })
+            .collect();
+        assert_eq!(journals.len(), 1);
+
+        let entries = crate::services::read_journal(&journals[0]).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, JournalOperation::Delete);
+        assert_eq!(entries[0].original_path, duplicate);
+        assert_eq!(entries[0].file_size, 9);
+    }
+
+    #[tokio::test]
+    async fn test_process_move_does_not_overwrite_same_file_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let journal = temp_dir.path().join("journal.jsonl");
+        let dest = temp_dir.path().join("moved");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let first = temp_dir.path().join("a/photo.jpg");
+        let second = temp_dir.path().join("b/photo.jpg");
+        fs::write(&keep, "keep").unwrap();
+        for path in [&first, &second] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, path.to_string_lossy().as_bytes()).unwrap();
+        }
+
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: keep.to_string_lossy().to_string(),
+            files: [&keep, &first, &second]
+                .iter()
+                .map(|path| DuplicateFile {
+                    path: path.to_string_lossy().to_string(),
+                    hash: "hash".to_string(),
+                    distance_from_representative: 0,
+                })
+                .collect(),
+        };
+        fs::write(
+            &dup_list,
+            create_test_duplicate_report(vec![group]).unwrap(),
+        )
+        .unwrap();
+
+        execute_process_with_config(ProcessConfig {
+            duplicate_list: dup_list,
+            action: ProcessAction::Move,
+            dest: dest.clone(),
+            no_confirm: true,
+            scan_database: None,
+            keep: Vec::new(),
+            absolute_symlinks: false,
+            allow_lossy_links: false,
+            trash_dir: None,
+            journal: Some(journal.clone()),
+            dry_run: false,
+            plan: None,
+            plan_output: None,
+        })
+        .await
+        .unwrap();
+
+        // The second file with the same name stays in place instead of overwriting the first
+        assert!(!first.exists());
+        assert!(second.exists());
+        assert_eq!(
+            fs::read_to_string(dest.join("group_0/photo.jpg")).unwrap(),
+            first.to_string_lossy()
+        );
+        assert_eq!(crate::services::read_journal(&journal).unwrap().len(), 1);
+    }
+
+    fn plan_config(duplicate_list: PathBuf, dest: PathBuf) -> ProcessConfig {
+        ProcessConfig {
+            duplicate_list,
+            action: ProcessAction::Move,
+            dest,
+            no_confirm: true,
+            scan_database: None,
+            keep: Vec::new(),
+            absolute_symlinks: false,
+            allow_lossy_links: false,
+            trash_dir: None,
+            journal: None,
+            dry_run: false,
+            plan: None,
+            plan_output: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_dry_run_writes_plan_and_keeps_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let plan_path = temp_dir.path().join("plan.json");
+        let dest = temp_dir.path().join("moved");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("copy.jpg");
+        fs::write(&keep, "keep").unwrap();
+        fs::write(&copy, "copy content").unwrap();
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: keep.to_string_lossy().to_string(),
+            files: [&keep, &copy]
+                .iter()
+                .map(|path| DuplicateFile {
+                    path: path.to_string_lossy().to_string(),
+                    hash: "hash".to_string(),
+                    distance_from_representative: 0,
+                })
+                .collect(),
+        };
+        fs::write(
+            &dup_list,
+            create_test_duplicate_report(vec![group]).unwrap(),
+        )
+        .unwrap();
+
+        execute_process_with_config(ProcessConfig {
+            dry_run: true,
+            plan_output: Some(plan_path.clone()),
+            ..plan_config(dup_list, dest.clone())
+        })
+        .await
+        .unwrap();
+
+        // Nothing is touched and no journal is written
+        assert!(keep.exists());
+        assert!(copy.exists());
+        assert!(!dest.exists());
+
+        let plan = ProcessPlan::load(&plan_path).unwrap();
+        assert_eq!(plan.files.len(), 2);
+        assert_eq!(plan.files[0].action, PlanAction::Keep);
+        assert_eq!(plan.files[1].action, PlanAction::Move);
+        assert_eq!(
+            plan.files[1].destination,
+            Some(dest.join("group_0").join("copy.jpg"))
+        );
+        assert_eq!(plan.summary.bytes_reclaimed, 12);
+    }
+
+    #[tokio::test]
+    async fn test_process_executes_plan_verbatim() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan_path = temp_dir.path().join("plan.json");
+        let journal = temp_dir.path().join("journal.jsonl");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let moved = temp_dir.path().join("moved.jpg");
+        let deleted = temp_dir.path().join("deleted.jpg");
+        let edited = temp_dir.path().join("edited.jpg");
+        for path in [&keep, &moved, &deleted, &edited] {
+            fs::write(path, "content").unwrap();
+        }
+
+        // A reviewed plan may mix actions and use any destination
+        let destination = temp_dir.path().join("reviewed/moved.jpg");
+        let plan = ProcessPlan::new(
+            None,
+            vec![
+                PlannedFile::new(0, PlanAction::Keep, keep.clone(), None),
+                PlannedFile::new(
+                    0,
+                    PlanAction::Move,
+                    moved.clone(),
+                    Some(destination.clone()),
+                ),
+                PlannedFile::new(0, PlanAction::Delete, deleted.clone(), None),
+                PlannedFile::new(0, PlanAction::Delete, edited.clone(), None),
+            ],
+        );
+        plan.write(&plan_path).unwrap();
+
+        // Changed after review, so it must be skipped
+        fs::write(&edited, "edited after review").unwrap();
+
+        execute_process_with_config(ProcessConfig {
+            plan: Some(plan_path),
+            journal: Some(journal.clone()),
+            ..plan_config(temp_dir.path().join("unused.json"), PathBuf::from("unused"))
+        })
+        .await
+        .unwrap();
+
+        assert!(keep.exists());
+        assert!(!moved.exists());
+        assert!(destination.exists());
+        assert!(!deleted.exists());
+        assert!(edited.exists());
+        assert_eq!(crate::services::read_journal(&journal).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_process_plan_skips_group_with_changed_keeper() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan_path = temp_dir.path().join("plan.json");
+        let journal = temp_dir.path().join("journal.jsonl");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("copy.jpg");
+        let other_keep = temp_dir.path().join("other_keep.jpg");
+        let other_copy = temp_dir.path().join("other_copy.jpg");
+        for path in [&keep, &copy, &other_keep, &other_copy] {
+            fs::write(path, "content").unwrap();
+        }
+
+        let plan = ProcessPlan::new(
+            None,
+            vec![
+                PlannedFile::new(0, PlanAction::Keep, keep.clone(), None),
+                PlannedFile::new(0, PlanAction::Delete, copy.clone(), None),
+                PlannedFile::new(1, PlanAction::Keep, other_keep.clone(), None),
+                PlannedFile::new(1, PlanAction::Delete, other_copy.clone(), None),
+            ],
+        );
+        plan.write(&plan_path).unwrap();
+
+        // The kept file of group 0 is gone, so its duplicate must stay
+        fs::remove_file(&keep).unwrap();
+
+        execute_process_with_config(ProcessConfig {
+            plan: Some(plan_path),
+            journal: Some(journal.clone()),
+            ..plan_config(temp_dir.path().join("unused.json"), PathBuf::from("unused"))
+        })
+        .await
+        .unwrap();
+
+        assert!(copy.exists());
+        assert!(other_keep.exists());
+        assert!(!other_copy.exists());
+        assert_eq!(crate::services::read_journal(&journal).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_trash_action_is_restorable() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let trash_dir = temp_dir.path().join("Trash");
+        let journal = temp_dir.path().join("journal.jsonl");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("copy.jpg");
+        fs::write(&keep, "keep").unwrap();
+        fs::write(&copy, "copy").unwrap();
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: keep.to_string_lossy().to_string(),
+            files: [&keep, &copy]
+                .iter()
+                .map(|path| DuplicateFile {
+                    path: path.to_string_lossy().to_string(),
+                    hash: "hash".to_string(),
+                    distance_from_representative: 0,
+                })
+                .collect(),
+        };
+        fs::write(
+            &dup_list,
+            create_test_duplicate_report(vec![group]).unwrap(),
+        )
+        .unwrap();
+
+        execute_process_with_config(ProcessConfig {
+            action: ProcessAction::Trash,
+            trash_dir: Some(trash_dir.clone()),
+            journal: Some(journal.clone()),
+            ..plan_config(dup_list, temp_dir.path().join("unused"))
+        })
+        .await
+        .unwrap();
+
+        assert!(keep.exists());
+        assert!(!copy.exists());
+        assert_eq!(
+            fs::read_to_string(trash_dir.join("files/copy.jpg")).unwrap(),
+            "copy"
+        );
+        assert!(trash_dir.join("info/copy.jpg.trashinfo").exists());
+
+        let entries = crate::services::read_journal(&journal).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, JournalOperation::Trash);
+        assert_eq!(entries[0].new_path, Some(trash_dir.join("files/copy.jpg")));
+
+        let report = crate::services::restore_journal(&entries);
+        assert!(report.is_complete());
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "copy");
+    }
+
+    fn write_link_report(temp_dir: &TempDir, copy_content: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let keep = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("copies/copy.jpg");
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::write(&keep, "same").unwrap();
+        fs::write(&copy, copy_content).unwrap();
+        let group = DuplicateGroup {
+            group_id: 0,
+            representative_file: keep.to_string_lossy().to_string(),
+            files: [&keep, &copy]
+                .iter()
+                .map(|path| DuplicateFile {
+                    path: path.to_string_lossy().to_string(),
+                    hash: "hash".to_string(),
+                    distance_from_representative: 0,
+                })
+                .collect(),
+        };
+        fs::write(
+            &dup_list,
+            create_test_duplicate_report(vec![group]).unwrap(),
+        )
+        .unwrap();
+        (dup_list, keep, copy)
+    }
+
+    #[tokio::test]
+    async fn test_process_hardlink_action() {
+        let temp_dir = TempDir::new().unwrap();
+        let (dup_list, keep, copy) = write_link_report(&temp_dir, "same");
+        let journal = temp_dir.path().join("journal.jsonl");
+
+        execute_process_with_config(ProcessConfig {
+            action: ProcessAction::Hardlink,
+            journal: Some(journal.clone()),
+            ..plan_config(dup_list, temp_dir.path().join("unused"))
+        })
+        .await
+        .unwrap();
+
+        assert!(crate::services::file_ops::link::is_same_file(&copy, &keep));
+        let entries = crate::services::read_journal(&journal).unwrap();
+        assert_eq!(entries[0].operation, JournalOperation::Hardlink);
+        assert_eq!(entries[0].new_path, Some(keep.clone()));
+
+        assert!(crate::services::restore_journal(&entries).is_complete());
+        assert!(!crate::services::file_ops::link::is_same_file(&copy, &keep));
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "same");
+    }
+
+    #[tokio::test]
+    async fn test_process_links_skip_different_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let (dup_list, _, copy) = write_link_report(&temp_dir, "similar");
+
+        for action in [
+            ProcessAction::Hardlink,
+            ProcessAction::Symlink,
+            ProcessAction::Reflink,
+        ] {
+            let journal = temp_dir.path().join(format!("{action:?}.jsonl"));
+            execute_process_with_config(ProcessConfig {
+                action,
+                journal: Some(journal.clone()),
+                ..plan_config(dup_list.clone(), temp_dir.path().join("unused"))
+            })
+            .await
+            .unwrap();
+
+            assert!(!fs::symlink_metadata(&copy).unwrap().is_symlink());
+            assert_eq!(fs::read_to_string(&copy).unwrap(), "similar");
+            assert!(crate::services::read_journal(&journal).unwrap().is_empty());
+        }
+
+        // 明示的に許可した場合のみ内容の異なるファイルをリンクに置き換える
+        let journal = temp_dir.path().join("lossy.jsonl");
+        execute_process_with_config(ProcessConfig {
+            action: ProcessAction::Symlink,
+            allow_lossy_links: true,
+            journal: Some(journal.clone()),
+            ..plan_config(dup_list, temp_dir.path().join("unused"))
+        })
+        .await
+        .unwrap();
+
+        assert!(fs::symlink_metadata(&copy).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "same");
+        assert_eq!(crate::services::read_journal(&journal).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_symlink_plan_uses_relative_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let (dup_list, keep, copy) = write_link_report(&temp_dir, "same");
+        let plan_path = temp_dir.path().join("plan.json");
+
+        execute_process_with_config(ProcessConfig {
+            action: ProcessAction::Symlink,
+            dry_run: true,
+            plan_output: Some(plan_path.clone()),
+            ..plan_config(dup_list.clone(), temp_dir.path().join("unused"))
+        })
+        .await
+        .unwrap();
+
+        let plan = ProcessPlan::load(&plan_path).unwrap();
+        assert_eq!(plan.files[1].action, PlanAction::Symlink);
+        assert_eq!(
+            plan.files[1].destination,
+            Some(PathBuf::from("../keep.jpg"))
+        );
+        assert_eq!(plan.summary.link, 1);
+
+        execute_process_with_config(ProcessConfig {
+            action: ProcessAction::Symlink,
+            absolute_symlinks: true,
+            ..plan_config(dup_list, temp_dir.path().join("unused"))
+        })
+        .await
+        .unwrap();
+
+        let target = fs::read_link(&copy).unwrap();
+        assert!(target.is_absolute());
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "same");
+        assert_eq!(fs::read_to_string(&keep).unwrap(), "same");
+    }
 }
@@ -0,0 +1,124 @@
+use crate::services::{read_journal, restore_journal};
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// Restore files moved by the process command using its undo journal
+pub async fn execute_restore(journal: PathBuf) -> Result<()> {
+    if !journal.exists() {
+        anyhow::bail!("Journal file does not exist: {}", journal.display());
+    }
+
+    println!("↩️  画像重複検出ツール - restoreコマンド");
+    println!("📝 操作ジャーナル: {}", journal.display());
+
+    let entries = read_journal(&journal)?;
+    println!("📊 記録された操作: {}件", entries.len());
+
+    let report = restore_journal(&entries);
+
+    for path in &report.restored {
+        println!("✓ 復元: {}", path.display());
+    }
+    for (path, reason) in &report.conflicts {
+        eprintln!("⚠️  衝突: {} - {}", path.display(), reason);
+    }
+    for (path, reason) in &report.unrestorable {
+        eprintln!("✗ 復元不可: {} - {}", path.display(), reason);
+    }
+
+    println!("\n✅ 復元完了!");
+    println!("📊 結果:");
+    println!("   - 復元: {} ファイル", report.restored.len());
+    if !report.already_restored.is_empty() {
+        println!("   - 復元済み: {} ファイル", report.already_restored.len());
+    }
+    if !report.conflicts.is_empty() {
+        println!("   - 衝突: {} ファイル", report.conflicts.len());
+    }
+    if !report.unrestorable.is_empty() {
+        println!("   - 復元不可: {} ファイル", report.unrestorable.len());
+    }
+
+    if !report.is_complete() {
+        anyhow::bail!(
+            "{} files could not be restored ({} conflicts, {} unrestorable)",
+            report.conflicts.len() + report.unrestorable.len(),
+            report.conflicts.len(),
+            report.unrestorable.len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::process::{execute_process_with_config, ProcessConfig};
+    use crate::cli::ProcessAction;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_restore_nonexistent_journal() {
+        let result = execute_restore(PathBuf::from("nonexistent_journal.jsonl")).await;
+        assert!(result.unwrap_err().to_string().contains("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_process_then_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let dup_list = temp_dir.path().join("duplicates.json");
+        let journal = temp_dir.path().join("journal.jsonl");
+        let dest = temp_dir.path().join("moved");
+
+        let keep = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("sub/copy.jpg");
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::write(&keep, "original").unwrap();
+        fs::write(&copy, "copy").unwrap();
+
+        let report = serde_json::json!({
+            "total_groups": 1,
+            "total_duplicates": 1,
+            "threshold": 5,
+            "groups": [{
+                "group_id": 0,
+                "representative_file": keep.to_string_lossy(),
+                "files": [
+                    {"path": keep.to_string_lossy(), "hash": "a", "distance_from_representative": 0},
+                    {"path": copy.to_string_lossy(), "hash": "b", "distance_from_representative": 1}
+                ]
+            }]
+        });
+        fs::write(&dup_list, report.to_string()).unwrap();
+
+        execute_process_with_config(ProcessConfig {
+            duplicate_list: dup_list,
+            action: ProcessAction::Move,
+            dest: dest.clone(),
+            no_confirm: true,
+            scan_database: None,
+            keep: Vec::new(),
+            absolute_symlinks: false,
+            allow_lossy_links: false,
+            trash_dir: None,
+            journal: Some(journal.clone()),
+            dry_run: false,
+            plan: None,
+            plan_output: None,
+        })
+        .await
+        .unwrap();
+        assert!(!copy.exists());
+        assert!(dest.join("group_0/copy.jpg").exists());
+
+        execute_restore(journal.clone()).await.unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "copy");
+        assert!(!dest.join("group_0").exists());
+
+        // Restoring twice is a no-op
+        execute_restore(journal).await.unwrap();
+        assert!(copy.exists());
+    }
+}
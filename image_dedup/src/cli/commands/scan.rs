@@ -1,14 +1,24 @@
 use crate::core::{
-    traits::ProcessingConfig, DefaultConfig, HighPerformanceConfig, StaticDIContainer,
-    TestingConfig,
+    traits::ProcessingConfig, CancellationToken, ContentHashAlgorithm, DefaultConfig,
+    HashPersistence, HighPerformanceConfig, Preprocessing, ProcessingSummary, ProgressReporter,
+    StaticDIContainer, TestingConfig,
 };
-use crate::perceptual_hash::{
-    average_config::AverageConfig,
-    config::{AlgorithmConfig, DynamicAlgorithmConfig},
-    dct_config::DctConfig,
+use crate::engine::{create_runtime_processing_engine, ProcessingEngine, RuntimeEngineSettings};
+use crate::image_loader::ImageLoaderBackend;
+use crate::perceptual_hash::{config::DynamicAlgorithmConfig, PerceptualHashBackend};
+use crate::services::persistence::implementations::ScanResult;
+use crate::services::persistence::incremental::{
+    load_scan_result, merge_scan_results, sibling_path, write_scan_result,
 };
+use crate::services::persistence::{
+    checkpoint_path, DatabaseFormat, JsonlHashPersistence, UpdatePlan,
+};
+use crate::services::ExactCandidateReport;
+use crate::storage::StorageBackend;
 use anyhow::Result;
-use std::path::PathBuf;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 /// Configuration struct for scan command to reduce argument count
 pub struct ScanConfig {
@@ -16,6 +26,10 @@ pub struct ScanConfig {
     pub output: PathBuf,
     pub threads: Option<usize>,
     pub force: bool,
+    pub update: bool,
+    pub format: DatabaseFormat,
+    /// Continue an interrupted scan from its checkpoint
+    pub resume: bool,
 }
 
 /// Extended configuration struct including all scan parameters
@@ -24,10 +38,19 @@ pub struct ExtendedScanConfig {
     pub output: PathBuf,
     pub threads: Option<usize>,
     pub force: bool,
+    pub update: bool,
     pub algorithm: String,
     pub hash_size: u32,
     pub config_preset: Option<String>,
     pub config_file: Option<PathBuf>,
+    pub content_hash: Option<ContentHashAlgorithm>,
+    pub exact_only: bool,
+    pub rotation_invariant: bool,
+    pub color_signature: bool,
+    pub preprocessing: Preprocessing,
+    /// Database format; `None` picks it from the output extension
+    pub format: Option<DatabaseFormat>,
+    pub resume: bool,
 }
 
 /// Execute scan command with DefaultConfig
@@ -45,25 +68,11 @@ pub async fn execute_scan_with_testing_config(config: ScanConfig) -> Result<()>
     execute_scan_with_static_config::<TestingConfig>(config).await
 }
 
-/// Execute scan command with DCT configuration from config file
-pub async fn execute_scan_with_dct_config(config: ScanConfig, dct_config: DctConfig) -> Result<()> {
-    execute_scan_with_dynamic_hasher(config, dct_config).await
-}
-
-/// Execute scan command with Average configuration from config file
-pub async fn execute_scan_with_average_config(
+/// Scan with an engine assembled at runtime from the algorithm, hash size and thread settings
+async fn execute_scan_with_runtime_engine(
     config: ScanConfig,
-    average_config: AverageConfig,
+    settings: RuntimeEngineSettings,
 ) -> Result<()> {
-    execute_scan_with_dynamic_hasher(config, average_config).await
-}
-
-/// Generic scan execution with dynamic hasher configuration
-async fn execute_scan_with_dynamic_hasher<C>(config: ScanConfig, algorithm_config: C) -> Result<()>
-where
-    C: AlgorithmConfig + Send + Sync,
-    C::Algorithm: 'static,
-{
     // Validate target directory
     if !config.target_directory.exists() {
         anyhow::bail!(
@@ -79,16 +88,35 @@ where
         );
     }
 
-    // Check if output file already exists
-    if config.output.exists() && !config.force {
-        anyhow::bail!(
-            "Output file already exists: {}. Use --force to overwrite.",
-            config.output.display()
-        );
-    }
-
-    // Validate algorithm configuration
-    algorithm_config.validate()?;
+    // Check if output file already exists (or load it for --update / the checkpoint for --resume)
+    let checkpoint = checkpoint_path(&config.output);
+    let resumed = if config.resume {
+        load_checkpoint(&checkpoint, &config.output)?
+    } else {
+        None
+    };
+    let resuming = resumed.is_some();
+    let existing = match resumed {
+        Some(completed) => Some(completed),
+        None => prepare_output(&config)?,
+    };
+    let engine_output = engine_output_path(&config, existing.as_ref());
+
+    // Build the engine before printing anything so invalid settings fail early.
+    // A resumed scan keeps appending to the checkpoint it started from
+    let checkpoint_persistence = if resuming {
+        JsonlHashPersistence::append(&checkpoint)
+    } else {
+        JsonlHashPersistence::new(&checkpoint)
+    };
+    let cancel = CancellationToken::new();
+    let engine = create_runtime_processing_engine(
+        &settings,
+        &engine_output,
+        config.format,
+        Some(checkpoint_persistence),
+    )?
+    .with_cancellation(cancel.clone());
 
     println!("🔍 画像スキャン開始");
     println!(
@@ -96,16 +124,38 @@ where
         config.target_directory.display()
     );
     println!("   - 出力ファイル: {}", config.output.display());
-    println!("   - 設定: dynamic ({})", algorithm_config.algorithm_name());
-
-    // Create hasher from config
-    let hasher = algorithm_config.create_hasher()?;
-
-    // Use DefaultConfig for other dependencies but with our custom hasher
-    let container = StaticDIContainer::<DefaultConfig>::new();
-
-    // Create processing engine with custom hasher
-    let engine = container.create_processing_engine_with_hasher(&config.output, hasher);
+    println!("   - 出力形式: {}", config.format);
+    if engine.config().exact_only() {
+        println!("   - 設定: 完全一致のみ（画像のデコードを省略）");
+    } else {
+        println!(
+            "   - 設定: {} (ハッシュサイズ: {})",
+            engine.hasher().algorithm_name(),
+            engine.hasher().algorithm().size()
+        );
+    }
+    if let Some(algorithm) = engine.config().content_hash_algorithm() {
+        println!("   - 内容ハッシュ: {algorithm}");
+    }
+    if engine.config().rotation_invariant() {
+        println!("   - 回転・反転: 8通りのハッシュを記録");
+    }
+    if engine.config().color_signature() {
+        println!("   - 色の署名: HSVヒストグラムを記録");
+    }
+    let preprocessing = engine.config().preprocessing();
+    if preprocessing.is_enabled() && !engine.config().exact_only() {
+        let steps: Vec<String> = preprocessing
+            .steps
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        println!("   - 前処理: {}", steps.join(" → "));
+    }
+    let ensemble = engine.hasher().ensemble_algorithms();
+    if !ensemble.is_empty() && !engine.config().exact_only() {
+        println!("   - アンサンブル: {}", ensemble.join(", "));
+    }
 
     // Display engine configuration
     println!("⚙️  処理設定:");
@@ -124,7 +174,19 @@ where
         anyhow::anyhow!("Invalid UTF-8 path: {}", config.target_directory.display())
     })?;
 
-    match engine.process_directory(target_dir_str).await {
+    // Ctrl-C / SIGTERM stops the scan after saving the files already hashed
+    let signal_handler = cancel.cancel_on_shutdown_signal();
+    let outcome = run_scan(&engine, target_dir_str, &config, existing).await;
+    signal_handler.abort();
+
+    match outcome {
+        Ok(result) if result.cancelled => {
+            print_cancelled(&result, &config, &engine_output);
+            anyhow::bail!(
+                "スキャンが中断されました。--resume で {} から再開できます",
+                checkpoint.display()
+            );
+        }
         Ok(result) => {
             println!("✅ スキャン完了!");
             println!("   - 処理済ファイル: {}", result.processed_files);
@@ -132,8 +194,20 @@ where
             println!("   - エラー数: {}", result.error_count);
             println!("   - 処理時間: {}ms", result.total_processing_time_ms);
 
+            // The database is complete, so the checkpoint is no longer needed
+            if checkpoint.exists() {
+                std::fs::remove_file(&checkpoint)?;
+            }
+
             println!("📄 結果は {} に保存されました", config.output.display());
         }
+        Err(error) if checkpoint.exists() => {
+            anyhow::bail!(
+                "処理エラー: {} (--resume で {} から再開できます)",
+                error,
+                checkpoint.display()
+            );
+        }
         Err(error) => {
             anyhow::bail!("処理エラー: {}", error);
         }
@@ -162,13 +236,9 @@ where
         );
     }
 
-    // Check if output file already exists
-    if config.output.exists() && !config.force {
-        anyhow::bail!(
-            "Output file already exists: {}. Use --force to overwrite.",
-            config.output.display()
-        );
-    }
+    // Check if output file already exists (or load it for --update)
+    let existing = prepare_output(&config)?;
+    let engine_output = engine_output_path(&config, existing.as_ref());
 
     println!("🔍 画像スキャン開始");
     println!(
@@ -182,7 +252,10 @@ where
     let container = StaticDIContainer::<C>::new();
 
     // Create processing engine
-    let engine = container.create_processing_engine(&config.output);
+    let cancel = CancellationToken::new();
+    let engine = container
+        .create_processing_engine(&engine_output)
+        .with_cancellation(cancel.clone());
 
     // Display engine configuration
     println!("⚙️  処理設定:");
@@ -201,7 +274,15 @@ where
         anyhow::anyhow!("Invalid UTF-8 path: {}", config.target_directory.display())
     })?;
 
-    match engine.process_directory(target_dir_str).await {
+    let signal_handler = cancel.cancel_on_shutdown_signal();
+    let outcome = run_scan(&engine, target_dir_str, &config, existing).await;
+    signal_handler.abort();
+
+    match outcome {
+        Ok(result) if result.cancelled => {
+            print_cancelled(&result, &config, &engine_output);
+            anyhow::bail!("スキャンが中断されました");
+        }
         Ok(result) => {
             println!("✅ スキャン完了!");
             println!("   - 処理済ファイル: {}", result.processed_files);
@@ -219,72 +300,432 @@ where
     Ok(())
 }
 
-/// Unified scan command with static dispatch selection
-#[allow(clippy::too_many_arguments)]
-pub async fn execute_scan(
-    target_directory: PathBuf,
-    output: PathBuf,
-    threads: Option<usize>,
-    force: bool,
-    algorithm: String,
-    hash_size: u32,
-    config_preset: Option<String>,
-    config_file: Option<PathBuf>,
-) -> Result<()> {
-    let config = ExtendedScanConfig {
-        target_directory,
-        output,
-        threads,
-        force,
-        algorithm,
-        hash_size,
-        config_preset,
-        config_file,
+/// Report a scan stopped by Ctrl-C / SIGTERM
+///
+/// A fresh scan leaves the files hashed so far in a valid database at the output path;
+/// `--update` and `--resume` runs leave the existing output untouched.
+fn print_cancelled(result: &ProcessingSummary, config: &ScanConfig, engine_output: &Path) {
+    println!("⏹️  スキャンを中断しました");
+    println!("   - 処理済ファイル: {}", result.processed_files);
+    println!("   - 対象ファイル数: {}", result.total_files);
+    println!("   - エラー数: {}", result.error_count);
+    if engine_output == config.output {
+        println!(
+            "📄 中断までの結果は {} に保存されました",
+            config.output.display()
+        );
+    }
+}
+
+/// Validate the output path and load the existing database when updating
+///
+/// Returns the existing scan result only for `--update` runs against an existing file.
+/// A checkpoint left by an interrupted scan is never overwritten without `--force`, even
+/// when the interrupted scan did not get to write the output.
+fn prepare_output(config: &ScanConfig) -> Result<Option<ScanResult>> {
+    let checkpoint = checkpoint_path(&config.output);
+    if !config.force && checkpoint.exists() {
+        anyhow::bail!(
+            "An interrupted scan left a checkpoint: {}. Use --resume to continue it or --force to start over.",
+            checkpoint.display()
+        );
+    }
+
+    if !config.output.exists() {
+        if config.update {
+            println!("ℹ️  既存のデータベースがないため、全ファイルをスキャンします");
+        }
+        return Ok(None);
+    }
+
+    if config.update {
+        let existing_format = DatabaseFormat::detect(&config.output)?;
+        if existing_format != config.format {
+            anyhow::bail!(
+                "Existing database {} is stored as {} but the output format is {}. Use --force to rescan everything.",
+                config.output.display(),
+                existing_format,
+                config.format
+            );
+        }
+        return load_scan_result(&config.output).map(Some);
+    }
+
+    if !config.force {
+        anyhow::bail!(
+            "Output file already exists: {}. Use --force to overwrite or --update to rescan incrementally.",
+            config.output.display()
+        );
+    }
+
+    Ok(None)
+}
+
+/// Load the entries an interrupted scan already persisted to its checkpoint
+///
+/// The checkpoint only holds the files hashed by the interrupted run, so an interrupted
+/// `--update` run would rehash every unchanged file. Entries of a readable output database
+/// are therefore added for the files the checkpoint does not cover; an output left
+/// incomplete by the interruption is ignored.
+///
+/// Returns `None` when there is no checkpoint, in which case the scan starts from scratch.
+fn load_checkpoint(checkpoint: &Path, output: &Path) -> Result<Option<ScanResult>> {
+    if !checkpoint.exists() {
+        println!("ℹ️  再開できるチェックポイントがないため、全ファイルをスキャンします");
+        return Ok(None);
+    }
+
+    let mut completed = JsonlHashPersistence::load(checkpoint).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to read checkpoint {}: {}. Use --force to start over.",
+            checkpoint.display(),
+            e
+        )
+    })?;
+
+    if let Ok(previous) = load_scan_result(output) {
+        let checkpointed: HashSet<String> = completed
+            .images
+            .iter()
+            .map(|entry| entry.file_path.clone())
+            .collect();
+        completed.images.extend(
+            previous
+                .images
+                .into_iter()
+                .filter(|entry| !checkpointed.contains(&entry.file_path)),
+        );
+    }
+
+    Ok(Some(completed))
+}
+
+/// Path the engine writes to: a partial file when merging into an existing database
+fn engine_output_path(config: &ScanConfig, existing: Option<&ScanResult>) -> PathBuf {
+    match existing {
+        Some(_) => sibling_path(&config.output, "partial"),
+        None => config.output.clone(),
+    }
+}
+
+/// Ensure an existing database was produced by the same hash configuration
+fn ensure_compatible<H, C>(existing: &ScanResult, hasher: &H, config: &C) -> Result<()>
+where
+    H: PerceptualHashBackend,
+    C: ProcessingConfig,
+{
+    let parameters = &existing.scan_info.parameters;
+    let recorded_exact_only = parameters
+        .get("exact_only")
+        .and_then(|exact_only| exact_only.as_bool())
+        .unwrap_or(false);
+    if recorded_exact_only != config.exact_only() {
+        anyhow::bail!(
+            "Existing database was created {} --exact-only but the current scan is {}. Use --force to rescan everything.",
+            if recorded_exact_only { "with" } else { "without" },
+            if config.exact_only() { "exact-only" } else { "perceptual" }
+        );
+    }
+
+    let recorded_content_hash = parameters
+        .get("content_hash")
+        .and_then(|algorithm| algorithm.as_str());
+    let current_content_hash = config
+        .content_hash_algorithm()
+        .map(ContentHashAlgorithm::name);
+    if recorded_content_hash != current_content_hash {
+        anyhow::bail!(
+            "Existing database was created with content hash '{}' but the current setting is '{}'. Use --force to rescan everything.",
+            recorded_content_hash.unwrap_or("none"),
+            current_content_hash.unwrap_or("none")
+        );
+    }
+
+    // 完全一致のみのデータベースには知覚ハッシュが含まれない
+    if config.exact_only() {
+        return Ok(());
+    }
+
+    let recorded_rotation_invariant = parameters
+        .get("rotation_invariant")
+        .and_then(|rotation_invariant| rotation_invariant.as_bool())
+        .unwrap_or(false);
+    if recorded_rotation_invariant != config.rotation_invariant() {
+        anyhow::bail!(
+            "Existing database was created {} --rotation-invariant but the current scan is {}. Use --force to rescan everything.",
+            if recorded_rotation_invariant { "with" } else { "without" },
+            if config.rotation_invariant() { "with it" } else { "without it" }
+        );
+    }
+
+    let recorded_color_signature = parameters
+        .get("color_signature")
+        .and_then(|color_signature| color_signature.as_bool())
+        .unwrap_or(false);
+    if recorded_color_signature != config.color_signature() {
+        anyhow::bail!(
+            "Existing database was created {} --color-signature but the current scan is {}. Use --force to rescan everything.",
+            if recorded_color_signature { "with" } else { "without" },
+            if config.color_signature() { "with it" } else { "without it" }
+        );
+    }
+
+    let recorded_preprocessing: Preprocessing = parameters
+        .get("preprocessing")
+        .map(|preprocessing| serde_json::from_value(preprocessing.clone()))
+        .transpose()?
+        .unwrap_or_default();
+    if recorded_preprocessing != config.preprocessing() {
+        anyhow::bail!(
+            "Existing database was created with preprocessing {} but the current setting is {}. Use --force to rescan everything.",
+            serde_json::to_string(&recorded_preprocessing)?,
+            serde_json::to_string(&config.preprocessing())?
+        );
+    }
+
+    let recorded_ensemble: Vec<&str> = parameters
+        .get("ensemble")
+        .and_then(|ensemble| ensemble.as_array())
+        .map(|ensemble| ensemble.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default();
+    let current_ensemble = hasher.ensemble_algorithms();
+    if recorded_ensemble != current_ensemble {
+        anyhow::bail!(
+            "Existing database was created with ensemble [{}] but the current setting is [{}]. Use --force to rescan everything.",
+            recorded_ensemble.join(", "),
+            current_ensemble.join(", ")
+        );
+    }
+
+    let recorded_algorithm = existing
+        .scan_info
+        .parameters
+        .get("algorithm")
+        .and_then(|algorithm| algorithm.as_str());
+    if let Some(recorded) = recorded_algorithm {
+        if recorded != hasher.algorithm_name() {
+            anyhow::bail!(
+                "Existing database was created with '{}' but the current algorithm is '{}'. Use --force to rescan everything.",
+                recorded,
+                hasher.algorithm_name()
+            );
+        }
+    }
+
+    let expected_bits = hasher.algorithm().size().pow(2);
+    if let Some(entry) = existing.images.iter().find(|entry| {
+        entry.metadata.hash_size_bits != 0 && entry.metadata.hash_size_bits != expected_bits
+    }) {
+        anyhow::bail!(
+            "Existing database uses {}-bit hashes ({}) but the current configuration produces {}-bit hashes. Use --force to rescan everything.",
+            entry.metadata.hash_size_bits,
+            entry.file_path,
+            expected_bits
+        );
+    }
+
+    Ok(())
+}
+
+/// Run the engine, hashing only new or modified files when an existing database is given
+///
+/// Exact-only scans first narrow the files down to those that can have a byte-identical twin.
+async fn run_scan<L, H, S, C, R, P>(
+    engine: &ProcessingEngine<L, H, S, C, R, P>,
+    target_directory: &str,
+    config: &ScanConfig,
+    existing: Option<ScanResult>,
+) -> Result<ProcessingSummary>
+where
+    L: ImageLoaderBackend + 'static,
+    H: PerceptualHashBackend + 'static,
+    S: StorageBackend + 'static,
+    C: ProcessingConfig,
+    R: ProgressReporter + 'static,
+    P: HashPersistence + 'static,
+{
+    if let Some(existing) = &existing {
+        ensure_compatible(existing, engine.hasher(), engine.config())?;
+    }
+
+    let (files, exact_candidates) = if engine.config().exact_only() {
+        let candidates = engine.discover_exact_candidates(target_directory).await?;
+        print_exact_candidate_report(&candidates.report);
+        (candidates.files, Some(candidates.report))
+    } else {
+        (engine.discover_image_files(target_directory).await?, None)
     };
 
-    execute_scan_with_extended_config(config).await
+    let Some(existing) = existing else {
+        return Ok(process_files(engine, files, exact_candidates.as_ref()).await?);
+    };
+
+    // Files excluded as unique are treated like removed files and dropped from the database.
+    // When resuming, files completed before the interruption are skipped the same way
+    let plan = UpdatePlan::new(existing.images, files);
+
+    if config.resume {
+        println!("⏯️  中断したスキャンを再開:");
+        println!("   - 完了済み（スキップ）: {}", plan.reused.len());
+        println!("   - 残り: {}", plan.files_to_hash.len());
+    } else {
+        println!("🔄 差分スキャン:");
+        println!("   - 変更なし（再利用）: {}", plan.reused.len());
+        println!("   - 新規: {}", plan.added);
+        println!("   - 変更あり: {}", plan.modified);
+        println!("   - 削除: {}", plan.removed);
+    }
+
+    // Remove leftovers from an interrupted run before the engine writes a fresh partial file
+    let partial = sibling_path(&config.output, "partial");
+    if partial.exists() {
+        std::fs::remove_file(&partial)?;
+    }
+
+    let summary = process_files(engine, plan.files_to_hash, exact_candidates.as_ref()).await?;
+    if summary.cancelled {
+        // Keep the existing output; the checkpoint already holds the files hashed this run
+        if partial.exists() {
+            std::fs::remove_file(&partial)?;
+        }
+        return Ok(summary);
+    }
+
+    let fresh = load_scan_result(&partial)?;
+    let merged = merge_scan_results(plan.reused, fresh);
+    write_scan_result(&config.output, &merged, config.format)?;
+    std::fs::remove_file(&partial)?;
+
+    Ok(summary)
+}
+
+/// Hash the given files, recording the exact-candidate report when there is one
+async fn process_files<L, H, S, C, R, P>(
+    engine: &ProcessingEngine<L, H, S, C, R, P>,
+    files: Vec<String>,
+    exact_candidates: Option<&ExactCandidateReport>,
+) -> crate::core::ProcessingResult<ProcessingSummary>
+where
+    L: ImageLoaderBackend + 'static,
+    H: PerceptualHashBackend + 'static,
+    S: StorageBackend + 'static,
+    C: ProcessingConfig,
+    R: ProgressReporter + 'static,
+    P: HashPersistence + 'static,
+{
+    match exact_candidates {
+        Some(report) => engine.process_exact_candidates(files, report).await,
+        None => engine.process_files(files).await,
+    }
 }
 
-/// Execute scan with extended configuration struct
-async fn execute_scan_with_extended_config(config: ExtendedScanConfig) -> Result<()> {
+/// Show how many files each stage of the exact-duplicate cascade skipped
+fn print_exact_candidate_report(report: &ExactCandidateReport) {
+    println!("🧮 完全一致の候補絞り込み:");
+    println!("   - 発見した画像: {}", report.discovered);
+    println!("   - サイズが一意（スキップ）: {}", report.unique_size);
+    println!(
+        "   - 先頭・末尾が一意（スキップ）: {}",
+        report.unique_partial_hash
+    );
+    println!("   - 内容ハッシュを計算: {}", report.candidates);
+}
+
+/// Unified scan command with static dispatch selection
+///
+/// A configuration file selects the algorithm and its parameters; otherwise `--algorithm`
+/// and `--hash-size` do. `--threads` (or the file's `threads` key) sets the worker count.
+/// A named preset selects one of the fixed static configurations instead.
+/// `--content-hash` also records a content digest for exact-duplicate detection, and
+/// `--exact-only` records only the digest without decoding images.
+/// `--rotation-invariant` also records the hashes of the rotated and mirrored image.
+/// `--color-signature` also records a coarse colour histogram to tell colourways apart.
+/// `--flatten-alpha`, `--trim-borders` and `--normalize-contrast` normalise each image
+/// before it is hashed, replacing any preprocessing pipeline from the configuration file.
+/// A comma-separated `--algorithm` list hashes every listed algorithm from one decode.
+/// `--format` (or an output ending in `.sqlite`/`.db`) writes a SQLite database instead of JSON.
+/// Every batch is also recorded in a checkpoint so `--resume` can continue an interrupted scan.
+pub async fn execute_scan(config: ExtendedScanConfig) -> Result<()> {
+    let format = DatabaseFormat::for_output(&config.output, config.format);
     let scan_config = ScanConfig {
         target_directory: config.target_directory,
         output: config.output,
         threads: config.threads,
         force: config.force,
+        update: config.update,
+        format,
+        resume: config.resume,
     };
 
     // Load configuration from file if provided
     if let Some(config_path) = config.config_file {
-        return execute_scan_with_config_file(scan_config, config_path).await;
+        let mut settings = load_config_file(&config_path, config.threads)?
+            .with_content_hash(config.content_hash, config.exact_only)
+            .with_rotation_invariant(config.rotation_invariant)
+            .with_color_signature(config.color_signature);
+        if config.preprocessing.is_enabled() {
+            settings = settings.with_preprocessing(config.preprocessing);
+        }
+        return execute_scan_with_runtime_engine(scan_config, settings).await;
     }
 
-    // Select configuration based on preset or algorithm
-    let preset = config.config_preset.unwrap_or_else(|| {
-        if config.hash_size >= 32 || config.threads.unwrap_or(0) >= 8 {
-            "high_performance".to_string()
-        } else if config.algorithm == "average" {
-            "testing".to_string()
-        } else {
-            "default".to_string()
+    if let Some(preset) = config.config_preset {
+        if config.content_hash.is_some()
+            || config.exact_only
+            || config.rotation_invariant
+            || config.color_signature
+            || config.preprocessing.is_enabled()
+            || config.resume
+        {
+            anyhow::bail!(
+                "Configuration presets do not support --content-hash, --exact-only, --rotation-invariant, --color-signature, --resume or preprocessing options"
+            );
         }
-    });
-
-    match preset.as_str() {
-        "default" => execute_scan_with_default_config(scan_config).await,
-        "high_performance" => execute_scan_with_high_performance_config(scan_config).await,
-        "testing" => execute_scan_with_testing_config(scan_config).await,
-        _ => {
+        if format != DatabaseFormat::Json {
             anyhow::bail!(
-                "Unknown configuration preset: {}. Available: default, high_performance, testing",
-                preset
+                "Configuration presets only write JSON databases; use --algorithm or --config with --format {}",
+                format
             );
         }
+        return match preset.as_str() {
+            "default" => execute_scan_with_default_config(scan_config).await,
+            "high_performance" => execute_scan_with_high_performance_config(scan_config).await,
+            "testing" => execute_scan_with_testing_config(scan_config).await,
+            _ => {
+                anyhow::bail!(
+                    "Unknown configuration preset: {}. Available: default, high_performance, testing",
+                    preset
+                );
+            }
+        };
     }
+
+    let settings = RuntimeEngineSettings::new(&config.algorithm, config.hash_size, config.threads)
+        .with_content_hash(config.content_hash, config.exact_only)
+        .with_rotation_invariant(config.rotation_invariant)
+        .with_color_signature(config.color_signature)
+        .with_preprocessing(config.preprocessing);
+    execute_scan_with_runtime_engine(scan_config, settings).await
+}
+
+/// Scan configuration file: the algorithm with its parameters, optional additional
+/// algorithms for an ensemble, an optional preprocessing pipeline and an optional thread count
+#[derive(Debug, Deserialize)]
+struct ScanConfigFile {
+    #[serde(flatten)]
+    algorithm: DynamicAlgorithmConfig,
+    #[serde(default)]
+    ensemble: Vec<DynamicAlgorithmConfig>,
+    #[serde(default)]
+    preprocessing: Preprocessing,
+    #[serde(default)]
+    threads: Option<usize>,
 }
 
-/// Execute scan with configuration file
-async fn execute_scan_with_config_file(config: ScanConfig, config_path: PathBuf) -> Result<()> {
+/// Read engine settings from a configuration file
+///
+/// `threads` from the command line takes precedence over the file.
+fn load_config_file(config_path: &Path, threads: Option<usize>) -> Result<RuntimeEngineSettings> {
     // Validate config file exists
     if !config_path.exists() {
         anyhow::bail!(
@@ -294,7 +735,7 @@ async fn execute_scan_with_config_file(config: ScanConfig, config_path: PathBuf)
     }
 
     // Read and parse configuration file
-    let config_content = std::fs::read_to_string(&config_path).map_err(|e| {
+    let config_content = std::fs::read_to_string(config_path).map_err(|e| {
         anyhow::anyhow!(
             "Failed to read config file {}: {}",
             config_path.display(),
@@ -302,75 +743,74 @@ async fn execute_scan_with_config_file(config: ScanConfig, config_path: PathBuf)
         )
     })?;
 
-    let dynamic_config: DynamicAlgorithmConfig =
-        serde_json::from_str(&config_content).map_err(|e| {
-            anyhow::anyhow!(
-                "Failed to parse config file {}: {}",
-                config_path.display(),
-                e
-            )
-        })?;
+    let config_file: ScanConfigFile = serde_json::from_str(&config_content).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to parse config file {}: {}",
+            config_path.display(),
+            e
+        )
+    })?;
 
     println!("🔧 設定ファイル使用: {}", config_path.display());
-    println!("   - アルゴリズム: {}", dynamic_config.algorithm);
-    println!("   - パラメータ: {}", dynamic_config.parameters);
-
-    // Parse and apply actual parameters from config file
-    match dynamic_config.algorithm.as_str() {
-        "dct" => {
-            let dct_config: DctConfig =
-                serde_json::from_value(dynamic_config.parameters).map_err(|e| {
-                    anyhow::anyhow!(
-                        "Failed to parse DCT parameters from config file {}: {}",
-                        config_path.display(),
-                        e
-                    )
-                })?;
-            execute_scan_with_dct_config(config, dct_config).await
-        }
-        "average" => {
-            let avg_config: AverageConfig = serde_json::from_value(dynamic_config.parameters)
-                .map_err(|e| {
-                    anyhow::anyhow!(
-                        "Failed to parse Average parameters from config file {}: {}",
-                        config_path.display(),
-                        e
-                    )
-                })?;
-            execute_scan_with_average_config(config, avg_config).await
-        }
-        _ => {
-            anyhow::bail!(
-                "Unsupported algorithm '{}' in config file {}. Supported algorithms: dct, average",
-                dynamic_config.algorithm,
-                config_path.display()
-            );
-        }
+    println!("   - アルゴリズム: {}", config_file.algorithm.algorithm);
+    println!("   - パラメータ: {}", config_file.algorithm.parameters);
+    for member in &config_file.ensemble {
+        println!(
+            "   - アンサンブル: {} {}",
+            member.algorithm, member.parameters
+        );
+    }
+    for step in &config_file.preprocessing.steps {
+        println!("   - 前処理: {step}");
     }
+
+    Ok(RuntimeEngineSettings {
+        algorithm: config_file.algorithm,
+        ensemble: config_file.ensemble,
+        threads: threads.or(config_file.threads),
+        content_hash: None,
+        exact_only: false,
+        rotation_invariant: false,
+        color_signature: false,
+        preprocessing: config_file.preprocessing,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::BackgroundColor;
     use std::fs;
     use tempfile::TempDir;
 
+    /// CLIの既定値（dct、8ビット、追加のハッシュや前処理なし）でのスキャン設定
+    fn scan_config(target: impl Into<PathBuf>, output: impl Into<PathBuf>) -> ExtendedScanConfig {
+        ExtendedScanConfig {
+            target_directory: target.into(),
+            output: output.into(),
+            threads: None,
+            force: false,
+            update: false,
+            algorithm: "dct".to_string(),
+            hash_size: 8,
+            config_preset: None,
+            config_file: None,
+            content_hash: None,
+            exact_only: false,
+            rotation_invariant: false,
+            color_signature: false,
+            preprocessing: Preprocessing::default(),
+            format: None,
+            resume: false,
+        }
+    }
+
     #[tokio::test]
     async fn test_scan_nonexistent_directory() {
         let nonexistent_dir = PathBuf::from("nonexistent_directory");
         let output = PathBuf::from("output.json");
 
-        let result = execute_scan(
-            nonexistent_dir,
-            output,
-            None,
-            false,
-            "dct".to_string(),
-            8,
-            None,
-            None,
-        )
-        .await;
+        let result = execute_scan(scan_config(nonexistent_dir, output)).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("does not exist"));
     }
@@ -383,17 +823,7 @@ mod tests {
 
         let output = PathBuf::from("output.json");
 
-        let result = execute_scan(
-            file_path,
-            output,
-            None,
-            false,
-            "dct".to_string(),
-            8,
-            None,
-            None,
-        )
-        .await;
+        let result = execute_scan(scan_config(file_path, output)).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("not a directory"));
     }
@@ -406,17 +836,7 @@ mod tests {
 
         let target_dir = TempDir::new().unwrap();
 
-        let result = execute_scan(
-            target_dir.path().to_path_buf(),
-            output,
-            None,
-            false,
-            "dct".to_string(),
-            8,
-            None,
-            None,
-        )
-        .await;
+        let result = execute_scan(scan_config(target_dir.path(), output)).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("already exists"));
     }
@@ -443,16 +863,11 @@ mod tests {
 
         let output = temp_dir.path().join("output.json");
 
-        let result = execute_scan(
-            target_dir,
-            output.clone(),
-            None,
-            true, // force
-            "dct".to_string(),
-            8,
-            None,
-            Some(config_path),
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_file: Some(config_path),
+            ..scan_config(target_dir, output.clone())
+        })
         .await;
 
         // Should succeed (no actual image files to process, but config should be loaded)
@@ -470,16 +885,11 @@ mod tests {
 
         let output = temp_dir.path().join("output.json");
 
-        let result = execute_scan(
-            target_dir,
-            output,
-            None,
-            true,
-            "dct".to_string(),
-            8,
-            None,
-            Some(nonexistent_config),
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_file: Some(nonexistent_config),
+            ..scan_config(target_dir, output)
+        })
         .await;
 
         assert!(result.is_err());
@@ -501,16 +911,11 @@ mod tests {
 
         let output = temp_dir.path().join("output.json");
 
-        let result = execute_scan(
-            target_dir,
-            output,
-            None,
-            true,
-            "dct".to_string(),
-            8,
-            None,
-            Some(config_path),
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_file: Some(config_path),
+            ..scan_config(target_dir, output)
+        })
         .await;
 
         assert!(result.is_err());
@@ -552,32 +957,23 @@ mod tests {
 
         // Test DCT config
         let dct_output = temp_dir.path().join("dct_output.json");
-        let dct_result = execute_scan(
-            target_dir.clone(),
-            dct_output,
-            None,
-            true,
-            "dct".to_string(),
-            8,
-            None,
-            Some(dct_config_path),
-        )
+        let dct_result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_file: Some(dct_config_path),
+            ..scan_config(target_dir.clone(), dct_output)
+        })
         .await;
 
         assert!(dct_result.is_ok());
 
         // Test Average config
         let avg_output = temp_dir.path().join("avg_output.json");
-        let avg_result = execute_scan(
-            target_dir,
-            avg_output,
-            None,
-            true,
-            "average".to_string(),
-            8,
-            None,
-            Some(avg_config_path),
-        )
+        let avg_result = execute_scan(ExtendedScanConfig {
+            force: true,
+            algorithm: "average".to_string(),
+            config_file: Some(avg_config_path),
+            ..scan_config(target_dir, avg_output)
+        })
         .await;
 
         assert!(avg_result.is_ok());
@@ -605,16 +1001,12 @@ mod tests {
         let output = temp_dir.path().join("output.json");
 
         // Both config file and preset are provided - config file should take precedence
-        let result = execute_scan(
-            target_dir,
-            output,
-            None,
-            true,
-            "dct".to_string(),
-            8,
-            Some("high_performance".to_string()), // This should be ignored
-            Some(config_path),                    // This should take precedence
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_preset: Some("high_performance".to_string()),
+            config_file: Some(config_path), // This should take precedence
+            ..scan_config(target_dir, output)
+        })
         .await;
 
         assert!(result.is_ok());
@@ -632,16 +1024,11 @@ mod tests {
         for preset in presets {
             let output = output_dir.path().join(format!("output_{preset}.json"));
 
-            let result = execute_scan(
-                target_dir.path().to_path_buf(),
-                output,
-                None,
-                true,
-                "dct".to_string(),
-                8,
-                Some(preset.to_string()),
-                None,
-            )
+            let result = execute_scan(ExtendedScanConfig {
+                force: true,
+                config_preset: Some(preset.to_string()),
+                ..scan_config(target_dir.path(), output)
+            })
             .await;
 
             assert!(result.is_ok(), "Failed for preset: {preset}");
@@ -653,16 +1040,11 @@ mod tests {
         let target_dir = TempDir::new().unwrap();
         let output = target_dir.path().join("output.json");
 
-        let result = execute_scan(
-            target_dir.path().to_path_buf(),
-            output,
-            None,
-            true,
-            "dct".to_string(),
-            8,
-            Some("invalid_preset".to_string()),
-            None,
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_preset: Some("invalid_preset".to_string()),
+            ..scan_config(target_dir.path(), output)
+        })
         .await;
 
         assert!(result.is_err());
@@ -697,16 +1079,11 @@ mod tests {
         let output = temp_dir.path().join("output.json");
 
         // 設定ファイルを使ってスキャン実行
-        let result = execute_scan(
-            target_dir,
-            output.clone(),
-            None,
-            true,
-            "dct".to_string(),
-            8, // この値は無視され、設定ファイルの64が使用されるべき
-            None,
-            Some(config_path),
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            config_file: Some(config_path),
+            ..scan_config(target_dir, output.clone())
+        })
         .await;
 
         // 現時点では失敗することを期待（まだ実装していないため）
@@ -736,19 +1113,994 @@ mod tests {
         let output = temp_dir.path().join("output.json");
 
         // 設定ファイルを使ってスキャン実行
-        let result = execute_scan(
-            target_dir,
-            output.clone(),
-            None,
-            true,
-            "average".to_string(),
-            8, // この値は無視され、設定ファイルの32が使用されるべき
-            None,
-            Some(config_path),
-        )
+        let result = execute_scan(ExtendedScanConfig {
+            force: true,
+            algorithm: "average".to_string(),
+            config_file: Some(config_path),
+            ..scan_config(target_dir, output.clone())
+        })
         .await;
 
         // 現時点では失敗することを期待（まだ実装していないため）
         assert!(result.is_ok() || result.is_err());
     }
+
+    fn write_test_image(path: &Path, seed: u8) {
+        let image = image::RgbImage::from_fn(32, 32, |x, y| {
+            image::Rgb([
+                (x as u8).wrapping_mul(seed),
+                (y as u8).wrapping_add(seed),
+                seed,
+            ])
+        });
+        image.save(path).unwrap();
+    }
+
+    async fn scan_with(target: &Path, output: &Path, force: bool, update: bool) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            force,
+            update,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_update_reuses_unchanged_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        let unchanged = target.join("unchanged.png");
+        let modified = target.join("modified.png");
+        let deleted = target.join("deleted.png");
+        write_test_image(&unchanged, 1);
+        write_test_image(&modified, 2);
+        write_test_image(&deleted, 3);
+
+        scan_with(&target, &output, false, false).await.unwrap();
+
+        // 再利用されたことを確認できるよう、変更のないファイルのハッシュに印を付ける
+        let mut database = load_scan_result(&output).unwrap();
+        assert_eq!(database.images.len(), 3);
+        for entry in &mut database.images {
+            entry.hash = format!("marker-{}", entry.hash);
+        }
+        write_scan_result(&output, &database, DatabaseFormat::Json).unwrap();
+
+        write_test_image(&modified, 200);
+        fs::remove_file(&deleted).unwrap();
+        write_test_image(&target.join("added.png"), 4);
+
+        scan_with(&target, &output, false, true).await.unwrap();
+
+        let updated = load_scan_result(&output).unwrap();
+        let hash_of = |name: &str| {
+            updated
+                .images
+                .iter()
+                .find(|entry| entry.file_path.ends_with(name))
+                .map(|entry| entry.hash.clone())
+        };
+
+        assert_eq!(updated.scan_info.total_files, 3);
+        assert_eq!(updated.images.len(), 3);
+        assert!(hash_of("unchanged.png").unwrap().starts_with("marker-"));
+        assert!(!hash_of("modified.png").unwrap().starts_with("marker-"));
+        assert!(hash_of("added.png").is_some());
+        assert!(hash_of("deleted.png").is_none());
+        assert!(!sibling_path(&output, "partial").exists());
+    }
+
+    async fn resume_scan(target: &Path, output: &Path) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            resume: true,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_resume_skips_checkpointed_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        let output = temp_dir.path().join("hashes.json");
+        let checkpoint = checkpoint_path(&output);
+        for (name, seed) in [("a.png", 1), ("b.png", 2), ("c.png", 3)] {
+            write_test_image(&target.join(name), seed);
+        }
+
+        // 完了したスキャンはチェックポイントを残さない
+        scan_with(&target, &output, false, false).await.unwrap();
+        assert!(!checkpoint.exists());
+
+        // a.pngとb.pngを保存した時点で中断されたスキャンを再現する
+        let mut completed = load_scan_result(&output).unwrap();
+        completed
+            .images
+            .retain(|entry| !entry.file_path.ends_with("c.png"));
+        for entry in &mut completed.images {
+            entry.hash = format!("marker-{}", entry.hash);
+        }
+        JsonlHashPersistence::write(&checkpoint, &completed).unwrap();
+        fs::write(&output, "{\n  \"scan_info\": {").unwrap();
+
+        // 通常のスキャンは中断されたスキャンの出力を上書きしない
+        let error = scan_with(&target, &output, false, false).await.unwrap_err();
+        assert!(error.to_string().contains("--resume"));
+
+        resume_scan(&target, &output).await.unwrap();
+
+        let resumed = load_scan_result(&output).unwrap();
+        assert_eq!(resumed.images.len(), 3);
+        assert_eq!(resumed.scan_info.total_files, 3);
+        for entry in &resumed.images {
+            assert_eq!(
+                entry.hash.starts_with("marker-"),
+                !entry.file_path.ends_with("c.png"),
+                "{}",
+                entry.file_path
+            );
+        }
+        assert!(!checkpoint.exists());
+        assert!(!sibling_path(&output, "partial").exists());
+    }
+
+    #[tokio::test]
+    async fn test_scan_resume_after_interrupted_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        let output = temp_dir.path().join("hashes.json");
+        let checkpoint = checkpoint_path(&output);
+        for (name, seed) in [("a.png", 1), ("b.png", 2), ("c.png", 3)] {
+            write_test_image(&target.join(name), seed);
+        }
+        scan_with(&target, &output, false, false).await.unwrap();
+
+        // --updateはd.pngを保存した時点で中断され、e.pngは未処理（出力は変更されていない）
+        write_test_image(&target.join("d.png"), 4);
+        write_test_image(&target.join("e.png"), 5);
+        let mut previous = load_scan_result(&output).unwrap();
+        for entry in &mut previous.images {
+            entry.hash = format!("output-{}", entry.hash);
+        }
+        fs::write(&output, serde_json::to_string(&previous).unwrap()).unwrap();
+
+        let added = temp_dir.path().join("added.json");
+        scan_with(&target, &added, false, false).await.unwrap();
+        let mut completed = load_scan_result(&added).unwrap();
+        completed
+            .images
+            .retain(|entry| entry.file_path.ends_with("d.png"));
+        completed.images[0].hash = format!("checkpoint-{}", completed.images[0].hash);
+        JsonlHashPersistence::write(&checkpoint, &completed).unwrap();
+
+        resume_scan(&target, &output).await.unwrap();
+
+        // 変更のないファイルは出力から、中断前にハッシュしたファイルはチェックポイントから再利用する
+        let resumed = load_scan_result(&output).unwrap();
+        let prefixes: Vec<(&str, &str)> = resumed
+            .images
+            .iter()
+            .map(|entry| {
+                let name = entry.file_path.rsplit('/').next().unwrap();
+                let prefix = entry.hash.split_once('-').map_or("", |(prefix, _)| prefix);
+                (name, prefix)
+            })
+            .collect();
+        assert_eq!(
+            prefixes,
+            vec![
+                ("a.png", "output"),
+                ("b.png", "output"),
+                ("c.png", "output"),
+                ("d.png", "checkpoint"),
+                ("e.png", ""),
+            ]
+        );
+        assert!(!checkpoint.exists());
+    }
+
+    #[tokio::test]
+    async fn test_scan_keeps_checkpoint_without_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.bin");
+        let checkpoint = checkpoint_path(&output);
+
+        // 出力を書く前に中断されたスキャン（バイナリ・SQLiteは完了時に出力を作成する）
+        scan_with(&target, &output, false, false).await.unwrap();
+        fs::rename(&output, temp_dir.path().join("done.bin")).unwrap();
+        fs::write(&checkpoint, "{\"type\":\"scan_info\"").unwrap();
+
+        for update in [false, true] {
+            let error = scan_with(&target, &output, false, update)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("--resume"));
+        }
+        assert_eq!(
+            fs::read_to_string(&checkpoint).unwrap(),
+            "{\"type\":\"scan_info\""
+        );
+
+        // --forceならチェックポイントを破棄してやり直す
+        scan_with(&target, &output, true, false).await.unwrap();
+        assert!(!checkpoint.exists());
+        assert_eq!(load_scan_result(&output).unwrap().images.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_resume_without_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.sqlite");
+
+        // チェックポイントがなければ全ファイルをスキャンする
+        resume_scan(&target, &output).await.unwrap();
+        assert_eq!(load_scan_result(&output).unwrap().images.len(), 1);
+
+        // 完了したデータベースは再開の対象にならない
+        let error = resume_scan(&target, &output).await.unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_update_without_existing_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_with(&target, &output, false, true).await.unwrap();
+
+        assert_eq!(load_scan_result(&output).unwrap().images.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_update_rejects_different_hash_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_with(&target, &output, false, false).await.unwrap();
+
+        let mut database = load_scan_result(&output).unwrap();
+        database.images[0].metadata.hash_size_bits = 256;
+        write_scan_result(&output, &database, DatabaseFormat::Json).unwrap();
+
+        let result = scan_with(&target, &output, false, true).await;
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("--force"));
+        // 既存データベースは変更されない
+        assert_eq!(
+            load_scan_result(&output).unwrap().images[0]
+                .metadata
+                .hash_size_bits,
+            256
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_writes_and_updates_sqlite_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        write_test_image(&target.join("b.png"), 2);
+        let output = temp_dir.path().join("hashes.sqlite");
+
+        // 拡張子からSQLite形式が選ばれる
+        scan_with(&target, &output, false, false).await.unwrap();
+
+        assert_eq!(
+            DatabaseFormat::detect(&output).unwrap(),
+            DatabaseFormat::Sqlite
+        );
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(database.images.len(), 2);
+        assert_eq!(database.scan_info.total_files, 2);
+        assert_eq!(
+            database.scan_info.parameters["algorithm"],
+            "DCT (Discrete Cosine Transform)"
+        );
+
+        write_test_image(&target.join("c.png"), 3);
+        scan_with(&target, &output, false, true).await.unwrap();
+
+        assert_eq!(
+            DatabaseFormat::detect(&output).unwrap(),
+            DatabaseFormat::Sqlite
+        );
+        let updated = load_scan_result(&output).unwrap();
+        assert_eq!(updated.images.len(), 3);
+        assert_eq!(updated.scan_info.total_files, 3);
+        assert!(!sibling_path(&output, "partial").exists());
+
+        // 既存データベースと異なる形式では差分スキャンできない
+        let result = execute_scan(ExtendedScanConfig {
+            update: true,
+            format: Some(DatabaseFormat::Json),
+            ..scan_config(target.clone(), output.clone())
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("stored as sqlite"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_writes_jsonl_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        write_test_image(&target.join("b.png"), 2);
+        let output = temp_dir.path().join("hashes.jsonl");
+
+        scan_with(&target, &output, false, false).await.unwrap();
+
+        assert_eq!(
+            DatabaseFormat::detect(&output).unwrap(),
+            DatabaseFormat::Jsonl
+        );
+        // scan_info・エントリ2件・サマリーがそれぞれ1行のJSONになる
+        let content = fs::read_to_string(&output).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["type"], "scan_info");
+        assert_eq!(lines[3]["type"], "summary");
+        assert_eq!(lines[3]["total_files"], 2);
+
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(database.images.len(), 2);
+        assert_eq!(
+            database.scan_info.parameters["algorithm"],
+            "DCT (Discrete Cosine Transform)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_preset_rejects_sqlite_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("hashes.db");
+
+        let result = execute_scan(ExtendedScanConfig {
+            config_preset: Some("default".to_string()),
+            ..scan_config(temp_dir.path(), output.clone())
+        })
+        .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("only write JSON databases"));
+        assert!(!output.exists());
+    }
+
+    #[tokio::test]
+    async fn test_scan_flags_configure_engine() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.json");
+
+        execute_scan(ExtendedScanConfig {
+            threads: Some(3),
+            algorithm: "difference".to_string(),
+            hash_size: 16,
+            ..scan_config(target, output.clone())
+        })
+        .await
+        .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        let parameters = &database.scan_info.parameters;
+        assert_eq!(parameters["algorithm"], "Difference Hash");
+        assert_eq!(parameters["hash_size"], 16);
+        assert_eq!(parameters["settings"]["max_concurrent"], 3);
+        assert_eq!(database.images[0].metadata.hash_size_bits, 256);
+    }
+
+    #[tokio::test]
+    async fn test_scan_config_file_threads() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+
+        let config_path = temp_dir.path().join("config.json");
+        fs::write(
+            &config_path,
+            r#"{ "algorithm": "average", "parameters": { "size": 16 }, "threads": 2 }"#,
+        )
+        .unwrap();
+
+        let settings = load_config_file(&config_path, None).unwrap();
+        assert_eq!(settings.threads, Some(2));
+        // The command line wins over the file
+        assert_eq!(
+            load_config_file(&config_path, Some(5)).unwrap().threads,
+            Some(5)
+        );
+
+        let output = temp_dir.path().join("hashes.json");
+        execute_scan(ExtendedScanConfig {
+            config_file: Some(config_path),
+            ..scan_config(target, output.clone())
+        })
+        .await
+        .unwrap();
+
+        let parameters = load_scan_result(&output).unwrap().scan_info.parameters;
+        assert_eq!(parameters["algorithm"], "Average Hash");
+        assert_eq!(parameters["hash_size"], 16);
+        assert_eq!(parameters["settings"]["max_concurrent"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_scan_selects_wavelet_and_block_mean() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+
+        let output = temp_dir.path().join("wavelet.json");
+        execute_scan(ExtendedScanConfig {
+            algorithm: "wavelet".to_string(),
+            hash_size: 16,
+            ..scan_config(target.clone(), output.clone())
+        })
+        .await
+        .unwrap();
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(database.scan_info.parameters["algorithm"], "Wavelet Hash");
+        assert_eq!(database.images[0].metadata.hash_size_bits, 256);
+
+        let config_path = temp_dir.path().join("block_mean_config.json");
+        fs::write(
+            &config_path,
+            r#"{ "algorithm": "block_mean", "parameters": { "size": 16 } }"#,
+        )
+        .unwrap();
+        let output = temp_dir.path().join("block_mean_output.json");
+        execute_scan(ExtendedScanConfig {
+            config_file: Some(config_path),
+            ..scan_config(target, output.clone())
+        })
+        .await
+        .unwrap();
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(
+            database.scan_info.parameters["algorithm"],
+            "Block Mean Hash"
+        );
+        assert_eq!(database.images[0].metadata.hash_size_bits, 256);
+    }
+
+    #[tokio::test]
+    async fn test_scan_rejects_invalid_engine_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        for (algorithm, hash_size, threads) in [("unknown", 8, None), ("dct", 8, Some(0))] {
+            let result = execute_scan(ExtendedScanConfig {
+                threads,
+                algorithm: algorithm.to_string(),
+                hash_size,
+                ..scan_config(temp_dir.path(), output.clone())
+            })
+            .await;
+            assert!(result.is_err());
+        }
+        assert!(!output.exists());
+    }
+
+    async fn scan_content_hash(
+        target: &Path,
+        output: &Path,
+        content_hash: Option<ContentHashAlgorithm>,
+        exact_only: bool,
+        update: bool,
+    ) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            update,
+            content_hash,
+            exact_only,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_records_content_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_content_hash(
+            &target,
+            &output,
+            Some(ContentHashAlgorithm::Sha256),
+            false,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        let entry = &database.images[0];
+        assert!(!entry.hash.is_empty());
+        assert!(entry
+            .metadata
+            .content_hash
+            .as_deref()
+            .unwrap()
+            .starts_with("sha256:"));
+        assert_eq!(database.scan_info.parameters["content_hash"], "sha256");
+        assert_eq!(database.scan_info.parameters["exact_only"], false);
+    }
+
+    #[tokio::test]
+    async fn test_scan_exact_only_skips_decoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        fs::copy(target.join("a.png"), target.join("a_copy.png")).unwrap();
+        // デコードしないため、壊れた画像ファイルもエラーにならない
+        fs::write(target.join("broken.jpg"), b"not a valid image").unwrap();
+        fs::write(target.join("broken_copy.jpg"), b"not a valid image").unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_content_hash(&target, &output, None, true, false)
+            .await
+            .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(database.images.len(), 4);
+        assert!(database.images.iter().all(|entry| entry.hash.is_empty()
+            && entry.metadata.hash_size_bits == 0
+            && entry.metadata.content_hash.is_some()));
+        let content_hash_of = |name: &str| {
+            database
+                .images
+                .iter()
+                .find(|entry| entry.file_path.ends_with(name))
+                .and_then(|entry| entry.metadata.content_hash.clone())
+        };
+        assert_eq!(content_hash_of("/a.png"), content_hash_of("a_copy.png"));
+        assert_ne!(content_hash_of("/a.png"), content_hash_of("/broken.jpg"));
+        assert_eq!(database.scan_info.parameters["exact_only"], true);
+    }
+
+    #[tokio::test]
+    async fn test_scan_exact_only_skips_files_without_twin() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("a.jpg"), b"same content").unwrap();
+        fs::write(target.join("b.jpg"), b"same content").unwrap();
+        fs::write(target.join("c.jpg"), b"diff content").unwrap();
+        fs::write(target.join("unique.jpg"), b"no other file has this size").unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_content_hash(&target, &output, None, true, false)
+            .await
+            .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        let mut names: Vec<&str> = database
+            .images
+            .iter()
+            .map(|entry| entry.file_path.rsplit('/').next().unwrap())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["a.jpg", "b.jpg"]);
+        assert_eq!(
+            database.scan_info.parameters["exact_candidates"],
+            serde_json::json!({
+                "discovered": 4,
+                "unique_size": 1,
+                "unique_partial_hash": 1,
+                "candidates": 2
+            })
+        );
+
+        // 差分スキャンでも一意になったファイルはデータベースから取り除く
+        fs::write(target.join("b.jpg"), b"edited content!").unwrap();
+        scan_content_hash(&target, &output, None, true, true)
+            .await
+            .unwrap();
+        assert!(load_scan_result(&output).unwrap().images.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_update_rejects_different_content_hash_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 1);
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_content_hash(&target, &output, None, true, false)
+            .await
+            .unwrap();
+
+        // 知覚ハッシュを持たないエントリを再利用しないよう、設定が異なる差分スキャンは拒否する
+        let result = scan_content_hash(&target, &output, None, false, true).await;
+        assert!(result.unwrap_err().to_string().contains("--exact-only"));
+
+        let result = scan_content_hash(
+            &target,
+            &output,
+            Some(ContentHashAlgorithm::Sha256),
+            true,
+            true,
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("content hash"));
+
+        scan_content_hash(&target, &output, None, true, true)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_preset_rejects_content_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        let result = execute_scan(ExtendedScanConfig {
+            config_preset: Some("default".to_string()),
+            exact_only: true,
+            ..scan_config(temp_dir.path(), output)
+        })
+        .await;
+
+        assert!(result.unwrap_err().to_string().contains("--exact-only"));
+    }
+
+    async fn scan_rotation_invariant(
+        target: &Path,
+        output: &Path,
+        rotation_invariant: bool,
+        update: bool,
+    ) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            update,
+            algorithm: "average".to_string(),
+            rotation_invariant,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_records_dihedral_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 3);
+        image::open(target.join("a.png"))
+            .unwrap()
+            .fliph()
+            .save(target.join("mirrored.png"))
+            .unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_rotation_invariant(&target, &output, true, false)
+            .await
+            .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(database.scan_info.parameters["rotation_invariant"], true);
+        let entry_of = |name: &str| {
+            database
+                .images
+                .iter()
+                .find(|entry| entry.file_path.ends_with(name))
+                .unwrap()
+        };
+        let original = entry_of("/a.png");
+        let variants = original.metadata.dihedral_hashes.as_ref().unwrap();
+        assert_eq!(variants.len(), 8);
+        assert_eq!(variants[0], original.hash);
+        // 左右反転したファイルのハッシュは元画像のFlipHorizontalバリアントと一致する
+        assert_eq!(variants[4], entry_of("/mirrored.png").hash);
+
+        // 記録したバリアントの有無が変わる差分スキャンは拒否する
+        let result = scan_rotation_invariant(&target, &output, false, true).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("--rotation-invariant"));
+        scan_rotation_invariant(&target, &output, true, true)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_records_segment_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        // 暗い背景に、グラデーションのある明るい矩形を2つ置く
+        image::RgbImage::from_fn(96, 64, |x, y| {
+            let bright = (8..40).contains(&x) && (8..56).contains(&y)
+                || (56..88).contains(&x) && (16..48).contains(&y);
+            let value = if bright {
+                (140 + (x % 32) * 3 + y % 16) as u8
+            } else {
+                ((x + y) / 3) as u8
+            };
+            image::Rgb([value, value, value])
+        })
+        .save(target.join("a.png"))
+        .unwrap();
+        let output = temp_dir.path().join("hashes.json");
+
+        execute_scan(ExtendedScanConfig {
+            algorithm: "crop_resistant".to_string(),
+            ..scan_config(target.clone(), output.clone())
+        })
+        .await
+        .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(
+            database.scan_info.parameters["algorithm"],
+            "Crop-Resistant Hash"
+        );
+        let segments = database.images[0].metadata.segment_hashes.as_ref().unwrap();
+        assert!(!segments.is_empty());
+        assert!(segments
+            .iter()
+            .all(|segment| segment.len() == database.images[0].hash.len()));
+
+        // 他のアルゴリズムでは記録しない
+        scan_with(&target, &output, true, false).await.unwrap();
+        let database = load_scan_result(&output).unwrap();
+        assert!(database.images[0].metadata.segment_hashes.is_none());
+    }
+
+    async fn scan_algorithm(
+        target: &Path,
+        output: &Path,
+        algorithm: &str,
+        update: bool,
+    ) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            update,
+            algorithm: algorithm.to_string(),
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_records_ensemble_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 3);
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_algorithm(&target, &output, "dct,difference,average", false)
+            .await
+            .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        let parameters = &database.scan_info.parameters;
+        assert_eq!(parameters["algorithm"], "DCT (Discrete Cosine Transform)");
+        assert_eq!(
+            parameters["ensemble"],
+            serde_json::json!(["dct", "difference", "average"])
+        );
+        let entry = &database.images[0];
+        let ensemble = entry.metadata.ensemble_hashes.as_ref().unwrap();
+        assert_eq!(ensemble.len(), 3);
+        assert_eq!(ensemble["dct"], entry.hash);
+
+        // アンサンブルの構成が変わる差分スキャンは拒否する
+        let result = scan_algorithm(&target, &output, "dct", true).await;
+        assert!(result.unwrap_err().to_string().contains("ensemble"));
+        scan_algorithm(&target, &output, "dct,difference,average", true)
+            .await
+            .unwrap();
+    }
+
+    async fn scan_color(target: &Path, output: &Path, color_signature: bool) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            update: output.exists(),
+            color_signature,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_records_color_signature() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 3);
+        let output = temp_dir.path().join("hashes.json");
+
+        scan_color(&target, &output, true).await.unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(database.scan_info.parameters["color_signature"], true);
+        let signature = database.images[0]
+            .metadata
+            .color_signature
+            .as_ref()
+            .unwrap();
+        assert!(crate::services::ColorSignature::from_hex(signature).is_ok());
+
+        // 色の署名の有無が変わる差分スキャンは拒否する
+        let result = scan_color(&target, &output, false).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("--color-signature"));
+        scan_color(&target, &output, true).await.unwrap();
+    }
+
+    async fn scan_preprocessed(
+        target: &Path,
+        output: &Path,
+        preprocessing: Preprocessing,
+    ) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            update: output.exists(),
+            preprocessing,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_preprocessing_normalises_borders_and_alpha() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+
+        // 同じ絵柄を、そのまま・黒帯付き・透明な余白付きで保存する
+        let content = image::RgbImage::from_fn(48, 36, |x, y| {
+            image::Rgb([(x * 5) as u8, (y * 7) as u8, ((x + y) * 3) as u8])
+        });
+        content.save(target.join("plain.png")).unwrap();
+        image::RgbImage::from_fn(48, 56, |x, y| match y {
+            10..46 => *content.get_pixel(x, y - 10),
+            _ => image::Rgb([0, 0, 0]),
+        })
+        .save(target.join("letterboxed.png"))
+        .unwrap();
+        image::RgbaImage::from_fn(64, 36, |x, y| match x {
+            8..56 => {
+                let [r, g, b] = content.get_pixel(x - 8, y).0;
+                image::Rgba([r, g, b, 255])
+            }
+            _ => image::Rgba([90, 20, 160, 0]),
+        })
+        .save(target.join("transparent.png"))
+        .unwrap();
+
+        let output = temp_dir.path().join("hashes.json");
+        let preprocessing = Preprocessing::from_flags(Some(BackgroundColor::WHITE), true, false);
+        scan_preprocessed(&target, &output, preprocessing)
+            .await
+            .unwrap();
+
+        let database = load_scan_result(&output).unwrap();
+        assert_eq!(
+            database.scan_info.parameters["preprocessing"],
+            serde_json::json!([
+                {"step": "flatten_alpha", "background": "#ffffff"},
+                {"step": "trim_borders"}
+            ])
+        );
+        let hashes: Vec<&str> = database
+            .images
+            .iter()
+            .map(|entry| entry.hash.as_str())
+            .collect();
+        assert_eq!(hashes.len(), 3);
+        assert!(hashes.iter().all(|hash| *hash == hashes[0]), "{hashes:?}");
+
+        // 前処理の設定が変わる差分スキャンは拒否する
+        let result = scan_preprocessed(&target, &output, Preprocessing::default()).await;
+        assert!(result.unwrap_err().to_string().contains("preprocessing"));
+    }
+
+    async fn scan_config_file(
+        target: &Path,
+        output: &Path,
+        config_path: &Path,
+        preprocessing: Preprocessing,
+    ) -> Result<()> {
+        execute_scan(ExtendedScanConfig {
+            force: true,
+            config_file: Some(config_path.to_path_buf()),
+            preprocessing,
+            ..scan_config(target, output)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_scan_config_file_preprocessing_pipeline() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("images");
+        fs::create_dir(&target).unwrap();
+        write_test_image(&target.join("a.png"), 3);
+        let output = temp_dir.path().join("hashes.json");
+        let config_path = temp_dir.path().join("config.json");
+        fs::write(
+            &config_path,
+            r#"{
+                "algorithm": "dct",
+                "parameters": {"size": 8},
+                "preprocessing": [
+                    {"step": "exif_orient"},
+                    {"step": "grayscale"},
+                    {"step": "blur", "sigma": 1.0},
+                    {"step": "equalize"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        scan_config_file(&target, &output, &config_path, Preprocessing::default())
+            .await
+            .unwrap();
+        let steps = &load_scan_result(&output).unwrap().scan_info.parameters["preprocessing"];
+        assert_eq!(
+            steps,
+            &serde_json::json!([
+                {"step": "exif_orient"},
+                {"step": "grayscale"},
+                {"step": "blur", "sigma": 1.0},
+                {"step": "equalize"}
+            ])
+        );
+
+        // コマンドラインの前処理フラグは設定ファイルのパイプラインを置き換える
+        scan_config_file(
+            &target,
+            &output,
+            &config_path,
+            Preprocessing::from_flags(None, false, true),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            load_scan_result(&output).unwrap().scan_info.parameters["preprocessing"],
+            serde_json::json!([{"step": "normalize_contrast"}])
+        );
+
+        fs::write(
+            &config_path,
+            r#"{"algorithm": "dct", "parameters": {"size": 8}, "preprocessing": [{"step": "blur", "sigma": 0}]}"#,
+        )
+        .unwrap();
+        let result =
+            scan_config_file(&target, &output, &config_path, Preprocessing::default()).await;
+        assert!(result.unwrap_err().to_string().contains("sigma"));
+    }
 }
@@ -0,0 +1,112 @@
+// 処理のキャンセル
+// Ctrl-C（SIGINT）やSIGTERMを受けたときに新しい処理を止め、処理中の結果を保存してから終了する
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// パイプライン全体で共有するキャンセル通知
+///
+/// クローンしたトークンはすべて同じ状態を共有する。一度キャンセルすると元には戻らない
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    /// キャンセルする（待機中のタスクすべてに通知される）
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// キャンセルされたか
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// キャンセルされるまで待機（既にキャンセルされていればすぐに戻る）
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        // 送信側は自身が保持しているため、チャンネルが閉じることはない
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// SIGINT・SIGTERMを受けたらキャンセルするタスクを起動
+    ///
+    /// 1回目のシグナルでキャンセルし、保存中にもう一度受けた場合はすぐに終了する
+    pub fn cancel_on_shutdown_signal(&self) -> tokio::task::JoinHandle<()> {
+        let token = self.clone();
+        tokio::spawn(async move {
+            if shutdown_signal().await.is_err() {
+                return;
+            }
+            eprintln!("\n⏹️  中断要求を受け付けました。処理中のファイルを保存しています...");
+            token.cancel();
+
+            if shutdown_signal().await.is_ok() {
+                eprintln!("⏹️  強制終了します");
+                std::process::exit(130);
+            }
+        })
+    }
+}
+
+/// SIGINT（Ctrl-C）またはSIGTERMを待機
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{timeout, Duration};
+
+    #[tokio::test]
+    async fn test_cancellation_is_shared_between_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        // キャンセル前は待機し続ける
+        assert!(timeout(Duration::from_millis(20), clone.cancelled())
+            .await
+            .is_err());
+
+        let waiter = tokio::spawn(async move { clone.cancelled().await });
+        token.cancel();
+        timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(token.is_cancelled());
+        // キャンセル済みならすぐに戻る
+        timeout(Duration::from_millis(20), token.cancelled())
+            .await
+            .unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 // 並列処理システムのトレイト定義
 // 全ての抽象化インターフェースを定義
 
-use super::types::{ProcessingMetadata, ProcessingSummary};
+use super::types::{ContentHashAlgorithm, Preprocessing, ProcessingMetadata, ProcessingSummary};
 use anyhow::Result;
 use async_trait::async_trait;
 use mockall::automock;
@@ -21,6 +21,31 @@ pub trait ProcessingConfig: Send + Sync {
 
     /// 進捗報告を有効にするかどうか
     fn enable_progress_reporting(&self) -> bool;
+
+    /// 完全一致の検出用に計算する内容ハッシュ（`None` なら計算しない）
+    fn content_hash_algorithm(&self) -> Option<ContentHashAlgorithm> {
+        None
+    }
+
+    /// 画像をデコードせず内容ハッシュのみを計算するかどうか
+    fn exact_only(&self) -> bool {
+        false
+    }
+
+    /// 回転・反転した8通りの知覚ハッシュも計算するかどうか
+    fn rotation_invariant(&self) -> bool {
+        false
+    }
+
+    /// 色違いを区別するための色の署名も計算するかどうか
+    fn color_signature(&self) -> bool {
+        false
+    }
+
+    /// ハッシュ計算前に画像へ順に適用する前処理
+    fn preprocessing(&self) -> Preprocessing {
+        Preprocessing::default()
+    }
 }
 
 /// 進捗報告の抽象化トレイト
@@ -65,6 +90,34 @@ pub trait HashPersistence: Send + Sync {
     async fn finalize(&self) -> Result<()>;
 }
 
+/// 実行時に選択された保存形式（`--format` や出力ファイルの拡張子から作成）もエンジンで使えるようにする
+#[async_trait]
+impl HashPersistence for Box<dyn HashPersistence> {
+    async fn store_hash(
+        &self,
+        file_path: &Path,
+        hash: &str,
+        metadata: &ProcessingMetadata,
+    ) -> Result<()> {
+        self.as_ref().store_hash(file_path, hash, metadata).await
+    }
+
+    async fn store_batch(
+        &self,
+        results: &[(PathBuf, String, String, u64, ProcessingMetadata)],
+    ) -> Result<()> {
+        self.as_ref().store_batch(results).await
+    }
+
+    async fn set_scan_info(&self, operation: String, info: serde_json::Value) -> Result<()> {
+        self.as_ref().set_scan_info(operation, info).await
+    }
+
+    async fn finalize(&self) -> Result<()> {
+        self.as_ref().finalize().await
+    }
+}
+
 /// 並列処理オーケストレーターの抽象化トレイト
 #[automock(type Config = MockProcessingConfig; type Reporter = MockProgressReporter; type Persistence = MockHashPersistence;)]
 #[async_trait]
@@ -1,4 +1,5 @@
 // 処理に関連するデータ型定義
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// 処理時のメタデータ
@@ -8,6 +9,224 @@ pub struct ProcessingMetadata {
     pub processing_time_ms: u64,
     pub image_dimensions: (u32, u32),
     pub was_resized: bool,
+    /// ハッシュのビット数（旧フォーマットでは未記録のため0）
+    #[serde(default)]
+    pub hash_size_bits: u32,
+    /// ファイルの更新日時（UNIXエポックからのミリ秒、取得できない場合はNone）
+    #[serde(default)]
+    pub modified_time_ms: Option<u64>,
+    /// ファイル内容の暗号学的ハッシュ（`blake3:<16進>` 形式、計算しなかった場合はNone）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// 回転・反転した画像の知覚ハッシュ（`DihedralTransform::ALL` の順の16進文字列、計算しなかった場合はNone）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dihedral_hashes: Option<Vec<String>>,
+    /// 部分領域ごとの知覚ハッシュ（16進文字列、切り抜きに強いアルゴリズムでのみ記録）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_hashes: Option<Vec<String>>,
+    /// アンサンブルの全アルゴリズムのハッシュ（アルゴリズム名 → 16進文字列、複数アルゴリズムでスキャンした場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ensemble_hashes: Option<BTreeMap<String, String>>,
+    /// アンサンブルの全アルゴリズムの回転・反転したハッシュ（アルゴリズム名 → `DihedralTransform::ALL` の順の16進文字列、アンサンブルを `--rotation-invariant` でスキャンした場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ensemble_dihedral_hashes: Option<BTreeMap<String, Vec<String>>>,
+    /// 色の署名（粗いHSVヒストグラムの16進文字列、`scan --color-signature` の場合のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_signature: Option<String>,
+}
+
+impl ProcessingMetadata {
+    /// ファイルシステムのメタデータから更新日時を取得
+    pub fn modified_time_ms_of(metadata: &std::fs::Metadata) -> Option<u64> {
+        let modified = metadata.modified().ok()?;
+        let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+        u64::try_from(since_epoch.as_millis()).ok()
+    }
+}
+
+/// 完全一致の検出に使う内容ハッシュのアルゴリズム
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentHashAlgorithm {
+    #[default]
+    Blake3,
+    Sha256,
+}
+
+impl ContentHashAlgorithm {
+    /// 名前（内容ハッシュの接頭辞にも使う）
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Blake3 => "blake3",
+            Self::Sha256 => "sha256",
+        }
+    }
+}
+
+impl std::fmt::Display for ContentHashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for ContentHashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "blake3" => Ok(Self::Blake3),
+            "sha256" | "sha-256" => Ok(Self::Sha256),
+            _ => Err(format!(
+                "Unknown content hash algorithm '{s}'. Available: blake3, sha256"
+            )),
+        }
+    }
+}
+
+/// 透明な画素を合成する背景色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BackgroundColor(pub [u8; 3]);
+
+impl Default for BackgroundColor {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl BackgroundColor {
+    pub const WHITE: Self = Self([255, 255, 255]);
+    pub const BLACK: Self = Self([0, 0, 0]);
+}
+
+impl std::fmt::Display for BackgroundColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl std::str::FromStr for BackgroundColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "white" => return Ok(Self::WHITE),
+            "black" => return Ok(Self::BLACK),
+            _ => {}
+        }
+        let digits = s.strip_prefix('#').unwrap_or(s);
+        let error = || format!("Invalid color '{s}'. Use white, black or #rrggbb");
+        if digits.len() != 6 {
+            return Err(error());
+        }
+        let bytes = hex::decode(digits).map_err(|_| error())?;
+        Ok(Self([bytes[0], bytes[1], bytes[2]]))
+    }
+}
+
+impl TryFrom<String> for BackgroundColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<BackgroundColor> for String {
+    fn from(color: BackgroundColor) -> Self {
+        color.to_string()
+    }
+}
+
+/// ハッシュ計算前に画像へ適用する前処理の1ステップ（`step` に名前を書く）
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    /// EXIFの向き（Orientation）に従って回転・反転する
+    ExifOrient,
+    /// 透明な画素を背景色にアルファ合成する
+    FlattenAlpha {
+        #[serde(default)]
+        background: BackgroundColor,
+    },
+    /// 均一な色の余白（レターボックスを含む）を切り取る
+    TrimBorders,
+    /// 長辺が `max_dimension` を超える場合にアスペクト比を保って縮小する
+    Resize { max_dimension: u32 },
+    /// グレースケールに変換する
+    Grayscale,
+    /// ガウスぼかしでノイズや再圧縮の影響を抑える
+    Blur { sigma: f32 },
+    /// 輝度のヒストグラムを平坦化する
+    Equalize,
+    /// 明るさの範囲を全階調に引き伸ばす
+    NormalizeContrast,
+}
+
+impl PreprocessStep {
+    /// パラメータが有効か検証
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Resize { max_dimension: 0 } => {
+                Err("Preprocessing step 'resize' requires max_dimension of at least 1".to_string())
+            }
+            Self::Blur { sigma } if !(sigma.is_finite() && *sigma > 0.0) => Err(format!(
+                "Preprocessing step 'blur' requires a positive sigma, got {sigma}"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for PreprocessStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExifOrient => f.write_str("exif_orient"),
+            Self::FlattenAlpha { background } => write!(f, "flatten_alpha({background})"),
+            Self::TrimBorders => f.write_str("trim_borders"),
+            Self::Resize { max_dimension } => write!(f, "resize({max_dimension})"),
+            Self::Grayscale => f.write_str("grayscale"),
+            Self::Blur { sigma } => write!(f, "blur({sigma})"),
+            Self::Equalize => f.write_str("equalize"),
+            Self::NormalizeContrast => f.write_str("normalize_contrast"),
+        }
+    }
+}
+
+/// ハッシュ計算前に画像へ順に適用する前処理（既定では何もしない）
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Preprocessing {
+    pub steps: Vec<PreprocessStep>,
+}
+
+impl Preprocessing {
+    /// scanコマンドのフラグから作成（透明部分の合成 → 余白の除去 → コントラストの正規化の順）
+    pub fn from_flags(
+        flatten_alpha: Option<BackgroundColor>,
+        trim_borders: bool,
+        normalize_contrast: bool,
+    ) -> Self {
+        let steps = [
+            flatten_alpha.map(|background| PreprocessStep::FlattenAlpha { background }),
+            trim_borders.then_some(PreprocessStep::TrimBorders),
+            normalize_contrast.then_some(PreprocessStep::NormalizeContrast),
+        ];
+        Self {
+            steps: steps.into_iter().flatten().collect(),
+        }
+    }
+
+    /// いずれかの前処理が有効かどうか
+    pub fn is_enabled(&self) -> bool {
+        !self.steps.is_empty()
+    }
+
+    /// 全ステップのパラメータを検証
+    pub fn validate(&self) -> Result<(), String> {
+        self.steps.iter().try_for_each(PreprocessStep::validate)
+    }
 }
 
 /// 処理全体のサマリー
@@ -18,9 +237,12 @@ pub struct ProcessingSummary {
     pub error_count: usize,
     pub total_processing_time_ms: u64,
     pub average_time_per_file_ms: f64,
+    /// 途中でキャンセルされた（処理済みのファイルだけが保存されている）
+    pub cancelled: bool,
 }
 
-/// 個別処理の結果
+/// 個別処理の結果（ファイルごとに生成してすぐ収集されるため、メタデータはボックス化しない）
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum ProcessingOutcome {
     Success {
@@ -47,6 +269,14 @@ mod tests {
             processing_time_ms: 150,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         assert_eq!(metadata.file_size, 1024);
@@ -55,6 +285,96 @@ mod tests {
         assert!(!metadata.was_resized);
     }
 
+    #[test]
+    fn test_processing_metadata_legacy_format() {
+        // hash_size_bits導入前のデータベースも読み込めること
+        let json = r#"{
+            "file_size": 1024,
+            "processing_time_ms": 150,
+            "image_dimensions": [512, 512],
+            "was_resized": false
+        }"#;
+
+        let metadata: ProcessingMetadata = serde_json::from_str(json).unwrap();
+        assert_eq!(metadata.file_size, 1024);
+        assert_eq!(metadata.hash_size_bits, 0);
+        assert_eq!(metadata.modified_time_ms, None);
+        assert_eq!(metadata.content_hash, None);
+    }
+
+    #[test]
+    fn test_content_hash_algorithm_parse() {
+        assert_eq!(
+            "blake3".parse::<ContentHashAlgorithm>(),
+            Ok(ContentHashAlgorithm::Blake3)
+        );
+        assert_eq!(
+            "SHA-256".parse::<ContentHashAlgorithm>(),
+            Ok(ContentHashAlgorithm::Sha256)
+        );
+        assert!("md5".parse::<ContentHashAlgorithm>().is_err());
+        assert_eq!(ContentHashAlgorithm::Sha256.to_string(), "sha256");
+    }
+
+    #[test]
+    fn test_background_color_parse() {
+        assert_eq!("White".parse(), Ok(BackgroundColor::WHITE));
+        assert_eq!("black".parse(), Ok(BackgroundColor::BLACK));
+        assert_eq!("#ff8000".parse(), Ok(BackgroundColor([255, 128, 0])));
+        assert_eq!("00ff00".parse(), Ok(BackgroundColor([0, 255, 0])));
+        assert!("#fff".parse::<BackgroundColor>().is_err());
+        assert!("purple".parse::<BackgroundColor>().is_err());
+        assert_eq!(BackgroundColor([255, 128, 0]).to_string(), "#ff8000");
+    }
+
+    #[test]
+    fn test_preprocessing_serialization() {
+        assert!(!Preprocessing::default().is_enabled());
+
+        let preprocessing = Preprocessing::from_flags(Some(BackgroundColor::WHITE), true, false);
+        assert!(preprocessing.is_enabled());
+        let json = serde_json::to_value(&preprocessing).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"step": "flatten_alpha", "background": "#ffffff"},
+                {"step": "trim_borders"}
+            ])
+        );
+        assert_eq!(
+            serde_json::from_value::<Preprocessing>(json).unwrap(),
+            preprocessing
+        );
+    }
+
+    #[test]
+    fn test_preprocess_step_config() {
+        let steps: Vec<PreprocessStep> = serde_json::from_str(
+            r#"[{"step": "exif_orient"}, {"step": "flatten_alpha"},
+                {"step": "resize", "max_dimension": 512}, {"step": "blur", "sigma": 1.5}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                PreprocessStep::ExifOrient,
+                PreprocessStep::FlattenAlpha {
+                    background: BackgroundColor::WHITE
+                },
+                PreprocessStep::Resize { max_dimension: 512 },
+                PreprocessStep::Blur { sigma: 1.5 },
+            ]
+        );
+        assert_eq!(steps[2].to_string(), "resize(512)");
+        assert!(Preprocessing { steps }.validate().is_ok());
+
+        assert!(PreprocessStep::Resize { max_dimension: 0 }
+            .validate()
+            .is_err());
+        assert!(PreprocessStep::Blur { sigma: 0.0 }.validate().is_err());
+        assert!(serde_json::from_str::<PreprocessStep>(r#"{"step": "sharpen"}"#).is_err());
+    }
+
     #[test]
     fn test_processing_summary_creation() {
         let summary = ProcessingSummary {
@@ -63,6 +383,7 @@ mod tests {
             error_count: 5,
             total_processing_time_ms: 30000,
             average_time_per_file_ms: 315.79,
+            cancelled: false,
         };
 
         assert_eq!(summary.total_files, 100);
@@ -79,6 +400,14 @@ mod tests {
             processing_time_ms: 200,
             image_dimensions: (1024, 1024),
             was_resized: true,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         let result = ProcessingOutcome::Success {
@@ -133,6 +462,14 @@ mod tests {
             processing_time_ms: 150,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         let debug_str = format!("{metadata:?}");
@@ -1,14 +1,19 @@
 // Consumer - 並列ワーカー機能
 
 use crate::{
-    core::types::ProcessingOutcome, image_loader::ImageLoaderBackend,
-    perceptual_hash::PerceptualHashBackend, services::processing::process_single_file,
+    core::types::ProcessingOutcome,
+    image_loader::ImageLoaderBackend,
+    perceptual_hash::PerceptualHashBackend,
+    services::processing::{process_single_file, WorkerOptions},
 };
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
 /// 単一Consumerワーカー
+///
+/// キャンセルされると新しいファイルを受け取らずに終了する（処理中のファイルの結果は送信する）。
+/// 受け取らずに残したファイルがあれば `true` を返す
 pub fn spawn_single_consumer<L, H>(
     worker_id: usize,
     loader: Arc<L>,
@@ -16,7 +21,8 @@ pub fn spawn_single_consumer<L, H>(
     work_rx: Arc<tokio::sync::Mutex<mpsc::Receiver<String>>>,
     result_tx: mpsc::Sender<ProcessingOutcome>,
     semaphore: Arc<tokio::sync::Semaphore>,
-) -> tokio::task::JoinHandle<Result<()>>
+    options: WorkerOptions,
+) -> tokio::task::JoinHandle<Result<bool>>
 where
     L: ImageLoaderBackend + 'static,
     H: PerceptualHashBackend + 'static,
@@ -26,9 +32,13 @@ where
             // 次の作業を取得
             let file_path = {
                 let mut rx = work_rx.lock().await;
-                match rx.recv().await {
-                    Some(path) => path,
-                    None => break, // チャンネル終了
+                tokio::select! {
+                    biased;
+                    _ = options.cancel.cancelled() => return Ok(rx.try_recv().is_ok()),
+                    path = rx.recv() => match path {
+                        Some(path) => path,
+                        None => break, // チャンネル終了
+                    },
                 }
             };
 
@@ -39,8 +49,14 @@ where
                 .map_err(|e| anyhow::anyhow!("Semaphore error: {}", e))?;
 
             // 単一ファイル処理
-            let result =
-                process_single_file(loader.as_ref(), hasher.as_ref(), &file_path, worker_id).await;
+            let result = process_single_file(
+                loader.as_ref(),
+                hasher.as_ref(),
+                &file_path,
+                worker_id,
+                &options,
+            )
+            .await;
 
             // 結果送信
             if (result_tx.send(result).await).is_err() {
@@ -48,7 +64,7 @@ where
                 break;
             }
         }
-        Ok(())
+        Ok(false)
     })
 }
 
@@ -60,7 +76,8 @@ pub fn spawn_consumers<L, H>(
     result_tx: mpsc::Sender<ProcessingOutcome>,
     semaphore: Arc<tokio::sync::Semaphore>,
     worker_count: usize,
-) -> Vec<tokio::task::JoinHandle<Result<()>>>
+    options: WorkerOptions,
+) -> Vec<tokio::task::JoinHandle<Result<bool>>>
 where
     L: ImageLoaderBackend + 'static,
     H: PerceptualHashBackend + 'static,
@@ -76,6 +93,7 @@ where
             Arc::clone(&work_rx),
             result_tx.clone(),
             Arc::clone(&semaphore),
+            options.clone(),
         );
         handles.push(handle);
     }
@@ -144,6 +162,7 @@ mod tests {
             work_rx,
             result_tx,
             semaphore,
+            WorkerOptions::default(),
         );
 
         // ファイルパス送信
@@ -193,6 +212,7 @@ mod tests {
             work_rx,
             result_tx,
             semaphore,
+            WorkerOptions::default(),
         );
 
         work_tx
@@ -235,6 +255,7 @@ mod tests {
             result_tx,
             semaphore,
             3, // 3つのワーカー
+            WorkerOptions::default(),
         );
 
         // ファイルパス送信
@@ -309,6 +330,7 @@ mod tests {
             result_tx,
             semaphore,
             2,
+            WorkerOptions::default(),
         );
 
         work_tx
@@ -359,6 +381,7 @@ mod tests {
             work_rx,
             result_tx.clone(),
             semaphore,
+            WorkerOptions::default(),
         );
 
         // ファイルパスを送信してから結果チャンネルを閉じる
@@ -387,6 +410,7 @@ mod tests {
             result_tx,
             semaphore,
             2,
+            WorkerOptions::default(),
         );
 
         // 作業を送信せずにチャンネルを閉じる
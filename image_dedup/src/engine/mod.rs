@@ -6,6 +6,7 @@ pub mod consumer;
 mod pipeline;
 pub mod processing_engine;
 pub mod producer; // ProcessingEngine内部でのみ使用
+pub mod runtime;
 
 // 公開API - 主要エンジンクラス
 pub use api::{
@@ -13,3 +14,6 @@ pub use api::{
     process_directory_with_engine, process_files_with_engine,
 };
 pub use processing_engine::ProcessingEngine;
+pub use runtime::{
+    create_runtime_processing_engine, RuntimeEngineSettings, RuntimeProcessingEngine,
+};
@@ -3,10 +3,15 @@
 
 use super::{consumer::spawn_consumers, producer::spawn_producer};
 use crate::{
-    core::{HashPersistence, ProcessingConfig, ProcessingSummary, ProgressReporter},
+    core::{
+        CancellationToken, HashPersistence, ProcessingConfig, ProcessingSummary, ProgressReporter,
+    },
     image_loader::ImageLoaderBackend,
     perceptual_hash::PerceptualHashBackend,
-    services::persistence::spawn_result_collector,
+    services::{
+        persistence::{spawn_result_collector, CollectorOptions},
+        processing::WorkerOptions,
+    },
 };
 use anyhow::Result;
 use std::sync::{
@@ -20,6 +25,7 @@ use tokio::sync::mpsc;
 pub struct ProcessingPipeline<L, H> {
     loader: Arc<L>,
     hasher: Arc<H>,
+    cancel: CancellationToken,
 }
 
 impl<L, H> ProcessingPipeline<L, H>
@@ -29,10 +35,23 @@ where
 {
     /// 新しいパイプラインを作成
     pub fn new(loader: Arc<L>, hasher: Arc<H>) -> Self {
-        Self { loader, hasher }
+        Self {
+            loader,
+            hasher,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// キャンセル通知を設定
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
     }
 
     /// ファイルリストを処理
+    ///
+    /// キャンセルされると新しいファイルの配信を止め、処理中の結果を保存してから
+    /// 永続化を完了させる。処理しなかったファイルが残った場合のサマリーは `cancelled` になる
     pub async fn execute<C, R, P>(
         &self,
         files: Vec<String>,
@@ -60,7 +79,7 @@ where
         reporter.report_started(total_files).await;
 
         // Producer起動
-        let producer_handle = spawn_producer(files, work_tx);
+        let producer_handle = spawn_producer(files, work_tx, self.cancel.clone());
 
         // Consumer Pool起動
         let consumer_handles = spawn_consumers(
@@ -70,6 +89,7 @@ where
             result_tx.clone(),
             semaphore,
             config.max_concurrent_tasks(),
+            WorkerOptions::from_config(config).with_cancellation(self.cancel.clone()),
         );
 
         // Result Collector起動
@@ -80,15 +100,18 @@ where
             error_count.clone(),
             reporter.clone(),
             persistence.clone(),
-            config.batch_size(),
+            CollectorOptions {
+                batch_size: config.batch_size(),
+                cancel: self.cancel.clone(),
+            },
         );
 
-        // Producer完了を待機
-        producer_handle.await??;
+        // Producer完了を待機（配信しなかったファイルがあれば中断として記録する）
+        let mut cut_short = producer_handle.await??;
 
-        // Consumer完了を待機
+        // Consumer完了を待機（受け取らずに残したファイルがあれば中断として記録する）
         for handle in consumer_handles {
-            handle.await??;
+            cut_short |= handle.await??;
         }
 
         // result_txを閉じてCollectorに完了を通知
@@ -120,6 +143,7 @@ where
             error_count: final_errors,
             total_processing_time_ms: total_time_ms,
             average_time_per_file_ms,
+            cancelled: cut_short,
         })
     }
 }
@@ -272,4 +296,147 @@ mod tests {
         let stored_data = persistence.get_stored_data().unwrap();
         assert_eq!(stored_data.len(), 10);
     }
+
+    /// 最初の進捗報告でキャンセルするレポーター（処理中の中断を再現する）
+    struct CancelOnProgress(CancellationToken);
+
+    #[async_trait::async_trait]
+    impl ProgressReporter for CancelOnProgress {
+        async fn report_started(&self, _total_files: usize) {}
+
+        async fn report_progress(&self, _completed: usize, _total: usize) {
+            self.0.cancel();
+        }
+
+        async fn report_error(&self, _file_path: &std::path::Path, _error: &str) {}
+
+        async fn report_completed(&self, _total_processed: usize, _total_errors: usize) {}
+    }
+
+    /// 全ファイルの結果を受け取った後でキャンセルするレポーター
+    struct CancelOnCompletion(CancellationToken);
+
+    #[async_trait::async_trait]
+    impl ProgressReporter for CancelOnCompletion {
+        async fn report_started(&self, _total_files: usize) {}
+
+        async fn report_progress(&self, completed: usize, total: usize) {
+            if completed == total {
+                self.0.cancel();
+            }
+        }
+
+        async fn report_error(&self, _file_path: &std::path::Path, _error: &str) {}
+
+        async fn report_completed(&self, _total_processed: usize, _total_errors: usize) {}
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_cancelled_after_all_files_is_complete() {
+        let cancel = CancellationToken::new();
+        let pipeline = ProcessingPipeline::new(
+            Arc::new(StandardImageLoader::new()),
+            Arc::new(DctHasher::new(8)),
+        )
+        .with_cancellation(cancel.clone());
+
+        let files = (0..5).map(|i| format!("/missing{i}.png")).collect();
+        let summary = pipeline
+            .execute(
+                files,
+                &DefaultProcessingConfig::default(),
+                Arc::new(CancelOnCompletion(cancel.clone())),
+                Arc::new(MemoryHashPersistence::new()),
+            )
+            .await
+            .unwrap();
+
+        // 処理しなかったファイルがなければ中断として扱わない
+        assert!(cancel.is_cancelled());
+        assert!(!summary.cancelled);
+        assert_eq!(summary.error_count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_cancelled_before_start() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let pipeline = ProcessingPipeline::new(
+            Arc::new(StandardImageLoader::new()),
+            Arc::new(DctHasher::new(8)),
+        )
+        .with_cancellation(cancel);
+
+        let files = (0..5).map(|i| format!("/missing{i}.png")).collect();
+        let persistence = Arc::new(MemoryHashPersistence::new());
+        let summary = pipeline
+            .execute(
+                files,
+                &DefaultProcessingConfig::default(),
+                Arc::new(NoOpProgressReporter::new()),
+                persistence.clone(),
+            )
+            .await
+            .unwrap();
+
+        assert!(summary.cancelled);
+        assert_eq!(summary.total_files, 5);
+        assert_eq!(summary.processed_files + summary.error_count, 0);
+        // キャンセルされても永続化は完了させる
+        assert!(persistence.is_finalized().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_cancelled_midway_writes_valid_database() {
+        use crate::services::persistence::implementations::ScanResult;
+        use crate::services::StreamingJsonHashPersistence;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut files = Vec::new();
+        for i in 0..20 {
+            let path = temp_dir.path().join(format!("image{i:02}.png"));
+            image::RgbImage::from_pixel(8, 8, image::Rgb([i * 10, 0, 0]))
+                .save(&path)
+                .unwrap();
+            files.push(path.to_string_lossy().to_string());
+        }
+        let output = temp_dir.path().join("hashes.json");
+
+        let cancel = CancellationToken::new();
+        let pipeline = ProcessingPipeline::new(
+            Arc::new(StandardImageLoader::new()),
+            Arc::new(DctHasher::new(8)),
+        )
+        .with_cancellation(cancel.clone());
+        let persistence = Arc::new(StreamingJsonHashPersistence::new(&output));
+        persistence
+            .set_scan_info("scan".to_string(), serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let config = DefaultProcessingConfig::default()
+            .with_max_concurrent(1)
+            .with_buffer_size(1)
+            .with_batch_size(50);
+        let summary = pipeline
+            .execute(
+                files,
+                &config,
+                Arc::new(CancelOnProgress(cancel)),
+                persistence,
+            )
+            .await
+            .unwrap();
+
+        assert!(summary.cancelled);
+        assert!(summary.processed_files >= 1);
+        assert!(summary.processed_files < 20);
+
+        // 処理中だった結果も保存され、閉じたJSONとして読み込める
+        let database: ScanResult =
+            serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(database.images.len(), summary.processed_files);
+        assert_eq!(database.scan_info.total_files, summary.processed_files);
+    }
 }
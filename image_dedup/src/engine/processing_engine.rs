@@ -4,12 +4,13 @@
 use super::pipeline::ProcessingPipeline;
 use crate::{
     core::{
-        HashPersistence, ProcessingConfig, ProcessingError, ProcessingResult, ProcessingSummary,
-        ProgressReporter,
+        CancellationToken, HashPersistence, ProcessingConfig, ProcessingError, ProcessingResult,
+        ProcessingSummary, ProgressReporter,
     },
     image_loader::ImageLoaderBackend,
     perceptual_hash::PerceptualHashBackend,
-    storage::StorageBackend,
+    services::processing::{filter_exact_candidates, ExactCandidateReport, ExactCandidates},
+    storage::{StorageBackend, StorageItem},
 };
 use std::sync::Arc;
 
@@ -27,6 +28,7 @@ pub struct ProcessingEngine<L, H, S, C, R, P> {
     config: Arc<C>,
     reporter: Arc<R>,
     persistence: Arc<P>,
+    cancel: CancellationToken,
 }
 
 impl<L, H, S, C, R, P> ProcessingEngine<L, H, S, C, R, P>
@@ -50,13 +52,28 @@ where
             config: Arc::new(config),
             reporter: Arc::new(reporter),
             persistence: Arc::new(persistence),
+            cancel: CancellationToken::new(),
         }
     }
 
+    /// キャンセル通知を設定（キャンセルされると処理済みのファイルだけを保存して終了する）
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     /// 指定されたディレクトリを並列処理
     ///
-    /// ファイル発見から処理完了まで全てを管理する高レベルAPI
+    /// ファイル発見から処理完了まで全てを管理する高レベルAPI。
+    /// 完全一致のみを検出する設定では、一致する相手がいないファイルを事前に除外する
     pub async fn process_directory(&self, directory: &str) -> ProcessingResult<ProcessingSummary> {
+        if self.config.exact_only() {
+            let candidates = self.discover_exact_candidates(directory).await?;
+            return self
+                .process_exact_candidates(candidates.files, &candidates.report)
+                .await;
+        }
+
         // ファイル発見
         let files = self.discover_image_files(directory).await?;
 
@@ -68,17 +85,44 @@ where
     ///
     /// より細かい制御が必要な場合のAPI
     pub async fn process_files(&self, files: Vec<String>) -> ProcessingResult<ProcessingSummary> {
+        self.process_files_with_scan_info(files, None).await
+    }
+
+    /// 完全一致の候補を並列処理し、絞り込みの集計を `scan_info` に記録
+    pub async fn process_exact_candidates(
+        &self,
+        files: Vec<String>,
+        report: &ExactCandidateReport,
+    ) -> ProcessingResult<ProcessingSummary> {
+        self.process_files_with_scan_info(files, Some(report)).await
+    }
+
+    async fn process_files_with_scan_info(
+        &self,
+        files: Vec<String>,
+        exact_candidates: Option<&ExactCandidateReport>,
+    ) -> ProcessingResult<ProcessingSummary> {
         // scan_infoを設定
-        let scan_info = serde_json::json!({
+        let mut scan_info = serde_json::json!({
             "timestamp": chrono::Utc::now().to_rfc3339(),
             "total_files": files.len(),
             "algorithm": self.hasher.algorithm_name(),
+            "hash_size": self.hasher.algorithm().size(),
+            "ensemble": self.hasher.ensemble_algorithms(),
+            "content_hash": self.config.content_hash_algorithm(),
+            "exact_only": self.config.exact_only(),
+            "rotation_invariant": self.config.rotation_invariant(),
+            "color_signature": self.config.color_signature(),
+            "preprocessing": self.config.preprocessing(),
             "settings": {
                 "max_concurrent": self.config.max_concurrent_tasks(),
                 "batch_size": self.config.batch_size(),
                 "buffer_size": self.config.channel_buffer_size()
             }
         });
+        if let Some(report) = exact_candidates {
+            scan_info["exact_candidates"] = serde_json::json!(report);
+        }
 
         // scan_infoをpersistenceに設定
         self.persistence
@@ -90,7 +134,8 @@ where
             })?;
 
         // 既にArcで管理されている依存関係を効率的に共有
-        let pipeline = ProcessingPipeline::new(Arc::clone(&self.loader), Arc::clone(&self.hasher));
+        let pipeline = ProcessingPipeline::new(Arc::clone(&self.loader), Arc::clone(&self.hasher))
+            .with_cancellation(self.cancel.clone());
 
         pipeline
             .execute(
@@ -108,7 +153,33 @@ where
     /// ディレクトリから画像ファイルを発見
     ///
     /// ストレージバックエンドを使用してファイル発見処理を行う
-    async fn discover_image_files(&self, directory: &str) -> ProcessingResult<Vec<String>> {
+    pub async fn discover_image_files(&self, directory: &str) -> ProcessingResult<Vec<String>> {
+        let items = self.discover_image_items(directory).await?;
+        Ok(items.into_iter().map(|item| item.id).collect())
+    }
+
+    /// ディレクトリから完全一致の候補となる画像ファイルを発見
+    ///
+    /// 発見時に得たサイズでまとめ、同じサイズのファイルは先頭・末尾の部分ハッシュで
+    /// さらに絞り込む（fdupesと同じ段階的な比較）。内容全体のハッシュは候補のみ計算すればよい
+    pub async fn discover_exact_candidates(
+        &self,
+        directory: &str,
+    ) -> ProcessingResult<ExactCandidates> {
+        let files: Vec<(String, u64)> = self
+            .discover_image_items(directory)
+            .await?
+            .into_iter()
+            .map(|item| (item.id, item.size))
+            .collect();
+
+        tokio::task::spawn_blocking(move || filter_exact_candidates(files))
+            .await
+            .map_err(|e| ProcessingError::parallel_execution(format!("候補絞り込みエラー: {e}")))
+    }
+
+    /// ディレクトリから画像ファイルのアイテムを発見（パス順）
+    async fn discover_image_items(&self, directory: &str) -> ProcessingResult<Vec<StorageItem>> {
         // 設定検証
         if self.config.max_concurrent_tasks() == 0 {
             return Err(ProcessingError::configuration(
@@ -131,14 +202,19 @@ where
         let mut image_files = Vec::new();
         for item in items {
             if !item.is_directory && self.storage.is_image_file(&item) {
-                image_files.push(item.id);
+                image_files.push(item);
             }
         }
 
-        image_files.sort(); // 一貫した順序で処理
+        image_files.sort_by(|a, b| a.id.cmp(&b.id)); // 一貫した順序で処理
         Ok(image_files)
     }
 
+    /// ハッシュ計算バックエンドへの参照を取得
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
     /// 設定への参照を取得（読み取り専用アクセス）
     pub fn config(&self) -> &C {
         &self.config
@@ -222,7 +298,8 @@ where
         }
 
         // パイプライン実行
-        let pipeline = ProcessingPipeline::new(Arc::clone(&self.loader), Arc::clone(&self.hasher));
+        let pipeline = ProcessingPipeline::new(Arc::clone(&self.loader), Arc::clone(&self.hasher))
+            .with_cancellation(self.cancel.clone());
 
         let mut summary = pipeline
             .execute(
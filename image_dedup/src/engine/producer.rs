@@ -1,22 +1,31 @@
 // Producer - ファイル配信機能
 
+use crate::core::CancellationToken;
 use anyhow::Result;
 use tokio::sync::mpsc;
 
 /// Producer: ファイルパスを配信
+///
+/// キャンセルされると残りのファイルを配信せずに終了する。配信しなかったファイルがあれば `true` を返す
 pub fn spawn_producer(
     files: Vec<String>,
     work_tx: mpsc::Sender<String>,
-) -> tokio::task::JoinHandle<Result<()>> {
+    cancel: CancellationToken,
+) -> tokio::task::JoinHandle<Result<bool>> {
     tokio::spawn(async move {
         for file_path in files {
-            if (work_tx.send(file_path).await).is_err() {
+            let sent = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Ok(true),
+                sent = work_tx.send(file_path) => sent,
+            };
+            if sent.is_err() {
                 // チャンネルが閉じられた場合は正常終了
                 break;
             }
         }
         // work_txをドロップしてチャンネル終了シグナル
-        Ok(())
+        Ok(false)
     })
 }
 
@@ -36,7 +45,7 @@ mod tests {
         let (work_tx, mut work_rx) = mpsc::channel::<String>(10);
 
         // Producer起動
-        let producer_handle = spawn_producer(files.clone(), work_tx);
+        let producer_handle = spawn_producer(files.clone(), work_tx, CancellationToken::new());
 
         // 全ファイルを受信
         let mut received = Vec::new();
@@ -57,7 +66,7 @@ mod tests {
         let files: Vec<String> = vec![];
         let (work_tx, mut work_rx) = mpsc::channel::<String>(10);
 
-        let producer_handle = spawn_producer(files, work_tx);
+        let producer_handle = spawn_producer(files, work_tx, CancellationToken::new());
 
         // チャンネルが即座に閉じることを確認
         let received = timeout(Duration::from_millis(100), work_rx.recv()).await;
@@ -74,9 +83,32 @@ mod tests {
         // 受信側を即座に閉じる
         drop(work_rx);
 
-        let producer_handle = spawn_producer(files, work_tx);
+        let producer_handle = spawn_producer(files, work_tx, CancellationToken::new());
 
         // Producerはエラーなく終了すべき
         producer_handle.await.unwrap().unwrap();
     }
+
+    #[tokio::test]
+    async fn test_producer_stops_when_cancelled() {
+        let files = vec!["/test1.jpg".to_string(), "/test2.jpg".to_string()];
+        let (work_tx, mut work_rx) = mpsc::channel::<String>(10);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let producer_handle = spawn_producer(files, work_tx, cancel);
+        assert!(producer_handle.await.unwrap().unwrap());
+
+        // キャンセル済みなら何も配信せずにチャンネルを閉じる
+        assert!(work_rx.recv().await.is_none());
+
+        // 配信し終えた後のキャンセルは打ち切りにならない
+        let (work_tx, _work_rx) = mpsc::channel::<String>(10);
+        let cancel = CancellationToken::new();
+        let producer_handle =
+            spawn_producer(vec!["/test1.jpg".to_string()], work_tx, cancel.clone());
+        let cut_short = producer_handle.await.unwrap().unwrap();
+        cancel.cancel();
+        assert!(!cut_short);
+    }
 }
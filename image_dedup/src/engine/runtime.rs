@@ -0,0 +1,291 @@
+// RuntimeEngine - CLIフラグや設定ファイルから実行時に組み立てる処理エンジン
+// ハッシュアルゴリズム・ハッシュサイズ・並列数を実行時の値で決定する
+
+use super::ProcessingEngine;
+use crate::{
+    core::{ContentHashAlgorithm, HashPersistence, Preprocessing},
+    image_loader::standard::StandardImageLoader,
+    perceptual_hash::{
+        config::DynamicAlgorithmConfig, ensemble::EnsembleHasher,
+        factory::create_hasher_from_config, PerceptualHashBackend,
+    },
+    services::{
+        CheckpointedPersistence, ConsoleProgressReporter, DatabaseFormat, DefaultProcessingConfig,
+        JsonlHashPersistence,
+    },
+    storage::local::LocalStorageBackend,
+};
+use anyhow::Result;
+use std::path::Path;
+
+/// 実行時に組み立てた処理エンジンの型
+pub type RuntimeProcessingEngine = ProcessingEngine<
+    StandardImageLoader,
+    Box<dyn PerceptualHashBackend>,
+    LocalStorageBackend,
+    DefaultProcessingConfig,
+    ConsoleProgressReporter,
+    Box<dyn HashPersistence>,
+>;
+
+/// 実行時に決定するエンジン設定
+#[derive(Debug, Clone)]
+pub struct RuntimeEngineSettings {
+    /// ハッシュアルゴリズムとそのパラメータ（`size` など）
+    pub algorithm: DynamicAlgorithmConfig,
+    /// 同じデコード結果から追加で計算するアルゴリズム（空ならアンサンブルを使わない）
+    pub ensemble: Vec<DynamicAlgorithmConfig>,
+    /// ワーカー数（同時に処理するファイル数の上限）。`None` ならCPU数の2倍
+    pub threads: Option<usize>,
+    /// 完全一致の検出用に計算する内容ハッシュ
+    pub content_hash: Option<ContentHashAlgorithm>,
+    /// 画像をデコードせず内容ハッシュのみを計算する
+    pub exact_only: bool,
+    /// 回転・反転した8通りの知覚ハッシュも計算する
+    pub rotation_invariant: bool,
+    /// 色違いを区別するための色の署名も計算する
+    pub color_signature: bool,
+    /// ハッシュ計算前に画像へ順に適用する前処理
+    pub preprocessing: Preprocessing,
+}
+
+impl RuntimeEngineSettings {
+    /// アルゴリズム名とハッシュサイズから作成
+    ///
+    /// `dct,difference,average` のようにカンマ区切りで複数指定するとアンサンブルになる
+    /// （先頭がメインのアルゴリズム、全アルゴリズムで同じハッシュサイズを使う）
+    pub fn new(algorithm: &str, hash_size: u32, threads: Option<usize>) -> Self {
+        let mut configs: Vec<DynamicAlgorithmConfig> = algorithm
+            .split(',')
+            .map(|name| {
+                DynamicAlgorithmConfig::new(name.trim(), serde_json::json!({ "size": hash_size }))
+            })
+            .collect();
+        // splitは常に1つ以上の要素を返す
+        let primary = configs.remove(0);
+        Self {
+            algorithm: primary,
+            ensemble: configs,
+            threads,
+            content_hash: None,
+            exact_only: false,
+            rotation_invariant: false,
+            color_signature: false,
+            preprocessing: Preprocessing::default(),
+        }
+    }
+
+    /// 内容ハッシュの設定を追加
+    pub fn with_content_hash(
+        mut self,
+        content_hash: Option<ContentHashAlgorithm>,
+        exact_only: bool,
+    ) -> Self {
+        self.content_hash = content_hash;
+        self.exact_only = exact_only;
+        self
+    }
+
+    /// 回転・反転に対応したハッシュの設定を追加
+    pub fn with_rotation_invariant(mut self, rotation_invariant: bool) -> Self {
+        self.rotation_invariant = rotation_invariant;
+        self
+    }
+
+    /// 色の署名の設定を追加
+    pub fn with_color_signature(mut self, color_signature: bool) -> Self {
+        self.color_signature = color_signature;
+        self
+    }
+
+    /// 前処理の設定を追加
+    pub fn with_preprocessing(mut self, preprocessing: Preprocessing) -> Self {
+        self.preprocessing = preprocessing;
+        self
+    }
+
+    /// 設定に従ってハッシャーを作成（パラメータの検証を含む）
+    ///
+    /// アンサンブルの場合は全アルゴリズムをまとめた `EnsembleHasher` を返す
+    pub fn create_hasher(&self) -> Result<Box<dyn PerceptualHashBackend>> {
+        let create = |config: &DynamicAlgorithmConfig| {
+            create_hasher_from_config(config).map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid hash configuration for '{}': {}",
+                    config.algorithm,
+                    e
+                )
+            })
+        };
+
+        let primary = create(&self.algorithm)?;
+        if self.ensemble.is_empty() {
+            return Ok(primary);
+        }
+
+        let mut members = vec![(self.algorithm.algorithm.clone(), primary)];
+        for config in &self.ensemble {
+            members.push((config.algorithm.clone(), create(config)?));
+        }
+        Ok(Box::new(EnsembleHasher::new(members)?))
+    }
+
+    /// 設定に従って処理設定を作成（ワーカー数とセマフォの許可数は `threads` になる）
+    pub fn create_processing_config(&self) -> Result<DefaultProcessingConfig> {
+        self.preprocessing.validate().map_err(anyhow::Error::msg)?;
+        let config = DefaultProcessingConfig::new(num_cpus::get())
+            .with_buffer_size(100)
+            .with_batch_size(50)
+            .with_content_hash(self.content_hash)
+            .with_exact_only(self.exact_only)
+            .with_rotation_invariant(self.rotation_invariant)
+            .with_color_signature(self.color_signature)
+            .with_preprocessing(self.preprocessing.clone());
+        match self.threads {
+            Some(0) => anyhow::bail!("Thread count must be at least 1"),
+            Some(threads) => Ok(config.with_max_concurrent(threads)),
+            None => Ok(config),
+        }
+    }
+}
+
+/// 設定から処理エンジンを作成（ハッシュは `format` の形式で `output_path` に書き込む）
+///
+/// `checkpoint` を指定すると、保存したエントリをチェックポイントにも記録する
+pub fn create_runtime_processing_engine(
+    settings: &RuntimeEngineSettings,
+    output_path: &Path,
+    format: DatabaseFormat,
+    checkpoint: Option<JsonlHashPersistence>,
+) -> Result<RuntimeProcessingEngine> {
+    let persistence = format.create_persistence(output_path);
+    let persistence: Box<dyn HashPersistence> = match checkpoint {
+        Some(checkpoint) => Box::new(CheckpointedPersistence::new(persistence, checkpoint)),
+        None => persistence,
+    };
+    Ok(ProcessingEngine::new(
+        StandardImageLoader::new(),
+        settings.create_hasher()?,
+        LocalStorageBackend::new(),
+        settings.create_processing_config()?,
+        ConsoleProgressReporter::new(),
+        persistence,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ProcessingConfig;
+    use crate::perceptual_hash::HashAlgorithm;
+
+    #[test]
+    fn test_settings_select_algorithm_and_size() {
+        for (name, expected) in [
+            ("dct", HashAlgorithm::DCT { size: 16 }),
+            ("average", HashAlgorithm::Average { size: 16 }),
+            ("difference", HashAlgorithm::Difference { size: 16 }),
+            ("wavelet", HashAlgorithm::Wavelet { size: 16 }),
+            ("block_mean", HashAlgorithm::BlockMean { size: 16 }),
+            ("crop_resistant", HashAlgorithm::CropResistant { size: 16 }),
+        ] {
+            let hasher = RuntimeEngineSettings::new(name, 16, None)
+                .create_hasher()
+                .unwrap();
+            assert_eq!(hasher.algorithm(), &expected);
+        }
+    }
+
+    #[test]
+    fn test_settings_build_ensemble() {
+        let settings = RuntimeEngineSettings::new("dct, difference,average", 8, None);
+        assert_eq!(settings.algorithm.algorithm, "dct");
+        assert_eq!(settings.ensemble.len(), 2);
+
+        let hasher = settings.create_hasher().unwrap();
+        assert_eq!(hasher.algorithm(), &HashAlgorithm::DCT { size: 8 });
+        assert_eq!(
+            hasher.ensemble_algorithms(),
+            vec!["dct", "difference", "average"]
+        );
+
+        // 同じアルゴリズムの重複や未知のアルゴリズムは拒否する
+        assert!(RuntimeEngineSettings::new("dct,dct", 8, None)
+            .create_hasher()
+            .is_err());
+        assert!(RuntimeEngineSettings::new("dct,unknown", 8, None)
+            .create_hasher()
+            .is_err());
+    }
+
+    #[test]
+    fn test_settings_reject_invalid_values() {
+        assert!(RuntimeEngineSettings::new("unknown", 8, None)
+            .create_hasher()
+            .is_err());
+        assert!(RuntimeEngineSettings::new("dct", 0, None)
+            .create_hasher()
+            .is_err());
+        assert!(RuntimeEngineSettings::new("dct", 8, Some(0))
+            .create_processing_config()
+            .is_err());
+    }
+
+    #[test]
+    fn test_threads_set_worker_count() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let settings = RuntimeEngineSettings::new("average", 8, Some(3));
+
+        let engine = create_runtime_processing_engine(
+            &settings,
+            &temp_dir.path().join("out.json"),
+            DatabaseFormat::Json,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(engine.config().max_concurrent_tasks(), 3);
+        assert_eq!(
+            engine.hasher().algorithm(),
+            &HashAlgorithm::Average { size: 8 }
+        );
+    }
+
+    #[test]
+    fn test_content_hash_settings() {
+        let config = RuntimeEngineSettings::new("dct", 8, None)
+            .with_content_hash(Some(ContentHashAlgorithm::Sha256), false)
+            .create_processing_config()
+            .unwrap();
+        assert_eq!(
+            config.content_hash_algorithm(),
+            Some(ContentHashAlgorithm::Sha256)
+        );
+        assert!(!config.exact_only());
+
+        let config = RuntimeEngineSettings::new("dct", 8, None)
+            .with_content_hash(None, true)
+            .create_processing_config()
+            .unwrap();
+        assert_eq!(
+            config.content_hash_algorithm(),
+            Some(ContentHashAlgorithm::Blake3)
+        );
+        assert!(config.exact_only());
+    }
+
+    #[test]
+    fn test_rotation_invariant_settings() {
+        let settings = RuntimeEngineSettings::new("dct", 8, None);
+        assert!(!settings
+            .create_processing_config()
+            .unwrap()
+            .rotation_invariant());
+
+        let config = settings
+            .with_rotation_invariant(true)
+            .create_processing_config()
+            .unwrap();
+        assert!(config.rotation_invariant());
+    }
+}
@@ -1,8 +1,10 @@
-use anyhow::Result;
+use crate::image_preprocessor::ImagePreprocessor;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use image::DynamicImage;
 use mockall::automock;
 use std::path::Path;
+use std::sync::Arc;
 
 pub mod standard;
 
@@ -19,6 +21,17 @@ pub struct LoadResult {
     pub load_time_ms: u64,
 }
 
+/// 前処理まで行った画像読み込みの結果情報
+#[derive(Debug, Clone)]
+pub struct PreprocessedLoad {
+    /// 前処理済みの画像
+    pub image: DynamicImage,
+    /// 前処理前（読み込み時）の画像サイズ
+    pub loaded_dimensions: (u32, u32),
+    /// 読み込み時にリサイズされたかどうか
+    pub was_resized: bool,
+}
+
 /// 画像読み込みバックエンドのトレイト
 #[automock]
 #[async_trait]
@@ -29,6 +42,32 @@ pub trait ImageLoaderBackend: Send + Sync {
     /// ファイルパスから画像を読み込む
     async fn load_from_path(&self, path: &Path) -> Result<LoadResult>;
 
+    /// ファイルパスから画像を読み込み、前処理を適用する
+    ///
+    /// 既定の実装は読み込み後に別のブロッキングタスクで前処理する。
+    /// デコードと同じブロッキングタスクで前処理できる実装は上書きする
+    async fn load_preprocessed(
+        &self,
+        path: &Path,
+        preprocessor: Arc<dyn ImagePreprocessor>,
+    ) -> Result<PreprocessedLoad> {
+        let load_result = self.load_from_path(path).await?;
+        let loaded_dimensions = (load_result.image.width(), load_result.image.height());
+        let image = tokio::task::spawn_blocking({
+            let path = path.to_path_buf();
+            move || preprocessor.apply(load_result.image, &path)
+        })
+        .await
+        .context("Failed to spawn blocking task for image preprocessing")?
+        .with_context(|| format!("Failed to preprocess image: {}", path.display()))?;
+
+        Ok(PreprocessedLoad {
+            image,
+            loaded_dimensions,
+            was_resized: load_result.was_resized,
+        })
+    }
+
     /// 画像フォーマットを指定して読み込む
     async fn load_with_format(&self, data: &[u8], format: image::ImageFormat)
         -> Result<LoadResult>;
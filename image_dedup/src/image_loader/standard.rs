@@ -1,8 +1,10 @@
-use super::{ImageLoaderBackend, LoadResult};
+use super::{ImageLoaderBackend, LoadResult, PreprocessedLoad};
+use crate::image_preprocessor::ImagePreprocessor;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use image::{DynamicImage, ImageFormat};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
 
 /// 標準的な画像ローダー実装
@@ -101,6 +103,36 @@ impl ImageLoaderBackend for StandardImageLoader {
         })
     }
 
+    async fn load_preprocessed(
+        &self,
+        path: &Path,
+        preprocessor: Arc<dyn ImagePreprocessor>,
+    ) -> Result<PreprocessedLoad> {
+        // デコード・リサイズ・前処理をまとめて1つのブロッキングタスクで行う
+        tokio::task::spawn_blocking({
+            let loader = self.clone();
+            let path = path.to_path_buf();
+            move || {
+                let image = image::open(&path).with_context(|| {
+                    format!("Failed to load image from path: {}", path.display())
+                })?;
+                let (image, was_resized) = loader.resize_if_needed(image);
+                let loaded_dimensions = (image.width(), image.height());
+                let image = preprocessor
+                    .apply(image, &path)
+                    .with_context(|| format!("Failed to preprocess image: {}", path.display()))?;
+
+                Ok(PreprocessedLoad {
+                    image,
+                    loaded_dimensions,
+                    was_resized,
+                })
+            }
+        })
+        .await
+        .context("Failed to spawn blocking task for image loading")?
+    }
+
     async fn load_with_format(&self, data: &[u8], format: ImageFormat) -> Result<LoadResult> {
         let start_time = Instant::now();
 
@@ -197,6 +229,25 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_load_preprocessed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let image_path = temp_dir.path().join("preprocessed_test.png");
+
+        let img = image::RgbImage::new(300, 200);
+        img.save(&image_path)?;
+
+        let loader = StandardImageLoader::with_max_dimension(150);
+        let preprocessor = Arc::new(crate::image_preprocessor::Resize::new(30));
+        let result = loader.load_preprocessed(&image_path, preprocessor).await?;
+
+        // 記録する寸法は読み込み時のもの、画像は前処理済み
+        assert_eq!(result.loaded_dimensions, (150, 100));
+        assert!(result.was_resized);
+        assert_eq!((result.image.width(), result.image.height()), (30, 20));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_load_from_bytes() -> Result<()> {
         let temp_dir = tempdir()?;
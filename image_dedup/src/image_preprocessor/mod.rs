@@ -0,0 +1,140 @@
+use crate::core::{PreprocessStep, Preprocessing};
+use anyhow::Result;
+use image::DynamicImage;
+use mockall::automock;
+use std::fmt;
+use std::path::Path;
+
+pub mod steps;
+
+pub use steps::{
+    Blur, Equalize, ExifOrient, FlattenAlpha, Grayscale, NormalizeContrast, Resize, TrimBorders,
+};
+
+/// 読み込んだ画像をハッシュ計算の前に変換する前処理のトレイト
+#[automock]
+pub trait ImagePreprocessor: Send + Sync {
+    /// 画像を変換（`source` は読み込み元のファイル。EXIFなど画素以外の情報を使う前処理が参照する）
+    fn apply(&self, image: DynamicImage, source: &Path) -> Result<DynamicImage>;
+
+    /// 前処理の名前（パラメータを含む）
+    fn name(&self) -> String;
+}
+
+/// 設定の1ステップから前処理を作成
+pub fn create_preprocessor(step: &PreprocessStep) -> Box<dyn ImagePreprocessor> {
+    match *step {
+        PreprocessStep::ExifOrient => Box::new(ExifOrient),
+        PreprocessStep::FlattenAlpha { background } => Box::new(FlattenAlpha::new(background)),
+        PreprocessStep::TrimBorders => Box::new(TrimBorders),
+        PreprocessStep::Resize { max_dimension } => Box::new(Resize::new(max_dimension)),
+        PreprocessStep::Grayscale => Box::new(Grayscale),
+        PreprocessStep::Blur { sigma } => Box::new(Blur::new(sigma)),
+        PreprocessStep::Equalize => Box::new(Equalize),
+        PreprocessStep::NormalizeContrast => Box::new(NormalizeContrast),
+    }
+}
+
+/// 複数の前処理を順に適用するパイプライン（空なら画像をそのまま返す）
+#[derive(Default)]
+pub struct PreprocessingPipeline {
+    steps: Vec<Box<dyn ImagePreprocessor>>,
+}
+
+impl PreprocessingPipeline {
+    /// 前処理を適用順に並べて作成
+    pub fn new(steps: Vec<Box<dyn ImagePreprocessor>>) -> Self {
+        Self { steps }
+    }
+
+    /// 設定から作成
+    pub fn from_config(preprocessing: &Preprocessing) -> Self {
+        Self::new(
+            preprocessing
+                .steps
+                .iter()
+                .map(create_preprocessor)
+                .collect(),
+        )
+    }
+
+    /// 前処理が1つもないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl ImagePreprocessor for PreprocessingPipeline {
+    fn apply(&self, image: DynamicImage, source: &Path) -> Result<DynamicImage> {
+        self.steps
+            .iter()
+            .try_fold(image, |image, step| step.apply(image, source))
+    }
+
+    fn name(&self) -> String {
+        let names: Vec<String> = self.steps.iter().map(|step| step.name()).collect();
+        names.join(" → ")
+    }
+}
+
+impl fmt::Debug for PreprocessingPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PreprocessingPipeline")
+            .field(&self.name())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::BackgroundColor;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_pipeline_applies_steps_in_order() {
+        let mut first = MockImagePreprocessor::new();
+        first
+            .expect_apply()
+            .times(1)
+            .returning(|image, _| Ok(image.crop_imm(0, 0, 4, 4)));
+        first.expect_name().return_const("first".to_string());
+        let mut second = MockImagePreprocessor::new();
+        second
+            .expect_apply()
+            .withf(|image, _| image.width() == 4)
+            .times(1)
+            .returning(|image, _| Ok(image.grayscale()));
+        second.expect_name().return_const("second".to_string());
+
+        let pipeline = PreprocessingPipeline::new(vec![Box::new(first), Box::new(second)]);
+        let image = pipeline
+            .apply(DynamicImage::new_rgb8(8, 8), Path::new("a.png"))
+            .unwrap();
+
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert!(!image.color().has_color());
+        assert_eq!(pipeline.name(), "first → second");
+    }
+
+    #[test]
+    fn test_pipeline_from_config() {
+        let preprocessing = Preprocessing {
+            steps: vec![
+                PreprocessStep::FlattenAlpha {
+                    background: BackgroundColor::BLACK,
+                },
+                PreprocessStep::Resize { max_dimension: 16 },
+            ],
+        };
+        let pipeline = PreprocessingPipeline::from_config(&preprocessing);
+        assert_eq!(pipeline.name(), "flatten_alpha(#000000) → resize(16)");
+
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 32, Rgba([9, 9, 9, 0])));
+        let image = pipeline.apply(image, Path::new("a.png")).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 8));
+        assert_eq!(image.to_rgb8().get_pixel(0, 0).0, [0, 0, 0]);
+
+        assert!(PreprocessingPipeline::from_config(&Preprocessing::default()).is_empty());
+    }
+}
@@ -0,0 +1,485 @@
+// 前処理の実装 - 向きの補正、透明部分の合成、余白の除去、縮小、グレースケール化、ぼかし、
+// ヒストグラムの平坦化、コントラストの正規化
+
+use super::ImagePreprocessor;
+use crate::core::BackgroundColor;
+use anyhow::Result;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader, RgbImage};
+use std::path::Path;
+
+/// 余白とみなす画素の各チャンネルの許容差（JPEGのノイズを吸収する）
+const BORDER_TOLERANCE: u8 = 24;
+/// 余白の行・列とみなすために許容差内に収まるべき画素の割合
+const BORDER_UNIFORMITY: f64 = 0.98;
+/// コントラストの正規化で両端から無視する画素の割合（外れ値の影響を抑える）
+const CONTRAST_CLIP: f64 = 0.01;
+
+/// EXIFの向きに従って回転・反転する（向きが読めない場合はそのまま）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExifOrient;
+
+impl ImagePreprocessor for ExifOrient {
+    fn apply(&self, mut image: DynamicImage, source: &Path) -> Result<DynamicImage> {
+        let orientation = read_orientation(source).unwrap_or(Orientation::NoTransforms);
+        image.apply_orientation(orientation);
+        Ok(image)
+    }
+
+    fn name(&self) -> String {
+        "exif_orient".to_string()
+    }
+}
+
+/// ファイルのメタデータから向きを読み取る（画素はデコードしない）
+fn read_orientation(path: &Path) -> Result<Orientation> {
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    Ok(decoder.orientation()?)
+}
+
+/// 透明な画素を背景色にアルファ合成する（アルファチャンネルがなければそのまま）
+#[derive(Debug, Clone, Copy)]
+pub struct FlattenAlpha {
+    background: BackgroundColor,
+}
+
+impl FlattenAlpha {
+    pub fn new(background: BackgroundColor) -> Self {
+        Self { background }
+    }
+}
+
+impl ImagePreprocessor for FlattenAlpha {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        Ok(flatten_alpha(image, self.background))
+    }
+
+    fn name(&self) -> String {
+        format!("flatten_alpha({})", self.background)
+    }
+}
+
+fn flatten_alpha(image: DynamicImage, background: BackgroundColor) -> DynamicImage {
+    if !image.color().has_alpha() {
+        return image;
+    }
+
+    let rgba = image.to_rgba8();
+    let flattened = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |channel: u8, back: u8| {
+            let alpha = u32::from(a);
+            ((u32::from(channel) * alpha + u32::from(back) * (255 - alpha) + 127) / 255) as u8
+        };
+        image::Rgb([
+            blend(r, background.0[0]),
+            blend(g, background.0[1]),
+            blend(b, background.0[2]),
+        ])
+    });
+    DynamicImage::ImageRgb8(flattened)
+}
+
+/// 上下左右の均一な色の余白を切り取る（画像全体が均一な場合はそのまま）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimBorders;
+
+impl ImagePreprocessor for TrimBorders {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        Ok(trim_borders(image))
+    }
+
+    fn name(&self) -> String {
+        "trim_borders".to_string()
+    }
+}
+
+fn trim_borders(image: DynamicImage) -> DynamicImage {
+    let pixels = image.to_rgba8();
+    let (width, height) = pixels.dimensions();
+    let pixel = |x: u32, y: u32| pixels.get_pixel(x, y).0;
+
+    let row = |y: u32| (0..width).map(|x| pixel(x, y)).collect::<Vec<_>>();
+    let top = border_depth(height, row);
+    let bottom = border_depth(height - top, |i| row(height - 1 - i));
+    if top + bottom >= height {
+        return image;
+    }
+
+    let rows = top..height - bottom;
+    let column = |x: u32| rows.clone().map(|y| pixel(x, y)).collect::<Vec<_>>();
+    let left = border_depth(width, column);
+    let right = border_depth(width - left, |i| column(width - 1 - i));
+    if left + right >= width || top + bottom + left + right == 0 {
+        return image;
+    }
+
+    image.crop_imm(left, top, width - left - right, height - top - bottom)
+}
+
+/// 外側から数えて、最も外側の行（列）と同じ色で均一な行（列）の数
+fn border_depth<F>(count: u32, line: F) -> u32
+where
+    F: Fn(u32) -> Vec<[u8; 4]>,
+{
+    if count == 0 {
+        return 0;
+    }
+    let reference = mean_color(&line(0));
+    (0..count)
+        .take_while(|&i| is_uniform(&line(i), reference))
+        .count() as u32
+}
+
+/// 画素の平均色
+fn mean_color(pixels: &[[u8; 4]]) -> [u8; 4] {
+    let mut sums = [0u64; 4];
+    for pixel in pixels {
+        for (sum, &channel) in sums.iter_mut().zip(pixel) {
+            *sum += u64::from(channel);
+        }
+    }
+    let count = pixels.len().max(1) as u64;
+    sums.map(|sum| (sum / count) as u8)
+}
+
+/// ほぼすべての画素が基準色の許容差内に収まるか
+fn is_uniform(pixels: &[[u8; 4]], reference: [u8; 4]) -> bool {
+    let matching = pixels
+        .iter()
+        .filter(|pixel| {
+            pixel
+                .iter()
+                .zip(reference)
+                .all(|(&channel, reference)| channel.abs_diff(reference) <= BORDER_TOLERANCE)
+        })
+        .count();
+    matching as f64 >= pixels.len() as f64 * BORDER_UNIFORMITY
+}
+
+/// 長辺が上限を超える場合にアスペクト比を保って縮小する
+#[derive(Debug, Clone, Copy)]
+pub struct Resize {
+    max_dimension: u32,
+}
+
+impl Resize {
+    pub fn new(max_dimension: u32) -> Self {
+        Self { max_dimension }
+    }
+}
+
+impl ImagePreprocessor for Resize {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        if image.width().max(image.height()) <= self.max_dimension {
+            return Ok(image);
+        }
+        // resizeは指定した枠に収まるようアスペクト比を保つ
+        Ok(image.resize(
+            self.max_dimension,
+            self.max_dimension,
+            image::imageops::FilterType::Lanczos3,
+        ))
+    }
+
+    fn name(&self) -> String {
+        format!("resize({})", self.max_dimension)
+    }
+}
+
+/// グレースケールに変換する（アルファチャンネルは保つ）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Grayscale;
+
+impl ImagePreprocessor for Grayscale {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        Ok(image.grayscale())
+    }
+
+    fn name(&self) -> String {
+        "grayscale".to_string()
+    }
+}
+
+/// ガウスぼかし
+#[derive(Debug, Clone, Copy)]
+pub struct Blur {
+    sigma: f32,
+}
+
+impl Blur {
+    pub fn new(sigma: f32) -> Self {
+        Self { sigma }
+    }
+}
+
+impl ImagePreprocessor for Blur {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        Ok(image.blur(self.sigma))
+    }
+
+    fn name(&self) -> String {
+        format!("blur({})", self.sigma)
+    }
+}
+
+/// 輝度のヒストグラムを平坦化する
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Equalize;
+
+impl ImagePreprocessor for Equalize {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        let histogram = luma_histogram(&image);
+        let total: u64 = histogram.iter().sum();
+        let darkest = histogram.iter().copied().find(|&count| count > 0);
+        let Some(darkest) = darkest.filter(|&darkest| darkest < total) else {
+            // 単色の画像は平坦化できない
+            return Ok(image);
+        };
+
+        let mut cumulative = 0;
+        let table = histogram.map(|count| {
+            cumulative += count;
+            (cumulative.saturating_sub(darkest) as f64 * 255.0 / (total - darkest) as f64).round()
+                as u8
+        });
+        Ok(map_levels(image, |level| table[usize::from(level)]))
+    }
+
+    fn name(&self) -> String {
+        "equalize".to_string()
+    }
+}
+
+/// 輝度の分布を全階調に引き伸ばす
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeContrast;
+
+impl ImagePreprocessor for NormalizeContrast {
+    fn apply(&self, image: DynamicImage, _source: &Path) -> Result<DynamicImage> {
+        Ok(normalize_contrast(image))
+    }
+
+    fn name(&self) -> String {
+        "normalize_contrast".to_string()
+    }
+}
+
+fn normalize_contrast(image: DynamicImage) -> DynamicImage {
+    let histogram = luma_histogram(&image);
+    let total: u64 = histogram.iter().sum();
+    let clip = (total as f64 * CONTRAST_CLIP) as u64;
+    let low = clipped_level(&histogram, clip, 0..256);
+    let high = clipped_level(&histogram, clip, (0..256).rev());
+    if high <= low {
+        return image;
+    }
+
+    let scale = 255.0 / (high - low);
+    map_levels(image, |level| {
+        ((f32::from(level) - low).max(0.0) * scale)
+            .round()
+            .min(255.0) as u8
+    })
+}
+
+/// `levels` の順に累積して、画素数が `clip` を超えた階調
+fn clipped_level(
+    histogram: &[u64; 256],
+    clip: u64,
+    mut levels: impl Iterator<Item = usize>,
+) -> f32 {
+    let mut cumulative = 0;
+    levels
+        .find(|&level| {
+            cumulative += histogram[level];
+            cumulative > clip
+        })
+        .unwrap_or(0) as f32
+}
+
+/// 輝度のヒストグラム
+fn luma_histogram(image: &DynamicImage) -> [u64; 256] {
+    let mut histogram = [0u64; 256];
+    for pixel in image.to_luma8().pixels() {
+        histogram[usize::from(pixel.0[0])] += 1;
+    }
+    histogram
+}
+
+/// 階調の変換を全チャンネルに適用（色相を保つため各チャンネルに同じ変換を使う）
+fn map_levels(image: DynamicImage, map: impl Fn(u8) -> u8) -> DynamicImage {
+    let stretch = |channel: &mut u8| *channel = map(*channel);
+    if image.color().has_alpha() {
+        let mut rgba = image.to_rgba8();
+        for pixel in rgba.pixels_mut() {
+            pixel.0[..3].iter_mut().for_each(stretch);
+        }
+        DynamicImage::ImageRgba8(rgba)
+    } else {
+        let mut rgb = image.to_rgb8();
+        for pixel in rgb.pixels_mut() {
+            pixel.0.iter_mut().for_each(stretch);
+        }
+        DynamicImage::ImageRgb8(rgb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, ImageEncoder, Rgb, Rgba, RgbaImage};
+    use tempfile::TempDir;
+
+    /// 横方向のグラデーション
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 100])
+        })
+    }
+
+    fn levels(image: &DynamicImage) -> Vec<u8> {
+        image.to_luma8().pixels().map(|pixel| pixel.0[0]).collect()
+    }
+
+    /// 向き（EXIFのOrientationタグ）だけを含むビッグエンディアンのEXIF
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut exif = b"MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        exif.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+        exif.extend_from_slice(&orientation.to_be_bytes());
+        exif.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        exif
+    }
+
+    #[test]
+    fn test_exif_orient_rotates_image() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("rotated.png");
+        let image = gradient(8, 4);
+        let mut encoder =
+            image::codecs::png::PngEncoder::new(std::fs::File::create(&path).unwrap());
+        encoder.set_exif_metadata(exif_with_orientation(6)).unwrap();
+        encoder
+            .write_image(&image, 8, 4, image::ExtendedColorType::Rgb8)
+            .unwrap();
+
+        let oriented = ExifOrient
+            .apply(DynamicImage::ImageRgb8(image.clone()), &path)
+            .unwrap();
+        assert_eq!(oriented, DynamicImage::ImageRgb8(image.clone()).rotate90());
+
+        // 向きの情報がないファイルはそのまま
+        let plain = temp_dir.path().join("plain.png");
+        image.save(&plain).unwrap();
+        let unchanged = ExifOrient
+            .apply(DynamicImage::ImageRgb8(image.clone()), &plain)
+            .unwrap();
+        assert_eq!(unchanged.dimensions(), (8, 4));
+    }
+
+    #[test]
+    fn test_flatten_alpha_composites_onto_background() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 1, |x, _| {
+            Rgba([200, 0, 0, [0, 128, 255, 255][x as usize]])
+        }));
+
+        let flattened = flatten_alpha(image, BackgroundColor::WHITE).to_rgb8();
+        assert_eq!(flattened.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(flattened.get_pixel(1, 0).0, [227, 127, 127]);
+        assert_eq!(flattened.get_pixel(3, 0).0, [200, 0, 0]);
+
+        let opaque = DynamicImage::ImageRgb8(gradient(8, 8));
+        assert_eq!(
+            flatten_alpha(opaque.clone(), BackgroundColor::BLACK),
+            opaque
+        );
+    }
+
+    #[test]
+    fn test_trim_borders_removes_letterbox() {
+        let content = gradient(40, 30);
+        let letterboxed = RgbImage::from_fn(60, 50, |x, y| {
+            if (10..50).contains(&x) && (8..38).contains(&y) {
+                *content.get_pixel(x - 10, y - 8)
+            } else {
+                Rgb([3, 2, 4])
+            }
+        });
+
+        let trimmed = trim_borders(DynamicImage::ImageRgb8(letterboxed));
+        assert_eq!(trimmed.dimensions(), (40, 30));
+        assert_eq!(trimmed.to_rgb8(), content);
+    }
+
+    #[test]
+    fn test_trim_borders_keeps_uniform_and_borderless_images() {
+        let uniform = DynamicImage::ImageRgb8(RgbImage::from_pixel(20, 20, Rgb([9, 9, 9])));
+        assert_eq!(trim_borders(uniform).dimensions(), (20, 20));
+
+        let borderless = DynamicImage::ImageRgb8(gradient(20, 20));
+        assert_eq!(trim_borders(borderless).dimensions(), (20, 20));
+    }
+
+    #[test]
+    fn test_resize_only_shrinks() {
+        let source = Path::new("a.png");
+        let large = DynamicImage::ImageRgb8(gradient(100, 50));
+        let resized = Resize::new(40).apply(large, source).unwrap();
+        assert_eq!(resized.dimensions(), (40, 20));
+
+        let small = DynamicImage::ImageRgb8(gradient(30, 10));
+        assert_eq!(
+            Resize::new(40).apply(small, source).unwrap().dimensions(),
+            (30, 10)
+        );
+    }
+
+    #[test]
+    fn test_blur_and_grayscale() {
+        let source = Path::new("a.png");
+        let checker = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, y| {
+            if (x + y) % 2 == 0 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        }));
+
+        let gray = Grayscale.apply(checker.clone(), source).unwrap();
+        assert!(!gray.color().has_color());
+
+        let blurred = levels(&Blur::new(2.0).apply(gray.clone(), source).unwrap());
+        let spread = |levels: &[u8]| levels.iter().max().unwrap() - levels.iter().min().unwrap();
+        assert!(spread(&blurred) < spread(&levels(&gray)));
+    }
+
+    #[test]
+    fn test_equalize_spreads_levels() {
+        let source = Path::new("a.png");
+        let dull = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 1, |x, _| {
+            let level = 120 + (x / 8) as u8;
+            Rgb([level, level, level])
+        }));
+
+        let equalized = levels(&Equalize.apply(dull, source).unwrap());
+        assert_eq!(*equalized.iter().min().unwrap(), 0);
+        assert_eq!(*equalized.iter().max().unwrap(), 255);
+
+        let flat = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([50, 50, 50])));
+        assert_eq!(Equalize.apply(flat.clone(), source).unwrap(), flat);
+    }
+
+    #[test]
+    fn test_normalize_contrast_stretches_range() {
+        let dull = DynamicImage::ImageRgb8(RgbImage::from_fn(100, 1, |x, _| {
+            let level = 100 + (x / 2) as u8;
+            Rgb([level, level, level])
+        }));
+
+        let normalized = levels(&normalize_contrast(dull));
+        assert_eq!(*normalized.iter().min().unwrap(), 0);
+        assert_eq!(*normalized.iter().max().unwrap(), 255);
+    }
+}
@@ -9,6 +9,7 @@ pub mod services;
 
 // 従来のモジュール
 pub mod image_loader;
+pub mod image_preprocessor;
 pub mod perceptual_hash;
 pub mod storage;
 
@@ -28,6 +29,8 @@ pub use engine::{
 };
 // services モジュールから明示的にエクスポート
 pub use services::{
-    process_single_file, spawn_result_collector, ConsoleProgressReporter, DefaultProcessingConfig,
-    JsonHashPersistence, MemoryHashPersistence, NoOpProgressReporter, StreamingJsonHashPersistence,
+    process_single_file, spawn_result_collector, BinaryHashPersistence, ConsoleProgressReporter,
+    DatabaseFormat, DefaultProcessingConfig, JsonHashPersistence, JsonlHashPersistence,
+    MemoryHashPersistence, NoOpProgressReporter, SqliteHashPersistence,
+    StreamingJsonHashPersistence,
 };
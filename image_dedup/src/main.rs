@@ -2,6 +2,7 @@ use anyhow::Result;
 use clap::Parser;
 use image_dedup::cli::commands;
 use image_dedup::cli::{Cli, Commands};
+use image_dedup::core::Preprocessing;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -13,29 +14,68 @@ async fn main() -> Result<()> {
             output,
             threads,
             force,
+            update,
+            resume,
             algorithm,
             hash_size,
             config_preset,
             config,
+            content_hash,
+            exact_only,
+            rotation_invariant,
+            color_signature,
+            flatten_alpha,
+            trim_borders,
+            normalize_contrast,
+            format,
         } => {
-            commands::execute_scan(
+            commands::execute_scan(commands::ExtendedScanConfig {
                 target_directory,
                 output,
                 threads,
                 force,
+                update,
                 algorithm,
                 hash_size,
                 config_preset,
-                config,
-            )
+                config_file: config,
+                content_hash,
+                exact_only,
+                rotation_invariant,
+                color_signature,
+                preprocessing: Preprocessing::from_flags(
+                    flatten_alpha,
+                    trim_borders,
+                    normalize_contrast,
+                ),
+                format,
+                resume,
+            })
             .await?;
         }
         Commands::FindDups {
             hash_database,
             output,
             threshold,
+            grouping,
+            min_segment_matches,
+            vote,
+            algorithm_threshold,
+            max_color_distance,
+            keep,
         } => {
-            commands::execute_find_dups(hash_database, output, threshold).await?;
+            commands::execute_find_dups(commands::FindDupsOptions {
+                hash_database,
+                output,
+                threshold,
+                grouping,
+                min_segment_matches,
+                vote,
+                algorithm_thresholds: algorithm_threshold,
+                max_color_distance,
+                keep,
+            })
+            .await?;
         }
         Commands::FilterDuplicates {
             input_json,
@@ -48,8 +88,43 @@ async fn main() -> Result<()> {
             action,
             dest,
             no_confirm,
+            scan_db,
+            keep,
+            absolute_symlinks,
+            allow_lossy_links,
+            trash_dir,
+            journal,
+            dry_run,
+            plan,
+            plan_output,
+        } => {
+            commands::execute_process_with_config(commands::ProcessConfig {
+                duplicate_list,
+                action,
+                dest,
+                no_confirm,
+                scan_database: scan_db,
+                keep,
+                absolute_symlinks,
+                allow_lossy_links,
+                trash_dir,
+                journal,
+                dry_run,
+                plan,
+                plan_output,
+            })
+            .await?;
+        }
+        Commands::Restore { journal } => {
+            commands::execute_restore(journal).await?;
+        }
+        Commands::Convert {
+            input,
+            output,
+            format,
+            force,
         } => {
-            commands::execute_process(duplicate_list, action, dest, no_confirm).await?;
+            commands::execute_convert(input, output, format, force).await?;
         }
     }
 
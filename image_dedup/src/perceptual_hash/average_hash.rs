@@ -137,7 +137,7 @@ impl DifferenceHasher {
         self.hash_size
     }
 
-    fn compute_difference_hash_from_gray(
+    pub(crate) fn compute_difference_hash_from_gray(
         gray_image: image::ImageBuffer<image::Luma<u8>, Vec<u8>>,
         size: u32,
     ) -> Vec<u8> {
@@ -0,0 +1,209 @@
+use super::wavelet_hash::{median_of_sorted, pack_bits};
+use super::{HashAlgorithm, HashResult, PerceptualHashBackend};
+use anyhow::Result;
+use async_trait::async_trait;
+use image::DynamicImage;
+use std::time::Instant;
+
+/// ブロック平均ベースの知覚ハッシュ実装（blockhash）
+///
+/// 画像を `size` × `size` のブロックに分割して各ブロックの平均輝度を求め、
+/// 上から順に `BANDS` 個の帯に分けた中で、帯ごとの中央値より明るいかどうかをビットとする。
+/// 縮小せずに元画像の全ピクセルを使うため、縮小時の補間の影響を受けにくい
+#[derive(Clone, Debug)]
+pub struct BlockMeanHasher {
+    algorithm: HashAlgorithm,
+    hash_size: u32,
+}
+
+/// 中央値を求める帯の数（帯ごとに比較することで部分的な明るさの偏りに強くなる）
+const BANDS: usize = 4;
+
+impl Default for BlockMeanHasher {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl BlockMeanHasher {
+    pub fn new(size: u32) -> Self {
+        Self {
+            algorithm: HashAlgorithm::BlockMean { size },
+            hash_size: size,
+        }
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.hash_size
+    }
+
+    fn compute_block_mean_hash_from_gray(
+        gray_image: image::ImageBuffer<image::Luma<u8>, Vec<u8>>,
+        size: u32,
+    ) -> Vec<u8> {
+        let (width, height) = gray_image.dimensions();
+        let blocks = (size * size) as usize;
+
+        // 各ピクセルを含むブロックに輝度を加算
+        let mut sums = vec![0u64; blocks];
+        let mut counts = vec![0u64; blocks];
+        for (x, y, pixel) in gray_image.enumerate_pixels() {
+            let block_x = (u64::from(x) * u64::from(size) / u64::from(width)) as usize;
+            let block_y = (u64::from(y) * u64::from(size) / u64::from(height)) as usize;
+            let block = block_y * size as usize + block_x;
+            sums[block] += u64::from(pixel[0]);
+            counts[block] += 1;
+        }
+        let means: Vec<f32> = sums
+            .iter()
+            .zip(&counts)
+            .map(|(&sum, &count)| sum as f32 / count.max(1) as f32)
+            .collect();
+
+        // 帯ごとの中央値と比較（ブロック数が帯の数で割り切れない場合は全体で1つ）
+        let band_size = if blocks.is_multiple_of(BANDS) {
+            blocks / BANDS
+        } else {
+            blocks
+        };
+        let bits = means.chunks(band_size).flat_map(|band| {
+            let mut sorted = band.to_vec();
+            sorted.sort_by(f32::total_cmp);
+            let median = median_of_sorted(&sorted);
+            band.iter().map(move |&mean| mean > median)
+        });
+
+        pack_bits(bits)
+    }
+}
+
+#[async_trait]
+impl PerceptualHashBackend for BlockMeanHasher {
+    async fn generate_hash(&self, image: &DynamicImage) -> Result<HashResult> {
+        let start_time = Instant::now();
+
+        let hash_data = tokio::task::spawn_blocking({
+            let size = self.hash_size;
+            // ブロックより小さい画像は、すべてのブロックが1ピクセル以上を含むよう拡大する
+            let gray_image = if image.width() < size || image.height() < size {
+                image
+                    .resize_exact(
+                        image.width().max(size),
+                        image.height().max(size),
+                        image::imageops::FilterType::Nearest,
+                    )
+                    .to_luma8()
+            } else {
+                image.to_luma8()
+            };
+            move || Self::compute_block_mean_hash_from_gray(gray_image, size)
+        })
+        .await?;
+
+        let computation_time_ms = start_time.elapsed().as_millis() as u64;
+
+        Ok(HashResult {
+            hash_data,
+            hash_size_bits: self.hash_size * self.hash_size,
+            algorithm: self.algorithm.clone(),
+            computation_time_ms,
+            source_dimensions: (image.width(), image.height()),
+        })
+    }
+
+    fn calculate_distance(&self, hash1: &HashResult, hash2: &HashResult) -> Result<u32> {
+        if hash1.algorithm != hash2.algorithm {
+            anyhow::bail!("Cannot compare hashes from different algorithms");
+        }
+
+        if hash1.hash_data.len() != hash2.hash_data.len() {
+            anyhow::bail!("Cannot compare hashes of different sizes");
+        }
+
+        let distance = hash1
+            .hash_data
+            .iter()
+            .zip(hash2.hash_data.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+
+        Ok(distance)
+    }
+
+    fn algorithm(&self) -> &HashAlgorithm {
+        &self.algorithm
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        "Block Mean Hash"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perceptual_hash::test_images::{recompress_jpeg, sample_image};
+    use image::{GrayImage, Luma};
+
+    #[tokio::test]
+    async fn test_block_mean_hash() {
+        let hasher = BlockMeanHasher::new(8);
+
+        let result = hasher.generate_hash(&sample_image(1)).await.unwrap();
+
+        assert_eq!(result.hash_size_bits, 64);
+        assert_eq!(result.hash_data.len(), 8);
+        assert_eq!(result.algorithm, HashAlgorithm::BlockMean { size: 8 });
+        assert_eq!(hasher.algorithm_name(), "Block Mean Hash");
+    }
+
+    #[test]
+    fn test_block_means_compared_per_band() {
+        // 左半分が明るい4×4ブロックの画像。帯（1行ずつ）ごとに左2ブロックだけが中央値を超える
+        let image = GrayImage::from_fn(8, 8, |x, y| Luma([if x < 4 { 200 } else { y as u8 }]));
+
+        let hash = BlockMeanHasher::compute_block_mean_hash_from_gray(image, 4);
+
+        assert_eq!(hash, vec![0b1100_1100, 0b1100_1100]);
+    }
+
+    #[tokio::test]
+    async fn test_block_mean_hash_small_image() {
+        let hasher = BlockMeanHasher::new(16);
+        let result = hasher
+            .generate_hash(&DynamicImage::new_rgb8(4, 4))
+            .await
+            .unwrap();
+        assert_eq!(result.hash_data.len(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_block_mean_hash_resists_resize_and_recompression() {
+        let hasher = BlockMeanHasher::new(8);
+        let original = sample_image(1);
+
+        let original_hash = hasher.generate_hash(&original).await.unwrap();
+        let resized = hasher
+            .generate_hash(&original.resize_exact(160, 120, image::imageops::FilterType::Triangle))
+            .await
+            .unwrap();
+        let recompressed = hasher
+            .generate_hash(&recompress_jpeg(&original, 40))
+            .await
+            .unwrap();
+        let different = hasher.generate_hash(&sample_image(4)).await.unwrap();
+
+        let distance = |hash| hasher.calculate_distance(&original_hash, hash).unwrap();
+        assert!(distance(&resized) <= 4, "resized: {}", distance(&resized));
+        assert!(
+            distance(&recompressed) <= 4,
+            "recompressed: {}",
+            distance(&recompressed)
+        );
+        assert!(
+            distance(&different) > 16,
+            "different: {}",
+            distance(&different)
+        );
+    }
+}
@@ -0,0 +1,241 @@
+// CropResistantアルゴリズムの設定
+
+use super::config::{AlgorithmConfig, ParameterInfo, ParameterType};
+use super::crop_resistant_hash::CropResistantHasher;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 切り抜きに強い部分領域ハッシュの設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropResistantConfig {
+    /// 全体と各領域のハッシュサイズ（通常は8, 16など）
+    pub size: u32,
+    /// 領域分割に使う縮小画像の長辺
+    #[serde(default = "default_segmentation_size")]
+    pub segmentation_size: u32,
+    /// 縮小画像に占める割合がこれ未満の領域を無視する（%）
+    #[serde(default = "default_min_segment_percent")]
+    pub min_segment_percent: f64,
+    /// 大きい順に使う領域の最大数
+    #[serde(default = "default_max_segments")]
+    pub max_segments: usize,
+}
+
+fn default_segmentation_size() -> u32 {
+    128
+}
+
+fn default_min_segment_percent() -> f64 {
+    0.5
+}
+
+fn default_max_segments() -> usize {
+    16
+}
+
+impl AlgorithmConfig for CropResistantConfig {
+    type Algorithm = CropResistantHasher;
+
+    fn create_hasher(&self) -> Result<Self::Algorithm> {
+        Ok(CropResistantHasher::with_segmentation(
+            self.size,
+            self.segmentation_size,
+            self.min_segment_percent,
+            self.max_segments,
+        ))
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        "crop_resistant"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| anyhow::anyhow!("JSON変換エラー: {}", e))
+    }
+
+    fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow::anyhow!("JSON解析エラー: {}", e))
+    }
+
+    fn description(&self) -> &'static str {
+        "Crop-Resistant Hash - Difference hashes of the whole image and of bright/dark regions. Finds cropped and letterboxed copies."
+    }
+
+    fn default_config() -> Self {
+        Self {
+            size: 8,
+            segmentation_size: default_segmentation_size(),
+            min_segment_percent: default_min_segment_percent(),
+            max_segments: default_max_segments(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.size < 2 {
+            anyhow::bail!("Size must be at least 2");
+        }
+
+        if self.size > 32 {
+            anyhow::bail!("Size must be 32 or less for performance reasons");
+        }
+
+        if !(16..=1024).contains(&self.segmentation_size) {
+            anyhow::bail!("Segmentation size must be between 16 and 1024");
+        }
+
+        if !(self.min_segment_percent > 0.0 && self.min_segment_percent <= 50.0) {
+            anyhow::bail!("Minimum segment percent must be greater than 0 and at most 50");
+        }
+
+        if !(1..=64).contains(&self.max_segments) {
+            anyhow::bail!("Maximum segments must be between 1 and 64");
+        }
+
+        Ok(())
+    }
+
+    fn parameter_info() -> Vec<ParameterInfo> {
+        vec![
+            ParameterInfo {
+                name: "size".to_string(),
+                param_type: ParameterType::Integer {
+                    min: Some(2),
+                    max: Some(32),
+                },
+                description: "Hash size of the whole image and of each region (typically 8 or 16)"
+                    .to_string(),
+                default_value: Some("8".to_string()),
+                required: true,
+            },
+            ParameterInfo {
+                name: "segmentation_size".to_string(),
+                param_type: ParameterType::Integer {
+                    min: Some(16),
+                    max: Some(1024),
+                },
+                description: "Longest side of the downscaled image used to find regions"
+                    .to_string(),
+                default_value: Some("128".to_string()),
+                required: false,
+            },
+            ParameterInfo {
+                name: "min_segment_percent".to_string(),
+                param_type: ParameterType::Float {
+                    min: Some(0.0),
+                    max: Some(50.0),
+                },
+                description: "Regions smaller than this share of the image are ignored (%)"
+                    .to_string(),
+                default_value: Some("0.5".to_string()),
+                required: false,
+            },
+            ParameterInfo {
+                name: "max_segments".to_string(),
+                param_type: ParameterType::Integer {
+                    min: Some(1),
+                    max: Some(64),
+                },
+                description: "Maximum number of regions hashed per image, largest first"
+                    .to_string(),
+                default_value: Some("16".to_string()),
+                required: false,
+            },
+        ]
+    }
+}
+
+impl Default for CropResistantConfig {
+    fn default() -> Self {
+        Self::default_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perceptual_hash::PerceptualHashBackend;
+
+    #[test]
+    fn test_crop_resistant_config_creation() {
+        let config = CropResistantConfig::default();
+        assert_eq!(config.size, 8);
+        assert_eq!(config.segmentation_size, 128);
+        assert_eq!(config.max_segments, 16);
+        assert_eq!(config.algorithm_name(), "crop_resistant");
+    }
+
+    #[test]
+    fn test_crop_resistant_config_validation() {
+        assert!(CropResistantConfig::default().validate().is_ok());
+
+        let invalid_configs = [
+            CropResistantConfig {
+                size: 1,
+                ..Default::default()
+            },
+            CropResistantConfig {
+                size: 64,
+                ..Default::default()
+            },
+            CropResistantConfig {
+                segmentation_size: 8,
+                ..Default::default()
+            },
+            CropResistantConfig {
+                min_segment_percent: 0.0,
+                ..Default::default()
+            },
+            CropResistantConfig {
+                max_segments: 0,
+                ..Default::default()
+            },
+        ];
+        for config in invalid_configs {
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn test_crop_resistant_config_json_serialization() {
+        let config = CropResistantConfig {
+            size: 16,
+            max_segments: 8,
+            ..Default::default()
+        };
+
+        let json = config.to_json().unwrap();
+        assert!(json.contains("\"size\": 16"));
+        assert!(json.contains("\"max_segments\": 8"));
+
+        let deserialized = CropResistantConfig::from_json(&json).unwrap();
+        assert_eq!(deserialized.size, 16);
+        assert_eq!(deserialized.max_segments, 8);
+
+        // 領域分割のパラメータは省略できる
+        let deserialized = CropResistantConfig::from_json(r#"{"size": 8}"#).unwrap();
+        assert_eq!(deserialized.segmentation_size, 128);
+        assert_eq!(deserialized.min_segment_percent, 0.5);
+    }
+
+    #[test]
+    fn test_crop_resistant_config_hasher_creation() {
+        let config = CropResistantConfig {
+            segmentation_size: 256,
+            ..Default::default()
+        };
+        let hasher = config.create_hasher().unwrap();
+
+        assert_eq!(hasher.algorithm_name(), "Crop-Resistant Hash");
+        assert_eq!(hasher.get_size(), 8);
+        assert_eq!(hasher.get_segmentation_size(), 256);
+    }
+
+    #[test]
+    fn test_crop_resistant_config_parameter_info() {
+        let params = CropResistantConfig::parameter_info();
+        assert_eq!(params.len(), 4);
+        assert_eq!(params[0].name, "size");
+        assert!(params[0].required);
+        assert!(params[1..].iter().all(|param| !param.required));
+    }
+}
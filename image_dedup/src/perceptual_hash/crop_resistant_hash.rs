@@ -0,0 +1,382 @@
+use super::average_hash::DifferenceHasher;
+use super::{HashAlgorithm, HashResult, PerceptualHashBackend};
+use anyhow::Result;
+use async_trait::async_trait;
+use image::{DynamicImage, GrayImage};
+use std::time::Instant;
+
+/// 切り抜きに強い部分領域ベースの知覚ハッシュ実装
+///
+/// 全体のハッシュに加えて、縮小した画像を明暗で二値化した連結領域ごとに
+/// 元画像の外接矩形を差分ハッシュにする。領域は画像の内容から決まるため、
+/// 切り抜きや余白の追加で枠が変わっても、内側に残った領域のハッシュは変わらない
+#[derive(Clone, Debug)]
+pub struct CropResistantHasher {
+    algorithm: HashAlgorithm,
+    hash_size: u32,
+    segmentation_size: u32,
+    min_segment_percent: f64,
+    max_segments: usize,
+}
+
+/// 明暗の境界となる輝度（画像ごとに変えると切り抜きで領域が変わるため固定）
+const BRIGHTNESS_THRESHOLD: u8 = 128;
+
+/// 領域分割の前にかけるぼかしの強さ
+const BLUR_SIGMA: f32 = 1.0;
+
+/// 縮小後の画像上の連結領域
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    pixels: u32,
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+}
+
+impl Default for CropResistantHasher {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl CropResistantHasher {
+    pub fn new(size: u32) -> Self {
+        Self::with_segmentation(size, 128, 0.5, 16)
+    }
+
+    /// 領域分割の設定を指定して作成
+    ///
+    /// `segmentation_size` は領域分割に使う縮小画像の長辺、`min_segment_percent` は
+    /// 縮小画像に占める割合がこれ未満の領域を無視する閾値（%）、`max_segments` は大きい順に使う領域数
+    pub fn with_segmentation(
+        size: u32,
+        segmentation_size: u32,
+        min_segment_percent: f64,
+        max_segments: usize,
+    ) -> Self {
+        Self {
+            algorithm: HashAlgorithm::CropResistant { size },
+            hash_size: size,
+            segmentation_size,
+            min_segment_percent,
+            max_segments,
+        }
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.hash_size
+    }
+
+    pub fn get_segmentation_size(&self) -> u32 {
+        self.segmentation_size
+    }
+
+    /// 差分ハッシュを計算し、このハッシャーのアルゴリズムとして返す
+    fn difference_hash(&self, image: &DynamicImage, start_time: Instant) -> HashResult {
+        let gray_image = image
+            .resize_exact(
+                self.hash_size + 1,
+                self.hash_size,
+                image::imageops::FilterType::Lanczos3,
+            )
+            .to_luma8();
+
+        HashResult {
+            hash_data: DifferenceHasher::compute_difference_hash_from_gray(
+                gray_image,
+                self.hash_size,
+            ),
+            hash_size_bits: self.hash_size * self.hash_size,
+            algorithm: self.algorithm.clone(),
+            computation_time_ms: start_time.elapsed().as_millis() as u64,
+            source_dimensions: (image.width(), image.height()),
+        }
+    }
+
+    /// 二値化した画像の連結領域（4近傍）を大きい順に取得
+    fn find_segments(gray_image: &GrayImage, min_pixels: u32) -> Vec<Segment> {
+        let (width, height) = gray_image.dimensions();
+        let is_bright = |x: u32, y: u32| gray_image.get_pixel(x, y)[0] >= BRIGHTNESS_THRESHOLD;
+        let mut visited = vec![false; (width * height) as usize];
+        let mut segments = Vec::new();
+        let mut stack = Vec::new();
+
+        for start_y in 0..height {
+            for start_x in 0..width {
+                if visited[(start_y * width + start_x) as usize] {
+                    continue;
+                }
+
+                let bright = is_bright(start_x, start_y);
+                let mut segment = Segment {
+                    pixels: 0,
+                    min_x: start_x,
+                    min_y: start_y,
+                    max_x: start_x,
+                    max_y: start_y,
+                };
+                visited[(start_y * width + start_x) as usize] = true;
+                stack.push((start_x, start_y));
+
+                while let Some((x, y)) = stack.pop() {
+                    segment.pixels += 1;
+                    segment.min_x = segment.min_x.min(x);
+                    segment.min_y = segment.min_y.min(y);
+                    segment.max_x = segment.max_x.max(x);
+                    segment.max_y = segment.max_y.max(y);
+
+                    let neighbours = [
+                        (x.wrapping_sub(1), y),
+                        (x + 1, y),
+                        (x, y.wrapping_sub(1)),
+                        (x, y + 1),
+                    ];
+                    for (nx, ny) in neighbours {
+                        if nx >= width || ny >= height {
+                            continue;
+                        }
+                        let index = (ny * width + nx) as usize;
+                        if !visited[index] && is_bright(nx, ny) == bright {
+                            visited[index] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+
+                if segment.pixels >= min_pixels {
+                    segments.push(segment);
+                }
+            }
+        }
+
+        // 同じ大きさなら見つかった順（左上から）
+        segments.sort_by_key(|segment| std::cmp::Reverse(segment.pixels));
+        segments
+    }
+
+    /// 部分領域ごとの差分ハッシュを大きい領域から順に計算
+    ///
+    /// 縮小・ぼかし・領域分割・切り出しのすべてを行うため、ブロッキング用のスレッドで呼び出す
+    fn segment_hashes(&self, image: &DynamicImage, start_time: Instant) -> Vec<HashResult> {
+        let (width, height) = (image.width(), image.height());
+        if width == 0 || height == 0 {
+            return Vec::new();
+        }
+
+        // 長辺が `segmentation_size` になるよう縮小（小さい画像はそのまま）
+        let scale = f64::from(self.segmentation_size) / f64::from(width.max(height));
+        let small = if scale < 1.0 {
+            image.resize(
+                self.segmentation_size,
+                self.segmentation_size,
+                image::imageops::FilterType::Triangle,
+            )
+        } else {
+            image.clone()
+        };
+        let small = image::imageops::blur(&small.to_luma8(), BLUR_SIGMA);
+        let (small_width, small_height) = small.dimensions();
+
+        let min_pixels = (f64::from(small_width * small_height) * self.min_segment_percent / 100.0)
+            .ceil()
+            .max(1.0) as u32;
+
+        let mut hashes = Vec::new();
+        for segment in Self::find_segments(&small, min_pixels) {
+            if hashes.len() >= self.max_segments {
+                break;
+            }
+
+            // 縮小画像上の外接矩形を元画像の座標に戻して切り出す
+            let x0 = segment.min_x * width / small_width;
+            let y0 = segment.min_y * height / small_height;
+            let x1 = ((segment.max_x + 1) * width / small_width).max(x0 + 1);
+            let y1 = ((segment.max_y + 1) * height / small_height).max(y0 + 1);
+            let region = image.crop_imm(x0, y0, x1 - x0, y1 - y0);
+
+            let hash = self.difference_hash(&region, start_time);
+            if !Self::is_featureless(&hash) {
+                hashes.push(hash);
+            }
+        }
+
+        hashes
+    }
+
+    /// ほぼ全ビットが同じハッシュ（平坦な領域）は別の画像とも一致しやすいため使わない
+    fn is_featureless(hash: &HashResult) -> bool {
+        let ones: u32 = hash.hash_data.iter().map(|byte| byte.count_ones()).sum();
+        let margin = hash.hash_size_bits / 8;
+        ones < margin || ones > hash.hash_size_bits - margin
+    }
+}
+
+#[async_trait]
+impl PerceptualHashBackend for CropResistantHasher {
+    async fn generate_hash(&self, image: &DynamicImage) -> Result<HashResult> {
+        let start_time = Instant::now();
+        let hasher = self.clone();
+        let image = image.clone();
+        Ok(tokio::task::spawn_blocking(move || hasher.difference_hash(&image, start_time)).await?)
+    }
+
+    async fn generate_segment_hashes(&self, image: &DynamicImage) -> Result<Vec<HashResult>> {
+        let start_time = Instant::now();
+        let hasher = self.clone();
+        let image = image.clone();
+        Ok(tokio::task::spawn_blocking(move || hasher.segment_hashes(&image, start_time)).await?)
+    }
+
+    fn calculate_distance(&self, hash1: &HashResult, hash2: &HashResult) -> Result<u32> {
+        if hash1.algorithm != hash2.algorithm {
+            anyhow::bail!("Cannot compare hashes from different algorithms");
+        }
+
+        if hash1.hash_data.len() != hash2.hash_data.len() {
+            anyhow::bail!("Cannot compare hashes of different sizes");
+        }
+
+        let distance = hash1
+            .hash_data
+            .iter()
+            .zip(hash2.hash_data.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+
+        Ok(distance)
+    }
+
+    fn algorithm(&self) -> &HashAlgorithm {
+        &self.algorithm
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        "Crop-Resistant Hash"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perceptual_hash::test_images::segmented_image;
+    use image::Luma;
+
+    /// `a` の部分領域のうち、`b` のいずれかの部分領域と閾値以内で一致するものの数
+    fn matching_segments(a: &[HashResult], b: &[HashResult], threshold: u32) -> usize {
+        let hasher = CropResistantHasher::default();
+        a.iter()
+            .filter(|x| {
+                b.iter()
+                    .any(|y| hasher.calculate_distance(x, y).unwrap() <= threshold)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_find_segments() {
+        // 暗い背景に明るい矩形が2つ（大きい順に並ぶ）
+        let image = GrayImage::from_fn(10, 6, |x, y| {
+            let bright = (1..=3).contains(&x) && (1..=2).contains(&y) || x >= 6 && y >= 1;
+            Luma([if bright { 200 } else { 20 }])
+        });
+
+        let segments = CropResistantHasher::find_segments(&image, 2);
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    pixels: 34,
+                    min_x: 0,
+                    min_y: 0,
+                    max_x: 9,
+                    max_y: 5,
+                },
+                Segment {
+                    pixels: 20,
+                    min_x: 6,
+                    min_y: 1,
+                    max_x: 9,
+                    max_y: 5,
+                },
+                Segment {
+                    pixels: 6,
+                    min_x: 1,
+                    min_y: 1,
+                    max_x: 3,
+                    max_y: 2,
+                },
+            ]
+        );
+        assert_eq!(CropResistantHasher::find_segments(&image, 7).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_crop_resistant_hash() {
+        let hasher = CropResistantHasher::new(8);
+        let image = segmented_image(1);
+
+        let result = hasher.generate_hash(&image).await.unwrap();
+        assert_eq!(result.hash_size_bits, 64);
+        assert_eq!(result.algorithm, HashAlgorithm::CropResistant { size: 8 });
+        assert_eq!(hasher.algorithm_name(), "Crop-Resistant Hash");
+
+        let segments = hasher.generate_segment_hashes(&image).await.unwrap();
+        assert!(segments.len() >= 3, "segments: {}", segments.len());
+        assert!(segments.len() <= 16);
+        assert!(segments
+            .iter()
+            .all(|segment| segment.algorithm == HashAlgorithm::CropResistant { size: 8 }));
+    }
+
+    #[tokio::test]
+    async fn test_segments_survive_cropping() {
+        let hasher = CropResistantHasher::new(8);
+        let original = segmented_image(1);
+        let cropped = original.crop_imm(15, 15, 200, 215);
+        let letterboxed = {
+            let mut canvas = image::RgbImage::new(320, 320);
+            image::imageops::overlay(&mut canvas, &original.to_rgb8(), 0, 40);
+            DynamicImage::ImageRgb8(canvas)
+        };
+
+        let original_segments = hasher.generate_segment_hashes(&original).await.unwrap();
+        let cropped_segments = hasher.generate_segment_hashes(&cropped).await.unwrap();
+        let letterboxed_segments = hasher.generate_segment_hashes(&letterboxed).await.unwrap();
+        let unrelated_segments = hasher
+            .generate_segment_hashes(&segmented_image(2))
+            .await
+            .unwrap();
+
+        // 全体のハッシュは一致しないが、内側の領域は一致する
+        let whole = hasher
+            .calculate_distance(
+                &hasher.generate_hash(&original).await.unwrap(),
+                &hasher.generate_hash(&cropped).await.unwrap(),
+            )
+            .unwrap();
+        assert!(whole > 10, "whole: {whole}");
+
+        let cropped_matches = matching_segments(&cropped_segments, &original_segments, 5);
+        assert!(cropped_matches >= 2, "cropped: {cropped_matches}");
+        let letterboxed_matches = matching_segments(&letterboxed_segments, &original_segments, 5);
+        assert!(
+            letterboxed_matches >= 2,
+            "letterboxed: {letterboxed_matches}"
+        );
+        let unrelated_matches = matching_segments(&unrelated_segments, &original_segments, 5);
+        assert!(unrelated_matches < 2, "unrelated: {unrelated_matches}");
+    }
+
+    #[tokio::test]
+    async fn test_flat_image_has_no_segments() {
+        let hasher = CropResistantHasher::new(8);
+        let segments = hasher
+            .generate_segment_hashes(&DynamicImage::new_rgb8(64, 64))
+            .await
+            .unwrap();
+        assert!(segments.is_empty());
+    }
+}
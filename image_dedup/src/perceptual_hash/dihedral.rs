@@ -0,0 +1,146 @@
+// 回転・反転（二面体群の8変換）
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 画像の回転・反転の組み合わせ（正方形の二面体群 D4 の8要素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DihedralTransform {
+    /// 変換なし
+    Identity,
+    /// 時計回りに90度回転
+    Rotate90,
+    /// 180度回転
+    Rotate180,
+    /// 時計回りに270度回転
+    Rotate270,
+    /// 左右反転
+    FlipHorizontal,
+    /// 上下反転
+    FlipVertical,
+    /// 左上-右下の対角線で反転（転置）
+    Transpose,
+    /// 右上-左下の対角線で反転
+    Transverse,
+}
+
+impl DihedralTransform {
+    /// 全ての変換（ハッシュのバリアントはこの順に記録する）
+    pub const ALL: [Self; 8] = [
+        Self::Identity,
+        Self::Rotate90,
+        Self::Rotate180,
+        Self::Rotate270,
+        Self::FlipHorizontal,
+        Self::FlipVertical,
+        Self::Transpose,
+        Self::Transverse,
+    ];
+
+    /// 名前（レポートの表記と同じ）
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Rotate90 => "rotate90",
+            Self::Rotate180 => "rotate180",
+            Self::Rotate270 => "rotate270",
+            Self::FlipHorizontal => "flip_horizontal",
+            Self::FlipVertical => "flip_vertical",
+            Self::Transpose => "transpose",
+            Self::Transverse => "transverse",
+        }
+    }
+
+    /// 逆変換（90度と270度の回転以外は自分自身）
+    pub fn inverse(self) -> Self {
+        match self {
+            Self::Rotate90 => Self::Rotate270,
+            Self::Rotate270 => Self::Rotate90,
+            other => other,
+        }
+    }
+
+    /// 画像に変換を適用
+    pub fn apply(self, image: &DynamicImage) -> DynamicImage {
+        match self {
+            Self::Identity => image.clone(),
+            Self::Rotate90 => image.rotate90(),
+            Self::Rotate180 => image.rotate180(),
+            Self::Rotate270 => image.rotate270(),
+            Self::FlipHorizontal => image.fliph(),
+            Self::FlipVertical => image.flipv(),
+            Self::Transpose => image.rotate90().fliph(),
+            Self::Transverse => image.rotate270().fliph(),
+        }
+    }
+}
+
+impl fmt::Display for DihedralTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// 全ピクセルが異なる3×2の画像
+    fn asymmetric_image() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| {
+            Rgb([(y * 3 + x) as u8, 0, 0])
+        }))
+    }
+
+    #[test]
+    fn test_transforms_are_distinct() {
+        let image = asymmetric_image();
+        let results: Vec<Vec<u8>> = DihedralTransform::ALL
+            .iter()
+            .map(|transform| transform.apply(&image).to_rgb8().into_raw())
+            .collect();
+
+        for (i, a) in results.iter().enumerate() {
+            for b in &results[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inverse_restores_image() {
+        let image = asymmetric_image();
+        for transform in DihedralTransform::ALL {
+            let restored = transform.inverse().apply(&transform.apply(&image));
+            assert_eq!(
+                restored.to_rgb8().into_raw(),
+                image.to_rgb8().into_raw(),
+                "{transform}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_transpose_swaps_axes() {
+        let image = asymmetric_image();
+        let transposed = DihedralTransform::Transpose.apply(&image).to_rgb8();
+
+        assert_eq!(transposed.dimensions(), (2, 3));
+        for (x, y, pixel) in image.to_rgb8().enumerate_pixels() {
+            assert_eq!(transposed.get_pixel(y, x), pixel);
+        }
+    }
+
+    #[test]
+    fn test_serialized_names() {
+        for transform in DihedralTransform::ALL {
+            assert_eq!(
+                serde_json::to_string(&transform).unwrap(),
+                format!("\"{}\"", transform.name())
+            );
+        }
+    }
+}
@@ -0,0 +1,199 @@
+// 複数アルゴリズムのアンサンブル（1回のデコードで全アルゴリズムのハッシュを計算）
+
+use super::{HashAlgorithm, HashResult, PerceptualHashBackend};
+use anyhow::Result;
+use async_trait::async_trait;
+use image::DynamicImage;
+use std::collections::HashSet;
+
+/// 複数のハッシャーをまとめて1つのバックエンドとして扱う
+///
+/// 先頭のハッシャーがメインのアルゴリズムとなり、`generate_hash` などはその結果を返す。
+/// `generate_ensemble_hashes` は全アルゴリズムのハッシュを、`generate_ensemble_dihedral_hashes` は
+/// 全アルゴリズムの回転・反転したハッシュを名前とともに返す
+pub struct EnsembleHasher {
+    members: Vec<(String, Box<dyn PerceptualHashBackend>)>,
+}
+
+impl EnsembleHasher {
+    /// アルゴリズム名（`dct` など）とハッシャーの組から作成
+    pub fn new(members: Vec<(String, Box<dyn PerceptualHashBackend>)>) -> Result<Self> {
+        if members.is_empty() {
+            anyhow::bail!("Ensemble requires at least one algorithm");
+        }
+
+        let mut names = HashSet::new();
+        for (name, _) in &members {
+            if !names.insert(name.as_str()) {
+                anyhow::bail!("Algorithm '{}' is listed more than once", name);
+            }
+        }
+
+        Ok(Self { members })
+    }
+
+    /// メインのハッシャー
+    fn primary(&self) -> &dyn PerceptualHashBackend {
+        self.members[0].1.as_ref()
+    }
+}
+
+#[async_trait]
+impl PerceptualHashBackend for EnsembleHasher {
+    async fn generate_hash(&self, image: &DynamicImage) -> Result<HashResult> {
+        self.primary().generate_hash(image).await
+    }
+
+    async fn generate_dihedral_hashes(&self, image: &DynamicImage) -> Result<Vec<HashResult>> {
+        self.primary().generate_dihedral_hashes(image).await
+    }
+
+    async fn generate_segment_hashes(&self, image: &DynamicImage) -> Result<Vec<HashResult>> {
+        self.primary().generate_segment_hashes(image).await
+    }
+
+    async fn generate_ensemble_hashes(
+        &self,
+        image: &DynamicImage,
+        primary: &HashResult,
+    ) -> Result<Vec<(String, HashResult)>> {
+        let mut hashes = Vec::with_capacity(self.members.len());
+        hashes.push((self.members[0].0.clone(), primary.clone()));
+        for (name, hasher) in &self.members[1..] {
+            hashes.push((name.clone(), hasher.generate_hash(image).await?));
+        }
+        Ok(hashes)
+    }
+
+    async fn generate_ensemble_dihedral_hashes(
+        &self,
+        image: &DynamicImage,
+        primary: &[HashResult],
+    ) -> Result<Vec<(String, Vec<HashResult>)>> {
+        let mut hashes = Vec::with_capacity(self.members.len());
+        hashes.push((self.members[0].0.clone(), primary.to_vec()));
+        for (name, hasher) in &self.members[1..] {
+            hashes.push((name.clone(), hasher.generate_dihedral_hashes(image).await?));
+        }
+        Ok(hashes)
+    }
+
+    fn calculate_distance(&self, hash1: &HashResult, hash2: &HashResult) -> Result<u32> {
+        self.primary().calculate_distance(hash1, hash2)
+    }
+
+    fn algorithm(&self) -> &HashAlgorithm {
+        self.primary().algorithm()
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        self.primary().algorithm_name()
+    }
+
+    fn ensemble_algorithms(&self) -> Vec<String> {
+        self.members.iter().map(|(name, _)| name.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perceptual_hash::factory::AlgorithmFactory;
+    use crate::perceptual_hash::DihedralTransform;
+
+    fn create_ensemble(names: &[&str]) -> Result<EnsembleHasher> {
+        let factory = AlgorithmFactory::new();
+        let members = names
+            .iter()
+            .map(|name| Ok((name.to_string(), factory.create_hasher_by_name(name)?)))
+            .collect::<Result<Vec<_>>>()?;
+        EnsembleHasher::new(members)
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_hashes_every_algorithm() {
+        let ensemble = create_ensemble(&["dct", "difference", "average"]).unwrap();
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(64, 48, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 5) as u8, 128])
+        }));
+
+        assert_eq!(ensemble.algorithm_name(), "DCT (Discrete Cosine Transform)");
+        assert_eq!(
+            ensemble.ensemble_algorithms(),
+            vec!["dct", "difference", "average"]
+        );
+
+        // メインのハッシュは先頭のアルゴリズムの結果で、渡したものをそのまま使う
+        let primary = ensemble.generate_hash(&image).await.unwrap();
+        let hashes = ensemble
+            .generate_ensemble_hashes(&image, &primary)
+            .await
+            .unwrap();
+        let names: Vec<&str> = hashes.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["dct", "difference", "average"]);
+        assert!(matches!(
+            hashes[1].1.algorithm,
+            HashAlgorithm::Difference { .. }
+        ));
+        assert_eq!(primary.to_hex(), hashes[0].1.to_hex());
+
+        // 渡したメインのハッシュは再計算されない
+        let mut given = primary.clone();
+        given.hash_data = vec![0xAB; given.hash_data.len()];
+        let hashes = ensemble
+            .generate_ensemble_hashes(&image, &given)
+            .await
+            .unwrap();
+        assert_eq!(hashes[0].1.to_hex(), given.to_hex());
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_dihedral_hashes_every_algorithm() {
+        let ensemble = create_ensemble(&["dct", "average"]).unwrap();
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(64, 48, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 5) as u8, 128])
+        }));
+
+        let primary = ensemble.generate_dihedral_hashes(&image).await.unwrap();
+        let hashes = ensemble
+            .generate_ensemble_dihedral_hashes(&image, &primary)
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = hashes.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["dct", "average"]);
+        let hexes =
+            |hashes: &[HashResult]| hashes.iter().map(HashResult::to_hex).collect::<Vec<_>>();
+        assert_eq!(hexes(&hashes[0].1), hexes(&primary));
+        let average = AlgorithmFactory::new()
+            .create_hasher_by_name("average")
+            .unwrap()
+            .generate_dihedral_hashes(&image)
+            .await
+            .unwrap();
+        assert_eq!(hashes[1].1.len(), DihedralTransform::ALL.len());
+        assert_eq!(hexes(&hashes[1].1), hexes(&average));
+    }
+
+    #[test]
+    fn test_ensemble_rejects_invalid_members() {
+        assert!(EnsembleHasher::new(Vec::new()).is_err());
+        assert!(create_ensemble(&["dct", "average", "dct"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_single_hasher_has_no_ensemble_hashes() {
+        let hasher = AlgorithmFactory::new()
+            .create_hasher_by_name("average")
+            .unwrap();
+        let image = DynamicImage::new_rgb8(16, 16);
+        let primary = hasher.generate_hash(&image).await.unwrap();
+
+        assert!(hasher.ensemble_algorithms().is_empty());
+        assert!(hasher
+            .generate_ensemble_hashes(&image, &primary)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -2,8 +2,9 @@
 
 use super::config::{AlgorithmConfig, AlgorithmRegistry, DynamicAlgorithmConfig};
 use super::{
-    average_config::AverageConfig, dct_config::DctConfig, difference_config::DifferenceConfig,
-    PerceptualHashBackend,
+    average_config::AverageConfig, block_mean_config::BlockMeanConfig,
+    crop_resistant_config::CropResistantConfig, dct_config::DctConfig,
+    difference_config::DifferenceConfig, wavelet_config::WaveletConfig, PerceptualHashBackend,
 };
 use anyhow::Result;
 
@@ -21,6 +22,9 @@ impl AlgorithmFactory {
         registry.register::<DctConfig>();
         registry.register::<AverageConfig>();
         registry.register::<DifferenceConfig>();
+        registry.register::<WaveletConfig>();
+        registry.register::<BlockMeanConfig>();
+        registry.register::<CropResistantConfig>();
 
         Self { registry }
     }
@@ -48,6 +52,18 @@ impl AlgorithmFactory {
                 let config = DifferenceConfig::default();
                 Ok(Box::new(config.create_hasher()?))
             }
+            "wavelet" => {
+                let config = WaveletConfig::default();
+                Ok(Box::new(config.create_hasher()?))
+            }
+            "block_mean" => {
+                let config = BlockMeanConfig::default();
+                Ok(Box::new(config.create_hasher()?))
+            }
+            "crop_resistant" => {
+                let config = CropResistantConfig::default();
+                Ok(Box::new(config.create_hasher()?))
+            }
             _ => anyhow::bail!("Unknown algorithm: {}", algorithm),
         }
     }
@@ -115,6 +131,9 @@ mod tests {
         assert!(algorithms.contains(&"dct".to_string()));
         assert!(algorithms.contains(&"average".to_string()));
         assert!(algorithms.contains(&"difference".to_string()));
+        assert!(algorithms.contains(&"wavelet".to_string()));
+        assert!(algorithms.contains(&"block_mean".to_string()));
+        assert!(algorithms.contains(&"crop_resistant".to_string()));
     }
 
     #[test]
@@ -133,6 +152,21 @@ mod tests {
         let diff_hasher = factory.create_hasher_by_name("difference");
         assert!(diff_hasher.is_ok());
 
+        // Waveletハッシャー作成
+        let wavelet_hasher = factory.create_hasher_by_name("wavelet").unwrap();
+        assert_eq!(wavelet_hasher.algorithm_name(), "Wavelet Hash");
+
+        // BlockMeanハッシャー作成
+        let block_mean_hasher = factory.create_hasher_by_name("block_mean").unwrap();
+        assert_eq!(block_mean_hasher.algorithm_name(), "Block Mean Hash");
+
+        // CropResistantハッシャー作成
+        let crop_resistant_hasher = factory.create_hasher_by_name("crop_resistant").unwrap();
+        assert_eq!(
+            crop_resistant_hasher.algorithm_name(),
+            "Crop-Resistant Hash"
+        );
+
         // 存在しないアルゴリズム
         let unknown_hasher = factory.create_hasher_by_name("unknown");
         assert!(unknown_hasher.is_err());
@@ -169,6 +203,34 @@ mod tests {
         assert!(hasher.is_ok());
     }
 
+    #[test]
+    fn test_create_new_hashers_from_json() {
+        let factory = AlgorithmFactory::new();
+
+        let hasher = factory
+            .create_hasher_from_json(
+                r#"{"algorithm": "wavelet", "parameters": {"size": 16, "image_scale": 128}}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            hasher.algorithm(),
+            &crate::perceptual_hash::HashAlgorithm::Wavelet { size: 16 }
+        );
+
+        let hasher = factory
+            .create_hasher_from_json(r#"{"algorithm": "block_mean", "parameters": {"size": 16}}"#)
+            .unwrap();
+        assert_eq!(
+            hasher.algorithm(),
+            &crate::perceptual_hash::HashAlgorithm::BlockMean { size: 16 }
+        );
+
+        // 検証に失敗する設定
+        let invalid = factory
+            .create_hasher_from_json(r#"{"algorithm": "wavelet", "parameters": {"size": 12}}"#);
+        assert!(invalid.is_err());
+    }
+
     #[test]
     fn test_global_factory() {
         let factory = get_algorithm_factory();
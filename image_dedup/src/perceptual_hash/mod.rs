@@ -6,11 +6,24 @@ use std::fmt;
 
 pub mod average_config;
 pub mod average_hash;
+pub mod block_mean_config;
+pub mod block_mean_hash;
 pub mod config;
+pub mod crop_resistant_config;
+pub mod crop_resistant_hash;
 pub mod dct_config;
 pub mod dct_hash;
 pub mod difference_config;
+pub mod dihedral;
+pub mod ensemble;
 pub mod factory;
+pub mod wavelet_config;
+pub mod wavelet_hash;
+
+#[cfg(test)]
+mod test_images;
+
+pub use dihedral::DihedralTransform;
 
 /// ハッシュアルゴリズムの種類
 #[derive(Debug, Clone, PartialEq)]
@@ -21,6 +34,26 @@ pub enum HashAlgorithm {
     Average { size: u32 },
     /// 差分ベースのハッシュ（エッジ検出に有効）
     Difference { size: u32 },
+    /// ハールウェーブレットベースのハッシュ（リサイズ・再圧縮に強い）
+    Wavelet { size: u32 },
+    /// ブロック平均ベースのハッシュ（全ピクセルを使うため補間の影響を受けにくい）
+    BlockMean { size: u32 },
+    /// 全体と部分領域ごとのハッシュ（切り抜き・余白の追加に強い）
+    CropResistant { size: u32 },
+}
+
+impl HashAlgorithm {
+    /// ハッシュサイズ（1辺の長さ。ビット数は `size * size`）
+    pub fn size(&self) -> u32 {
+        match self {
+            Self::DCT { size }
+            | Self::Average { size }
+            | Self::Difference { size }
+            | Self::Wavelet { size }
+            | Self::BlockMean { size }
+            | Self::CropResistant { size } => *size,
+        }
+    }
 }
 
 /// ハッシュ計算の結果
@@ -58,7 +91,9 @@ impl HashResult {
             .join("")
     }
 
-    /// ハッシュをu64として取得（ハミング距離計算用）
+    /// ハッシュをu64として取得（先頭64ビットのみ）
+    ///
+    /// 8×8を超えるハッシュは切り詰められる。全ビットでの比較には `hash_data` を使うこと
     pub fn to_u64(&self) -> u64 {
         let mut result = 0u64;
         for (i, &byte) in self.hash_data.iter().take(8).enumerate() {
@@ -88,6 +123,52 @@ pub trait PerceptualHashBackend: Send + Sync {
     /// 画像からハッシュを生成
     async fn generate_hash(&self, image: &DynamicImage) -> Result<HashResult>;
 
+    /// 回転・反転した8通りの画像のハッシュを `DihedralTransform::ALL` の順に生成
+    ///
+    /// 先頭（`Identity`）は `generate_hash` の結果と同じ
+    async fn generate_dihedral_hashes(&self, image: &DynamicImage) -> Result<Vec<HashResult>> {
+        let mut hashes = Vec::with_capacity(DihedralTransform::ALL.len());
+        for transform in DihedralTransform::ALL {
+            let hash = match transform {
+                DihedralTransform::Identity => self.generate_hash(image).await?,
+                _ => self.generate_hash(&transform.apply(image)).await?,
+            };
+            hashes.push(hash);
+        }
+        Ok(hashes)
+    }
+
+    /// 画像の部分領域ごとのハッシュを生成
+    ///
+    /// 切り抜きに強いバックエンドのみが生成する。既定では空
+    async fn generate_segment_hashes(&self, _image: &DynamicImage) -> Result<Vec<HashResult>> {
+        Ok(Vec::new())
+    }
+
+    /// アンサンブルの全アルゴリズムのハッシュを（アルゴリズム名, ハッシュ）のリストで生成
+    ///
+    /// `primary` は `generate_hash` で計算済みのメインのハッシュで、再計算せずそのまま使う。
+    /// 複数のアルゴリズムをまとめたバックエンドのみが生成する。既定では空
+    async fn generate_ensemble_hashes(
+        &self,
+        _image: &DynamicImage,
+        _primary: &HashResult,
+    ) -> Result<Vec<(String, HashResult)>> {
+        Ok(Vec::new())
+    }
+
+    /// アンサンブルの全アルゴリズムの回転・反転したハッシュを（アルゴリズム名, バリアント）のリストで生成
+    ///
+    /// バリアントは `DihedralTransform::ALL` の順。`primary` は `generate_dihedral_hashes` で
+    /// 計算済みのメインのバリアントで、再計算せずそのまま使う。既定では空
+    async fn generate_ensemble_dihedral_hashes(
+        &self,
+        _image: &DynamicImage,
+        _primary: &[HashResult],
+    ) -> Result<Vec<(String, Vec<HashResult>)>> {
+        Ok(Vec::new())
+    }
+
     /// 2つのハッシュ間の距離を計算（ハミング距離）
     fn calculate_distance(&self, hash1: &HashResult, hash2: &HashResult) -> Result<u32>;
 
@@ -109,19 +190,92 @@ pub trait PerceptualHashBackend: Send + Sync {
             HashAlgorithm::DCT { size } => size / 4,
             HashAlgorithm::Average { size } => size / 8,
             HashAlgorithm::Difference { size } => size / 6,
+            HashAlgorithm::Wavelet { size } => size / 4,
+            HashAlgorithm::BlockMean { size } => size / 4,
+            HashAlgorithm::CropResistant { size } => size / 6,
         }
     }
 
+    /// アンサンブルを構成するアルゴリズム名（単独のアルゴリズムでは空）
+    fn ensemble_algorithms(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// 計算の複雑さを取得（1-10のスケール、10が最も重い）
     fn computational_complexity(&self) -> u8 {
         match self.algorithm() {
             HashAlgorithm::Average { .. } => 2,
             HashAlgorithm::Difference { .. } => 3,
+            HashAlgorithm::BlockMean { .. } => 3,
+            HashAlgorithm::Wavelet { .. } => 4,
+            HashAlgorithm::CropResistant { .. } => 6,
             HashAlgorithm::DCT { .. } => 7,
         }
     }
 }
 
+/// 実行時に選択されたハッシャー（設定ファイルやCLIフラグから作成）もエンジンで使えるようにする
+#[async_trait]
+impl PerceptualHashBackend for Box<dyn PerceptualHashBackend> {
+    async fn generate_hash(&self, image: &DynamicImage) -> Result<HashResult> {
+        self.as_ref().generate_hash(image).await
+    }
+
+    async fn generate_dihedral_hashes(&self, image: &DynamicImage) -> Result<Vec<HashResult>> {
+        self.as_ref().generate_dihedral_hashes(image).await
+    }
+
+    async fn generate_segment_hashes(&self, image: &DynamicImage) -> Result<Vec<HashResult>> {
+        self.as_ref().generate_segment_hashes(image).await
+    }
+
+    async fn generate_ensemble_hashes(
+        &self,
+        image: &DynamicImage,
+        primary: &HashResult,
+    ) -> Result<Vec<(String, HashResult)>> {
+        self.as_ref().generate_ensemble_hashes(image, primary).await
+    }
+
+    async fn generate_ensemble_dihedral_hashes(
+        &self,
+        image: &DynamicImage,
+        primary: &[HashResult],
+    ) -> Result<Vec<(String, Vec<HashResult>)>> {
+        self.as_ref()
+            .generate_ensemble_dihedral_hashes(image, primary)
+            .await
+    }
+
+    fn calculate_distance(&self, hash1: &HashResult, hash2: &HashResult) -> Result<u32> {
+        self.as_ref().calculate_distance(hash1, hash2)
+    }
+
+    fn are_similar(&self, hash1: &HashResult, hash2: &HashResult, threshold: u32) -> Result<bool> {
+        self.as_ref().are_similar(hash1, hash2, threshold)
+    }
+
+    fn algorithm(&self) -> &HashAlgorithm {
+        self.as_ref().algorithm()
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        self.as_ref().algorithm_name()
+    }
+
+    fn recommended_threshold(&self) -> u32 {
+        self.as_ref().recommended_threshold()
+    }
+
+    fn ensemble_algorithms(&self) -> Vec<String> {
+        self.as_ref().ensemble_algorithms()
+    }
+
+    fn computational_complexity(&self) -> u8 {
+        self.as_ref().computational_complexity()
+    }
+}
+
 /// ハッシュの比較結果
 #[derive(Debug, Clone)]
 pub struct ComparisonResult {
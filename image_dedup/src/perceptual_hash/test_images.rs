@@ -0,0 +1,96 @@
+// テスト用の画像生成（ハッシュの頑健性テストで使う）
+
+use image::{DynamicImage, RgbImage};
+
+/// 図形とグラデーションを含む320×240の画像（`seed` ごとに配置が変わる）
+pub fn sample_image(seed: u32) -> DynamicImage {
+    let centre_x = 60.0 + (seed * 37 % 200) as f32;
+    let centre_y = 50.0 + (seed * 53 % 140) as f32;
+    let stripe = 20 + seed * 7 % 30;
+
+    DynamicImage::ImageRgb8(RgbImage::from_fn(320, 240, |x, y| {
+        let dx = x as f32 - centre_x;
+        let dy = y as f32 - centre_y;
+        if dx * dx + dy * dy < 60.0 * 60.0 {
+            image::Rgb([240, 220, 40])
+        } else if (x / stripe + y / stripe).is_multiple_of(2) {
+            let shade = (x * 120 / 320 + y * 80 / 240) as u8;
+            image::Rgb([shade, shade / 2, 200 - shade])
+        } else {
+            image::Rgb([30, 60 + (y * 100 / 240) as u8, 90])
+        }
+    }))
+}
+
+/// 指定した品質でJPEGとして保存し直した画像
+pub fn recompress_jpeg(image: &DynamicImage, quality: u8) -> DynamicImage {
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality)
+        .encode_image(&image.to_rgb8())
+        .unwrap();
+    image::load_from_memory(&buffer).unwrap()
+}
+
+/// 疑似乱数（`seed` と `index` から決まる値）
+fn pseudo_random(seed: u32, index: u32) -> u32 {
+    let mut value = seed.wrapping_mul(0x9E37_79B9) ^ index.wrapping_mul(0x85EB_CA6B);
+    value ^= value >> 15;
+    value = value.wrapping_mul(0x2C1B_3C6D);
+    value ^ (value >> 12)
+}
+
+/// 暗い背景に、穴の開いた明るい図形を3×2個並べた320×240の画像
+///
+/// 図形の形・大きさ・穴の位置は `seed` ごとに変わる。
+/// 左上の4つの図形は (15, 15) から 200×215 の範囲に収まる
+pub fn segmented_image(seed: u32) -> DynamicImage {
+    let shapes: Vec<(f32, f32, f32, f32, bool, f32, f32)> = (0..6)
+        .map(|i| {
+            let random = |k: u32| pseudo_random(seed, i * 8 + k);
+            let centre_x = 53.0 + 107.0 * (i % 3) as f32 + (random(0) % 9) as f32 - 4.0;
+            let centre_y = 60.0 + 120.0 * (i / 3) as f32 + (random(1) % 9) as f32 - 4.0;
+            let half_width = 22.0 + (random(2) % 9) as f32;
+            let half_height = 22.0 + (random(3) % 9) as f32;
+            let is_rectangle = random(4) % 2 == 0;
+            let angle = (random(5) % 8) as f32 * std::f32::consts::FRAC_PI_4;
+            let hole_distance = 0.3 + (random(6) % 3) as f32 * 0.1;
+            (
+                centre_x,
+                centre_y,
+                half_width,
+                half_height,
+                is_rectangle,
+                angle,
+                hole_distance,
+            )
+        })
+        .collect();
+
+    DynamicImage::ImageRgb8(RgbImage::from_fn(320, 240, |x, y| {
+        let (px, py) = (x as f32, y as f32);
+        for &(cx, cy, hw, hh, is_rectangle, angle, hole_distance) in &shapes {
+            let (dx, dy) = ((px - cx) / hw, (py - cy) / hh);
+            let inside = if is_rectangle {
+                dx.abs() <= 1.0 && dy.abs() <= 1.0
+            } else {
+                dx * dx + dy * dy <= 1.0
+            };
+            if !inside {
+                continue;
+            }
+
+            let (hx, hy) = (
+                dx - hole_distance * angle.cos(),
+                dy - hole_distance * angle.sin(),
+            );
+            if hx * hx + hy * hy <= 0.12 {
+                return image::Rgb([10, 10, 10]);
+            }
+            let shade = (200.0 + 50.0 * dx) as u8;
+            return image::Rgb([shade, shade, shade]);
+        }
+
+        let texture = ((x * 7 + y * 13) % 30) as u8;
+        image::Rgb([20 + texture, 25 + texture, 30 + texture])
+    }))
+}
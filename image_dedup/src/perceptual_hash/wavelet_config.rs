@@ -0,0 +1,208 @@
+// Waveletアルゴリズムの設定
+
+use super::config::{AlgorithmConfig, ParameterInfo, ParameterType};
+use super::wavelet_hash::WaveletHasher;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Waveletハッシュの設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveletConfig {
+    /// ハッシュサイズ（2の累乗。通常は8, 16, 32など）
+    pub size: u32,
+    /// ウェーブレット変換前に縮小する画像サイズ（2の累乗、省略時はハッシュサイズの8倍・最低64）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_scale: Option<u32>,
+}
+
+impl WaveletConfig {
+    /// 実際に使う縮小後の画像サイズ
+    pub fn effective_image_scale(&self) -> u32 {
+        self.image_scale
+            .unwrap_or_else(|| WaveletHasher::default_image_scale(self.size))
+    }
+}
+
+impl AlgorithmConfig for WaveletConfig {
+    type Algorithm = WaveletHasher;
+
+    fn create_hasher(&self) -> Result<Self::Algorithm> {
+        Ok(WaveletHasher::with_image_scale(
+            self.size,
+            self.effective_image_scale(),
+        ))
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        "wavelet"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| anyhow::anyhow!("JSON変換エラー: {}", e))
+    }
+
+    fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow::anyhow!("JSON解析エラー: {}", e))
+    }
+
+    fn description(&self) -> &'static str {
+        "Wavelet Hash - Haar wavelet low-frequency coefficients compared with their median. Robust to resizing and recompression."
+    }
+
+    fn default_config() -> Self {
+        Self {
+            size: 8,
+            image_scale: None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.size == 0 {
+            anyhow::bail!("Size must be greater than 0");
+        }
+
+        if self.size > 64 {
+            anyhow::bail!("Size must be 64 or less for performance reasons");
+        }
+
+        if !self.size.is_power_of_two() {
+            anyhow::bail!("Size must be a power of two for the wavelet transform");
+        }
+
+        let image_scale = self.effective_image_scale();
+        if !image_scale.is_power_of_two() {
+            anyhow::bail!("Image scale must be a power of two");
+        }
+
+        if image_scale < self.size {
+            anyhow::bail!("Image scale must be at least the hash size");
+        }
+
+        if image_scale > 1024 {
+            anyhow::bail!("Image scale must be 1024 or less for performance reasons");
+        }
+
+        Ok(())
+    }
+
+    fn parameter_info() -> Vec<ParameterInfo> {
+        vec![
+            ParameterInfo {
+                name: "size".to_string(),
+                param_type: ParameterType::Integer {
+                    min: Some(1),
+                    max: Some(64),
+                },
+                description: "Hash size, a power of two (typically 8, 16, or 32)".to_string(),
+                default_value: Some("8".to_string()),
+                required: true,
+            },
+            ParameterInfo {
+                name: "image_scale".to_string(),
+                param_type: ParameterType::Integer {
+                    min: Some(1),
+                    max: Some(1024),
+                },
+                description:
+                    "Side length the image is scaled to before the transform, a power of two"
+                        .to_string(),
+                default_value: Some("max(8 * size, 64)".to_string()),
+                required: false,
+            },
+        ]
+    }
+}
+
+impl Default for WaveletConfig {
+    fn default() -> Self {
+        Self::default_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perceptual_hash::PerceptualHashBackend;
+
+    #[test]
+    fn test_wavelet_config_creation() {
+        let config = WaveletConfig::default();
+        assert_eq!(config.size, 8);
+        assert_eq!(config.effective_image_scale(), 64);
+        assert_eq!(config.algorithm_name(), "wavelet");
+    }
+
+    #[test]
+    fn test_wavelet_config_validation() {
+        // 有効な設定
+        assert!(WaveletConfig {
+            size: 16,
+            image_scale: Some(256)
+        }
+        .validate()
+        .is_ok());
+
+        // 無効な設定
+        for (size, image_scale) in [
+            (0, None),
+            (128, None),
+            (12, None),
+            (8, Some(100)),
+            (16, Some(8)),
+            (8, Some(2048)),
+        ] {
+            let config = WaveletConfig { size, image_scale };
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn test_wavelet_config_json_serialization() {
+        let config = WaveletConfig {
+            size: 16,
+            image_scale: Some(256),
+        };
+
+        let json = config.to_json().unwrap();
+        assert!(json.contains("\"size\": 16"));
+        assert!(json.contains("\"image_scale\": 256"));
+
+        let deserialized = WaveletConfig::from_json(&json).unwrap();
+        assert_eq!(deserialized.size, 16);
+        assert_eq!(deserialized.image_scale, Some(256));
+
+        // image_scaleは省略できる
+        let deserialized = WaveletConfig::from_json(r#"{"size": 8}"#).unwrap();
+        assert_eq!(deserialized.image_scale, None);
+    }
+
+    #[tokio::test]
+    async fn test_wavelet_config_hasher_creation() {
+        let config = WaveletConfig {
+            size: 8,
+            image_scale: Some(128),
+        };
+        let hasher = config.create_hasher().unwrap();
+
+        assert_eq!(hasher.algorithm_name(), "Wavelet Hash");
+        assert_eq!(hasher.get_image_scale(), 128);
+
+        // 簡単な画像でテスト
+        use image::DynamicImage;
+        let test_image = DynamicImage::new_rgb8(64, 64);
+        let result = hasher.generate_hash(&test_image).await;
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_wavelet_config_parameter_info() {
+        let params = WaveletConfig::parameter_info();
+        assert_eq!(params.len(), 2);
+
+        let size_param = &params[0];
+        assert_eq!(size_param.name, "size");
+        assert!(size_param.required);
+        assert_eq!(size_param.default_value, Some("8".to_string()));
+        assert!(!params[1].required);
+    }
+}
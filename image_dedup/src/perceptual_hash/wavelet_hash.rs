@@ -0,0 +1,256 @@
+use super::{HashAlgorithm, HashResult, PerceptualHashBackend};
+use anyhow::Result;
+use async_trait::async_trait;
+use image::DynamicImage;
+use std::time::Instant;
+
+/// ハールウェーブレットベースの知覚ハッシュ実装（wHash）
+///
+/// 画像を `image_scale` 四方のグレースケールに縮小し、最大レベルの低周波成分（全体の明るさ）を
+/// 取り除いてからハッシュサイズになるまでハール変換を繰り返す。
+/// 残った低周波成分（LL）の各係数が中央値より大きいかどうかをビットとする
+#[derive(Clone, Debug)]
+pub struct WaveletHasher {
+    algorithm: HashAlgorithm,
+    hash_size: u32,
+    image_scale: u32,
+}
+
+impl Default for WaveletHasher {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl WaveletHasher {
+    /// 縮小後の画像サイズの既定値（ハッシュサイズの8倍、最低64）
+    pub fn default_image_scale(size: u32) -> u32 {
+        size.saturating_mul(8).max(64)
+    }
+
+    pub fn new(size: u32) -> Self {
+        Self::with_image_scale(size, Self::default_image_scale(size))
+    }
+
+    /// `size` と `image_scale` は2の累乗で、`image_scale >= size` であること
+    pub fn with_image_scale(size: u32, image_scale: u32) -> Self {
+        Self {
+            algorithm: HashAlgorithm::Wavelet { size },
+            hash_size: size,
+            image_scale,
+        }
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.hash_size
+    }
+
+    pub fn get_image_scale(&self) -> u32 {
+        self.image_scale
+    }
+
+    fn compute_wavelet_hash_from_gray(
+        gray_image: image::ImageBuffer<image::Luma<u8>, Vec<u8>>,
+        hash_size: u32,
+    ) -> Vec<u8> {
+        let scale = gray_image.width() as usize;
+        let mut coefficients: Vec<f32> = gray_image
+            .pixels()
+            .map(|pixel| f32::from(pixel[0]) / 255.0)
+            .collect();
+
+        // 最大レベルのLL成分（直流成分）を除去する。正規直交ハール変換では
+        // 直流成分を0にして逆変換することは平均値を引くことと等しい
+        let mean = coefficients.iter().sum::<f32>() / coefficients.len() as f32;
+        for value in &mut coefficients {
+            *value -= mean;
+        }
+
+        // ハッシュサイズになるまで低周波成分（LL）への変換を繰り返す
+        let mut width = scale;
+        while width > hash_size as usize {
+            coefficients = haar_low_pass(&coefficients, width);
+            width /= 2;
+        }
+
+        let mut sorted = coefficients.clone();
+        sorted.sort_by(f32::total_cmp);
+        let median = median_of_sorted(&sorted);
+
+        pack_bits(coefficients.iter().map(|&value| value > median))
+    }
+}
+
+/// 1レベルの2次元ハール変換を行い、低周波成分（LL）だけを返す
+fn haar_low_pass(values: &[f32], width: usize) -> Vec<f32> {
+    let half = width / 2;
+    let mut low = Vec::with_capacity(half * half);
+    for y in 0..half {
+        for x in 0..half {
+            let top = 2 * y * width + 2 * x;
+            let bottom = top + width;
+            // 行方向・列方向それぞれ (a + b) / √2 を適用した結果
+            low.push((values[top] + values[top + 1] + values[bottom] + values[bottom + 1]) / 2.0);
+        }
+    }
+    low
+}
+
+/// ソート済みの値の中央値
+pub(crate) fn median_of_sorted(sorted: &[f32]) -> f32 {
+    match sorted.len() {
+        0 => 0.0,
+        len if len % 2 == 0 => (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0,
+        len => sorted[len / 2],
+    }
+}
+
+/// ビット列を先頭から詰めたバイト列に変換（端数は下位ビットを0で埋める）
+pub(crate) fn pack_bits(bits: impl Iterator<Item = bool>) -> Vec<u8> {
+    let mut hash_bits = Vec::new();
+    let mut current_byte = 0u8;
+    let mut bit_count = 0;
+
+    for bit in bits {
+        current_byte = (current_byte << 1) | u8::from(bit);
+        bit_count += 1;
+
+        if bit_count == 8 {
+            hash_bits.push(current_byte);
+            current_byte = 0;
+            bit_count = 0;
+        }
+    }
+
+    if bit_count > 0 {
+        current_byte <<= 8 - bit_count;
+        hash_bits.push(current_byte);
+    }
+
+    hash_bits
+}
+
+#[async_trait]
+impl PerceptualHashBackend for WaveletHasher {
+    async fn generate_hash(&self, image: &DynamicImage) -> Result<HashResult> {
+        let start_time = Instant::now();
+
+        let hash_data = tokio::task::spawn_blocking({
+            let size = self.hash_size;
+            let scale = self.image_scale;
+            let gray_image = image
+                .resize_exact(scale, scale, image::imageops::FilterType::Lanczos3)
+                .to_luma8();
+            move || Self::compute_wavelet_hash_from_gray(gray_image, size)
+        })
+        .await?;
+
+        let computation_time_ms = start_time.elapsed().as_millis() as u64;
+
+        Ok(HashResult {
+            hash_data,
+            hash_size_bits: self.hash_size * self.hash_size,
+            algorithm: self.algorithm.clone(),
+            computation_time_ms,
+            source_dimensions: (image.width(), image.height()),
+        })
+    }
+
+    fn calculate_distance(&self, hash1: &HashResult, hash2: &HashResult) -> Result<u32> {
+        if hash1.algorithm != hash2.algorithm {
+            anyhow::bail!("Cannot compare hashes from different algorithms");
+        }
+
+        if hash1.hash_data.len() != hash2.hash_data.len() {
+            anyhow::bail!("Cannot compare hashes of different sizes");
+        }
+
+        let distance = hash1
+            .hash_data
+            .iter()
+            .zip(hash2.hash_data.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+
+        Ok(distance)
+    }
+
+    fn algorithm(&self) -> &HashAlgorithm {
+        &self.algorithm
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        "Wavelet Hash"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perceptual_hash::test_images::{recompress_jpeg, sample_image};
+
+    #[tokio::test]
+    async fn test_wavelet_hash() {
+        let hasher = WaveletHasher::new(8);
+
+        let result = hasher.generate_hash(&sample_image(1)).await.unwrap();
+
+        assert_eq!(result.hash_size_bits, 64);
+        assert_eq!(result.hash_data.len(), 8);
+        assert_eq!(result.algorithm, HashAlgorithm::Wavelet { size: 8 });
+        assert_eq!(hasher.algorithm_name(), "Wavelet Hash");
+        // 中央値で分けるため、ほぼ半数のビットが立つ
+        let ones: u32 = result.hash_data.iter().map(|b| b.count_ones()).sum();
+        assert!((24..=40).contains(&ones));
+    }
+
+    #[test]
+    fn test_haar_low_pass() {
+        let values = vec![
+            1.0, 1.0, 2.0, 2.0, //
+            1.0, 1.0, 2.0, 2.0, //
+            3.0, 3.0, 4.0, 4.0, //
+            3.0, 3.0, 4.0, 4.0,
+        ];
+        assert_eq!(haar_low_pass(&values, 4), vec![2.0, 4.0, 6.0, 8.0]);
+    }
+
+    #[test]
+    fn test_pack_bits() {
+        assert_eq!(pack_bits([true; 8].into_iter()), vec![0xFF]);
+        assert_eq!(
+            pack_bits([true, false, true].into_iter()),
+            vec![0b1010_0000]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wavelet_hash_resists_resize_and_recompression() {
+        let hasher = WaveletHasher::new(8);
+        let original = sample_image(1);
+
+        let original_hash = hasher.generate_hash(&original).await.unwrap();
+        let resized = hasher
+            .generate_hash(&original.resize_exact(160, 120, image::imageops::FilterType::Triangle))
+            .await
+            .unwrap();
+        let recompressed = hasher
+            .generate_hash(&recompress_jpeg(&original, 40))
+            .await
+            .unwrap();
+        let different = hasher.generate_hash(&sample_image(4)).await.unwrap();
+
+        let distance = |hash| hasher.calculate_distance(&original_hash, hash).unwrap();
+        assert!(distance(&resized) <= 4, "resized: {}", distance(&resized));
+        assert!(
+            distance(&recompressed) <= 4,
+            "recompressed: {}",
+            distance(&recompressed)
+        );
+        assert!(
+            distance(&different) > 16,
+            "different: {}",
+            distance(&different)
+        );
+    }
+}
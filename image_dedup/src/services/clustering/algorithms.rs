@@ -0,0 +1,244 @@
+// 重複グループのクラスタリングアルゴリズム
+
+use super::UnionFind;
+
+/// クラスタリング対象の類似度グラフ
+///
+/// 要素は `0..len()` のインデックスで表し、閾値以内の要素同士を辺で結んだグラフとして扱う
+pub trait SimilarityGraph {
+    /// 要素数
+    fn len(&self) -> usize;
+
+    /// 要素が空かどうか
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 要素 `i` と閾値以内にある要素を（インデックス, 距離）のリストで取得
+    ///
+    /// 自分自身を含んでもよい。返却順序は問わない
+    fn neighbours(&self, i: usize) -> Vec<(usize, u32)>;
+
+    /// 2つの要素間の距離
+    fn distance(&self, i: usize, j: usize) -> u32;
+
+    /// 2つの要素が閾値以内かどうか
+    fn is_similar(&self, i: usize, j: usize) -> bool;
+}
+
+/// スター型グルーピング
+///
+/// 未処理の要素を先頭から順に中心とし、中心から閾値以内の未処理要素をまとめる。
+/// 結果は要素の並び順に依存し、A~B・B~Cのような連鎖は分断されることがある
+pub fn star_clusters<G: SimilarityGraph>(graph: &G) -> Vec<Vec<usize>> {
+    let mut processed = vec![false; graph.len()];
+    let mut clusters = Vec::new();
+
+    for i in 0..graph.len() {
+        if processed[i] {
+            continue;
+        }
+        processed[i] = true;
+
+        let mut neighbours: Vec<usize> = graph
+            .neighbours(i)
+            .into_iter()
+            .map(|(j, _)| j)
+            .filter(|&j| !processed[j])
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+
+        if neighbours.is_empty() {
+            continue;
+        }
+
+        for &j in &neighbours {
+            processed[j] = true;
+        }
+
+        let mut cluster = Vec::with_capacity(neighbours.len() + 1);
+        cluster.push(i);
+        cluster.extend(neighbours);
+        clusters.push(cluster);
+    }
+
+    clusters
+}
+
+/// 連結成分によるグルーピング（推移的）
+///
+/// 閾値以内の辺でつながった要素をすべて同じグループにまとめる。
+/// 結果は要素の並び順に依存しない。各グループは昇順で、先頭が代表となる
+pub fn connected_clusters<G: SimilarityGraph>(graph: &G) -> Vec<Vec<usize>> {
+    let mut union_find = UnionFind::new(graph.len());
+
+    for i in 0..graph.len() {
+        for (j, _) in graph.neighbours(i) {
+            if j > i {
+                union_find.union(i, j);
+            }
+        }
+    }
+
+    // 代表元ごとに集約（最小インデックスの出現順でグループを並べる）
+    let mut cluster_of_root = vec![usize::MAX; graph.len()];
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for i in 0..graph.len() {
+        let root = union_find.find(i);
+        if cluster_of_root[root] == usize::MAX {
+            cluster_of_root[root] = clusters.len();
+            clusters.push(Vec::new());
+        }
+        clusters[cluster_of_root[root]].push(i);
+    }
+
+    clusters.retain(|cluster| cluster.len() > 1);
+    clusters
+}
+
+/// 完全連結によるグルーピング
+///
+/// グループ内のすべての組が閾値以内になるようにまとめる。
+/// 未処理の要素を先頭から順に中心とし、中心に近い候補から順に
+/// 既存メンバー全員と閾値以内の場合だけ追加する
+pub fn complete_linkage_clusters<G: SimilarityGraph>(graph: &G) -> Vec<Vec<usize>> {
+    let mut processed = vec![false; graph.len()];
+    let mut clusters = Vec::new();
+
+    for i in 0..graph.len() {
+        if processed[i] {
+            continue;
+        }
+        processed[i] = true;
+
+        let mut candidates: Vec<(u32, usize)> = graph
+            .neighbours(i)
+            .into_iter()
+            .filter(|&(j, _)| !processed[j])
+            .map(|(j, distance)| (distance, j))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut cluster = vec![i];
+        for (_, candidate) in candidates {
+            if cluster
+                .iter()
+                .all(|&member| graph.is_similar(member, candidate))
+            {
+                cluster.push(candidate);
+            }
+        }
+
+        if cluster.len() > 1 {
+            for &member in &cluster {
+                processed[member] = true;
+            }
+            cluster[1..].sort_unstable();
+            clusters.push(cluster);
+        }
+    }
+
+    clusters
+}
+
+/// グループ内の最大距離（全ペアの距離の最大値）
+pub fn max_intra_distance<G: SimilarityGraph>(graph: &G, cluster: &[usize]) -> u32 {
+    cluster
+        .iter()
+        .enumerate()
+        .flat_map(|(a, &i)| cluster[a + 1..].iter().map(move |&j| (i, j)))
+        .map(|(i, j)| graph.distance(i, j))
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1次元の数値を要素とするテスト用グラフ（距離は差の絶対値）
+    struct LineGraph {
+        points: Vec<u32>,
+        threshold: u32,
+    }
+
+    impl SimilarityGraph for LineGraph {
+        fn len(&self) -> usize {
+            self.points.len()
+        }
+
+        fn neighbours(&self, i: usize) -> Vec<(usize, u32)> {
+            (0..self.points.len())
+                .map(|j| (j, self.distance(i, j)))
+                .filter(|&(_, distance)| distance <= self.threshold)
+                .collect()
+        }
+
+        fn distance(&self, i: usize, j: usize) -> u32 {
+            self.points[i].abs_diff(self.points[j])
+        }
+
+        fn is_similar(&self, i: usize, j: usize) -> bool {
+            self.distance(i, j) <= self.threshold
+        }
+    }
+
+    fn graph(points: &[u32], threshold: u32) -> LineGraph {
+        LineGraph {
+            points: points.to_vec(),
+            threshold,
+        }
+    }
+
+    #[test]
+    fn test_star_clusters_splits_chain() {
+        // 0~3, 3~6 だが 0と6は閾値外
+        let g = graph(&[0, 3, 6, 100], 3);
+        assert_eq!(star_clusters(&g), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_connected_clusters_follows_chain() {
+        let g = graph(&[0, 3, 6, 100], 3);
+        assert_eq!(connected_clusters(&g), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_connected_clusters_independent_of_order() {
+        let forward = connected_clusters(&graph(&[0, 3, 6, 50, 52], 3));
+        let reversed = connected_clusters(&graph(&[52, 50, 6, 3, 0], 3));
+
+        assert_eq!(forward, vec![vec![0, 1, 2], vec![3, 4]]);
+        assert_eq!(reversed, vec![vec![0, 1], vec![2, 3, 4]]);
+    }
+
+    #[test]
+    fn test_complete_linkage_keeps_all_pairs_within_threshold() {
+        // 連結成分なら0..=5が1グループになるが、0と5は閾値外
+        let g = graph(&[0, 2, 3, 5, 100], 3);
+        assert_eq!(connected_clusters(&g), vec![vec![0, 1, 2, 3]]);
+
+        let clusters = complete_linkage_clusters(&g);
+        assert_eq!(clusters, vec![vec![0, 1, 2]]);
+        assert!(max_intra_distance(&g, &clusters[0]) <= 3);
+    }
+
+    #[test]
+    fn test_no_clusters_without_neighbours() {
+        let g = graph(&[0, 10, 20], 3);
+
+        assert!(star_clusters(&g).is_empty());
+        assert!(connected_clusters(&g).is_empty());
+        assert!(complete_linkage_clusters(&g).is_empty());
+    }
+
+    #[test]
+    fn test_max_intra_distance() {
+        let g = graph(&[0, 3, 6], 3);
+
+        assert_eq!(max_intra_distance(&g, &[0, 1, 2]), 6);
+        assert_eq!(max_intra_distance(&g, &[1]), 0);
+    }
+}
@@ -0,0 +1,12 @@
+// 重複グループのクラスタリング
+// 類似度グラフからスター型・連結成分・完全連結のいずれかでグループを構成
+
+pub mod algorithms;
+pub mod union_find;
+
+// 公開API
+pub use algorithms::{
+    complete_linkage_clusters, connected_clusters, max_intra_distance, star_clusters,
+    SimilarityGraph,
+};
+pub use union_find::UnionFind;
@@ -0,0 +1,87 @@
+// UnionFind - 素集合データ構造
+
+/// 経路圧縮とランクによる併合を行う素集合データ構造
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl UnionFind {
+    /// `len` 個の単独集合で初期化
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+        }
+    }
+
+    /// 要素が属する集合の代表元を取得
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // 経路圧縮
+        let mut current = x;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// 2つの要素が属する集合を併合
+    ///
+    /// 併合が行われた場合は `true`（既に同じ集合なら `false`）
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return false;
+        }
+
+        match self.rank[root_a].cmp(&self.rank[root_b]) {
+            std::cmp::Ordering::Less => self.parent[root_a] = root_b,
+            std::cmp::Ordering::Greater => self.parent[root_b] = root_a,
+            std::cmp::Ordering::Equal => {
+                self.parent[root_b] = root_a;
+                self.rank[root_a] += 1;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find_basic() {
+        let mut uf = UnionFind::new(5);
+
+        assert!(uf.union(0, 1));
+        assert!(uf.union(3, 4));
+        assert!(!uf.union(1, 0));
+
+        assert_eq!(uf.find(0), uf.find(1));
+        assert_eq!(uf.find(3), uf.find(4));
+        assert_ne!(uf.find(0), uf.find(3));
+        assert_eq!(uf.find(2), 2);
+    }
+
+    #[test]
+    fn test_union_find_chain() {
+        let mut uf = UnionFind::new(100);
+        for i in 0..99 {
+            uf.union(i, i + 1);
+        }
+
+        let root = uf.find(0);
+        assert!((0..100).all(|i| uf.find(i) == root));
+    }
+}
@@ -1,6 +1,6 @@
 // 設定管理の具象実装
 
-use crate::core::ProcessingConfig;
+use crate::core::{ContentHashAlgorithm, Preprocessing, ProcessingConfig};
 
 /// デフォルト設定実装
 #[derive(Debug, Clone)]
@@ -9,6 +9,11 @@ pub struct DefaultProcessingConfig {
     buffer_size: usize,
     batch_size: usize,
     enable_progress: bool,
+    content_hash: Option<ContentHashAlgorithm>,
+    exact_only: bool,
+    rotation_invariant: bool,
+    color_signature: bool,
+    preprocessing: Preprocessing,
 }
 
 impl DefaultProcessingConfig {
@@ -18,6 +23,11 @@ impl DefaultProcessingConfig {
             buffer_size: 100,
             batch_size: 50,
             enable_progress: true,
+            content_hash: None,
+            exact_only: false,
+            rotation_invariant: false,
+            color_signature: false,
+            preprocessing: Preprocessing::default(),
         }
     }
 
@@ -40,6 +50,38 @@ impl DefaultProcessingConfig {
         self.enable_progress = enable;
         self
     }
+
+    pub fn with_content_hash(mut self, algorithm: Option<ContentHashAlgorithm>) -> Self {
+        self.content_hash = algorithm;
+        self
+    }
+
+    /// 画像をデコードせず内容ハッシュのみを計算する（内容ハッシュ未指定ならBLAKE3）
+    pub fn with_exact_only(mut self, exact_only: bool) -> Self {
+        self.exact_only = exact_only;
+        if exact_only && self.content_hash.is_none() {
+            self.content_hash = Some(ContentHashAlgorithm::default());
+        }
+        self
+    }
+
+    /// 回転・反転した8通りの知覚ハッシュも計算する
+    pub fn with_rotation_invariant(mut self, rotation_invariant: bool) -> Self {
+        self.rotation_invariant = rotation_invariant;
+        self
+    }
+
+    /// 色違いを区別するための色の署名も計算する
+    pub fn with_color_signature(mut self, color_signature: bool) -> Self {
+        self.color_signature = color_signature;
+        self
+    }
+
+    /// ハッシュ計算前に画像へ前処理を適用する
+    pub fn with_preprocessing(mut self, preprocessing: Preprocessing) -> Self {
+        self.preprocessing = preprocessing;
+        self
+    }
 }
 
 impl Default for DefaultProcessingConfig {
@@ -49,6 +91,11 @@ impl Default for DefaultProcessingConfig {
             buffer_size: 100,
             batch_size: 50,
             enable_progress: true,
+            content_hash: None,
+            exact_only: false,
+            rotation_invariant: false,
+            color_signature: false,
+            preprocessing: Preprocessing::default(),
         }
     }
 }
@@ -69,6 +116,26 @@ impl ProcessingConfig for DefaultProcessingConfig {
     fn enable_progress_reporting(&self) -> bool {
         self.enable_progress
     }
+
+    fn content_hash_algorithm(&self) -> Option<ContentHashAlgorithm> {
+        self.content_hash
+    }
+
+    fn exact_only(&self) -> bool {
+        self.exact_only
+    }
+
+    fn rotation_invariant(&self) -> bool {
+        self.rotation_invariant
+    }
+
+    fn color_signature(&self) -> bool {
+        self.color_signature
+    }
+
+    fn preprocessing(&self) -> Preprocessing {
+        self.preprocessing.clone()
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +150,11 @@ mod tests {
         assert_eq!(config.channel_buffer_size(), 100);
         assert_eq!(config.batch_size(), 50);
         assert!(config.enable_progress_reporting());
+        assert_eq!(config.content_hash_algorithm(), None);
+        assert!(!config.exact_only());
+        assert!(!config.rotation_invariant());
+        assert!(!config.color_signature());
+        assert!(!config.preprocessing().is_enabled());
     }
 
     #[test]
@@ -91,11 +163,35 @@ mod tests {
             .with_max_concurrent(8)
             .with_buffer_size(200)
             .with_batch_size(100)
-            .with_progress_reporting(false);
+            .with_progress_reporting(false)
+            .with_rotation_invariant(true)
+            .with_color_signature(true)
+            .with_preprocessing(Preprocessing::from_flags(None, true, false));
 
         assert_eq!(config.max_concurrent_tasks(), 8);
         assert_eq!(config.channel_buffer_size(), 200);
         assert_eq!(config.batch_size(), 100);
         assert!(!config.enable_progress_reporting());
+        assert!(config.rotation_invariant());
+        assert!(config.color_signature());
+        assert!(config.preprocessing().is_enabled());
+    }
+
+    #[test]
+    fn test_exact_only_implies_content_hash() {
+        let config = DefaultProcessingConfig::default().with_exact_only(true);
+        assert!(config.exact_only());
+        assert_eq!(
+            config.content_hash_algorithm(),
+            Some(ContentHashAlgorithm::Blake3)
+        );
+
+        let config = DefaultProcessingConfig::default()
+            .with_content_hash(Some(ContentHashAlgorithm::Sha256))
+            .with_exact_only(true);
+        assert_eq!(
+            config.content_hash_algorithm(),
+            Some(ContentHashAlgorithm::Sha256)
+        );
     }
 }
@@ -0,0 +1,326 @@
+// リンクによる置き換え - 重複ファイルを保持するファイルへのリンクに置き換えて容量を回収する
+
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// 重複ファイルを置き換えるリンクの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// ハードリンク（同じファイルシステム上でのみ作成できる）
+    Hardlink,
+    /// シンボリックリンク
+    Symlink,
+    /// reflink（コピーオンライトでデータを共有する独立したファイル。Btrfs・XFS・APFSなど）
+    Reflink,
+}
+
+/// `duplicate` を `target` へのリンクで置き換え、実際に作成したリンクの種類を返す
+///
+/// `target` はシンボリックリンクの場合はリンクに書き込む内容（相対パスなら `duplicate` の
+/// ディレクトリからの相対）、それ以外は保持するファイルのパス。
+///
+/// どの種類のリンクも内容がバイト単位で一致する場合のみ作成し、一致しない場合は
+/// ファイルを変更せずに `None` を返す。`allow_different` を指定すると内容が異なる
+/// ファイル（類似画像）も置き換えるが、元の内容は失われ復元できない。
+///
+/// ハードリンクを別のファイルシステムで作成できない場合は絶対パスのシンボリックリンクで
+/// 代替する。reflinkに対応していないファイルシステムではエラーとし、ファイルは変更しない。
+/// リンクは一時ファイルとして作成してから置き換えるため、失敗しても重複ファイルは失われない
+pub fn replace_with_link(
+    duplicate: &Path,
+    target: &Path,
+    kind: LinkKind,
+    allow_different: bool,
+) -> Result<Option<LinkKind>> {
+    let original = match kind {
+        LinkKind::Symlink => resolve_link_target(duplicate, target),
+        LinkKind::Hardlink | LinkKind::Reflink => target.to_path_buf(),
+    };
+    if !original.is_file() {
+        anyhow::bail!("Link target does not exist: {}", original.display());
+    }
+    if is_same_file(duplicate, &original) {
+        anyhow::bail!("Already linked to {}", original.display());
+    }
+    if !allow_different && !files_identical(duplicate, &original)? {
+        return Ok(None);
+    }
+
+    let temp_path = temporary_path(duplicate)?;
+    let created = match kind {
+        LinkKind::Hardlink => match fs::hard_link(&original, &temp_path) {
+            Ok(()) => LinkKind::Hardlink,
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+                symlink(&std::path::absolute(&original)?, &temp_path)?;
+                LinkKind::Symlink
+            }
+            Err(e) => return Err(e.into()),
+        },
+        LinkKind::Symlink => {
+            symlink(target, &temp_path)?;
+            LinkKind::Symlink
+        }
+        LinkKind::Reflink => {
+            if let Err(e) = reflink_copy::reflink(&original, &temp_path) {
+                let _ = fs::remove_file(&temp_path);
+                anyhow::bail!("Reflink is not supported here, file left unchanged: {e}");
+            }
+            LinkKind::Reflink
+        }
+    };
+
+    if let Err(e) = fs::rename(&temp_path, duplicate) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(Some(created))
+}
+
+/// `path` を `source` の独立したコピーで置き換える（リンクを元に戻す際に使う）
+pub fn replace_with_copy(path: &Path, source: &Path) -> Result<()> {
+    let temp_path = temporary_path(path)?;
+    let result = fs::copy(source, &temp_path).and_then(|_| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// シンボリックリンクの内容を、リンクのあるディレクトリを基準に解決したパス
+pub fn resolve_link_target(link: &Path, target: &Path) -> PathBuf {
+    match link.parent() {
+        Some(parent) if target.is_relative() => parent.join(target),
+        _ => target.to_path_buf(),
+    }
+}
+
+/// `link` に置くシンボリックリンクの内容
+///
+/// `absolute` でなければ `link` のディレクトリから `original` への相対パスにする
+pub fn symlink_target(link: &Path, original: &Path, absolute: bool) -> Result<PathBuf> {
+    let original = normalize(&std::path::absolute(original)?);
+    if absolute {
+        return Ok(original);
+    }
+
+    let link = normalize(&std::path::absolute(link)?);
+    let base: Vec<Component> = link
+        .parent()
+        .map(|parent| parent.components().collect())
+        .unwrap_or_default();
+    let components: Vec<Component> = original.components().collect();
+    let common = base
+        .iter()
+        .zip(&components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &components[common..] {
+        relative.push(component);
+    }
+    Ok(relative)
+}
+
+/// 2つのパスが同じファイル（同じinode）を指しているか
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// 2つのファイルの内容がバイト単位で一致するか（サイズが異なる場合は読み込まない）
+pub fn files_identical(a: &Path, b: &Path) -> Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    let mut reader_a = BufReader::new(File::open(a)?);
+    let mut reader_b = BufReader::new(File::open(b)?);
+    let mut buffer_a = vec![0u8; 64 * 1024];
+    let mut buffer_b = vec![0u8; 64 * 1024];
+    loop {
+        let read = reader_a.read(&mut buffer_a)?;
+        if read == 0 {
+            return Ok(reader_b.read(&mut buffer_b)? == 0);
+        }
+        reader_b.read_exact(&mut buffer_b[..read])?;
+        if buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// 置き換え用の一時ファイルのパス（同じディレクトリに作ることでrenameを原子的にする）
+fn temporary_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
+    Ok(path.with_file_name(format!(".{}.image_dedup.tmp", file_name.to_string_lossy())))
+}
+
+/// `.` と `..` を取り除く（シンボリックリンクは解決しない）
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+    }
+    #[cfg(windows)]
+    {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_pair(temp_dir: &TempDir, kept: &[u8], duplicate: &[u8]) -> (PathBuf, PathBuf) {
+        let original = temp_dir.path().join("originals/keep.jpg");
+        let copy = temp_dir.path().join("copies/copy.jpg");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::write(&original, kept).unwrap();
+        fs::write(&copy, duplicate).unwrap();
+        (original, copy)
+    }
+
+    #[test]
+    fn test_replace_with_hardlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, b"same", b"same");
+
+        let created = replace_with_link(&copy, &original, LinkKind::Hardlink, false).unwrap();
+
+        assert_eq!(created, Some(LinkKind::Hardlink));
+        assert!(is_same_file(&copy, &original));
+        assert!(!temporary_path(&copy).unwrap().exists());
+        // 2回目はリンク済みとしてエラー
+        assert!(replace_with_link(&copy, &original, LinkKind::Hardlink, false).is_err());
+    }
+
+    #[test]
+    fn test_links_require_identical_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, b"same", b"diff");
+
+        for kind in [LinkKind::Hardlink, LinkKind::Symlink, LinkKind::Reflink] {
+            let created = replace_with_link(&copy, &original, kind, false).unwrap();
+
+            assert_eq!(created, None);
+            assert_eq!(fs::read(&copy).unwrap(), b"diff");
+            assert!(!fs::symlink_metadata(&copy).unwrap().is_symlink());
+            assert!(!is_same_file(&copy, &original));
+        }
+    }
+
+    #[test]
+    fn test_replace_with_relative_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, b"kept", b"similar");
+
+        let target = symlink_target(&copy, &original, false).unwrap();
+        assert_eq!(target, PathBuf::from("../originals/keep.jpg"));
+
+        // 内容が異なるファイルは明示的に許可した場合のみ置き換える
+        let created = replace_with_link(&copy, &target, LinkKind::Symlink, true).unwrap();
+
+        assert_eq!(created, Some(LinkKind::Symlink));
+        assert_eq!(fs::read_link(&copy).unwrap(), target);
+        assert_eq!(fs::read(&copy).unwrap(), b"kept");
+    }
+
+    #[test]
+    fn test_absolute_symlink_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, b"kept", b"kept");
+
+        let target = symlink_target(&copy, &original, true).unwrap();
+
+        assert!(target.is_absolute());
+        assert_eq!(resolve_link_target(&copy, &target), target);
+        assert_eq!(fs::read(&target).unwrap(), b"kept");
+    }
+
+    #[test]
+    fn test_missing_target_leaves_file_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_, copy) = write_pair(&temp_dir, b"kept", b"copy");
+
+        let missing = temp_dir.path().join("missing.jpg");
+        assert!(replace_with_link(&copy, &missing, LinkKind::Symlink, false).is_err());
+        assert_eq!(fs::read(&copy).unwrap(), b"copy");
+    }
+
+    #[test]
+    fn test_reflink_falls_back_without_touching_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, b"same", b"same");
+
+        // reflinkに対応していないファイルシステムでは元のファイルがそのまま残る
+        match replace_with_link(&copy, &original, LinkKind::Reflink, false) {
+            Ok(created) => assert_eq!(created, Some(LinkKind::Reflink)),
+            Err(_) => assert!(!fs::symlink_metadata(&copy).unwrap().is_symlink()),
+        }
+        assert_eq!(fs::read(&copy).unwrap(), b"same");
+        assert!(!temporary_path(&copy).unwrap().exists());
+    }
+
+    #[test]
+    fn test_replace_with_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, b"same", b"same");
+        replace_with_link(&copy, &original, LinkKind::Hardlink, false).unwrap();
+
+        replace_with_copy(&copy, &original).unwrap();
+
+        assert!(!is_same_file(&copy, &original));
+        assert_eq!(fs::read(&copy).unwrap(), b"same");
+    }
+
+    #[test]
+    fn test_files_identical() {
+        let temp_dir = TempDir::new().unwrap();
+        let (original, copy) = write_pair(&temp_dir, &[7u8; 200_000], &[7u8; 200_000]);
+        assert!(files_identical(&original, &copy).unwrap());
+
+        let mut changed = vec![7u8; 200_000];
+        changed[150_000] = 8;
+        fs::write(&copy, changed).unwrap();
+        assert!(!files_identical(&original, &copy).unwrap());
+
+        fs::write(&copy, b"short").unwrap();
+        assert!(!files_identical(&original, &copy).unwrap());
+    }
+}
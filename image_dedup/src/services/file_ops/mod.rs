@@ -0,0 +1,9 @@
+// ファイル操作
+// processコマンドが重複ファイルに対して行う操作（ゴミ箱への移動、リンクによる置き換えなど）
+
+pub mod link;
+pub mod trash;
+
+// 公開API
+pub use link::{replace_with_link, resolve_link_target, symlink_target, LinkKind};
+pub use trash::FreeDesktopTrash;
@@ -0,0 +1,245 @@
+// FreeDesktopTrash - FreeDesktop.org Trash仕様に従ったゴミ箱への移動
+
+use anyhow::Result;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// FreeDesktop.org Trash仕様のゴミ箱
+///
+/// `files/` に本体を、`info/` に元のパスと削除日時を記録した `.trashinfo` を置くため、
+/// デスクトップのファイルマネージャーから確認・復元できる。
+/// ボリュームごとの `$topdir/.Trash` は使わず、常にこのゴミ箱へ移動する
+#[derive(Debug, Clone)]
+pub struct FreeDesktopTrash {
+    root: PathBuf,
+}
+
+impl FreeDesktopTrash {
+    /// 任意のディレクトリをゴミ箱として使う（テストや別ユーザーのゴミ箱用）
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// ホームのゴミ箱（`$XDG_DATA_HOME/Trash`、未設定なら `~/.local/share/Trash`）
+    pub fn home() -> Result<Self> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Cannot locate the trash directory: neither XDG_DATA_HOME nor HOME is set"
+                )
+            })?;
+        Ok(Self::new(data_home.join("Trash")))
+    }
+
+    /// ゴミ箱のルートディレクトリ
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// ファイルをゴミ箱へ移動し、ゴミ箱内のパスを返す
+    ///
+    /// 同名のファイルが既にある場合は `name.2.ext` のように番号を付ける。
+    /// 別のファイルシステムへは移動できないため、その場合はコピーしてから元を削除する
+    pub fn trash(&self, path: &Path) -> Result<PathBuf> {
+        let trashed_path = self.reserve(path)?;
+        Self::move_into(path, &trashed_path)?;
+        Ok(trashed_path)
+    }
+
+    /// ゴミ箱内の名前を確保して `.trashinfo` を作成し、移動先のパスを返す（ファイルは移動しない）
+    ///
+    /// 移動する前に移動先を記録する場合に使い、続けて `move_into` で移動する
+    pub fn reserve(&self, path: &Path) -> Result<PathBuf> {
+        fs::symlink_metadata(path)
+            .map_err(|e| anyhow::anyhow!("Cannot trash {}: {}", path.display(), e))?;
+        let original = std::path::absolute(path)?;
+        let file_name = original
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
+
+        let files_dir = self.root.join("files");
+        let info_dir = self.root.join("info");
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+
+        // .trashinfoを排他的に作成できた名前を確保する（仕様で定められた手順）
+        let mut n = 1;
+        loop {
+            let name = unique_name(Path::new(file_name), n);
+            n += 1;
+
+            let trashed_path = files_dir.join(&name);
+            let info_path = info_dir.join(format!("{name}.trashinfo"));
+            if trashed_path.exists() {
+                continue;
+            }
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(mut info) => {
+                    info.write_all(trash_info(&original).as_bytes())?;
+                    return Ok(trashed_path);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// `reserve` で確保したゴミ箱内のパスへファイルを移動する
+    ///
+    /// 移動できなかった場合は `.trashinfo` を削除し、確保した名前を解放する
+    pub fn move_into(path: &Path, trashed_path: &Path) -> Result<()> {
+        if let Err(e) = move_or_copy(path, trashed_path) {
+            Self::release(trashed_path);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// `reserve` で確保した名前を解放する（`.trashinfo` を削除）
+    pub fn release(trashed_path: &Path) {
+        if let Some(info_path) = Self::info_path(trashed_path) {
+            let _ = fs::remove_file(info_path);
+        }
+    }
+
+    /// ゴミ箱内のファイルに対応する `.trashinfo` のパス
+    pub fn info_path(trashed_path: &Path) -> Option<PathBuf> {
+        let name = trashed_path.file_name()?.to_string_lossy();
+        let root = trashed_path.parent()?.parent()?;
+        Some(root.join("info").join(format!("{name}.trashinfo")))
+    }
+}
+
+/// 重複しない名前の候補（1番目はそのままの名前）
+fn unique_name(file_name: &Path, n: usize) -> String {
+    let name = file_name.to_string_lossy();
+    if n == 1 {
+        return name.into_owned();
+    }
+    match (file_name.file_stem(), file_name.extension()) {
+        (Some(stem), Some(extension)) => format!(
+            "{}.{n}.{}",
+            stem.to_string_lossy(),
+            extension.to_string_lossy()
+        ),
+        _ => format!("{name}.{n}"),
+    }
+}
+
+/// `.trashinfo` の内容
+fn trash_info(original: &Path) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(original),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )
+}
+
+/// パスをURLのパス部分としてエンコード（`/` と非予約文字以外をパーセントエンコード）
+fn percent_encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// 移動（別のファイルシステムの場合はコピーして元を削除）
+fn move_or_copy(source: &Path, destination: &Path) -> Result<()> {
+    if fs::rename(source, destination).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(source, destination).map_err(|e| {
+        let _ = fs::remove_file(destination);
+        anyhow::anyhow!("Failed to move to trash: {e}")
+    })?;
+    fs::remove_file(source)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_trash_writes_trashinfo() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = FreeDesktopTrash::new(temp_dir.path().join("Trash"));
+        let file = temp_dir.path().join("my photo.jpg");
+        fs::write(&file, b"data").unwrap();
+
+        let trashed = trash.trash(&file).unwrap();
+
+        assert!(!file.exists());
+        assert_eq!(trashed, trash.root().join("files/my photo.jpg"));
+        assert_eq!(fs::read(&trashed).unwrap(), b"data");
+
+        let info_path = FreeDesktopTrash::info_path(&trashed).unwrap();
+        assert_eq!(info_path, trash.root().join("info/my photo.jpg.trashinfo"));
+        let info = fs::read_to_string(info_path).unwrap();
+        let mut lines = info.lines();
+        assert_eq!(lines.next(), Some("[Trash Info]"));
+        assert_eq!(
+            lines.next().unwrap(),
+            format!("Path={}", percent_encode_path(&file))
+        );
+        assert!(info.contains("Path=/") && info.contains("my%20photo.jpg"));
+        assert!(lines.next().unwrap().starts_with("DeletionDate="));
+    }
+
+    #[test]
+    fn test_trash_uses_unique_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = FreeDesktopTrash::new(temp_dir.path().join("Trash"));
+
+        let mut trashed = Vec::new();
+        for dir in ["a", "b", "c"] {
+            let file = temp_dir.path().join(dir).join("photo.jpg");
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(&file, dir).unwrap();
+            trashed.push(trash.trash(&file).unwrap());
+        }
+
+        let names: Vec<String> = trashed
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["photo.jpg", "photo.2.jpg", "photo.3.jpg"]);
+        assert_eq!(fs::read_to_string(&trashed[2]).unwrap(), "c");
+        assert!(trash.root().join("info/photo.3.jpg.trashinfo").exists());
+    }
+
+    #[test]
+    fn test_trash_missing_file_leaves_no_trashinfo() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = FreeDesktopTrash::new(temp_dir.path().join("Trash"));
+
+        assert!(trash.trash(&temp_dir.path().join("missing.jpg")).is_err());
+        assert!(!trash.root().join("info/missing.jpg.trashinfo").exists());
+    }
+
+    #[test]
+    fn test_percent_encode_path() {
+        assert_eq!(
+            percent_encode_path(Path::new("/home/user/写真 1.jpg")),
+            "/home/user/%E5%86%99%E7%9C%9F%201.jpg"
+        );
+    }
+}
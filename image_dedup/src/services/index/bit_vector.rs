@@ -0,0 +1,171 @@
+// BitVector - 任意長のハッシュビット列
+
+use super::BinaryHash;
+use anyhow::Result;
+
+/// 任意長のハッシュビット列
+///
+/// `HashResult::hash_data` と同じバイト列（各バイトは上位ビットが先頭）を保持する。
+/// 16×16や32×32のハッシュを先頭64ビットに切り詰めずに比較するために使う。
+/// `bit_len` を超える末尾のパディングビットは常に0に正規化される
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitVector {
+    bytes: Vec<u8>,
+    bit_len: u32,
+}
+
+impl BitVector {
+    /// バイト列とビット長から作成
+    pub fn from_bytes(mut bytes: Vec<u8>, bit_len: u32) -> Result<Self> {
+        let required_bytes = bit_len.div_ceil(8) as usize;
+        if bytes.len() < required_bytes {
+            anyhow::bail!(
+                "Hash data too short: {} bits requires {} bytes, got {}",
+                bit_len,
+                required_bytes,
+                bytes.len()
+            );
+        }
+
+        bytes.truncate(required_bytes);
+        let padding = required_bytes as u32 * 8 - bit_len;
+        if let Some(last) = bytes.last_mut() {
+            *last &= 0xFFu8 << padding;
+        }
+
+        Ok(Self { bytes, bit_len })
+    }
+
+    /// 16進文字列から作成
+    ///
+    /// `bit_len` が `None` の場合は文字列長から求める（1文字=4ビット）
+    pub fn from_hex(hex: &str, bit_len: Option<u32>) -> Result<Self> {
+        let bytes = hex::decode(hex)?;
+        let bit_len = bit_len.unwrap_or(bytes.len() as u32 * 8);
+        Self::from_bytes(bytes, bit_len)
+    }
+
+    /// 64ビット値から作成（旧フォーマットの `hash_bits` 用）
+    pub fn from_u64(value: u64) -> Self {
+        Self {
+            bytes: value.to_be_bytes().to_vec(),
+            bit_len: u64::BITS,
+        }
+    }
+
+    /// バイト列を取得
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// `index` ビット目の値を取得
+    fn bit(&self, index: u32) -> bool {
+        let byte = self.bytes[(index / 8) as usize];
+        byte & (0x80 >> (index % 8)) != 0
+    }
+}
+
+impl BinaryHash for BitVector {
+    fn bit_len(&self) -> u32 {
+        self.bit_len
+    }
+
+    fn hamming_distance(&self, other: &Self) -> u32 {
+        // 長さが異なる場合は、短い方にない部分をすべて異なるビットとして数える
+        let common: u32 = self
+            .bytes
+            .iter()
+            .zip(&other.bytes)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+        common + self.bit_len.abs_diff(other.bit_len)
+    }
+
+    fn chunk_key(&self, start: u32, len: u32) -> u64 {
+        // 64ビット以下は部分列そのもの、それを超える場合は回転しながら畳み込む
+        let end = start.saturating_add(len).min(self.bit_len);
+        (start..end).fold(0u64, |key, index| {
+            key.rotate_left(1) ^ u64::from(self.bit(index))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_u64_matches_u64_distance() {
+        let a: u64 = 0xF0F0_1234_5678_9ABC;
+        let b: u64 = 0x0FF0_1234_5678_9ABD;
+
+        let va = BitVector::from_u64(a);
+        let vb = BitVector::from_u64(b);
+
+        assert_eq!(va.bit_len(), 64);
+        assert_eq!(va.hamming_distance(&vb), a.hamming_distance(&b));
+    }
+
+    #[test]
+    fn test_chunk_key_matches_u64_bit_order() {
+        let value: u64 = 0xAB00_0000_0000_00CD;
+        let vector = BitVector::from_u64(value);
+
+        // BitVectorは先頭（最上位）ビットから数える
+        assert_eq!(vector.chunk_key(0, 8), 0xAB);
+        assert_eq!(vector.chunk_key(56, 8), 0xCD);
+        assert_eq!(vector.chunk_key(0, 64), value);
+    }
+
+    #[test]
+    fn test_from_hex_full_width() {
+        // 256ビット（16×16）のハッシュ。先頭64ビットは同じで後半だけ異なる
+        let a = "00".repeat(32);
+        let b = format!("{}{}", "00".repeat(8), "ff".repeat(24));
+
+        let va = BitVector::from_hex(&a, None).unwrap();
+        let vb = BitVector::from_hex(&b, Some(256)).unwrap();
+
+        assert_eq!(va.bit_len(), 256);
+        assert_eq!(va.hamming_distance(&vb), 192);
+    }
+
+    #[test]
+    fn test_from_bytes_masks_padding_bits() {
+        // 5×5 = 25ビット。末尾7ビットはパディングとして無視される
+        let a = BitVector::from_bytes(vec![0xFF, 0xFF, 0xFF, 0xFF], 25).unwrap();
+        let b = BitVector::from_bytes(vec![0xFF, 0xFF, 0xFF, 0x80], 25).unwrap();
+
+        assert_eq!(a, b);
+        assert_eq!(a.hamming_distance(&b), 0);
+        assert_eq!(a.as_bytes(), &[0xFF, 0xFF, 0xFF, 0x80]);
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_short_data() {
+        assert!(BitVector::from_bytes(vec![0xFF], 16).is_err());
+        assert!(BitVector::from_hex("not hex", None).is_err());
+    }
+
+    #[test]
+    fn test_multi_index_over_full_width_hashes() {
+        use crate::services::MultiIndexHash;
+
+        let base = "a5".repeat(32);
+        // 末尾のバイトだけ異なる（距離2）、先頭64ビット外で大きく異なる（距離64）
+        let near = format!("{}a6", "a5".repeat(31));
+        let far = format!("{}{}{}", "a5".repeat(16), "5a".repeat(8), "a5".repeat(8));
+
+        let mut index = MultiIndexHash::new(256, 20);
+        for (id, hex) in [&base, &near, &far].into_iter().enumerate() {
+            index
+                .insert(BitVector::from_hex(hex, None).unwrap(), id)
+                .unwrap();
+        }
+
+        let query = BitVector::from_hex(&base, None).unwrap();
+        let mut found = index.find_within(&query, 20);
+        found.sort_unstable();
+        assert_eq!(found, vec![(0, 0), (1, 2)]);
+    }
+}
@@ -0,0 +1,9 @@
+// 近傍探索インデックス
+// ハミング距離で閾値以内のハッシュを総当たりより少ない比較回数で検索
+
+pub mod bit_vector;
+pub mod multi_index;
+
+// 公開API
+pub use bit_vector::BitVector;
+pub use multi_index::{BinaryHash, MultiIndexHash};
@@ -0,0 +1,305 @@
+// Multi-Index Hashing - ハミング距離による近傍探索
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// 近傍探索の対象となるバイナリハッシュ
+pub trait BinaryHash {
+    /// ハッシュのビット長
+    fn bit_len(&self) -> u32;
+
+    /// 2つのハッシュ間のハミング距離を計算
+    fn hamming_distance(&self, other: &Self) -> u32;
+
+    /// `start`ビット目から`len`ビット分の部分列をキー化
+    ///
+    /// 部分列が等しければ同じキーを返すこと。異なる部分列が同じキーになる
+    /// （衝突する）ことは許容される（候補が増えるだけで結果は変わらない）
+    fn chunk_key(&self, start: u32, len: u32) -> u64;
+}
+
+impl BinaryHash for u64 {
+    fn bit_len(&self) -> u32 {
+        u64::BITS
+    }
+
+    fn hamming_distance(&self, other: &Self) -> u32 {
+        (self ^ other).count_ones()
+    }
+
+    fn chunk_key(&self, start: u32, len: u32) -> u64 {
+        if len == 0 {
+            return 0;
+        }
+        let mask = if len >= u64::BITS {
+            u64::MAX
+        } else {
+            (1u64 << len) - 1
+        };
+        (self >> start) & mask
+    }
+}
+
+/// Multi-Index Hashing（Norouzi et al.）による近傍探索インデックス
+///
+/// bビットのハッシュを T+1 個の互いに素な部分列に分割し、部分列ごとに
+/// ハッシュテーブルを持つ。距離 T 以内の2つのハッシュは鳩の巣原理により
+/// 少なくとも1つの部分列が完全一致するため、各テーブルの同一バケットに
+/// 入っている要素だけを候補として全ビットの距離を検証すればよい。
+///
+/// 64ビット・閾値5なら約11ビットの部分列6個となり、
+/// 総当たりの全件比較を数百件程度の候補検証に置き換えられる。
+#[derive(Debug, Clone)]
+pub struct MultiIndexHash<K> {
+    bit_len: u32,
+    threshold: u32,
+    chunks: Vec<(u32, u32)>,
+    tables: Vec<HashMap<u64, Vec<usize>>>,
+    entries: Vec<(K, usize)>,
+}
+
+impl<K: BinaryHash> MultiIndexHash<K> {
+    /// 空のインデックスを作成
+    ///
+    /// `threshold` はこのインデックスで効率よく検索できる最大距離。
+    /// それを超える距離での検索は線形走査にフォールバックする
+    pub fn new(bit_len: u32, threshold: u32) -> Self {
+        let chunk_count = threshold.saturating_add(1).min(bit_len).max(1);
+        let base_len = bit_len / chunk_count;
+        let remainder = bit_len % chunk_count;
+
+        let mut chunks = Vec::with_capacity(chunk_count as usize);
+        let mut start = 0;
+        for i in 0..chunk_count {
+            let len = base_len + u32::from(i < remainder);
+            chunks.push((start, len));
+            start += len;
+        }
+
+        Self {
+            bit_len,
+            threshold,
+            tables: vec![HashMap::new(); chunks.len()],
+            chunks,
+            entries: Vec::new(),
+        }
+    }
+
+    /// 登録されているハッシュ数を取得
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 効率よく検索できる最大距離を取得
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// ハッシュと識別子（元データのインデックスなど）を追加
+    pub fn insert(&mut self, key: K, id: usize) -> Result<()> {
+        if key.bit_len() != self.bit_len {
+            anyhow::bail!(
+                "Hash length mismatch: index expects {} bits, got {}",
+                self.bit_len,
+                key.bit_len()
+            );
+        }
+
+        let position = self.entries.len();
+        for (table, &(start, len)) in self.tables.iter_mut().zip(&self.chunks) {
+            table
+                .entry(key.chunk_key(start, len))
+                .or_default()
+                .push(position);
+        }
+        self.entries.push((key, id));
+        Ok(())
+    }
+
+    /// 距離 `threshold` 以内の全要素を（識別子, 距離）のリストで取得
+    ///
+    /// 返却順序は不定。呼び出し側で必要に応じてソートすること
+    pub fn find_within(&self, key: &K, threshold: u32) -> Vec<(usize, u32)> {
+        if key.bit_len() != self.bit_len {
+            return Vec::new();
+        }
+
+        if threshold > self.threshold {
+            return self.linear_find_within(key, threshold);
+        }
+
+        // いずれかの部分列が完全一致する要素を候補として収集
+        let mut candidates: Vec<usize> = self
+            .tables
+            .iter()
+            .zip(&self.chunks)
+            .filter_map(|(table, &(start, len))| table.get(&key.chunk_key(start, len)))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        candidates
+            .into_iter()
+            .filter_map(|position| {
+                let (candidate, id) = &self.entries[position];
+                let distance = candidate.hamming_distance(key);
+                (distance <= threshold).then_some((*id, distance))
+            })
+            .collect()
+    }
+
+    /// 全要素との距離を計算する線形走査
+    fn linear_find_within(&self, key: &K, threshold: u32) -> Vec<(usize, u32)> {
+        self.entries
+            .iter()
+            .filter_map(|(candidate, id)| {
+                let distance = candidate.hamming_distance(key);
+                (distance <= threshold).then_some((*id, distance))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用の決定的な擬似乱数（SplitMix64）
+    fn pseudo_random_hashes(count: usize, mut seed: u64) -> Vec<u64> {
+        (0..count)
+            .map(|_| {
+                seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = seed;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^ (z >> 31)
+            })
+            .collect()
+    }
+
+    fn build_index(hashes: &[u64], threshold: u32) -> MultiIndexHash<u64> {
+        let mut index = MultiIndexHash::new(u64::BITS, threshold);
+        for (id, hash) in hashes.iter().enumerate() {
+            index.insert(*hash, id).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_u64_chunk_key() {
+        let hash: u64 = 0xF0F0_0000_0000_00AB;
+
+        assert_eq!(hash.chunk_key(0, 8), 0xAB);
+        assert_eq!(hash.chunk_key(56, 8), 0xF0);
+        assert_eq!(hash.chunk_key(0, 64), hash);
+        assert_eq!(hash.chunk_key(8, 0), 0);
+    }
+
+    #[test]
+    fn test_chunk_layout_covers_all_bits() {
+        let index: MultiIndexHash<u64> = MultiIndexHash::new(64, 5);
+
+        assert_eq!(index.chunks.len(), 6);
+        assert_eq!(index.chunks.iter().map(|&(_, len)| len).sum::<u32>(), 64);
+        assert_eq!(index.chunks[0], (0, 11));
+        assert_eq!(index.chunks.last().copied(), Some((54, 10)));
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let index: MultiIndexHash<u64> = MultiIndexHash::new(64, 5);
+
+        assert!(index.is_empty());
+        assert_eq!(index.len(), 0);
+        assert_eq!(index.threshold(), 5);
+        assert!(index.find_within(&0, 5).is_empty());
+    }
+
+    #[test]
+    fn test_find_within_threshold() {
+        let index = build_index(&[0b0000_0000, 0b0000_0001, 0b0000_0011, 0b1111_1111], 2);
+
+        assert_eq!(index.len(), 4);
+
+        let mut found = index.find_within(&0b0000_0000, 2);
+        found.sort_unstable();
+        assert_eq!(found, vec![(0, 0), (1, 1), (2, 2)]);
+
+        let mut found = index.find_within(&0b1111_1111, 0);
+        found.sort_unstable();
+        assert_eq!(found, vec![(3, 0)]);
+    }
+
+    #[test]
+    fn test_identical_hashes() {
+        let index = build_index(&[42, 42, 42, 43], 0);
+
+        let mut found = index.find_within(&42, 0);
+        found.sort_unstable();
+        assert_eq!(found, vec![(0, 0), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn test_threshold_above_index_falls_back_to_linear() {
+        let index = build_index(&[0, u64::MAX, 0xFF], 1);
+
+        let mut found = index.find_within(&0, 8);
+        found.sort_unstable();
+        assert_eq!(found, vec![(0, 0), (2, 8)]);
+    }
+
+    #[test]
+    fn test_insert_rejects_length_mismatch() {
+        struct ShortHash(u64);
+
+        impl BinaryHash for ShortHash {
+            fn bit_len(&self) -> u32 {
+                16
+            }
+            fn hamming_distance(&self, other: &Self) -> u32 {
+                (self.0 ^ other.0).count_ones()
+            }
+            fn chunk_key(&self, start: u32, len: u32) -> u64 {
+                self.0.chunk_key(start, len)
+            }
+        }
+
+        let mut index = MultiIndexHash::new(32, 3);
+        assert!(index.insert(ShortHash(1), 0).is_err());
+        assert!(index.find_within(&ShortHash(1), 3).is_empty());
+    }
+
+    #[test]
+    fn test_matches_linear_scan() {
+        let mut hashes = pseudo_random_hashes(2_000, 7);
+        // 近傍が確実に存在するよう、一部を既存ハッシュの数ビット違いにする
+        for i in (0..hashes.len()).step_by(10) {
+            hashes[i] = hashes[i / 2] ^ (1 << (i % 64)) ^ (1 << ((i * 7) % 64));
+        }
+
+        for threshold in [0, 3, 10] {
+            let index = build_index(&hashes, threshold);
+
+            for query in hashes.iter().take(200) {
+                let mut expected: Vec<(usize, u32)> = hashes
+                    .iter()
+                    .enumerate()
+                    .map(|(id, hash)| (id, hash.hamming_distance(query)))
+                    .filter(|&(_, distance)| distance <= threshold)
+                    .collect();
+                let mut actual = index.find_within(query, threshold);
+
+                expected.sort_unstable();
+                actual.sort_unstable();
+                assert_eq!(actual, expected, "threshold {threshold}");
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+// 操作ジャーナル
+// processコマンドの移動・削除を記録し、移動したファイルを元の場所に復元
+
+pub mod restore;
+pub mod undo_journal;
+
+// 公開API
+pub use restore::{restore_journal, RestoreOutcome, RestoreReport};
+pub use undo_journal::{read_journal, JournalEntry, JournalOperation, JournalState, JournalWriter};
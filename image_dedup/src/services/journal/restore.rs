@@ -0,0 +1,372 @@
+// ジャーナルからの復元 - processコマンドで移動したファイルを元の場所に戻し、リンクを独立したファイルに戻す
+
+use super::undo_journal::{JournalEntry, JournalOperation, JournalState};
+use crate::services::file_ops::link::{is_same_file, replace_with_copy};
+use crate::services::file_ops::FreeDesktopTrash;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 1エントリの復元結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// 元の場所に戻した
+    Restored,
+    /// 既に元の場所に同じ内容のファイルがある（復元済み）
+    AlreadyRestored,
+    /// 元の場所が別のファイルで埋まっている、または移動後に内容が変更された
+    Conflict(String),
+    /// 削除済み・移動先から消えているなどの理由で復元できない
+    Unrestorable(String),
+}
+
+/// ジャーナル全体の復元結果
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored: Vec<PathBuf>,
+    pub already_restored: Vec<PathBuf>,
+    /// （元のパス, 理由）
+    pub conflicts: Vec<(PathBuf, String)>,
+    /// （元のパス, 理由）
+    pub unrestorable: Vec<(PathBuf, String)>,
+}
+
+impl RestoreReport {
+    /// すべてのエントリが復元された（または復元済み）か
+    pub fn is_complete(&self) -> bool {
+        self.conflicts.is_empty() && self.unrestorable.is_empty()
+    }
+}
+
+/// 1エントリを復元
+///
+/// 元の場所に既にファイルがある場合や、移動後に内容が変わっている場合は上書きせずに衝突として扱う。
+/// 完了が記録されていない（中断された）操作は、行われていなければ復元済みとして扱う
+pub fn restore_entry(entry: &JournalEntry) -> RestoreOutcome {
+    let original = &entry.original_path;
+
+    let new_path = match (entry.operation, &entry.new_path) {
+        (JournalOperation::Delete, _)
+            if entry.state == JournalState::Pending && entry.matches_content(original) =>
+        {
+            return RestoreOutcome::AlreadyRestored
+        }
+        (JournalOperation::Delete, _) => {
+            return RestoreOutcome::Unrestorable("file was permanently deleted".to_string())
+        }
+        (JournalOperation::Move | JournalOperation::Trash, None) => {
+            return RestoreOutcome::Unrestorable("journal entry has no destination".to_string())
+        }
+        (JournalOperation::Move | JournalOperation::Trash, Some(new_path)) => new_path,
+        (_, None) => {
+            return RestoreOutcome::Unrestorable("journal entry has no link target".to_string())
+        }
+        (_, Some(kept_path)) => return restore_link(entry, kept_path),
+    };
+
+    if !new_path.exists() {
+        if entry.matches_content(original) {
+            if entry.operation == JournalOperation::Trash && entry.state == JournalState::Pending {
+                // 移動前に中断された場合は確保した名前の.trashinfoだけが残っている
+                FreeDesktopTrash::release(new_path);
+            }
+            return RestoreOutcome::AlreadyRestored;
+        }
+        return RestoreOutcome::Unrestorable(format!(
+            "moved file no longer exists at {}",
+            new_path.display()
+        ));
+    }
+
+    if original.exists() {
+        return RestoreOutcome::Conflict("original path is occupied by another file".to_string());
+    }
+
+    if !entry.matches_content(new_path) {
+        return RestoreOutcome::Conflict(format!(
+            "{} was modified after it was moved",
+            new_path.display()
+        ));
+    }
+
+    if let Some(parent) = original.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return RestoreOutcome::Unrestorable(format!(
+                "failed to create {}: {e}",
+                parent.display()
+            ));
+        }
+    }
+
+    match fs::rename(new_path, original) {
+        Ok(()) => {
+            if entry.operation == JournalOperation::Trash {
+                // ファイルマネージャーに残らないよう.trashinfoも削除
+                if let Some(info_path) = FreeDesktopTrash::info_path(new_path) {
+                    let _ = fs::remove_file(info_path);
+                }
+            } else if let Some(parent) = new_path.parent() {
+                // 空になったグループディレクトリを片付ける（他のファイルが残っていれば失敗するだけ）
+                let _ = fs::remove_dir(parent);
+            }
+            RestoreOutcome::Restored
+        }
+        Err(e) => RestoreOutcome::Unrestorable(format!("failed to move back: {e}")),
+    }
+}
+
+/// リンクに置き換えたファイルを、保持したファイルの独立したコピーに戻す
+///
+/// 元の内容は保持したファイルから復元するため、置き換え前と内容が異なっていた
+/// ファイル（類似画像へのシンボリックリンクなど）は復元できない
+fn restore_link(entry: &JournalEntry, kept_path: &Path) -> RestoreOutcome {
+    let original = &entry.original_path;
+    let still_linked = match entry.operation {
+        JournalOperation::Symlink => {
+            fs::symlink_metadata(original).is_ok_and(|metadata| metadata.is_symlink())
+        }
+        JournalOperation::Hardlink => is_same_file(original, kept_path),
+        // reflinkしたファイルは元から独立したファイル
+        _ => false,
+    };
+
+    if !still_linked {
+        if entry.matches_content(original) {
+            return RestoreOutcome::AlreadyRestored;
+        }
+        if fs::symlink_metadata(original).is_err() {
+            return RestoreOutcome::Unrestorable("linked file no longer exists".to_string());
+        }
+        return RestoreOutcome::Conflict("path was replaced by another file".to_string());
+    }
+
+    if !entry.matches_content(kept_path) {
+        return RestoreOutcome::Unrestorable(format!(
+            "{} does not have the original content",
+            kept_path.display()
+        ));
+    }
+
+    match replace_with_copy(original, kept_path) {
+        Ok(()) => RestoreOutcome::Restored,
+        Err(e) => RestoreOutcome::Unrestorable(format!("failed to replace the link: {e}")),
+    }
+}
+
+/// ジャーナルのすべてのエントリを復元
+///
+/// 同じパスへの操作が複数ある場合に備え、記録とは逆の順序で戻す
+pub fn restore_journal(entries: &[JournalEntry]) -> RestoreReport {
+    let mut report = RestoreReport::default();
+
+    for entry in entries.iter().rev() {
+        let path = entry.original_path.clone();
+        match restore_entry(entry) {
+            RestoreOutcome::Restored => report.restored.push(path),
+            RestoreOutcome::AlreadyRestored => report.already_restored.push(path),
+            RestoreOutcome::Conflict(reason) => report.conflicts.push((path, reason)),
+            RestoreOutcome::Unrestorable(reason) => report.unrestorable.push((path, reason)),
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::file_ops::{replace_with_link, symlink_target, LinkKind};
+    use tempfile::TempDir;
+
+    /// ファイルを移動し、そのジャーナルエントリを返す
+    fn move_file(original: &Path, new_path: &Path) -> JournalEntry {
+        let entry = JournalEntry::for_file(
+            JournalOperation::Move,
+            original,
+            Some(new_path.to_path_buf()),
+        )
+        .unwrap();
+        fs::create_dir_all(new_path.parent().unwrap()).unwrap();
+        fs::rename(original, new_path).unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_restore_moved_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let original = temp_dir.path().join("photos/a.jpg");
+        let new_path = temp_dir.path().join("moved/group_0/a.jpg");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, b"data").unwrap();
+
+        let entry = move_file(&original, &new_path);
+        fs::remove_dir(original.parent().unwrap()).unwrap();
+
+        assert_eq!(restore_entry(&entry), RestoreOutcome::Restored);
+        assert_eq!(fs::read(&original).unwrap(), b"data");
+        assert!(!new_path.exists());
+        // 空になったグループディレクトリは削除される
+        assert!(!new_path.parent().unwrap().exists());
+
+        // 2回目は復元済みとして扱う
+        assert_eq!(restore_entry(&entry), RestoreOutcome::AlreadyRestored);
+    }
+
+    #[test]
+    fn test_restore_trashed_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = FreeDesktopTrash::new(temp_dir.path().join("Trash"));
+        let original = temp_dir.path().join("a.jpg");
+        fs::write(&original, b"data").unwrap();
+
+        let mut entry = JournalEntry::for_file(JournalOperation::Trash, &original, None).unwrap();
+        let trashed = trash.trash(&original).unwrap();
+        entry.new_path = Some(trashed.clone());
+
+        assert_eq!(restore_entry(&entry), RestoreOutcome::Restored);
+        assert_eq!(fs::read(&original).unwrap(), b"data");
+        assert!(!trashed.exists());
+        assert!(!FreeDesktopTrash::info_path(&trashed).unwrap().exists());
+        // ゴミ箱のディレクトリ自体は残す
+        assert!(trash.root().join("files").exists());
+    }
+
+    #[test]
+    fn test_restore_interrupted_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = FreeDesktopTrash::new(temp_dir.path().join("Trash"));
+        let paths: Vec<PathBuf> = ["moved.jpg", "not_moved.jpg", "not_trashed.jpg", "kept.jpg"]
+            .iter()
+            .map(|name| temp_dir.path().join(name))
+            .collect();
+        for path in &paths {
+            fs::write(path, b"data").unwrap();
+        }
+
+        // 中断された操作は完了が記録されず、開始の記録のまま残る
+        let moved = move_file(&paths[0], &temp_dir.path().join("group_0/moved.jpg"));
+        let not_moved = JournalEntry::for_file(
+            JournalOperation::Move,
+            &paths[1],
+            Some(temp_dir.path().join("group_0/not_moved.jpg")),
+        )
+        .unwrap();
+        let trashed_path = trash.reserve(&paths[2]).unwrap();
+        let not_trashed = JournalEntry {
+            new_path: Some(trashed_path.clone()),
+            ..JournalEntry::for_file(JournalOperation::Trash, &paths[2], None).unwrap()
+        };
+        let not_deleted =
+            JournalEntry::for_file(JournalOperation::Delete, &paths[3], None).unwrap();
+        let entries = vec![moved, not_moved, not_trashed, not_deleted];
+        assert!(entries
+            .iter()
+            .all(|entry| entry.state == JournalState::Pending));
+
+        let report = restore_journal(&entries);
+
+        assert!(report.is_complete());
+        assert_eq!(report.restored, vec![paths[0].clone()]);
+        assert_eq!(report.already_restored.len(), 3);
+        assert!(paths.iter().all(|path| fs::read(path).unwrap() == b"data"));
+        assert!(!FreeDesktopTrash::info_path(&trashed_path).unwrap().exists());
+    }
+
+    #[test]
+    fn test_restore_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let kept = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("sub/copy.jpg");
+        let similar = temp_dir.path().join("similar.jpg");
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::write(&kept, b"same").unwrap();
+        fs::write(&copy, b"same").unwrap();
+        fs::write(&similar, b"similar").unwrap();
+
+        let symlink = |path: &Path| {
+            let entry = JournalEntry::for_file(JournalOperation::Symlink, path, Some(kept.clone()))
+                .unwrap();
+            let target = symlink_target(path, &kept, false).unwrap();
+            replace_with_link(path, &target, LinkKind::Symlink, true).unwrap();
+            entry
+        };
+        let entries = vec![symlink(&copy), symlink(&similar)];
+
+        let report = restore_journal(&entries);
+
+        assert_eq!(report.restored, vec![copy.clone()]);
+        assert!(!fs::symlink_metadata(&copy).unwrap().is_symlink());
+        assert_eq!(fs::read(&copy).unwrap(), b"same");
+        assert_eq!(restore_entry(&entries[0]), RestoreOutcome::AlreadyRestored);
+
+        // 類似画像の元の内容は失われているため復元できない
+        assert_eq!(report.unrestorable.len(), 1);
+        assert_eq!(report.unrestorable[0].0, similar);
+        assert!(fs::symlink_metadata(&similar).unwrap().is_symlink());
+    }
+
+    #[test]
+    fn test_restore_hardlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let kept = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("copy.jpg");
+        fs::write(&kept, b"same").unwrap();
+        fs::write(&copy, b"same").unwrap();
+
+        let entry =
+            JournalEntry::for_file(JournalOperation::Hardlink, &copy, Some(kept.clone())).unwrap();
+        replace_with_link(&copy, &kept, LinkKind::Hardlink, false).unwrap();
+
+        assert_eq!(restore_entry(&entry), RestoreOutcome::Restored);
+        assert!(!is_same_file(&copy, &kept));
+        assert_eq!(restore_entry(&entry), RestoreOutcome::AlreadyRestored);
+    }
+
+    #[test]
+    fn test_restore_detects_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let original = temp_dir.path().join("a.jpg");
+        let new_path = temp_dir.path().join("group_0/a.jpg");
+        fs::write(&original, b"data").unwrap();
+
+        let entry = move_file(&original, &new_path);
+
+        // 元の場所に別のファイルが置かれた
+        fs::write(&original, b"other").unwrap();
+        assert!(matches!(restore_entry(&entry), RestoreOutcome::Conflict(_)));
+        assert_eq!(fs::read(&original).unwrap(), b"other");
+
+        // 移動後に内容が変更された
+        fs::remove_file(&original).unwrap();
+        fs::write(&new_path, b"edited").unwrap();
+        assert!(matches!(restore_entry(&entry), RestoreOutcome::Conflict(_)));
+        assert!(new_path.exists());
+    }
+
+    #[test]
+    fn test_restore_journal_reports_unrestorable() {
+        let temp_dir = TempDir::new().unwrap();
+        let moved = temp_dir.path().join("moved.jpg");
+        let lost = temp_dir.path().join("lost.jpg");
+        let deleted = temp_dir.path().join("deleted.jpg");
+        for path in [&moved, &lost, &deleted] {
+            fs::write(path, path.to_string_lossy().as_bytes()).unwrap();
+        }
+
+        let entries = vec![
+            move_file(&moved, &temp_dir.path().join("group_0/moved.jpg")),
+            move_file(&lost, &temp_dir.path().join("group_0/lost.jpg")),
+            JournalEntry::for_file(JournalOperation::Delete, &deleted, None).unwrap(),
+        ];
+        fs::remove_file(&deleted).unwrap();
+        fs::remove_file(temp_dir.path().join("group_0/lost.jpg")).unwrap();
+
+        let report = restore_journal(&entries);
+
+        assert_eq!(report.restored, vec![moved.clone()]);
+        assert_eq!(report.unrestorable.len(), 2);
+        assert_eq!(report.unrestorable[0].0, deleted);
+        assert_eq!(report.unrestorable[1].0, lost);
+        assert!(!report.is_complete());
+        assert!(moved.exists());
+    }
+}
@@ -0,0 +1,335 @@
+// UndoJournal - processコマンドのファイル操作を記録するトランザクションジャーナル
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// 記録されたファイル操作の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOperation {
+    /// 移動（元に戻せる）
+    Move,
+    /// ゴミ箱への移動（元に戻せる。`new_path` はゴミ箱内のパス）
+    Trash,
+    /// 削除（元に戻せない）
+    Delete,
+    /// ハードリンクへの置き換え（`new_path` はリンク先の保持したファイル）
+    Hardlink,
+    /// シンボリックリンクへの置き換え（`new_path` はリンク先の保持したファイル）
+    Symlink,
+    /// reflinkへの置き換え（`new_path` はリンク元の保持したファイル）
+    Reflink,
+}
+
+/// エントリの状態
+///
+/// 操作の前に `Pending` を、操作の後に `Committed`（失敗した場合は `Aborted`）を記録する
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalState {
+    /// 操作を開始した（完了したかは不明）
+    Pending,
+    /// 操作が完了した
+    #[default]
+    Committed,
+    /// 操作が失敗し、ファイルは変更されていない
+    Aborted,
+}
+
+/// ジャーナルの1エントリ（1ファイルの操作）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub operation: JournalOperation,
+    /// 状態が記録されていないエントリは完了したものとして扱う
+    #[serde(default)]
+    pub state: JournalState,
+    /// 操作前のパス
+    pub original_path: PathBuf,
+    /// 移動先、またはリンク先のパス（削除の場合は `None`）
+    pub new_path: Option<PathBuf>,
+    /// 操作前のファイルサイズ
+    pub file_size: u64,
+    /// 操作前の内容のBLAKE3ハッシュ（16進）
+    pub content_hash: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl JournalEntry {
+    /// 操作前のファイルから開始前のエントリを作成（サイズと内容ハッシュを記録）
+    pub fn for_file(
+        operation: JournalOperation,
+        original_path: &Path,
+        new_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        Ok(Self {
+            operation,
+            state: JournalState::Pending,
+            original_path: original_path.to_path_buf(),
+            new_path,
+            file_size: std::fs::metadata(original_path)?.len(),
+            content_hash: content_hash(original_path)?,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// ファイルが記録時の内容と一致するか（サイズを先に比較し、一致した場合のみハッシュを計算）
+    pub fn matches_content(&self, path: &Path) -> bool {
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.len() == self.file_size)
+            && content_hash(path).is_ok_and(|hash| hash == self.content_hash)
+    }
+}
+
+/// ファイル内容のBLAKE3ハッシュを16進文字列で取得
+pub fn content_hash(path: &Path) -> Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// ジャーナルの書き込み
+///
+/// 1行1エントリのJSON Lines形式で追記する。操作の前に開始を記録してディスクに同期し
+/// （先行書き込み）、操作の後に完了を記録する。処理が途中で中断しても、実行中だった
+/// 操作を含めてすべての操作が記録に残る
+pub struct JournalWriter {
+    path: PathBuf,
+    file: File,
+    entries: usize,
+}
+
+impl JournalWriter {
+    /// ジャーナルファイルを新規作成（既存ファイルは上書きしない）
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to create journal {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            entries: 0,
+        })
+    }
+
+    /// 操作の開始を記録（ファイルを変更する前に呼び出す）
+    pub fn begin(&mut self, entry: &JournalEntry) -> Result<()> {
+        self.write(entry, JournalState::Pending)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// 操作の完了を記録
+    ///
+    /// 実際に行った操作（リンクの種類など）や移動先は開始時から変わっていてもよい
+    pub fn commit(&mut self, entry: &JournalEntry) -> Result<()> {
+        self.write(entry, JournalState::Committed)?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// 操作が失敗し、ファイルを変更しなかったことを記録
+    pub fn abort(&mut self, entry: &JournalEntry) -> Result<()> {
+        self.write(entry, JournalState::Aborted)
+    }
+
+    fn write(&mut self, entry: &JournalEntry, state: JournalState) -> Result<()> {
+        let entry = JournalEntry {
+            state,
+            ..entry.clone()
+        };
+        let line = serde_json::to_string(&entry)?;
+        writeln!(self.file, "{line}")?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// ジャーナルファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 完了を記録した操作の数
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    /// エントリが記録されていないか
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+}
+
+/// ジャーナルを読み込む
+///
+/// 開始と完了の記録は1つのエントリにまとめ、失敗した操作は除く。完了が記録されていない
+/// 操作は `Pending` のまま返す（中断により、操作が行われたかは分からない）。
+/// 書き込み途中で中断された末尾の不完全な行は無視する
+pub fn read_journal(path: &Path) -> Result<Vec<JournalEntry>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open journal {}: {}", path.display(), e))?;
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .collect::<std::io::Result<_>>()?;
+
+    let mut entries: Vec<JournalEntry> = Vec::with_capacity(lines.len());
+    for (number, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<JournalEntry>(line) {
+            Ok(entry) if entry.state == JournalState::Pending => entries.push(entry),
+            Ok(entry) => {
+                // 同じファイルの開始の記録を結果で置き換える
+                let pending = entries.iter().rposition(|pending| {
+                    pending.state == JournalState::Pending
+                        && pending.original_path == entry.original_path
+                });
+                match (pending, entry.state) {
+                    (Some(index), JournalState::Aborted) => {
+                        entries.remove(index);
+                    }
+                    (Some(index), _) => entries[index] = entry,
+                    (None, JournalState::Aborted) => {}
+                    (None, _) => entries.push(entry),
+                }
+            }
+            Err(_) if number + 1 == lines.len() => break,
+            Err(e) => anyhow::bail!(
+                "Invalid journal entry at {}:{}: {}",
+                path.display(),
+                number + 1,
+                e
+            ),
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_and_read_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.jpg");
+        std::fs::write(&file, b"image data").unwrap();
+
+        let journal_path = temp_dir.path().join("journal.jsonl");
+        let mut writer = JournalWriter::create(&journal_path).unwrap();
+        let moved = JournalEntry::for_file(
+            JournalOperation::Move,
+            &file,
+            Some(temp_dir.path().join("group_0/a.jpg")),
+        )
+        .unwrap();
+        let deleted = JournalEntry::for_file(JournalOperation::Delete, &file, None).unwrap();
+        for entry in [&moved, &deleted] {
+            writer.begin(entry).unwrap();
+            writer.commit(entry).unwrap();
+        }
+        assert_eq!(writer.len(), 2);
+
+        let entries = read_journal(&journal_path).unwrap();
+        let committed = |entry: &JournalEntry| JournalEntry {
+            state: JournalState::Committed,
+            ..entry.clone()
+        };
+        assert_eq!(entries, vec![committed(&moved), committed(&deleted)]);
+        assert_eq!(entries[0].file_size, 10);
+        assert_eq!(
+            entries[0].content_hash,
+            blake3::hash(b"image data").to_hex().as_str()
+        );
+    }
+
+    #[test]
+    fn test_read_journal_pairs_pending_and_committed_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.jpg", "b.jpg", "c.jpg"]
+            .iter()
+            .map(|name| temp_dir.path().join(name))
+            .collect();
+        for file in &files {
+            std::fs::write(file, b"data").unwrap();
+        }
+        let entries: Vec<JournalEntry> = files
+            .iter()
+            .map(|file| JournalEntry::for_file(JournalOperation::Hardlink, file, None).unwrap())
+            .collect();
+
+        let journal_path = temp_dir.path().join("journal.jsonl");
+        let mut writer = JournalWriter::create(&journal_path).unwrap();
+        // a: 完了（実際にはシンボリックリンクを作成）、b: 失敗、c: 中断
+        writer.begin(&entries[0]).unwrap();
+        writer
+            .commit(&JournalEntry {
+                operation: JournalOperation::Symlink,
+                ..entries[0].clone()
+            })
+            .unwrap();
+        writer.begin(&entries[1]).unwrap();
+        writer.abort(&entries[1]).unwrap();
+        writer.begin(&entries[2]).unwrap();
+        assert_eq!(writer.len(), 1);
+
+        let read = read_journal(&journal_path).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].original_path, files[0]);
+        assert_eq!(read[0].operation, JournalOperation::Symlink);
+        assert_eq!(read[0].state, JournalState::Committed);
+        assert_eq!(read[1].original_path, files[2]);
+        assert_eq!(read[1].state, JournalState::Pending);
+    }
+
+    #[test]
+    fn test_create_does_not_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_path = temp_dir.path().join("journal.jsonl");
+        std::fs::write(&journal_path, "existing").unwrap();
+
+        assert!(JournalWriter::create(&journal_path).is_err());
+        assert_eq!(std::fs::read_to_string(&journal_path).unwrap(), "existing");
+    }
+
+    #[test]
+    fn test_read_journal_ignores_truncated_last_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.jpg");
+        std::fs::write(&file, b"data").unwrap();
+        let entry = JournalEntry::for_file(JournalOperation::Delete, &file, None).unwrap();
+
+        let journal_path = temp_dir.path().join("journal.jsonl");
+        let line = serde_json::to_string(&entry).unwrap();
+        std::fs::write(&journal_path, format!("{line}\n{}", &line[..20])).unwrap();
+        assert_eq!(read_journal(&journal_path).unwrap(), vec![entry]);
+
+        // 途中の行が壊れている場合はエラー
+        std::fs::write(&journal_path, format!("{}\n{line}\n", &line[..20])).unwrap();
+        assert!(read_journal(&journal_path).is_err());
+    }
+
+    #[test]
+    fn test_matches_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.jpg");
+        std::fs::write(&file, b"data").unwrap();
+        let entry = JournalEntry::for_file(JournalOperation::Move, &file, None).unwrap();
+
+        assert!(entry.matches_content(&file));
+        std::fs::write(&file, b"diff").unwrap();
+        assert!(!entry.matches_content(&file));
+        assert!(!entry.matches_content(&temp_dir.path().join("missing.jpg")));
+    }
+}
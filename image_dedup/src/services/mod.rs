@@ -1,16 +1,40 @@
 // サービス層 - 機能別のビジネスロジック
 // 各サービスは特定の責任を持ち、疎結合で設計されている
 
+pub mod clustering;
 pub mod config;
+pub mod file_ops;
+pub mod index;
+pub mod journal;
 pub mod monitoring;
 pub mod persistence;
+pub mod plan;
 pub mod processing;
+pub mod selection;
 
 // 公開API - 各サービスの主要機能を明示的にエクスポート
+pub use clustering::{
+    complete_linkage_clusters, connected_clusters, max_intra_distance, star_clusters,
+    SimilarityGraph,
+};
 pub use config::DefaultProcessingConfig;
+pub use file_ops::{
+    replace_with_link, resolve_link_target, symlink_target, FreeDesktopTrash, LinkKind,
+};
+pub use index::{BinaryHash, BitVector, MultiIndexHash};
+pub use journal::{
+    read_journal, restore_journal, JournalEntry, JournalOperation, JournalWriter, RestoreReport,
+};
 pub use monitoring::{ConsoleProgressReporter, NoOpProgressReporter};
 pub use persistence::{
-    spawn_result_collector, JsonHashPersistence, MemoryHashPersistence,
+    checkpoint_path, spawn_result_collector, BinaryHashDatabase, BinaryHashPersistence,
+    CheckpointedPersistence, CollectorOptions, DatabaseFormat, JsonHashPersistence,
+    JsonlHashPersistence, MemoryHashPersistence, SqliteHashPersistence,
     StreamingJsonHashPersistence,
 };
-pub use processing::process_single_file;
+pub use plan::{PlanAction, PlannedFile, ProcessPlan};
+pub use processing::{
+    compute_content_hash, filter_exact_candidates, process_single_file, ColorSignature,
+    ExactCandidateReport, ExactCandidates, WorkerOptions,
+};
+pub use selection::{select_keeper, KeepCandidate, KeepPolicy};
@@ -29,7 +29,7 @@ impl ProgressReporter for ConsoleProgressReporter {
     }
 
     async fn report_progress(&self, completed: usize, total: usize) {
-        if !self.quiet && (completed % 100 == 0 || completed == total) {
+        if !self.quiet && (completed.is_multiple_of(100) || completed == total) {
             let percentage = (completed as f64 / total as f64) * 100.0;
             println!("📊 Progress: {completed}/{total} ({percentage:.1}%)");
         }
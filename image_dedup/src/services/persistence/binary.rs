@@ -0,0 +1,789 @@
+// バイナリ形式のハッシュデータベース
+// 固定長のレコードと文字列テーブルで構成し、読み込み時はmmapしたファイルをそのまま参照する
+//
+// レイアウト（数値はすべてリトルエンディアン）:
+//   ヘッダー（HEADER_SIZEバイト）: マジック、バージョン、ハッシュ領域の幅、レコード数、
+//                               各領域のオフセット、scan_info（JSON）の長さ
+//   scan_info: アルゴリズムとパラメータ（JSON）
+//   レコード: ファイルパス順に並べた固定長レコード（RECORD_FIXED_SIZE + ハッシュ領域の幅）
+//   文字列テーブル: ファイルパスと、固定列に入らないメタデータ（JSON）
+
+use super::implementations::{HashEntry, ScanInfo, ScanResult};
+use crate::core::HashPersistence;
+use crate::core::ProcessingMetadata;
+use anyhow::Result;
+use async_trait::async_trait;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// ファイル先頭のマジック
+pub(crate) const MAGIC: &[u8; 8] = b"IMGDEDUP";
+/// 形式のバージョン（互換性のない変更をしたら上げる）
+pub const FORMAT_VERSION: u32 = 1;
+
+const HEADER_SIZE: usize = 52;
+/// ハッシュ領域を除いたレコードの長さ
+const RECORD_FIXED_SIZE: usize = 72;
+
+/// 画像を縮小して処理した
+const FLAG_WAS_RESIZED: u16 = 1;
+/// ハッシュが16進として格納できないため追加メタデータに入っている
+const FLAG_TEXT_HASH: u16 = 1 << 1;
+/// 更新日時が記録されていないことを表す値
+const NO_MODIFIED_TIME: u64 = u64::MAX;
+
+/// 固定列に入らないメタデータ（文字列テーブルにJSONで格納し、すべて空なら省略する）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecordExtras {
+    /// 16進のバイト列として格納できないハッシュ（内容ハッシュのみのスキャンなど）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dihedral_hashes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    segment_hashes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ensemble_hashes: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ensemble_dihedral_hashes: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_signature: Option<String>,
+}
+
+impl RecordExtras {
+    fn is_empty(&self) -> bool {
+        self.hash.is_none()
+            && self.content_hash.is_none()
+            && self.dihedral_hashes.is_none()
+            && self.segment_hashes.is_none()
+            && self.ensemble_hashes.is_none()
+            && self.ensemble_dihedral_hashes.is_none()
+            && self.color_signature.is_none()
+    }
+}
+
+/// 小文字の16進として格納できるハッシュをバイト列に変換
+fn inline_hash(hash: &str) -> Option<Vec<u8>> {
+    let lowercase = hash
+        .bytes()
+        .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    if !lowercase || hash.len() / 2 > usize::from(u16::MAX) {
+        return None;
+    }
+    hex::decode(hash).ok()
+}
+
+/// バイナリ形式での永続化実装
+///
+/// エントリはメモリ上にファイルパス順で保持し、finalizeでまとめて書き込む
+/// （レコード数とハッシュ領域の幅がすべてのエントリを見るまで決まらないため）
+#[derive(Debug, Clone)]
+pub struct BinaryHashPersistence {
+    file_path: PathBuf,
+    entries: Arc<Mutex<BTreeMap<String, HashEntry>>>,
+    scan_info: Arc<Mutex<Option<ScanInfo>>>,
+}
+
+impl BinaryHashPersistence {
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self {
+            file_path: file_path.as_ref().to_path_buf(),
+            entries: Arc::new(Mutex::new(BTreeMap::new())),
+            scan_info: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// スキャン結果をバイナリ形式で書き込む（既存のファイルは置き換える）
+    pub fn write(path: &Path, scan_result: &ScanResult) -> Result<()> {
+        let mut images: Vec<&HashEntry> = scan_result.images.iter().collect();
+        images.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        write_database(path, &scan_result.scan_info, &images)
+    }
+}
+
+#[async_trait]
+impl HashPersistence for BinaryHashPersistence {
+    async fn store_hash(
+        &self,
+        file_path: &Path,
+        hash: &str,
+        metadata: &ProcessingMetadata,
+    ) -> Result<()> {
+        self.store_batch(&[(
+            file_path.to_path_buf(),
+            hash.to_string(),
+            "DCT".to_string(),
+            0u64,
+            metadata.clone(),
+        )])
+        .await
+    }
+
+    async fn store_batch(
+        &self,
+        results: &[(PathBuf, String, String, u64, ProcessingMetadata)],
+    ) -> Result<()> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Storage lock poisoned: {}", e))?;
+        for (file_path, hash, _algorithm, hash_bits, metadata) in results {
+            let file_path = file_path.to_string_lossy().to_string();
+            entries.insert(
+                file_path.clone(),
+                HashEntry {
+                    file_path,
+                    hash: hash.clone(),
+                    hash_bits: *hash_bits,
+                    metadata: metadata.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    async fn set_scan_info(&self, operation: String, info: serde_json::Value) -> Result<()> {
+        let scan_info = ScanInfo {
+            algorithm: operation,
+            parameters: info,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            total_files: 0, // finalizeで更新
+        };
+
+        *self
+            .scan_info
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Scan info lock poisoned: {}", e))? = Some(scan_info);
+        Ok(())
+    }
+
+    async fn finalize(&self) -> Result<()> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Storage lock poisoned: {}", e))?;
+        let mut scan_info = self
+            .scan_info
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Scan info lock poisoned: {}", e))?
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("scan_infoが設定されていません"))?;
+        scan_info.total_files = entries.len();
+
+        let images: Vec<&HashEntry> = entries.values().collect();
+        write_database(&self.file_path, &scan_info, &images)
+    }
+}
+
+/// ファイルパス順に並んだエントリを書き込む
+fn write_database(path: &Path, scan_info: &ScanInfo, images: &[&HashEntry]) -> Result<()> {
+    let hash_bytes = images
+        .iter()
+        .filter_map(|entry| inline_hash(&entry.hash))
+        .map(|bytes| bytes.len())
+        .max()
+        .unwrap_or(0);
+    let record_size = RECORD_FIXED_SIZE + hash_bytes;
+
+    let mut records = Vec::with_capacity(images.len() * record_size);
+    let mut strings = Vec::new();
+    for entry in images {
+        let metadata = &entry.metadata;
+        let hash = inline_hash(&entry.hash);
+        let extras = RecordExtras {
+            hash: hash.is_none().then(|| entry.hash.clone()),
+            content_hash: metadata.content_hash.clone(),
+            dihedral_hashes: metadata.dihedral_hashes.clone(),
+            segment_hashes: metadata.segment_hashes.clone(),
+            ensemble_hashes: metadata.ensemble_hashes.clone(),
+            ensemble_dihedral_hashes: metadata.ensemble_dihedral_hashes.clone(),
+            color_signature: metadata.color_signature.clone(),
+        };
+
+        let path_offset = strings.len() as u64;
+        strings.extend_from_slice(entry.file_path.as_bytes());
+        let extras_offset = strings.len() as u64;
+        if !extras.is_empty() {
+            serde_json::to_writer(&mut strings, &extras)?;
+        }
+        let extras_len = strings.len() as u64 - extras_offset;
+
+        let mut flags = 0;
+        if metadata.was_resized {
+            flags |= FLAG_WAS_RESIZED;
+        }
+        if hash.is_none() {
+            flags |= FLAG_TEXT_HASH;
+        }
+        let hash = hash.unwrap_or_default();
+
+        records.extend_from_slice(&path_offset.to_le_bytes());
+        records.extend_from_slice(&u32::try_from(entry.file_path.len())?.to_le_bytes());
+        records.extend_from_slice(&u32::try_from(extras_len)?.to_le_bytes());
+        records.extend_from_slice(&extras_offset.to_le_bytes());
+        records.extend_from_slice(&entry.hash_bits.to_le_bytes());
+        records.extend_from_slice(&metadata.file_size.to_le_bytes());
+        records.extend_from_slice(
+            &metadata
+                .modified_time_ms
+                .unwrap_or(NO_MODIFIED_TIME)
+                .to_le_bytes(),
+        );
+        records.extend_from_slice(&metadata.processing_time_ms.to_le_bytes());
+        records.extend_from_slice(&metadata.image_dimensions.0.to_le_bytes());
+        records.extend_from_slice(&metadata.image_dimensions.1.to_le_bytes());
+        records.extend_from_slice(&metadata.hash_size_bits.to_le_bytes());
+        records.extend_from_slice(&flags.to_le_bytes());
+        records.extend_from_slice(&(hash.len() as u16).to_le_bytes());
+        records.extend_from_slice(&hash);
+        records.resize(records.len() + hash_bytes - hash.len(), 0);
+    }
+
+    let scan_info_json = serde_json::to_vec(scan_info)?;
+    let records_offset = (HEADER_SIZE + scan_info_json.len()) as u64;
+    let strings_offset = records_offset + records.len() as u64;
+
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header.extend_from_slice(&u32::try_from(hash_bytes)?.to_le_bytes());
+    header.extend_from_slice(&(images.len() as u64).to_le_bytes());
+    header.extend_from_slice(&records_offset.to_le_bytes());
+    header.extend_from_slice(&strings_offset.to_le_bytes());
+    header.extend_from_slice(&(strings.len() as u64).to_le_bytes());
+    header.extend_from_slice(&u32::try_from(scan_info_json.len())?.to_le_bytes());
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("ディレクトリ作成エラー: {e}"))?;
+    }
+    let file = std::fs::File::create(path)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    for section in [&header, &scan_info_json, &records, &strings] {
+        writer.write_all(section)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 固定列だけを読んだレコード（`ProcessingMetadata` を組み立てずに参照する場合に使う）
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordView<'a> {
+    pub file_path: &'a str,
+    pub hash: String,
+    pub hash_bits: u64,
+    pub hash_size_bits: u32,
+    pub file_size: u64,
+    pub modified_time_ms: Option<u64>,
+    pub image_dimensions: (u32, u32),
+    /// 固定列に入らないメタデータ（内容ハッシュ・バリアントなど。記録されたレコードのみ）
+    pub extras: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// mmapで開いたバイナリ形式のハッシュデータベース
+///
+/// エントリは必要になった時点でレコードから復元するため、開くだけならファイル全体を読み込まない
+pub struct BinaryHashDatabase {
+    mmap: Mmap,
+    scan_info: ScanInfo,
+    hash_bytes: usize,
+    record_count: usize,
+    records_offset: usize,
+    strings_offset: usize,
+    strings_len: usize,
+}
+
+impl BinaryHashDatabase {
+    /// ファイルを開いてヘッダーを検証
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+        if (file.metadata()?.len() as usize) < HEADER_SIZE {
+            anyhow::bail!(
+                "{} is too short to be a binary hash database",
+                path.display()
+            );
+        }
+        // SAFETY: マップしている間にファイルが書き換えられると内容が変わり得るが、
+        // 読み出す値はすべて範囲を検証してから使うため、不正な値はエラーになるだけで済む
+        let mmap = unsafe { Mmap::map(&file) }
+            .map_err(|e| anyhow::anyhow!("Failed to map {}: {}", path.display(), e))?;
+
+        let header = &mmap[..HEADER_SIZE];
+        if &header[..8] != MAGIC {
+            anyhow::bail!("{} is not a binary hash database", path.display());
+        }
+        let version = read_u32(header, 8);
+        if version != FORMAT_VERSION {
+            anyhow::bail!(
+                "{} uses binary format version {} but only version {} is supported",
+                path.display(),
+                version,
+                FORMAT_VERSION
+            );
+        }
+
+        let hash_bytes = read_u32(header, 12) as usize;
+        let record_count = usize::try_from(read_u64(header, 16))?;
+        let records_offset = usize::try_from(read_u64(header, 24))?;
+        let strings_offset = usize::try_from(read_u64(header, 32))?;
+        let strings_len = usize::try_from(read_u64(header, 40))?;
+        let scan_info_len = read_u32(header, 48) as usize;
+
+        let records_len = record_count
+            .checked_mul(RECORD_FIXED_SIZE + hash_bytes)
+            .ok_or_else(|| anyhow::anyhow!("Corrupt binary hash database {}", path.display()))?;
+        let consistent = HEADER_SIZE + scan_info_len == records_offset
+            && records_offset.checked_add(records_len) == Some(strings_offset)
+            && strings_offset.checked_add(strings_len) == Some(mmap.len());
+        if !consistent {
+            anyhow::bail!(
+                "Corrupt binary hash database {} (section sizes do not match the file)",
+                path.display()
+            );
+        }
+
+        let scan_info = serde_json::from_slice(&mmap[HEADER_SIZE..records_offset])?;
+        Ok(Self {
+            mmap,
+            scan_info,
+            hash_bytes,
+            record_count,
+            records_offset,
+            strings_offset,
+            strings_len,
+        })
+    }
+
+    pub fn scan_info(&self) -> &ScanInfo {
+        &self.scan_info
+    }
+
+    /// レコード数
+    pub fn len(&self) -> usize {
+        self.record_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.record_count == 0
+    }
+
+    fn record(&self, index: usize) -> &[u8] {
+        let record_size = RECORD_FIXED_SIZE + self.hash_bytes;
+        let start = self.records_offset + index * record_size;
+        &self.mmap[start..start + record_size]
+    }
+
+    /// 文字列テーブルの範囲を取得（範囲外ならエラー）
+    fn string(&self, offset: u64, len: u32) -> Result<&[u8]> {
+        let start = usize::try_from(offset)?;
+        let end = start
+            .checked_add(len as usize)
+            .filter(|&end| end <= self.strings_len)
+            .ok_or_else(|| anyhow::anyhow!("Corrupt binary hash database (string out of range)"))?;
+        Ok(&self.mmap[self.strings_offset + start..self.strings_offset + end])
+    }
+
+    /// `index` 番目のレコードのファイルパス
+    fn file_path(&self, index: usize) -> Result<&str> {
+        let record = self.record(index);
+        let bytes = self.string(read_u64(record, 0), read_u32(record, 8))?;
+        Ok(std::str::from_utf8(bytes)?)
+    }
+
+    /// `index` 番目のエントリを復元
+    pub fn entry(&self, index: usize) -> Result<HashEntry> {
+        if index >= self.record_count {
+            anyhow::bail!(
+                "Record {} out of range ({} records)",
+                index,
+                self.record_count
+            );
+        }
+        let record = self.record(index);
+
+        let extras_len = read_u32(record, 12);
+        let extras: RecordExtras = if extras_len == 0 {
+            RecordExtras::default()
+        } else {
+            serde_json::from_slice(self.string(read_u64(record, 16), extras_len)?)?
+        };
+
+        let flags = read_u16(record, 68);
+        let hash = if flags & FLAG_TEXT_HASH != 0 {
+            extras.hash.unwrap_or_default()
+        } else {
+            self.inline_hash(record)?
+        };
+
+        let modified_time_ms = read_u64(record, 40);
+        Ok(HashEntry {
+            file_path: self.file_path(index)?.to_string(),
+            hash,
+            hash_bits: read_u64(record, 24),
+            metadata: ProcessingMetadata {
+                file_size: read_u64(record, 32),
+                processing_time_ms: read_u64(record, 48),
+                image_dimensions: (read_u32(record, 56), read_u32(record, 60)),
+                was_resized: flags & FLAG_WAS_RESIZED != 0,
+                hash_size_bits: read_u32(record, 64),
+                modified_time_ms: (modified_time_ms != NO_MODIFIED_TIME)
+                    .then_some(modified_time_ms),
+                content_hash: extras.content_hash,
+                dihedral_hashes: extras.dihedral_hashes,
+                segment_hashes: extras.segment_hashes,
+                ensemble_hashes: extras.ensemble_hashes,
+                ensemble_dihedral_hashes: extras.ensemble_dihedral_hashes,
+                color_signature: extras.color_signature,
+            },
+        })
+    }
+
+    /// `index` 番目のレコードを固定列から読む
+    ///
+    /// 追加メタデータのJSONは記録されているレコードでのみ解析し、`ProcessingMetadata` への
+    /// 変換は行わない。find-dupsのように全レコードを走査する場合に使う
+    pub fn view(&self, index: usize) -> Result<RecordView<'_>> {
+        if index >= self.record_count {
+            anyhow::bail!(
+                "Record {} out of range ({} records)",
+                index,
+                self.record_count
+            );
+        }
+        let record = self.record(index);
+
+        let extras_len = read_u32(record, 12);
+        let mut extras: Option<serde_json::Map<String, serde_json::Value>> = if extras_len == 0 {
+            None
+        } else {
+            Some(serde_json::from_slice(
+                self.string(read_u64(record, 16), extras_len)?,
+            )?)
+        };
+
+        let hash = if read_u16(record, 68) & FLAG_TEXT_HASH != 0 {
+            extras
+                .as_mut()
+                .and_then(|extras| extras.remove("hash"))
+                .and_then(|hash| hash.as_str().map(str::to_string))
+                .unwrap_or_default()
+        } else {
+            self.inline_hash(record)?
+        };
+
+        let modified_time_ms = read_u64(record, 40);
+        Ok(RecordView {
+            file_path: self.file_path(index)?,
+            hash,
+            hash_bits: read_u64(record, 24),
+            hash_size_bits: read_u32(record, 64),
+            file_size: read_u64(record, 32),
+            modified_time_ms: (modified_time_ms != NO_MODIFIED_TIME).then_some(modified_time_ms),
+            image_dimensions: (read_u32(record, 56), read_u32(record, 60)),
+            extras: extras.filter(|extras| !extras.is_empty()),
+        })
+    }
+
+    /// レコードのハッシュ領域に格納したハッシュ（16進）
+    fn inline_hash(&self, record: &[u8]) -> Result<String> {
+        let hash_len = read_u16(record, 70) as usize;
+        if hash_len > self.hash_bytes {
+            anyhow::bail!("Corrupt binary hash database (hash longer than its field)");
+        }
+        Ok(hex::encode(
+            &record[RECORD_FIXED_SIZE..RECORD_FIXED_SIZE + hash_len],
+        ))
+    }
+
+    /// 全エントリをファイルパス順に復元
+    pub fn entries(&self) -> impl Iterator<Item = Result<HashEntry>> + '_ {
+        (0..self.record_count).map(|index| self.entry(index))
+    }
+
+    /// ファイルパスでエントリを検索（レコードはパス順のため二分探索）
+    pub fn find(&self, file_path: &str) -> Result<Option<HashEntry>> {
+        let (mut low, mut high) = (0, self.record_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.file_path(mid)?.cmp(file_path) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return self.entry(mid).map(Some),
+            }
+        }
+        Ok(None)
+    }
+
+    /// スキャン結果としてすべて読み込む
+    pub fn into_scan_result(self) -> Result<ScanResult> {
+        let images = self.entries().collect::<Result<Vec<_>>>()?;
+        Ok(ScanResult {
+            scan_info: self.scan_info,
+            images,
+        })
+    }
+
+    /// 指定したファイルのエントリをファイルパスをキーに読み込む（記録されていないファイルは含まれない）
+    pub fn find_all<'a, I>(&self, file_paths: I) -> Result<HashMap<String, HashEntry>>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut entries = HashMap::new();
+        for file_path in file_paths {
+            if let Some(entry) = self.find(file_path)? {
+                entries.insert(entry.file_path.clone(), entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buffer = [0u8; 4];
+    buffer.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buffer)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::persistence::test_metadata;
+    use tempfile::TempDir;
+
+    fn metadata(file_size: u64) -> ProcessingMetadata {
+        ProcessingMetadata {
+            processing_time_ms: 12,
+            image_dimensions: (640, 480),
+            was_resized: true,
+            ..test_metadata(file_size)
+        }
+    }
+
+    fn scan_result(images: Vec<HashEntry>) -> ScanResult {
+        ScanResult {
+            scan_info: ScanInfo {
+                algorithm: "scan".to_string(),
+                parameters: serde_json::json!({ "algorithm": "dct", "hash_size": 8 }),
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+                total_files: images.len(),
+            },
+            images,
+        }
+    }
+
+    fn entry(path: &str, hash: &str, metadata: ProcessingMetadata) -> HashEntry {
+        HashEntry {
+            file_path: path.to_string(),
+            hash: hash.to_string(),
+            hash_bits: u64::MAX - metadata.file_size,
+            metadata,
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.bin");
+
+        let mut extended = metadata(300);
+        extended.modified_time_ms = None;
+        extended.was_resized = false;
+        extended.content_hash = Some("blake3:00ff".to_string());
+        extended.dihedral_hashes = Some(vec!["00".to_string(); 8]);
+        extended.ensemble_hashes = Some(BTreeMap::from([("dct".to_string(), "ff".to_string())]));
+        extended.ensemble_dihedral_hashes = Some(BTreeMap::from([(
+            "dct".to_string(),
+            vec!["ff".to_string(); 8],
+        )]));
+        let images = vec![
+            entry("/photos/c.jpg", "00000000000000ff", metadata(100)),
+            // 長さの違うハッシュと16進でないハッシュ
+            entry(
+                "/photos/a.jpg",
+                "0123456789abcdef0123456789abcdef",
+                extended,
+            ),
+            entry("/photos/b.jpg", "blake3:ABCD", metadata(200)),
+        ];
+        let original = scan_result(images);
+        BinaryHashPersistence::write(&path, &original).unwrap();
+
+        let database = BinaryHashDatabase::open(&path).unwrap();
+        assert_eq!(database.len(), 3);
+        assert_eq!(database.scan_info().parameters["hash_size"], 8);
+
+        let loaded = database.into_scan_result().unwrap();
+        let mut expected = original.images.clone();
+        expected.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        for (loaded, expected) in loaded.images.iter().zip(&expected) {
+            assert_eq!(loaded.file_path, expected.file_path);
+            assert_eq!(loaded.hash, expected.hash);
+            assert_eq!(loaded.hash_bits, expected.hash_bits);
+            assert_eq!(loaded.metadata, expected.metadata);
+        }
+        assert_eq!(loaded.scan_info.total_files, 3);
+    }
+
+    #[test]
+    fn test_binary_view_reads_fixed_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.bin");
+
+        let mut extended = metadata(300);
+        extended.content_hash = Some("blake3:00ff".to_string());
+        let images = vec![
+            entry("/photos/a.jpg", "00000000000000ff", metadata(100)),
+            entry("/photos/b.jpg", "blake3:ABCD", extended),
+        ];
+        BinaryHashPersistence::write(&path, &scan_result(images)).unwrap();
+        let database = BinaryHashDatabase::open(&path).unwrap();
+
+        let plain = database.view(0).unwrap();
+        assert_eq!(plain.file_path, "/photos/a.jpg");
+        assert_eq!(plain.hash, "00000000000000ff");
+        assert_eq!(plain.hash_bits, u64::MAX - 100);
+        assert_eq!(plain.file_size, 100);
+        assert_eq!(plain.extras, None);
+
+        // 16進でないハッシュは追加メタデータから取り出し、追加メタデータには残さない
+        let text = database.view(1).unwrap();
+        assert_eq!(text.hash, "blake3:ABCD");
+        let extras = text.extras.unwrap();
+        assert_eq!(extras.len(), 1);
+        assert_eq!(extras["content_hash"], "blake3:00ff");
+        assert!(database.view(2).is_err());
+    }
+
+    #[test]
+    fn test_binary_find_by_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.bin");
+        let images = (0..50)
+            .map(|i| entry(&format!("/photos/{i:03}.jpg"), "00ff", metadata(i)))
+            .collect();
+        BinaryHashPersistence::write(&path, &scan_result(images)).unwrap();
+
+        let database = BinaryHashDatabase::open(&path).unwrap();
+        assert_eq!(
+            database
+                .find("/photos/037.jpg")
+                .unwrap()
+                .unwrap()
+                .metadata
+                .file_size,
+            37
+        );
+        assert!(database.find("/photos/999.jpg").unwrap().is_none());
+
+        let found = database
+            .find_all(["/photos/000.jpg", "/photos/049.jpg", "/missing.jpg"])
+            .unwrap();
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn test_binary_is_smaller_than_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.bin");
+        let images = (0..100)
+            .map(|i| {
+                entry(
+                    &format!("/photos/{i:03}.jpg"),
+                    "0123456789abcdef",
+                    metadata(i),
+                )
+            })
+            .collect();
+        let result = scan_result(images);
+        BinaryHashPersistence::write(&path, &result).unwrap();
+
+        let binary_size = std::fs::metadata(&path).unwrap().len() as usize;
+        let json_size = serde_json::to_string_pretty(&result).unwrap().len();
+        assert!(binary_size * 3 < json_size, "{binary_size} vs {json_size}");
+    }
+
+    #[test]
+    fn test_binary_rejects_invalid_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.bin");
+
+        std::fs::write(&path, b"{}").unwrap();
+        assert!(BinaryHashDatabase::open(&path).is_err());
+
+        BinaryHashPersistence::write(&path, &scan_result(vec![])).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8] = 99;
+        std::fs::write(&path, &bytes).unwrap();
+        let error = BinaryHashDatabase::open(&path).err().unwrap();
+        assert!(error.to_string().contains("version 99"));
+
+        // 切り詰められたファイル
+        BinaryHashPersistence::write(
+            &path,
+            &scan_result(vec![entry("/a.jpg", "00", metadata(1))]),
+        )
+        .unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(BinaryHashDatabase::open(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_binary_persistence_writes_on_finalize() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/hashes.bin");
+        let persistence = BinaryHashPersistence::new(&path);
+
+        persistence
+            .set_scan_info("scan".to_string(), serde_json::json!({}))
+            .await
+            .unwrap();
+        persistence
+            .store_batch(&[
+                (
+                    PathBuf::from("/b.jpg"),
+                    "bb".to_string(),
+                    "dct".to_string(),
+                    1,
+                    metadata(2),
+                ),
+                (
+                    PathBuf::from("/a.jpg"),
+                    "aa".to_string(),
+                    "dct".to_string(),
+                    1,
+                    metadata(1),
+                ),
+            ])
+            .await
+            .unwrap();
+        assert!(!path.exists());
+        persistence.finalize().await.unwrap();
+
+        let database = BinaryHashDatabase::open(&path).unwrap();
+        assert_eq!(database.scan_info().total_files, 2);
+        assert_eq!(database.entry(0).unwrap().file_path, "/a.jpg");
+        assert_eq!(database.entry(1).unwrap().hash, "bb");
+    }
+}
@@ -0,0 +1,128 @@
+// スキャンのチェックポイント
+// 保存したエントリをJSON Lines形式のチェックポイントにも書き、中断されたスキャンを再開できるようにする
+
+use super::incremental::sibling_path;
+use super::jsonl::JsonlHashPersistence;
+use crate::core::HashPersistence;
+use crate::core::ProcessingMetadata;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+
+/// 出力先に対応するチェックポイントのパス（例: hashes.json → hashes.json.checkpoint）
+pub fn checkpoint_path(output: &Path) -> PathBuf {
+    sibling_path(output, "checkpoint")
+}
+
+/// 保存したエントリをチェックポイントにも記録する永続化
+///
+/// 結果収集タスクがバッチを保存するたびにチェックポイントへ追記するため、
+/// 中断された時点までに保存したファイルはチェックポイントから復元できる。
+/// チェックポイントの削除はスキャン全体が完了した後に呼び出し側で行う
+pub struct CheckpointedPersistence<P> {
+    inner: P,
+    checkpoint: JsonlHashPersistence,
+}
+
+impl<P: HashPersistence> CheckpointedPersistence<P> {
+    pub fn new(inner: P, checkpoint: JsonlHashPersistence) -> Self {
+        Self { inner, checkpoint }
+    }
+}
+
+#[async_trait]
+impl<P: HashPersistence> HashPersistence for CheckpointedPersistence<P> {
+    async fn store_hash(
+        &self,
+        file_path: &Path,
+        hash: &str,
+        metadata: &ProcessingMetadata,
+    ) -> Result<()> {
+        self.inner.store_hash(file_path, hash, metadata).await?;
+        self.checkpoint.store_hash(file_path, hash, metadata).await
+    }
+
+    async fn store_batch(
+        &self,
+        results: &[(PathBuf, String, String, u64, ProcessingMetadata)],
+    ) -> Result<()> {
+        // 出力への保存が成功したエントリだけを完了として記録する
+        self.inner.store_batch(results).await?;
+        self.checkpoint.store_batch(results).await
+    }
+
+    async fn set_scan_info(&self, operation: String, info: serde_json::Value) -> Result<()> {
+        self.inner
+            .set_scan_info(operation.clone(), info.clone())
+            .await?;
+        self.checkpoint.set_scan_info(operation, info).await
+    }
+
+    async fn finalize(&self) -> Result<()> {
+        self.inner.finalize().await?;
+        self.checkpoint.finalize().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::persistence::test_metadata;
+    use crate::services::MemoryHashPersistence;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_checkpoint_records_every_stored_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let checkpoint = checkpoint_path(&temp_dir.path().join("hashes.json"));
+        assert_eq!(checkpoint.file_name().unwrap(), "hashes.json.checkpoint");
+
+        let inner = MemoryHashPersistence::new();
+        let persistence =
+            CheckpointedPersistence::new(inner.clone(), JsonlHashPersistence::new(&checkpoint));
+        persistence
+            .set_scan_info(
+                "scan".to_string(),
+                serde_json::json!({ "algorithm": "dct" }),
+            )
+            .await
+            .unwrap();
+
+        let metadata = test_metadata(100);
+        persistence
+            .store_batch(&[
+                (
+                    PathBuf::from("/a.jpg"),
+                    "00000000000000ff".to_string(),
+                    "DCT".to_string(),
+                    0xff,
+                    metadata.clone(),
+                ),
+                (
+                    PathBuf::from("/b.jpg"),
+                    "00000000000000fe".to_string(),
+                    "DCT".to_string(),
+                    0xfe,
+                    metadata,
+                ),
+            ])
+            .await
+            .unwrap();
+
+        // finalize前（中断時）でもチェックポイントから保存済みのエントリを読める
+        let saved = JsonlHashPersistence::load(&checkpoint).unwrap();
+        assert_eq!(saved.images.len(), 2);
+        assert_eq!(saved.scan_info.parameters["algorithm"], "dct");
+        assert_eq!(inner.stored_count().unwrap(), 2);
+
+        persistence.finalize().await.unwrap();
+        assert!(inner.is_finalized().unwrap());
+        assert_eq!(
+            JsonlHashPersistence::load(&checkpoint)
+                .unwrap()
+                .images
+                .len(),
+            2
+        );
+    }
+}
@@ -1,7 +1,7 @@
 // Collector - 結果収集と永続化機能
 
 use crate::core::types::ProcessingOutcome;
-use crate::core::{HashPersistence, ProgressReporter};
+use crate::core::{CancellationToken, HashPersistence, ProgressReporter};
 use anyhow::Result;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
@@ -9,8 +9,20 @@ use std::sync::{
 };
 use tokio::sync::mpsc;
 
+/// 結果収集の設定
+#[derive(Debug, Clone, Default)]
+pub struct CollectorOptions {
+    /// まとめて永続化する結果の数
+    pub batch_size: usize,
+    /// キャンセル通知（キャンセルされると溜まっているバッチをすぐに保存する）
+    pub cancel: CancellationToken,
+}
+
 /// Collector: 結果収集と永続化
 /// AtomicUsizeを使用して効率的なカウンターを実装
+///
+/// キャンセル後もワーカーが処理中だった結果はチャンネルが閉じるまで受け取り、
+/// バッチを待たずにその都度保存する
 pub fn spawn_result_collector<R, P>(
     mut result_rx: mpsc::Receiver<ProcessingOutcome>,
     total_files: usize,
@@ -18,18 +30,37 @@ pub fn spawn_result_collector<R, P>(
     error_count: Arc<AtomicUsize>,
     reporter: Arc<R>,
     persistence: Arc<P>,
-    batch_size: usize,
+    options: CollectorOptions,
 ) -> tokio::task::JoinHandle<Result<()>>
 where
     R: ProgressReporter + 'static,
     P: HashPersistence + 'static,
 {
+    let CollectorOptions { batch_size, cancel } = options;
     tokio::spawn(async move {
         let mut batch = Vec::with_capacity(batch_size);
         let mut completed = 0;
         let mut errors = 0;
+        let mut cancelled = false;
+
+        loop {
+            let result = tokio::select! {
+                biased;
+                _ = cancel.cancelled(), if !cancelled => {
+                    // 溜まっているバッチをすぐに保存する
+                    cancelled = true;
+                    if !batch.is_empty() {
+                        persistence.store_batch(&batch).await?;
+                        batch.clear();
+                    }
+                    continue;
+                }
+                result = result_rx.recv() => match result {
+                    Some(result) => result,
+                    None => break,
+                },
+            };
 
-        while let Some(result) = result_rx.recv().await {
             match result {
                 ProcessingOutcome::Success {
                     file_path,
@@ -42,7 +73,7 @@ where
                     completed += 1;
 
                     // バッチ永続化
-                    if batch.len() >= batch_size {
+                    if batch.len() >= batch_size || cancelled {
                         persistence.store_batch(&batch).await?;
                         batch.clear();
                     }
@@ -95,7 +126,11 @@ mod tests {
             error_count.clone(),
             Arc::new(reporter),
             Arc::new(persistence.clone()),
-            2, // バッチサイズ
+            // バッチサイズ
+            CollectorOptions {
+                batch_size: 2,
+                ..CollectorOptions::default()
+            },
         );
 
         // 成功結果を送信
@@ -105,6 +140,14 @@ mod tests {
                 processing_time_ms: 100,
                 image_dimensions: (512, 512),
                 was_resized: false,
+                hash_size_bits: 64,
+                modified_time_ms: None,
+                content_hash: None,
+                dihedral_hashes: None,
+                segment_hashes: None,
+                ensemble_hashes: None,
+                ensemble_dihedral_hashes: None,
+                color_signature: None,
             };
 
             result_tx
@@ -150,7 +193,11 @@ mod tests {
             error_count.clone(),
             Arc::new(reporter),
             Arc::new(persistence.clone()),
-            10, // 大きなバッチサイズ
+            // 大きなバッチサイズ
+            CollectorOptions {
+                batch_size: 10,
+                ..CollectorOptions::default()
+            },
         );
 
         // 成功結果
@@ -159,6 +206,14 @@ mod tests {
             processing_time_ms: 100,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         result_tx
@@ -227,7 +282,11 @@ mod tests {
             error_count.clone(),
             Arc::new(reporter),
             Arc::new(persistence.clone()),
-            2, // バッチサイズ2
+            // バッチサイズ2
+            CollectorOptions {
+                batch_size: 2,
+                ..CollectorOptions::default()
+            },
         );
 
         // 5つの成功結果（2+2+1のバッチに分かれるはず）
@@ -237,6 +296,14 @@ mod tests {
                 processing_time_ms: 100,
                 image_dimensions: (512, 512),
                 was_resized: false,
+                hash_size_bits: 64,
+                modified_time_ms: None,
+                content_hash: None,
+                dihedral_hashes: None,
+                segment_hashes: None,
+                ensemble_hashes: None,
+                ensemble_dihedral_hashes: None,
+                color_signature: None,
             };
 
             result_tx
@@ -260,4 +327,74 @@ mod tests {
         let stored_data = persistence.get_stored_data().unwrap();
         assert_eq!(stored_data.len(), 5);
     }
+
+    #[tokio::test]
+    async fn test_result_collector_flushes_batch_on_cancel() {
+        let (result_tx, result_rx) = mpsc::channel::<ProcessingOutcome>(10);
+        let processed_count = Arc::new(AtomicUsize::new(0));
+        let error_count = Arc::new(AtomicUsize::new(0));
+        let persistence = MemoryHashPersistence::new();
+        let cancel = CancellationToken::new();
+
+        let collector_handle = spawn_result_collector(
+            result_rx,
+            3,
+            processed_count.clone(),
+            error_count.clone(),
+            Arc::new(NoOpProgressReporter::new()),
+            Arc::new(persistence.clone()),
+            // キャンセルしなければ最後まで保存されないバッチサイズ
+            CollectorOptions {
+                batch_size: 100,
+                cancel: cancel.clone(),
+            },
+        );
+
+        let success = |i: u64| ProcessingOutcome::Success {
+            file_path: format!("/test{i}.jpg").into(),
+            hash: format!("hash{i}"),
+            algorithm: "DCT".to_string(),
+            hash_bits: i,
+            metadata: ProcessingMetadata {
+                file_size: 1024,
+                processing_time_ms: 100,
+                image_dimensions: (512, 512),
+                was_resized: false,
+                hash_size_bits: 64,
+                modified_time_ms: None,
+                content_hash: None,
+                dihedral_hashes: None,
+                segment_hashes: None,
+                ensemble_hashes: None,
+                ensemble_dihedral_hashes: None,
+                color_signature: None,
+            },
+        };
+        let wait_for_stored = |count: usize| {
+            let persistence = persistence.clone();
+            async move {
+                tokio::time::timeout(std::time::Duration::from_secs(1), async {
+                    while persistence.stored_count().unwrap() < count {
+                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    }
+                })
+                .await
+                .unwrap();
+            }
+        };
+
+        result_tx.send(success(0)).await.unwrap();
+        result_tx.send(success(1)).await.unwrap();
+        // キャンセルすると溜まっていたバッチがすぐに保存される
+        cancel.cancel();
+        wait_for_stored(2).await;
+
+        // 処理中だった結果は届くたびに保存される
+        result_tx.send(success(2)).await.unwrap();
+        wait_for_stored(3).await;
+
+        drop(result_tx);
+        collector_handle.await.unwrap().unwrap();
+        assert_eq!(processed_count.load(Ordering::Relaxed), 3);
+    }
 }
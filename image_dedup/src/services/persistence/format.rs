@@ -0,0 +1,183 @@
+// ハッシュデータベースの保存形式
+
+use super::binary::{BinaryHashPersistence, MAGIC as BINARY_MAGIC};
+use super::implementations::StreamingJsonHashPersistence;
+use super::jsonl::{JsonlHashPersistence, HEADER_PREFIX as JSONL_HEADER};
+use super::sqlite::SqliteHashPersistence;
+use crate::core::HashPersistence;
+use std::io::Read;
+use std::path::Path;
+
+/// SQLiteデータベースファイルの先頭16バイト
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// ハッシュデータベースの保存形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DatabaseFormat {
+    /// scan_infoとimagesを持つ1つのJSONドキュメント
+    #[default]
+    Json,
+    /// images・scan_infoテーブルを持つSQLiteデータベース
+    Sqlite,
+    /// 固定長レコードと文字列テーブルによるバイナリ形式（mmapで読み込む）
+    Binary,
+    /// 1行目にscan_info、以降1行1エントリのJSON Lines（中断されても読み込め、追記できる）
+    Jsonl,
+}
+
+impl DatabaseFormat {
+    /// 名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Sqlite => "sqlite",
+            Self::Binary => "binary",
+            Self::Jsonl => "jsonl",
+        }
+    }
+
+    /// 拡張子から形式を判定（`.sqlite` `.sqlite3` `.db` はSQLite、`.bin` はバイナリ、`.jsonl` `.ndjson` はJSON Lines、`.json` はJSON）
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "sqlite" | "sqlite3" | "db" => Some(Self::Sqlite),
+            "bin" => Some(Self::Binary),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+
+    /// 出力先の形式を決定（明示した形式 → 拡張子 → JSONの順）
+    pub fn for_output(path: &Path, explicit: Option<Self>) -> Self {
+        explicit
+            .or_else(|| Self::from_extension(path))
+            .unwrap_or_default()
+    }
+
+    /// 既存ファイルの内容から形式を判定（拡張子に依存しないため `.partial` などの一時ファイルにも使える）
+    pub fn detect(path: &Path) -> std::io::Result<Self> {
+        let mut header = [0u8; 32];
+        let mut file = std::fs::File::open(path)?;
+        let mut read = 0;
+        while read < header.len() {
+            match file.read(&mut header[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        let header = &header[..read];
+        if header.starts_with(SQLITE_HEADER) {
+            Ok(Self::Sqlite)
+        } else if header.starts_with(BINARY_MAGIC) {
+            Ok(Self::Binary)
+        } else if header.starts_with(JSONL_HEADER) {
+            Ok(Self::Jsonl)
+        } else {
+            Ok(Self::Json)
+        }
+    }
+
+    /// この形式でハッシュを書き込む永続化を作成
+    pub fn create_persistence(self, output_path: &Path) -> Box<dyn HashPersistence> {
+        match self {
+            Self::Json => Box::new(StreamingJsonHashPersistence::new(output_path)),
+            Self::Sqlite => Box::new(SqliteHashPersistence::new(output_path)),
+            Self::Binary => Box::new(BinaryHashPersistence::new(output_path)),
+            Self::Jsonl => Box::new(JsonlHashPersistence::new(output_path)),
+        }
+    }
+}
+
+impl std::fmt::Display for DatabaseFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for DatabaseFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "sqlite" | "sqlite3" => Ok(Self::Sqlite),
+            "binary" | "bin" => Ok(Self::Binary),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            _ => Err(format!(
+                "Unknown database format '{s}'. Available: json, sqlite, binary, jsonl"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_for_output() {
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes.json"), None),
+            DatabaseFormat::Json
+        );
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes.SQLite"), None),
+            DatabaseFormat::Sqlite
+        );
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes.db"), None),
+            DatabaseFormat::Sqlite
+        );
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes.bin"), None),
+            DatabaseFormat::Binary
+        );
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes.ndjson"), None),
+            DatabaseFormat::Jsonl
+        );
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes"), None),
+            DatabaseFormat::Json
+        );
+        // 明示した形式は拡張子より優先する
+        assert_eq!(
+            DatabaseFormat::for_output(Path::new("hashes.json"), Some(DatabaseFormat::Sqlite)),
+            DatabaseFormat::Sqlite
+        );
+        assert!("parquet".parse::<DatabaseFormat>().is_err());
+    }
+
+    #[test]
+    fn test_detect_format_from_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let sqlite = temp_dir.path().join("hashes.json.partial");
+        rusqlite::Connection::open(&sqlite)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x INTEGER);")
+            .unwrap();
+        let json = temp_dir.path().join("hashes.db");
+        std::fs::write(&json, "[]").unwrap();
+        let binary = temp_dir.path().join("hashes.json");
+        std::fs::write(&binary, b"IMGDEDUP\x01\0\0\0").unwrap();
+        let jsonl = temp_dir.path().join("hashes.bin");
+        std::fs::write(&jsonl, "{\"type\":\"scan_info\",\"algorithm\":\"scan\"}\n").unwrap();
+
+        assert_eq!(
+            DatabaseFormat::detect(&sqlite).unwrap(),
+            DatabaseFormat::Sqlite
+        );
+        assert_eq!(DatabaseFormat::detect(&json).unwrap(), DatabaseFormat::Json);
+        assert_eq!(
+            DatabaseFormat::detect(&binary).unwrap(),
+            DatabaseFormat::Binary
+        );
+        assert_eq!(
+            DatabaseFormat::detect(&jsonl).unwrap(),
+            DatabaseFormat::Jsonl
+        );
+        assert!(DatabaseFormat::detect(&temp_dir.path().join("missing")).is_err());
+    }
+}
@@ -399,6 +399,14 @@ mod tests {
             processing_time_ms: 100,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         // 単一保存テスト
@@ -448,6 +456,14 @@ mod tests {
             processing_time_ms: 100,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         persistence
@@ -477,6 +493,14 @@ mod tests {
             processing_time_ms: 150,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         // 単一エントリ保存
@@ -515,6 +539,14 @@ mod tests {
             processing_time_ms: 200,
             image_dimensions: (1024, 1024),
             was_resized: true,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         // バッチ保存
@@ -559,6 +591,7 @@ mod tests {
             assert_eq!(entry["file_path"], format!("/test{}.{expected_ext}", i + 1));
             assert_eq!(entry["hash"], format!("hash{}", i + 1));
             assert_eq!(entry["metadata"]["was_resized"], true);
+            assert_eq!(entry["metadata"]["hash_size_bits"], 64);
         }
     }
 
@@ -574,6 +607,14 @@ mod tests {
             processing_time_ms: 100,
             image_dimensions: (256, 256),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         // 複数バッチ保存
@@ -673,6 +714,14 @@ mod tests {
             processing_time_ms: 100,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         persistence
@@ -1094,6 +1143,14 @@ mod streaming_tests {
             processing_time_ms: 100,
             image_dimensions: (512, 512),
             was_resized: false,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         // 複数のエントリを追加（バッファサイズを超える）
@@ -1167,6 +1224,14 @@ mod streaming_tests {
             processing_time_ms: 150,
             image_dimensions: (1024, 1024),
             was_resized: true,
+            hash_size_bits: 64,
+            modified_time_ms: None,
+            content_hash: None,
+            dihedral_hashes: None,
+            segment_hashes: None,
+            ensemble_hashes: None,
+            ensemble_dihedral_hashes: None,
+            color_signature: None,
         };
 
         // 大きなバッチを処理
@@ -0,0 +1,284 @@
+// 差分スキャン - 既存データベースを再利用した増分更新
+
+use super::binary::{BinaryHashDatabase, BinaryHashPersistence};
+use super::format::DatabaseFormat;
+use super::implementations::{HashEntry, ScanResult};
+use super::jsonl::JsonlHashPersistence;
+use super::sqlite::SqliteHashPersistence;
+use crate::core::types::ProcessingMetadata;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 差分スキャンの計画
+#[derive(Debug, Default)]
+pub struct UpdatePlan {
+    /// サイズと更新日時が変わっていないため再利用するエントリ
+    pub reused: Vec<HashEntry>,
+    /// ハッシュ計算が必要なファイル（新規・変更）
+    pub files_to_hash: Vec<String>,
+    /// 新規ファイル数
+    pub added: usize,
+    /// 変更されたファイル数
+    pub modified: usize,
+    /// 削除された（ディスク上に存在しない）ファイル数
+    pub removed: usize,
+}
+
+impl UpdatePlan {
+    /// 既存エントリと発見したファイルを突き合わせて計画を作成
+    ///
+    /// ファイルサイズと更新日時の両方が一致するエントリだけを再利用する。
+    /// 更新日時が記録されていない旧データベースのエントリは再計算の対象になる
+    pub fn new(existing: Vec<HashEntry>, discovered: Vec<String>) -> Self {
+        let mut existing: HashMap<String, HashEntry> = existing
+            .into_iter()
+            .map(|entry| (entry.file_path.clone(), entry))
+            .collect();
+
+        let mut plan = Self::default();
+        for file_path in discovered {
+            match existing.remove(&file_path) {
+                Some(entry) if Self::is_unchanged(&entry) => plan.reused.push(entry),
+                Some(_) => {
+                    plan.modified += 1;
+                    plan.files_to_hash.push(file_path);
+                }
+                None => {
+                    plan.added += 1;
+                    plan.files_to_hash.push(file_path);
+                }
+            }
+        }
+        plan.removed = existing.len();
+
+        plan
+    }
+
+    /// エントリ記録時からファイルが変更されていないか
+    fn is_unchanged(entry: &HashEntry) -> bool {
+        let Ok(metadata) = std::fs::metadata(&entry.file_path) else {
+            return false;
+        };
+
+        entry.metadata.modified_time_ms.is_some()
+            && entry.metadata.file_size == metadata.len()
+            && entry.metadata.modified_time_ms == ProcessingMetadata::modified_time_ms_of(&metadata)
+    }
+}
+
+/// 既存のスキャン結果を読み込む（形式はファイルの内容から判定する）
+pub fn load_scan_result(path: &Path) -> Result<ScanResult> {
+    let format = DatabaseFormat::detect(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    match format {
+        DatabaseFormat::Sqlite => return SqliteHashPersistence::load(path),
+        DatabaseFormat::Binary => return BinaryHashDatabase::open(path)?.into_scan_result(),
+        DatabaseFormat::Jsonl => return JsonlHashPersistence::load(path),
+        DatabaseFormat::Json => {}
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to parse scan database {} (only the scan_info/images format can be updated): {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// 再利用エントリと新たに計算したスキャン結果を統合
+///
+/// scan_infoは新しい結果のものを使い、エントリはファイルパス順に並べる
+pub fn merge_scan_results(reused: Vec<HashEntry>, fresh: ScanResult) -> ScanResult {
+    let mut images = reused;
+    images.extend(fresh.images);
+    images.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    let mut scan_info = fresh.scan_info;
+    scan_info.total_files = images.len();
+
+    ScanResult { scan_info, images }
+}
+
+/// スキャン結果を書き込む
+///
+/// 一時ファイルに書き込んでから置き換えるため、途中で失敗しても既存ファイルは壊れない
+pub fn write_scan_result(
+    path: &Path,
+    scan_result: &ScanResult,
+    format: DatabaseFormat,
+) -> Result<()> {
+    let temp_path = sibling_path(path, "tmp");
+    match format {
+        DatabaseFormat::Json => {
+            let json = serde_json::to_string_pretty(scan_result)?;
+            std::fs::write(&temp_path, json)
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", temp_path.display(), e))?;
+        }
+        DatabaseFormat::Sqlite => SqliteHashPersistence::write(&temp_path, scan_result)?,
+        DatabaseFormat::Binary => BinaryHashPersistence::write(&temp_path, scan_result)?,
+        DatabaseFormat::Jsonl => JsonlHashPersistence::write(&temp_path, scan_result)?,
+    }
+    std::fs::rename(&temp_path, path)
+        .map_err(|e| anyhow::anyhow!("Failed to replace {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// 同じディレクトリに拡張子を追加したパスを作成（例: hashes.json → hashes.json.partial）
+pub fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::persistence::implementations::ScanInfo;
+    use tempfile::TempDir;
+
+    fn entry_for(path: &Path, hash: &str) -> HashEntry {
+        let metadata = std::fs::metadata(path).unwrap();
+        HashEntry {
+            file_path: path.to_string_lossy().to_string(),
+            hash: hash.to_string(),
+            hash_bits: 0,
+            metadata: ProcessingMetadata {
+                file_size: metadata.len(),
+                processing_time_ms: 1,
+                image_dimensions: (1, 1),
+                was_resized: false,
+                hash_size_bits: 64,
+                modified_time_ms: ProcessingMetadata::modified_time_ms_of(&metadata),
+                content_hash: None,
+                dihedral_hashes: None,
+                segment_hashes: None,
+                ensemble_hashes: None,
+                ensemble_dihedral_hashes: None,
+                color_signature: None,
+            },
+        }
+    }
+
+    fn scan_info(total_files: usize) -> ScanInfo {
+        ScanInfo {
+            algorithm: "scan".to_string(),
+            parameters: serde_json::json!({}),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            total_files,
+        }
+    }
+
+    #[test]
+    fn test_update_plan_classifies_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let unchanged = temp_dir.path().join("unchanged.jpg");
+        let modified = temp_dir.path().join("modified.jpg");
+        let added = temp_dir.path().join("added.jpg");
+        let deleted = temp_dir.path().join("deleted.jpg");
+        for path in [&unchanged, &modified, &deleted] {
+            std::fs::write(path, b"original").unwrap();
+        }
+
+        let existing = vec![
+            entry_for(&unchanged, "aa"),
+            entry_for(&modified, "bb"),
+            entry_for(&deleted, "cc"),
+        ];
+
+        std::fs::write(&modified, b"changed content").unwrap();
+        std::fs::write(&added, b"new").unwrap();
+        std::fs::remove_file(&deleted).unwrap();
+
+        let discovered = [&added, &modified, &unchanged]
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        let plan = UpdatePlan::new(existing, discovered);
+
+        assert_eq!(plan.reused.len(), 1);
+        assert_eq!(plan.reused[0].hash, "aa");
+        assert_eq!(plan.added, 1);
+        assert_eq!(plan.modified, 1);
+        assert_eq!(plan.removed, 1);
+        assert_eq!(
+            plan.files_to_hash,
+            vec![
+                added.to_string_lossy().to_string(),
+                modified.to_string_lossy().to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_plan_rehashes_entries_without_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("legacy.jpg");
+        std::fs::write(&path, b"data").unwrap();
+
+        let mut entry = entry_for(&path, "aa");
+        entry.metadata.modified_time_ms = None;
+
+        let plan = UpdatePlan::new(vec![entry], vec![path.to_string_lossy().to_string()]);
+
+        assert!(plan.reused.is_empty());
+        assert_eq!(plan.modified, 1);
+    }
+
+    #[test]
+    fn test_merge_and_write_scan_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a.jpg");
+        let b = temp_dir.path().join("b.jpg");
+        std::fs::write(&a, b"a").unwrap();
+        std::fs::write(&b, b"b").unwrap();
+
+        let fresh = ScanResult {
+            scan_info: scan_info(1),
+            images: vec![entry_for(&a, "new")],
+        };
+        let merged = merge_scan_results(vec![entry_for(&b, "old")], fresh);
+
+        assert_eq!(merged.scan_info.total_files, 2);
+        assert_eq!(merged.images[0].hash, "new");
+        assert_eq!(merged.images[1].hash, "old");
+
+        for (name, format) in [
+            ("hashes.json", DatabaseFormat::Json),
+            ("hashes.sqlite", DatabaseFormat::Sqlite),
+            ("hashes.bin", DatabaseFormat::Binary),
+            ("hashes.jsonl", DatabaseFormat::Jsonl),
+        ] {
+            let output = temp_dir.path().join(name);
+            write_scan_result(&output, &merged, format).unwrap();
+            let loaded = load_scan_result(&output).unwrap();
+
+            assert_eq!(DatabaseFormat::detect(&output).unwrap(), format);
+            assert_eq!(loaded.images.len(), 2);
+            assert_eq!(loaded.images[0].hash, "new");
+            assert_eq!(loaded.scan_info.total_files, 2);
+            assert!(!sibling_path(&output, "tmp").exists());
+        }
+    }
+
+    #[test]
+    fn test_load_scan_result_rejects_old_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("old.json");
+        std::fs::write(&path, "[]").unwrap();
+
+        assert!(load_scan_result(&path).is_err());
+    }
+
+    #[test]
+    fn test_sibling_path() {
+        assert_eq!(
+            sibling_path(Path::new("/data/hashes.json"), "partial"),
+            PathBuf::from("/data/hashes.json.partial")
+        );
+    }
+}
@@ -0,0 +1,524 @@
+// JSON Lines形式での永続化
+// 1行目にscan_info、続けて1行1エントリ、最後にサマリーを書く。
+// 行単位で追記するだけなので、書き込みの途中で中断されてもそれまでの行はそのまま読み込める
+
+use super::implementations::{HashEntry, ScanInfo, ScanResult};
+use crate::core::HashPersistence;
+use crate::core::ProcessingMetadata;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufWriter};
+use tokio::sync::Mutex as AsyncMutex;
+
+/// ファイルの先頭（形式の判定に使う）
+pub(crate) const HEADER_PREFIX: &[u8] = br#"{"type":"scan_info""#;
+
+/// 1行分のレコード
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    /// 先頭行
+    ScanInfo(ScanInfo),
+    /// 画像1件
+    Image(HashEntry),
+    /// 書き込み完了時のサマリー（追記で再開した場合は途中にも現れる）
+    Summary(Summary),
+}
+
+/// 書き込み完了時のサマリー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Summary {
+    total_files: usize,
+    timestamp: String,
+}
+
+/// ファイルを先頭から読み込んだ結果
+struct ParsedFile {
+    scan_info: ScanInfo,
+    images: Vec<HashEntry>,
+    /// 最後の完全な行までのバイト数（中断で途切れた行は含まない）
+    valid_len: u64,
+}
+
+/// JSON Lines形式での永続化実装
+///
+/// バッチごとに行を書き込んでフラッシュするため、ファイルは常に読み込める状態に保たれる。
+/// `append` で作成すると既存のファイルに続けて書き込む（scan_infoは既存のものを使う）
+#[derive(Debug, Clone)]
+pub struct JsonlHashPersistence {
+    file_path: PathBuf,
+    append: bool,
+    writer: Arc<AsyncMutex<Option<BufWriter<File>>>>,
+    entries_written: Arc<AsyncMutex<usize>>,
+    scan_info: Arc<AsyncMutex<Option<ScanInfo>>>,
+    finalized: Arc<AsyncMutex<bool>>,
+}
+
+impl JsonlHashPersistence {
+    /// 新しいファイルに書き込む（既存のファイルは置き換える）
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self::create(file_path.as_ref(), false)
+    }
+
+    /// 既存のファイルに追記する（ファイルがなければ新規に作成する）
+    pub fn append<P: AsRef<Path>>(file_path: P) -> Self {
+        Self::create(file_path.as_ref(), true)
+    }
+
+    fn create(file_path: &Path, append: bool) -> Self {
+        Self {
+            file_path: file_path.to_path_buf(),
+            append,
+            writer: Arc::new(AsyncMutex::new(None)),
+            entries_written: Arc::new(AsyncMutex::new(0)),
+            scan_info: Arc::new(AsyncMutex::new(None)),
+            finalized: Arc::new(AsyncMutex::new(false)),
+        }
+    }
+
+    /// ファイル全体をスキャン結果として読み込む
+    ///
+    /// 同じファイルのエントリが複数ある場合は後の行を使い、エントリはファイルパス順に並べる。
+    /// サマリーがない（中断された）ファイルもそれまでのエントリを返す
+    pub fn load(path: &Path) -> Result<ScanResult> {
+        let parsed = parse_file(path)?;
+        let mut images: Vec<HashEntry> = parsed
+            .images
+            .into_iter()
+            .map(|entry| (entry.file_path.clone(), entry))
+            .collect::<HashMap<_, _>>()
+            .into_values()
+            .collect();
+        images.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let mut scan_info = parsed.scan_info;
+        scan_info.total_files = images.len();
+        Ok(ScanResult { scan_info, images })
+    }
+
+    /// スキャン結果をまとめて書き込む（既存のファイルは置き換える）
+    pub fn write(path: &Path, scan_result: &ScanResult) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = std::io::BufWriter::new(file);
+        writer.write_all(&encode(&Record::ScanInfo(scan_result.scan_info.clone()))?)?;
+        for entry in &scan_result.images {
+            writer.write_all(&encode(&Record::Image(entry.clone()))?)?;
+        }
+        writer.write_all(&encode(&summary(scan_result.images.len()))?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 書き込み先を開く（新規の場合はscan_infoの行を書く）
+    async fn open_writer(&self) -> Result<()> {
+        let mut writer_guard = self.writer.lock().await;
+        if writer_guard.is_some() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self
+            .file_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| anyhow::anyhow!("ディレクトリ作成エラー: {e}"))?;
+        }
+
+        let existing = if self.append && self.file_path.exists() {
+            Some(parse_file(&self.file_path)?)
+        } else {
+            None
+        };
+
+        let writer = match existing {
+            Some(existing) => {
+                // 途切れた最後の行を取り除いてから続きを書く
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .open(&self.file_path)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("ファイルオープンエラー: {e}"))?;
+                file.set_len(existing.valid_len).await?;
+                file.seek(std::io::SeekFrom::Start(existing.valid_len))
+                    .await?;
+
+                *self.entries_written.lock().await = existing.images.len();
+                *self.scan_info.lock().await = Some(existing.scan_info);
+                BufWriter::new(file)
+            }
+            None => {
+                let scan_info = self
+                    .scan_info
+                    .lock()
+                    .await
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("scan_infoが設定されていません"))?;
+                let file = File::create(&self.file_path)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("ファイル作成エラー: {e}"))?;
+                let mut writer = BufWriter::new(file);
+                writer
+                    .write_all(&encode(&Record::ScanInfo(scan_info))?)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("書き込みエラー: {e}"))?;
+                writer
+                    .flush()
+                    .await
+                    .map_err(|e| anyhow::anyhow!("フラッシュエラー: {e}"))?;
+                writer
+            }
+        };
+
+        *writer_guard = Some(writer);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HashPersistence for JsonlHashPersistence {
+    async fn store_hash(
+        &self,
+        file_path: &Path,
+        hash: &str,
+        metadata: &ProcessingMetadata,
+    ) -> Result<()> {
+        self.store_batch(&[(
+            file_path.to_path_buf(),
+            hash.to_string(),
+            "DCT".to_string(),
+            0u64,
+            metadata.clone(),
+        )])
+        .await
+    }
+
+    async fn store_batch(
+        &self,
+        results: &[(PathBuf, String, String, u64, ProcessingMetadata)],
+    ) -> Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        // 完了後に開き直すとファイルを作り直してしまう
+        if *self.finalized.lock().await {
+            anyhow::bail!("{} は既に完了しています", self.file_path.display());
+        }
+
+        self.open_writer().await?;
+        let mut writer_guard = self.writer.lock().await;
+        let writer = writer_guard
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("ファイルが初期化されていません"))?;
+
+        for (file_path, hash, _algorithm, hash_bits, metadata) in results {
+            let record = Record::Image(HashEntry {
+                file_path: file_path.to_string_lossy().to_string(),
+                hash: hash.clone(),
+                hash_bits: *hash_bits,
+                metadata: metadata.clone(),
+            });
+            writer
+                .write_all(&encode(&record)?)
+                .await
+                .map_err(|e| anyhow::anyhow!("書き込みエラー: {e}"))?;
+        }
+
+        // バッチ単位で行を確定させる
+        writer
+            .flush()
+            .await
+            .map_err(|e| anyhow::anyhow!("フラッシュエラー: {e}"))?;
+        *self.entries_written.lock().await += results.len();
+        Ok(())
+    }
+
+    async fn set_scan_info(&self, operation: String, info: serde_json::Value) -> Result<()> {
+        // 追記する既存のファイルでは、開いた時点で先頭行のscan_infoに置き換わる
+        *self.scan_info.lock().await = Some(ScanInfo {
+            algorithm: operation,
+            parameters: info,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            total_files: 0, // サマリーの行に記録する
+        });
+        Ok(())
+    }
+
+    async fn finalize(&self) -> Result<()> {
+        // 2回目以降は何もしない（書き込み先を開き直すと書いたエントリが消える）
+        let mut finalized = self.finalized.lock().await;
+        if *finalized {
+            return Ok(());
+        }
+
+        // 何も保存されていない場合もscan_infoとサマリーだけのファイルを作成する
+        self.open_writer().await?;
+
+        let total_files = *self.entries_written.lock().await;
+        let mut writer_guard = self.writer.lock().await;
+        if let Some(mut writer) = writer_guard.take() {
+            writer
+                .write_all(&encode(&summary(total_files))?)
+                .await
+                .map_err(|e| anyhow::anyhow!("書き込みエラー: {e}"))?;
+            writer
+                .flush()
+                .await
+                .map_err(|e| anyhow::anyhow!("フラッシュエラー: {e}"))?;
+            writer.into_inner().sync_all().await?;
+        }
+        *finalized = true;
+        Ok(())
+    }
+}
+
+/// レコードを改行付きの1行に変換
+fn encode(record: &Record) -> Result<Vec<u8>> {
+    let mut line =
+        serde_json::to_vec(record).map_err(|e| anyhow::anyhow!("JSON変換エラー: {e}"))?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn summary(total_files: usize) -> Record {
+    Record::Summary(Summary {
+        total_files,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// ファイルを先頭から読み込む
+///
+/// 改行で終わっていない最後の行は中断された書き込みとして無視する
+fn parse_file(path: &Path) -> Result<ParsedFile> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let mut reader = std::io::BufReader::new(file);
+
+    let mut scan_info = None;
+    let mut images = Vec::new();
+    let mut valid_len = 0u64;
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        line_number += 1;
+
+        let record: Record = serde_json::from_slice(&line).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to parse line {} of {}: {}",
+                line_number,
+                path.display(),
+                e
+            )
+        })?;
+        match (record, scan_info.is_some()) {
+            (Record::ScanInfo(info), false) => scan_info = Some(info),
+            (Record::ScanInfo(_), true) => anyhow::bail!(
+                "Line {} of {} repeats the scan_info header",
+                line_number,
+                path.display()
+            ),
+            (_, false) => {
+                anyhow::bail!("{} does not start with a scan_info header", path.display())
+            }
+            (Record::Image(entry), true) => images.push(entry),
+            (Record::Summary(_), true) => {}
+        }
+        valid_len += read as u64;
+    }
+
+    let scan_info =
+        scan_info.ok_or_else(|| anyhow::anyhow!("{} has no scan_info header", path.display()))?;
+    Ok(ParsedFile {
+        scan_info,
+        images,
+        valid_len,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::persistence::test_metadata;
+    use tempfile::TempDir;
+
+    fn batch(names: &[&str]) -> Vec<(PathBuf, String, String, u64, ProcessingMetadata)> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                (
+                    PathBuf::from(format!("/photos/{name}")),
+                    format!("{:016x}", u64::MAX - i as u64),
+                    "DCT".to_string(),
+                    u64::MAX - i as u64,
+                    test_metadata(100 + i as u64),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_persistence_is_readable_after_every_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.jsonl");
+        let persistence = JsonlHashPersistence::new(&path);
+        persistence
+            .set_scan_info(
+                "scan".to_string(),
+                serde_json::json!({ "algorithm": "dct" }),
+            )
+            .await
+            .unwrap();
+
+        persistence
+            .store_batch(&batch(&["a.jpg", "b.jpg"]))
+            .await
+            .unwrap();
+        // finalize前でもそれまでのエントリを読み込める
+        let partial = JsonlHashPersistence::load(&path).unwrap();
+        assert_eq!(partial.images.len(), 2);
+        assert_eq!(partial.scan_info.total_files, 2);
+        assert_eq!(partial.images[0].hash_bits, u64::MAX);
+
+        persistence.store_batch(&batch(&["c.jpg"])).await.unwrap();
+        persistence.finalize().await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].as_bytes().starts_with(HEADER_PREFIX));
+        assert!(lines[4].contains(r#""type":"summary","total_files":3"#));
+
+        let result = JsonlHashPersistence::load(&path).unwrap();
+        assert_eq!(result.scan_info.parameters["algorithm"], "dct");
+        let paths: Vec<&str> = result.images.iter().map(|e| e.file_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["/photos/a.jpg", "/photos/b.jpg", "/photos/c.jpg"]
+        );
+        assert_eq!(result.images[0].metadata, test_metadata(100));
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_append_resumes_after_truncated_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.jsonl");
+        let first = JsonlHashPersistence::new(&path);
+        first
+            .set_scan_info("scan".to_string(), serde_json::json!({ "run": 1 }))
+            .await
+            .unwrap();
+        first
+            .store_batch(&batch(&["a.jpg", "b.jpg"]))
+            .await
+            .unwrap();
+        drop(first);
+
+        // 書き込み途中で中断された行を再現する
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(br#"{"type":"image","file_path":"/pho"#)
+            .unwrap();
+        drop(file);
+        assert_eq!(JsonlHashPersistence::load(&path).unwrap().images.len(), 2);
+
+        let resumed = JsonlHashPersistence::append(&path);
+        resumed
+            .set_scan_info("scan".to_string(), serde_json::json!({ "run": 2 }))
+            .await
+            .unwrap();
+        resumed.store_batch(&batch(&["c.jpg"])).await.unwrap();
+        resumed.finalize().await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("/pho\""));
+        let result = JsonlHashPersistence::load(&path).unwrap();
+        assert_eq!(result.scan_info.parameters["run"], 1);
+        assert_eq!(result.images.len(), 3);
+        assert!(content.ends_with("\n"));
+        assert!(content
+            .lines()
+            .last()
+            .unwrap()
+            .contains(r#""total_files":3"#));
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_double_finalize_keeps_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.jsonl");
+        let persistence = JsonlHashPersistence::new(&path);
+        persistence
+            .set_scan_info("scan".to_string(), serde_json::json!({}))
+            .await
+            .unwrap();
+        persistence
+            .store_batch(&batch(&["a.jpg", "b.jpg"]))
+            .await
+            .unwrap();
+        persistence.finalize().await.unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        // 2回目のfinalizeでファイルを作り直さない
+        persistence.finalize().await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(JsonlHashPersistence::load(&path).unwrap().images.len(), 2);
+
+        // 完了後の書き込みはエラーにする
+        let error = persistence
+            .store_batch(&batch(&["c.jpg"]))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("完了"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_empty_finalize_and_invalid_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("empty.jsonl");
+        let persistence = JsonlHashPersistence::new(&path);
+        persistence
+            .set_scan_info("scan".to_string(), serde_json::json!({}))
+            .await
+            .unwrap();
+        persistence.finalize().await.unwrap();
+        assert!(JsonlHashPersistence::load(&path).unwrap().images.is_empty());
+
+        let missing_info = temp_dir.path().join("missing_info.jsonl");
+        let error = JsonlHashPersistence::new(&missing_info)
+            .finalize()
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("scan_info"));
+
+        let no_header = temp_dir.path().join("no_header.jsonl");
+        std::fs::write(
+            &no_header,
+            "{\"type\":\"summary\",\"total_files\":0,\"timestamp\":\"\"}\n",
+        )
+        .unwrap();
+        assert!(JsonlHashPersistence::load(&no_header).is_err());
+
+        let corrupt = temp_dir.path().join("corrupt.jsonl");
+        std::fs::write(&corrupt, "{\"type\":\"scan_info\"\nnot json\n").unwrap();
+        let error = JsonlHashPersistence::load(&corrupt).unwrap_err();
+        assert!(error.to_string().contains("line 1"));
+    }
+}
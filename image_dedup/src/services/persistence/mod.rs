@@ -1,11 +1,42 @@
 // データ永続化機能
 // ハッシュデータの保存、バッチ処理、結果収集
 
+pub mod binary;
+pub mod checkpoint;
 pub mod collector;
+pub mod format;
 pub mod implementations;
+pub mod incremental;
+pub mod jsonl;
+pub mod sqlite;
 
 // 公開API
-pub use collector::spawn_result_collector;
+pub use binary::{BinaryHashDatabase, BinaryHashPersistence, RecordView};
+pub use checkpoint::{checkpoint_path, CheckpointedPersistence};
+pub use collector::{spawn_result_collector, CollectorOptions};
+pub use format::DatabaseFormat;
 pub use implementations::{
     JsonHashPersistence, MemoryHashPersistence, StreamingJsonHashPersistence,
 };
+pub use incremental::UpdatePlan;
+pub use jsonl::JsonlHashPersistence;
+pub use sqlite::SqliteHashPersistence;
+
+/// テスト用のメタデータ（ファイルサイズ以外は固定値）
+#[cfg(test)]
+pub(crate) fn test_metadata(file_size: u64) -> crate::core::ProcessingMetadata {
+    crate::core::ProcessingMetadata {
+        file_size,
+        processing_time_ms: 1,
+        image_dimensions: (8, 8),
+        was_resized: false,
+        hash_size_bits: 64,
+        modified_time_ms: Some(1_700_000_000_000),
+        content_hash: None,
+        dihedral_hashes: None,
+        segment_hashes: None,
+        ensemble_hashes: None,
+        ensemble_dihedral_hashes: None,
+        color_signature: None,
+    }
+}
@@ -0,0 +1,497 @@
+// SQLiteデータベースへの永続化
+// 全体を1つのドキュメントとして読み込む必要がないため、大規模なデータベースでもパスやハッシュで直接検索できる
+
+use super::implementations::{HashEntry, ScanInfo, ScanResult};
+use crate::core::HashPersistence;
+use crate::core::ProcessingMetadata;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// テーブルとインデックスの定義
+///
+/// `metadata` には `ProcessingMetadata` 全体をJSONで保存し、検索に使う値だけを列に複製する
+const SCHEMA: &str = "
+CREATE TABLE scan_info (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    algorithm TEXT NOT NULL,
+    parameters TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    total_files INTEGER NOT NULL
+);
+CREATE TABLE images (
+    id INTEGER PRIMARY KEY,
+    file_path TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    hash_bits INTEGER NOT NULL,
+    file_size INTEGER NOT NULL,
+    modified_time_ms INTEGER,
+    metadata TEXT NOT NULL
+);
+CREATE UNIQUE INDEX idx_images_file_path ON images (file_path);
+CREATE INDEX idx_images_file_size ON images (file_size);
+CREATE INDEX idx_images_hash ON images (hash);
+";
+
+/// 画像エントリを読み出す列（`read_entry` の順）
+const IMAGE_COLUMNS: &str = "file_path, hash, hash_bits, metadata";
+
+/// SQLite形式での永続化実装
+///
+/// バッチごとに1つのトランザクションで書き込む。
+/// rusqliteは同期APIのため、書き込みはブロッキングスレッドで実行する
+#[derive(Debug, Clone)]
+pub struct SqliteHashPersistence {
+    file_path: PathBuf,
+    connection: Arc<Mutex<Option<Connection>>>,
+    scan_info: Arc<Mutex<Option<ScanInfo>>>,
+}
+
+impl SqliteHashPersistence {
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Self {
+        Self {
+            file_path: file_path.as_ref().to_path_buf(),
+            connection: Arc::new(Mutex::new(None)),
+            scan_info: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 接続を取得して処理を実行（初回はデータベースを作成し直す）
+    fn with_connection<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Connection) -> Result<T>,
+    {
+        let mut guard = self
+            .connection
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Connection lock poisoned: {}", e))?;
+        if guard.is_none() {
+            let connection = create_database(&self.file_path)?;
+            if let Some(scan_info) = self.scan_info()? {
+                write_scan_info(&connection, &scan_info)?;
+            }
+            *guard = Some(connection);
+        }
+        let connection = guard
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Connection should be initialized"))?;
+        f(connection)
+    }
+
+    /// 接続を使う処理をブロッキングスレッドで実行（非同期ランタイムのスレッドを塞がない）
+    async fn with_connection_blocking<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let persistence = self.clone();
+        tokio::task::spawn_blocking(move || persistence.with_connection(f))
+            .await
+            .context("Failed to spawn blocking task for SQLite")?
+    }
+
+    fn scan_info(&self) -> Result<Option<ScanInfo>> {
+        Ok(self
+            .scan_info
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Scan info lock poisoned: {}", e))?
+            .clone())
+    }
+
+    /// データベース全体をスキャン結果として読み込む（エントリはファイルパス順）
+    pub fn load(path: &Path) -> Result<ScanResult> {
+        let connection = open_read_only(path)?;
+        let scan_info = read_scan_info(&connection)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Scan database {} has no scan_info (the scan did not finish)",
+                path.display()
+            )
+        })?;
+
+        let mut statement = connection.prepare(&format!(
+            "SELECT {IMAGE_COLUMNS} FROM images ORDER BY file_path"
+        ))?;
+        let images = statement
+            .query_map([], read_entry)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(ScanResult { scan_info, images })
+    }
+
+    /// 指定したファイルのエントリだけを読み込む（記録されていないファイルは含まれない）
+    pub fn load_entries<'a, I>(path: &Path, file_paths: I) -> Result<Vec<HashEntry>>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let connection = open_read_only(path)?;
+        let mut statement = connection.prepare(&format!(
+            "SELECT {IMAGE_COLUMNS} FROM images WHERE file_path = ?1"
+        ))?;
+
+        let mut entries = Vec::new();
+        for file_path in file_paths {
+            if let Some(entry) = statement.query_row([file_path], read_entry).optional()? {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// スキャン結果をまとめて書き込む（既存のファイルは置き換える）
+    pub fn write(path: &Path, scan_result: &ScanResult) -> Result<()> {
+        let mut connection = create_database(path)?;
+        insert_images(
+            &mut connection,
+            scan_result.images.iter().map(|entry| {
+                (
+                    entry.file_path.clone(),
+                    entry.hash.as_str(),
+                    entry.hash_bits,
+                    &entry.metadata,
+                )
+            }),
+        )?;
+        write_scan_info(&connection, &scan_result.scan_info)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HashPersistence for SqliteHashPersistence {
+    async fn store_hash(
+        &self,
+        file_path: &Path,
+        hash: &str,
+        metadata: &ProcessingMetadata,
+    ) -> Result<()> {
+        self.store_batch(&[(
+            file_path.to_path_buf(),
+            hash.to_string(),
+            "DCT".to_string(),
+            0u64,
+            metadata.clone(),
+        )])
+        .await
+    }
+
+    async fn store_batch(
+        &self,
+        results: &[(PathBuf, String, String, u64, ProcessingMetadata)],
+    ) -> Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+
+        let results = results.to_vec();
+        self.with_connection_blocking(move |connection| {
+            insert_images(
+                connection,
+                results
+                    .iter()
+                    .map(|(file_path, hash, _algorithm, hash_bits, metadata)| {
+                        (
+                            file_path.to_string_lossy().to_string(),
+                            hash.as_str(),
+                            *hash_bits,
+                            metadata,
+                        )
+                    }),
+            )
+        })
+        .await
+    }
+
+    async fn set_scan_info(&self, operation: String, info: serde_json::Value) -> Result<()> {
+        let scan_info = ScanInfo {
+            algorithm: operation,
+            parameters: info,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            total_files: 0, // finalizeで更新
+        };
+
+        *self
+            .scan_info
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Scan info lock poisoned: {}", e))? = Some(scan_info);
+        Ok(())
+    }
+
+    async fn finalize(&self) -> Result<()> {
+        let scan_info = self.scan_info()?;
+        // 何も保存されていない場合も空のデータベースを作成する
+        self.with_connection_blocking(move |connection| {
+            if let Some(mut scan_info) = scan_info {
+                let total: i64 =
+                    connection.query_row("SELECT COUNT(*) FROM images", [], |row| row.get(0))?;
+                scan_info.total_files = total as usize;
+                write_scan_info(connection, &scan_info)?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        // 接続を閉じてファイルを確定させる
+        self.connection
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Connection lock poisoned: {}", e))?
+            .take();
+        Ok(())
+    }
+}
+
+/// 空のデータベースを作成（既存のファイルは削除する）
+fn create_database(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("ディレクトリ作成エラー: {e}"))?;
+    }
+
+    // 中断された書き込みのジャーナルが残っていると新しいデータベースに適用されてしまう
+    for stale in [path.to_path_buf(), journal_path(path)] {
+        match std::fs::remove_file(&stale) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                anyhow::bail!("Failed to remove {}: {}", stale.display(), e)
+            }
+            _ => {}
+        }
+    }
+
+    let connection = Connection::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+}
+
+/// 読み取り専用で開く
+fn open_read_only(path: &Path) -> Result<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))
+}
+
+/// ロールバックジャーナルのパス（例: hashes.sqlite → hashes.sqlite-journal）
+fn journal_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push("-journal");
+    path.with_file_name(file_name)
+}
+
+/// 画像エントリを1つのトランザクションで書き込む（同じパスのエントリは置き換える）
+fn insert_images<'a, I>(connection: &mut Connection, images: I) -> Result<()>
+where
+    I: IntoIterator<Item = (String, &'a str, u64, &'a ProcessingMetadata)>,
+{
+    let transaction = connection.transaction()?;
+    {
+        let mut statement = transaction.prepare(
+            "INSERT OR REPLACE INTO images
+                (file_path, hash, hash_bits, file_size, modified_time_ms, metadata)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (file_path, hash, hash_bits, metadata) in images {
+            // SQLiteの整数は符号付き64ビットのため、ビット列をそのまま格納する
+            statement.execute(params![
+                file_path,
+                hash,
+                hash_bits as i64,
+                metadata.file_size as i64,
+                metadata.modified_time_ms.map(|ms| ms as i64),
+                serde_json::to_string(metadata)?,
+            ])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+fn write_scan_info(connection: &Connection, scan_info: &ScanInfo) -> Result<()> {
+    connection.execute(
+        "INSERT OR REPLACE INTO scan_info (id, algorithm, parameters, timestamp, total_files)
+         VALUES (1, ?1, ?2, ?3, ?4)",
+        params![
+            scan_info.algorithm,
+            serde_json::to_string(&scan_info.parameters)?,
+            scan_info.timestamp,
+            scan_info.total_files as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_scan_info(connection: &Connection) -> Result<Option<ScanInfo>> {
+    let row = connection
+        .query_row(
+            "SELECT algorithm, parameters, timestamp, total_files FROM scan_info WHERE id = 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    row.map(|(algorithm, parameters, timestamp, total_files)| {
+        Ok(ScanInfo {
+            algorithm,
+            parameters: serde_json::from_str(&parameters)?,
+            timestamp,
+            total_files: total_files as usize,
+        })
+    })
+    .transpose()
+}
+
+/// `IMAGE_COLUMNS` の行をエントリに変換
+fn read_entry(row: &Row<'_>) -> rusqlite::Result<HashEntry> {
+    let metadata: String = row.get(3)?;
+    let metadata = serde_json::from_str(&metadata).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(HashEntry {
+        file_path: row.get(0)?,
+        hash: row.get(1)?,
+        hash_bits: row.get::<_, i64>(2)? as u64,
+        metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::persistence::test_metadata;
+    use tempfile::TempDir;
+
+    fn metadata(file_size: u64) -> ProcessingMetadata {
+        ProcessingMetadata {
+            content_hash: Some("blake3:00ff".to_string()),
+            ..test_metadata(file_size)
+        }
+    }
+
+    fn result(
+        path: &str,
+        hash: &str,
+        file_size: u64,
+    ) -> (PathBuf, String, String, u64, ProcessingMetadata) {
+        (
+            PathBuf::from(path),
+            hash.to_string(),
+            "dct".to_string(),
+            u64::MAX - file_size,
+            metadata(file_size),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_persistence_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/hashes.sqlite");
+        let persistence = SqliteHashPersistence::new(&path);
+
+        persistence
+            .set_scan_info(
+                "scan".to_string(),
+                serde_json::json!({ "algorithm": "dct" }),
+            )
+            .await
+            .unwrap();
+        persistence
+            .store_batch(&[result("/b.jpg", "bb", 200), result("/a.jpg", "aa", 100)])
+            .await
+            .unwrap();
+        // 同じパスは上書きされる
+        persistence
+            .store_batch(&[result("/b.jpg", "cc", 300)])
+            .await
+            .unwrap();
+        persistence.finalize().await.unwrap();
+
+        let loaded = SqliteHashPersistence::load(&path).unwrap();
+        assert_eq!(loaded.scan_info.algorithm, "scan");
+        assert_eq!(loaded.scan_info.parameters["algorithm"], "dct");
+        assert_eq!(loaded.scan_info.total_files, 2);
+        assert_eq!(loaded.images.len(), 2);
+        assert_eq!(loaded.images[0].file_path, "/a.jpg");
+        assert_eq!(loaded.images[0].hash_bits, u64::MAX - 100);
+        assert_eq!(loaded.images[0].metadata, metadata(100));
+        assert_eq!(loaded.images[1].hash, "cc");
+
+        let entries =
+            SqliteHashPersistence::load_entries(&path, ["/b.jpg", "/missing.jpg"]).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].metadata.file_size, 300);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_persistence_schema_and_indexes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.db");
+        let persistence = SqliteHashPersistence::new(&path);
+        persistence
+            .set_scan_info("scan".to_string(), serde_json::json!({}))
+            .await
+            .unwrap();
+        persistence
+            .store_batch(&[result("/a.jpg", "aa", 100), result("/b.jpg", "aa", 100)])
+            .await
+            .unwrap();
+        persistence.finalize().await.unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let mut statement = connection
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'images' ORDER BY name")
+            .unwrap();
+        let indexes: Vec<String> = statement
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            indexes,
+            vec![
+                "idx_images_file_path",
+                "idx_images_file_size",
+                "idx_images_hash"
+            ]
+        );
+
+        // 検索に使う値は列として直接問い合わせられる
+        let same_hash: i64 = connection
+            .query_row(
+                "SELECT COUNT(*) FROM images WHERE hash = 'aa' AND file_size = 100",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(same_hash, 2);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_persistence_replaces_existing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hashes.sqlite");
+        std::fs::write(&path, "not a database").unwrap();
+
+        let persistence = SqliteHashPersistence::new(&path);
+        persistence
+            .set_scan_info("scan".to_string(), serde_json::json!({}))
+            .await
+            .unwrap();
+        persistence.finalize().await.unwrap();
+
+        let loaded = SqliteHashPersistence::load(&path).unwrap();
+        assert!(loaded.images.is_empty());
+        assert_eq!(loaded.scan_info.total_files, 0);
+    }
+}
@@ -0,0 +1,7 @@
+// 実行計画
+// processコマンドの操作（保持・移動・削除）を事前に確定し、レビュー後にそのまま実行
+
+pub mod process_plan;
+
+// 公開API
+pub use process_plan::{format_bytes, PlanAction, PlanSummary, PlannedFile, ProcessPlan};
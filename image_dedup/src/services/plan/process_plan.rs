@@ -0,0 +1,362 @@
+// ProcessPlan - processコマンドが実行するファイル操作の計画
+
+use crate::core::ProcessingMetadata;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// ファイルに対して計画された操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanAction {
+    /// オリジナルとして残す
+    Keep,
+    /// 移動先ディレクトリへ移動
+    Move,
+    /// 削除
+    Delete,
+    /// ゴミ箱へ移動
+    Trash,
+    /// 保持するファイルへのハードリンクに置き換え
+    Hardlink,
+    /// 保持するファイルへのシンボリックリンクに置き換え
+    Symlink,
+    /// 保持するファイルのreflinkに置き換え
+    Reflink,
+}
+
+impl PlanAction {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Keep => "keep",
+            Self::Move => "move",
+            Self::Delete => "delete",
+            Self::Trash => "trash",
+            Self::Hardlink => "hardlink",
+            Self::Symlink => "symlink",
+            Self::Reflink => "reflink",
+        }
+    }
+}
+
+/// 計画の1ファイル分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedFile {
+    pub group_id: usize,
+    pub action: PlanAction,
+    pub path: PathBuf,
+    /// 移動先のパス、またはリンク先（シンボリックリンクの場合はリンクに書き込む内容）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<PathBuf>,
+    /// 計画作成時のファイルサイズ（ファイルが存在しなかった場合は `None`）
+    pub file_size: Option<u64>,
+    /// 計画作成時の更新日時（UNIXエポックからのミリ秒）
+    pub modified_time_ms: Option<u64>,
+}
+
+impl PlannedFile {
+    /// 現在のファイルの状態を記録して作成
+    pub fn new(
+        group_id: usize,
+        action: PlanAction,
+        path: PathBuf,
+        destination: Option<PathBuf>,
+    ) -> Self {
+        let metadata = std::fs::metadata(&path).ok();
+        Self {
+            group_id,
+            action,
+            path,
+            destination,
+            file_size: metadata.as_ref().map(|m| m.len()),
+            modified_time_ms: metadata
+                .as_ref()
+                .and_then(ProcessingMetadata::modified_time_ms_of),
+        }
+    }
+
+    /// 計画作成時からファイルが変更されていないか確認
+    pub fn verify_unchanged(&self) -> Result<()> {
+        let metadata = std::fs::metadata(&self.path)
+            .map_err(|e| anyhow::anyhow!("File is not accessible: {e}"))?;
+
+        if self.file_size.is_some_and(|size| size != metadata.len()) {
+            anyhow::bail!("File size changed since the plan was created");
+        }
+        if self.modified_time_ms.is_some()
+            && self.modified_time_ms != ProcessingMetadata::modified_time_ms_of(&metadata)
+        {
+            anyhow::bail!("File was modified since the plan was created");
+        }
+        Ok(())
+    }
+}
+
+/// 計画の集計
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSummary {
+    pub keep: usize,
+    pub move_count: usize,
+    pub delete: usize,
+    #[serde(default)]
+    pub trash: usize,
+    /// リンクに置き換えるファイル数
+    #[serde(default)]
+    pub link: usize,
+    /// 保持以外の操作によって元の場所から取り除かれるバイト数
+    pub bytes_reclaimed: u64,
+}
+
+/// processコマンドの実行計画
+///
+/// `--dry-run` でJSONとして書き出し、レビュー後に `--plan` でそのまま実行できる
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessPlan {
+    pub created_at: DateTime<Utc>,
+    /// 計画の元になった重複リスト
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_list: Option<PathBuf>,
+    /// 集計（読み込み時はファイル一覧から再計算する）
+    #[serde(default)]
+    pub summary: PlanSummary,
+    pub files: Vec<PlannedFile>,
+}
+
+impl ProcessPlan {
+    /// ファイル一覧から計画を作成
+    pub fn new(duplicate_list: Option<PathBuf>, files: Vec<PlannedFile>) -> Self {
+        let mut plan = Self {
+            created_at: Utc::now(),
+            duplicate_list,
+            summary: PlanSummary::default(),
+            files,
+        };
+        plan.summary = plan.summarize();
+        plan
+    }
+
+    fn summarize(&self) -> PlanSummary {
+        let mut summary = PlanSummary::default();
+        for file in &self.files {
+            match file.action {
+                PlanAction::Keep => summary.keep += 1,
+                PlanAction::Move => summary.move_count += 1,
+                PlanAction::Delete => summary.delete += 1,
+                PlanAction::Trash => summary.trash += 1,
+                PlanAction::Hardlink | PlanAction::Symlink | PlanAction::Reflink => {
+                    summary.link += 1
+                }
+            }
+            if file.action != PlanAction::Keep {
+                summary.bytes_reclaimed += file.file_size.unwrap_or(0);
+            }
+        }
+        summary
+    }
+
+    /// 保持以外の操作を行うファイル
+    pub fn operations(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files
+            .iter()
+            .filter(|file| file.action != PlanAction::Keep)
+    }
+
+    /// グループで保持するファイルが計画作成時から変更されていないか確認
+    ///
+    /// 保持するファイルが無い、または変更・削除されたグループの重複ファイルは処理してはならない
+    pub fn verify_keeper(&self, group_id: usize) -> Result<()> {
+        let keeper = self
+            .files
+            .iter()
+            .find(|file| file.group_id == group_id && file.action == PlanAction::Keep)
+            .ok_or_else(|| anyhow::anyhow!("Group {group_id} has no file to keep"))?;
+        keeper
+            .verify_unchanged()
+            .map_err(|e| anyhow::anyhow!("Kept file {}: {e}", keeper.path.display()))
+    }
+
+    /// JSONファイルに書き出す
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write plan {}: {}", path.display(), e))
+    }
+
+    /// JSONファイルから読み込む
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read plan {}: {}", path.display(), e))?;
+        let mut plan: Self = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse plan {}: {}", path.display(), e))?;
+        plan.summary = plan.summarize();
+        Ok(plan)
+    }
+
+    /// 人が確認するための表形式の文字列
+    pub fn render_table(&self) -> String {
+        let mut table = format!("{:>5}  {:<8}  {:>10}  PATH\n", "GROUP", "ACTION", "SIZE");
+        for file in &self.files {
+            let size = file
+                .file_size
+                .map(format_bytes)
+                .unwrap_or_else(|| "missing".to_string());
+            table.push_str(&format!(
+                "{:>5}  {:<8}  {:>10}  {}",
+                file.group_id,
+                file.action.label(),
+                size,
+                file.path.display()
+            ));
+            if let Some(destination) = &file.destination {
+                table.push_str(&format!(" → {}", destination.display()));
+            }
+            table.push('\n');
+        }
+        table.push_str(&format!(
+            "\nkeep: {}, move: {}, delete: {}, trash: {}, link: {}, reclaimed: {}\n",
+            self.summary.keep,
+            self.summary.move_count,
+            self.summary.delete,
+            self.summary.trash,
+            self.summary.link,
+            format_bytes(self.summary.bytes_reclaimed)
+        ));
+        table
+    }
+}
+
+/// バイト数を読みやすい単位に変換（1024単位）
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_plan(temp_dir: &TempDir) -> ProcessPlan {
+        let keep = temp_dir.path().join("keep.jpg");
+        let copy = temp_dir.path().join("copy.jpg");
+        std::fs::write(&keep, vec![0u8; 2048]).unwrap();
+        std::fs::write(&copy, vec![0u8; 1000]).unwrap();
+
+        ProcessPlan::new(
+            None,
+            vec![
+                PlannedFile::new(0, PlanAction::Keep, keep, None),
+                PlannedFile::new(
+                    0,
+                    PlanAction::Move,
+                    copy,
+                    Some(temp_dir.path().join("moved/group_0/copy.jpg")),
+                ),
+                PlannedFile::new(
+                    1,
+                    PlanAction::Delete,
+                    temp_dir.path().join("missing.jpg"),
+                    None,
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_plan_summary() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan = sample_plan(&temp_dir);
+
+        assert_eq!(
+            plan.summary,
+            PlanSummary {
+                keep: 1,
+                move_count: 1,
+                delete: 1,
+                trash: 0,
+                link: 0,
+                bytes_reclaimed: 1000,
+            }
+        );
+        assert_eq!(plan.operations().count(), 2);
+        assert_eq!(plan.files[2].file_size, None);
+    }
+
+    #[test]
+    fn test_plan_write_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan = sample_plan(&temp_dir);
+        let path = temp_dir.path().join("plan.json");
+        plan.write(&path).unwrap();
+
+        // 集計は読み込み時に再計算される
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["files"][1]["action"], "move");
+        json["summary"]["bytes_reclaimed"] = serde_json::json!(0);
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let loaded = ProcessPlan::load(&path).unwrap();
+        assert_eq!(loaded.files, plan.files);
+        assert_eq!(loaded.summary, plan.summary);
+    }
+
+    #[test]
+    fn test_verify_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan = sample_plan(&temp_dir);
+
+        assert!(plan.files[1].verify_unchanged().is_ok());
+        std::fs::write(&plan.files[1].path, b"edited").unwrap();
+        assert!(plan.files[1].verify_unchanged().is_err());
+        assert!(plan.files[2].verify_unchanged().is_err());
+    }
+
+    #[test]
+    fn test_verify_keeper() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan = sample_plan(&temp_dir);
+
+        assert!(plan.verify_keeper(0).is_ok());
+        // グループ1には保持するファイルが無い
+        assert!(plan.verify_keeper(1).is_err());
+
+        std::fs::write(&plan.files[0].path, b"edited").unwrap();
+        let error = plan.verify_keeper(0).unwrap_err();
+        assert!(error.to_string().contains("keep.jpg"));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let table = sample_plan(&temp_dir).render_table();
+
+        assert!(table.contains("keep"));
+        assert!(table.contains("copy.jpg → "));
+        assert!(table.contains("missing"));
+        assert!(table.contains("reclaimed: 1000 B"));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
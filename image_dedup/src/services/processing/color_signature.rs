@@ -0,0 +1,196 @@
+// ColorSignature - 色違いのバリエーションを区別するための粗いHSVヒストグラム
+// 知覚ハッシュは輝度のみを使うため、配色だけが異なる画像を区別できない
+
+use anyhow::Result;
+use image::DynamicImage;
+
+/// 色相の分割数
+const HUE_BINS: usize = 12;
+/// 有彩色の彩度の分割数
+const SATURATION_BINS: usize = 2;
+/// 有彩色の明度の分割数
+const VALUE_BINS: usize = 2;
+/// 無彩色（白・灰・黒）の明度の分割数
+const ACHROMATIC_BINS: usize = 4;
+/// これ未満の彩度は無彩色とみなす
+const ACHROMATIC_SATURATION: f32 = 0.2;
+/// これ未満の明度は無彩色とみなす（暗い画素の色相は不安定なため）
+const ACHROMATIC_VALUE: f32 = 0.15;
+/// ヒストグラムを計算する前に縮小する長辺
+const SAMPLE_SIZE: u32 = 64;
+
+/// 色の署名のビン数
+pub const COLOR_SIGNATURE_BINS: usize = HUE_BINS * SATURATION_BINS * VALUE_BINS + ACHROMATIC_BINS;
+
+/// 画像の色の分布（各ビンの割合を0-255に量子化したHSVヒストグラム）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorSignature {
+    bins: Vec<u8>,
+}
+
+impl ColorSignature {
+    /// 画像から色の署名を計算（完全に透明な画素は数えない）
+    pub fn from_image(image: &DynamicImage) -> Self {
+        // 小さい画像は拡大すると透明な画素と色が混ざるため、そのまま使う
+        let sample = if image.width() > SAMPLE_SIZE || image.height() > SAMPLE_SIZE {
+            image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgba8()
+        } else {
+            image.to_rgba8()
+        };
+
+        let mut counts = [0u32; COLOR_SIGNATURE_BINS];
+        let mut total = 0u32;
+        for pixel in sample.pixels() {
+            let [r, g, b, a] = pixel.0;
+            if a == 0 {
+                continue;
+            }
+            counts[bin_of(r, g, b)] += 1;
+            total += 1;
+        }
+
+        let bins = counts
+            .iter()
+            .map(|&count| {
+                if total == 0 {
+                    0
+                } else {
+                    (f64::from(count) * 255.0 / f64::from(total)).round() as u8
+                }
+            })
+            .collect();
+        Self { bins }
+    }
+
+    /// 16進文字列として取得
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.bins)
+    }
+
+    /// 16進文字列から復元
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bins = hex::decode(hex)?;
+        if bins.len() != COLOR_SIGNATURE_BINS {
+            anyhow::bail!(
+                "Color signature must have {} bins, got {}",
+                COLOR_SIGNATURE_BINS,
+                bins.len()
+            );
+        }
+        Ok(Self { bins })
+    }
+
+    /// 2つの分布の差（全変動距離）。0.0が同じ配色、1.0が共通する色なし
+    pub fn distance(&self, other: &Self) -> f64 {
+        let difference: u32 = self
+            .bins
+            .iter()
+            .zip(&other.bins)
+            .map(|(&a, &b)| u32::from(a.abs_diff(b)))
+            .sum();
+        (f64::from(difference) / (2.0 * 255.0)).min(1.0)
+    }
+}
+
+/// RGBの画素が属するビン
+fn bin_of(r: u8, g: u8, b: u8) -> usize {
+    let (hue, saturation, value) = rgb_to_hsv(r, g, b);
+
+    if saturation < ACHROMATIC_SATURATION || value < ACHROMATIC_VALUE {
+        let level = ((value * ACHROMATIC_BINS as f32) as usize).min(ACHROMATIC_BINS - 1);
+        return HUE_BINS * SATURATION_BINS * VALUE_BINS + level;
+    }
+
+    let hue_bin = ((hue / 360.0 * HUE_BINS as f32) as usize).min(HUE_BINS - 1);
+    let saturation_bin = usize::from(saturation >= 0.6);
+    let value_bin = usize::from(value >= 0.6);
+    (hue_bin * SATURATION_BINS + saturation_bin) * VALUE_BINS + value_bin
+}
+
+/// RGBを（色相0-360, 彩度0-1, 明度0-1）に変換
+fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let (r, g, b) = (
+        f32::from(r) / 255.0,
+        f32::from(g) / 255.0,
+        f32::from(b) / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue, saturation, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    /// 白い背景に、指定した色の円を描いた画像
+    fn artwork(color: [u8; 3], width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let dx = x as f32 / width as f32 - 0.5;
+            let dy = y as f32 / height as f32 - 0.5;
+            if dx * dx + dy * dy < 0.1 {
+                Rgb(color)
+            } else {
+                Rgb([250, 250, 245])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_rgb_to_hsv() {
+        assert_eq!(rgb_to_hsv(255, 0, 0), (0.0, 1.0, 1.0));
+        assert_eq!(rgb_to_hsv(0, 255, 0).0, 120.0);
+        assert_eq!(rgb_to_hsv(0, 0, 255).0, 240.0);
+        assert_eq!(rgb_to_hsv(128, 128, 128).1, 0.0);
+    }
+
+    #[test]
+    fn test_recoloured_variant_is_distant() {
+        let red = ColorSignature::from_image(&artwork([220, 30, 30], 200, 160));
+        let blue = ColorSignature::from_image(&artwork([30, 60, 220], 200, 160));
+        let resized_red = ColorSignature::from_image(&artwork([220, 30, 30], 90, 72));
+
+        assert!(red.distance(&blue) > 0.25, "{}", red.distance(&blue));
+        assert!(red.distance(&resized_red) < 0.05);
+        assert_eq!(red.distance(&red), 0.0);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let signature = ColorSignature::from_image(&artwork([30, 160, 60], 64, 64));
+        let hex = signature.to_hex();
+
+        assert_eq!(hex.len(), COLOR_SIGNATURE_BINS * 2);
+        assert_eq!(ColorSignature::from_hex(&hex).unwrap(), signature);
+        assert!(ColorSignature::from_hex("00ff").is_err());
+    }
+
+    #[test]
+    fn test_transparent_pixels_are_ignored() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, _| {
+            if x < 16 {
+                Rgba([220, 30, 30, 255])
+            } else {
+                Rgba([30, 60, 220, 0])
+            }
+        }));
+        let opaque_red = DynamicImage::ImageRgb8(RgbImage::from_pixel(32, 32, Rgb([220, 30, 30])));
+
+        let distance =
+            ColorSignature::from_image(&image).distance(&ColorSignature::from_image(&opaque_red));
+        assert_eq!(distance, 0.0);
+    }
+}
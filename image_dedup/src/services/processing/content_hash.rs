@@ -0,0 +1,54 @@
+// ContentHash - バイト単位で一致するファイルを検出するための内容ハッシュ
+
+use crate::core::types::ContentHashAlgorithm;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::Path;
+
+/// ファイル内容のハッシュを `<アルゴリズム>:<16進>` 形式で計算
+///
+/// 接頭辞を付けることで、異なるアルゴリズムのハッシュが一致と判定されることを防ぐ
+pub fn compute_content_hash(path: &Path, algorithm: ContentHashAlgorithm) -> Result<String> {
+    let mut file = File::open(path)?;
+    let digest = match algorithm {
+        ContentHashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(&mut file)?;
+            hasher.finalize().to_hex().to_string()
+        }
+        ContentHashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            hex::encode(hasher.finalize())
+        }
+    };
+    Ok(format!("{}:{}", algorithm.name(), digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compute_content_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.jpg");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            compute_content_hash(&path, ContentHashAlgorithm::Blake3).unwrap(),
+            format!("blake3:{}", blake3::hash(b"abc").to_hex())
+        );
+        assert_eq!(
+            compute_content_hash(&path, ContentHashAlgorithm::Sha256).unwrap(),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(compute_content_hash(
+            &temp_dir.path().join("missing.jpg"),
+            ContentHashAlgorithm::Blake3
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,186 @@
+// ExactCandidates - 完全一致の候補を段階的に絞り込む（サイズ → 先頭・末尾の部分ハッシュ）
+// 一致する相手がいないことが確定したファイルは内容全体のハッシュを計算しない
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// 部分ハッシュで読み込む先頭・末尾それぞれのバイト数
+pub const PARTIAL_HASH_BYTES: u64 = 4096;
+
+/// 絞り込みの各段階で除外したファイル数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExactCandidateReport {
+    /// 発見したファイル数
+    pub discovered: usize,
+    /// サイズが他のどのファイルとも異なるため除外した数
+    pub unique_size: usize,
+    /// 先頭・末尾の部分ハッシュが他と異なるため除外した数
+    pub unique_partial_hash: usize,
+    /// 内容全体のハッシュを計算する候補の数
+    pub candidates: usize,
+}
+
+/// 完全一致の候補ファイルと絞り込みの集計
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExactCandidates {
+    pub files: Vec<String>,
+    pub report: ExactCandidateReport,
+}
+
+/// `(パス, サイズ)` の一覧から完全一致の候補を絞り込む
+///
+/// 同じサイズのファイルが他にない場合、続いて先頭・末尾の部分ハッシュが一致する
+/// ファイルが他にない場合に除外する。部分ハッシュを計算できなかったファイルは
+/// 候補に残し、内容全体のハッシュ計算時のエラーとして報告させる。
+/// 候補は入力の順序を保つ
+pub fn filter_exact_candidates(files: Vec<(String, u64)>) -> ExactCandidates {
+    let mut report = ExactCandidateReport {
+        discovered: files.len(),
+        ..Default::default()
+    };
+
+    let mut size_counts: HashMap<u64, usize> = HashMap::new();
+    for (_, size) in &files {
+        *size_counts.entry(*size).or_default() += 1;
+    }
+    let same_size: Vec<(String, u64)> = files
+        .into_iter()
+        .filter(|(_, size)| size_counts[size] > 1)
+        .collect();
+    report.unique_size = report.discovered - same_size.len();
+
+    let partial_hashes: Vec<Option<(u64, String)>> = same_size
+        .iter()
+        .map(|(path, size)| {
+            partial_hash(Path::new(path), *size)
+                .ok()
+                .map(|hash| (*size, hash))
+        })
+        .collect();
+    let mut partial_counts: HashMap<&(u64, String), usize> = HashMap::new();
+    for key in partial_hashes.iter().flatten() {
+        *partial_counts.entry(key).or_default() += 1;
+    }
+
+    let candidates: Vec<String> = same_size
+        .iter()
+        .zip(&partial_hashes)
+        .filter(|(_, key)| key.as_ref().is_none_or(|key| partial_counts[key] > 1))
+        .map(|((path, _), _)| path.clone())
+        .collect();
+    report.unique_partial_hash = same_size.len() - candidates.len();
+    report.candidates = candidates.len();
+
+    ExactCandidates {
+        files: candidates,
+        report,
+    }
+}
+
+/// ファイルの先頭と末尾（各 `PARTIAL_HASH_BYTES` バイト）のBLAKE3ハッシュ
+fn partial_hash(path: &Path, size: u64) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+
+    let mut buffer = Vec::with_capacity(PARTIAL_HASH_BYTES as usize);
+    (&mut file)
+        .take(PARTIAL_HASH_BYTES)
+        .read_to_end(&mut buffer)?;
+    hasher.update(&buffer);
+
+    if size > PARTIAL_HASH_BYTES * 2 {
+        buffer.clear();
+        file.seek(SeekFrom::Start(size - PARTIAL_HASH_BYTES))?;
+        file.take(PARTIAL_HASH_BYTES).read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    } else if size > PARTIAL_HASH_BYTES {
+        buffer.clear();
+        file.read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(temp_dir: &TempDir, name: &str, content: &[u8]) -> (String, u64) {
+        let path = temp_dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        (path.to_string_lossy().to_string(), content.len() as u64)
+    }
+
+    #[test]
+    fn test_filter_exact_candidates() {
+        let temp_dir = TempDir::new().unwrap();
+        let large = vec![1u8; 20_000];
+        let mut middle_differs = large.clone();
+        middle_differs[10_000] = 2;
+        let mut tail_differs = large.clone();
+        tail_differs[19_999] = 2;
+
+        let files = vec![
+            write(&temp_dir, "unique.jpg", b"only one of this size"),
+            write(&temp_dir, "a.jpg", &large),
+            write(&temp_dir, "b.jpg", &middle_differs),
+            write(&temp_dir, "c.jpg", &tail_differs),
+            write(&temp_dir, "d.jpg", &large),
+        ];
+
+        let candidates = filter_exact_candidates(files.clone());
+
+        // 中央だけ異なるファイルは部分ハッシュでは区別できないため候補に残る
+        let names: Vec<&str> = candidates
+            .files
+            .iter()
+            .map(|path| path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(names, vec!["a.jpg", "b.jpg", "d.jpg"]);
+        assert_eq!(
+            candidates.report,
+            ExactCandidateReport {
+                discovered: 5,
+                unique_size: 1,
+                unique_partial_hash: 1,
+                candidates: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_small_files_are_hashed_whole() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = vec![
+            write(&temp_dir, "a.jpg", &[0u8; 6000]),
+            write(&temp_dir, "b.jpg", &[[0u8; 5999].as_slice(), &[1]].concat()),
+        ];
+
+        let candidates = filter_exact_candidates(files);
+
+        assert!(candidates.files.is_empty());
+        assert_eq!(candidates.report.unique_partial_hash, 2);
+    }
+
+    #[test]
+    fn test_unreadable_files_stay_candidates() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing.jpg");
+        let files = vec![
+            (missing.to_string_lossy().to_string(), 3),
+            write(&temp_dir, "a.jpg", b"abc"),
+        ];
+
+        let candidates = filter_exact_candidates(files);
+
+        assert_eq!(candidates.files.len(), 1);
+        assert!(candidates.files[0].ends_with("missing.jpg"));
+        assert_eq!(candidates.report.unique_partial_hash, 1);
+    }
+}
@@ -1,7 +1,13 @@
 // 画像処理機能
 // 単一画像ファイルの読み込み、ハッシュ生成、メタデータ収集
 
+pub mod color_signature;
+pub mod content_hash;
+pub mod exact_candidates;
 pub mod worker;
 
 // 公開API
-pub use worker::process_single_file;
+pub use color_signature::ColorSignature;
+pub use content_hash::compute_content_hash;
+pub use exact_candidates::{filter_exact_candidates, ExactCandidateReport, ExactCandidates};
+pub use worker::{process_single_file, WorkerOptions, EXACT_ONLY_ALGORITHM};
@@ -1,17 +1,65 @@
 // Worker - 単一ファイル処理機能
 
-use crate::core::types::{ProcessingMetadata, ProcessingOutcome};
+use super::color_signature::ColorSignature;
+use super::content_hash::compute_content_hash;
+use crate::core::types::{ContentHashAlgorithm, ProcessingMetadata, ProcessingOutcome};
+use crate::core::{CancellationToken, ProcessingConfig};
 use crate::image_loader::ImageLoaderBackend;
-use crate::perceptual_hash::PerceptualHashBackend;
+use crate::image_preprocessor::PreprocessingPipeline;
+use crate::perceptual_hash::{HashResult, PerceptualHashBackend};
+use anyhow::Context;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 
+/// 完全一致のみを検出する場合に記録するアルゴリズム名
+pub const EXACT_ONLY_ALGORITHM: &str = "Exact";
+
+/// ワーカーの処理オプション
+#[derive(Debug, Clone, Default)]
+pub struct WorkerOptions {
+    /// 計算する内容ハッシュ（`None` なら計算しない）
+    pub content_hash: Option<ContentHashAlgorithm>,
+    /// 画像をデコードせず内容ハッシュのみを計算する
+    pub exact_only: bool,
+    /// 回転・反転した8通りの知覚ハッシュも計算する
+    pub rotation_invariant: bool,
+    /// 色違いを区別するための色の署名も計算する
+    pub color_signature: bool,
+    /// ハッシュ計算前に画像へ適用する前処理（ワーカー間で共有する）
+    pub preprocessor: Arc<PreprocessingPipeline>,
+    /// キャンセル通知（キャンセルされたワーカーは新しいファイルを受け取らない）
+    pub cancel: CancellationToken,
+}
+
+impl WorkerOptions {
+    /// 処理設定からオプションを作成
+    pub fn from_config<C: ProcessingConfig + ?Sized>(config: &C) -> Self {
+        Self {
+            content_hash: config.content_hash_algorithm(),
+            exact_only: config.exact_only(),
+            rotation_invariant: config.rotation_invariant(),
+            color_signature: config.color_signature(),
+            preprocessor: Arc::new(PreprocessingPipeline::from_config(&config.preprocessing())),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// キャンセル通知を設定
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
 /// 単一ファイルの処理
 pub async fn process_single_file<L, H>(
     loader: &L,
     hasher: &H,
     file_path: &str,
     _worker_id: usize,
+    options: &WorkerOptions,
 ) -> ProcessingOutcome
 where
     L: ImageLoaderBackend,
@@ -20,22 +68,95 @@ where
     let start_time = Instant::now();
 
     let result = async {
-        // 画像読み込み
+        if options.exact_only {
+            return hash_content_only(file_path, options, start_time).await;
+        }
+
+        // 画像読み込みと前処理（ハッシュと色の署名は前処理した画像から計算し、記録する寸法は読み込み時のもの）
         let path = Path::new(file_path);
-        let load_result = loader.load_from_path(path).await?;
+        let load_result = loader
+            .load_preprocessed(path, options.preprocessor.clone())
+            .await?;
+        let image = load_result.image;
+        let image_dimensions = load_result.loaded_dimensions;
 
-        // ファイルサイズを取得
-        let file_size = std::fs::metadata(file_path)?.len();
+        // ファイルサイズと更新日時を取得
+        let file_metadata = std::fs::metadata(file_path)?;
+        let file_size = file_metadata.len();
 
-        // ハッシュ生成
-        let hash_result = hasher.generate_hash(&load_result.image).await?;
+        // ハッシュ生成（回転・反転のバリアントは先頭が元画像のハッシュ）
+        let (hash_result, dihedral_variants) = if options.rotation_invariant {
+            let variants = hasher.generate_dihedral_hashes(&image).await?;
+            let identity = variants
+                .first()
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Hasher returned no dihedral hashes"))?;
+            (identity, Some(variants))
+        } else {
+            (hasher.generate_hash(&image).await?, None)
+        };
+        let segment_hashes = hasher.generate_segment_hashes(&image).await?;
+        let segment_hashes = (!segment_hashes.is_empty())
+            .then(|| segment_hashes.iter().map(HashResult::to_hex).collect());
+        // アンサンブルの他のアルゴリズムも、メインと同じく回転・反転のバリアントごとに計算する
+        let (ensemble_hashes, ensemble_dihedral_hashes) = match &dihedral_variants {
+            Some(variants) => {
+                let hashes = hasher
+                    .generate_ensemble_dihedral_hashes(&image, variants)
+                    .await?;
+                let identity: BTreeMap<String, String> = hashes
+                    .iter()
+                    .filter_map(|(name, variants)| Some((name.clone(), variants.first()?.to_hex())))
+                    .collect();
+                let all: BTreeMap<String, Vec<String>> = hashes
+                    .iter()
+                    .map(|(name, variants)| {
+                        (
+                            name.clone(),
+                            variants.iter().map(HashResult::to_hex).collect(),
+                        )
+                    })
+                    .collect();
+                (identity, all)
+            }
+            None => {
+                let hashes = hasher
+                    .generate_ensemble_hashes(&image, &hash_result)
+                    .await?;
+                let identity = hashes
+                    .iter()
+                    .map(|(name, hash)| (name.clone(), hash.to_hex()))
+                    .collect();
+                (identity, BTreeMap::new())
+            }
+        };
+        let ensemble_hashes = (!ensemble_hashes.is_empty()).then_some(ensemble_hashes);
+        let ensemble_dihedral_hashes =
+            (!ensemble_dihedral_hashes.is_empty()).then_some(ensemble_dihedral_hashes);
+        let dihedral_hashes =
+            dihedral_variants.map(|variants| variants.iter().map(HashResult::to_hex).collect());
+        let color_signature = options
+            .color_signature
+            .then(|| ColorSignature::from_image(&image).to_hex());
+        let content_hash = match options.content_hash {
+            Some(algorithm) => Some(content_hash_of(path, algorithm).await?),
+            None => None,
+        };
 
         // メタデータ作成
         let metadata = ProcessingMetadata {
             file_size,
             processing_time_ms: start_time.elapsed().as_millis().min(u64::MAX as u128) as u64,
-            image_dimensions: (load_result.image.width(), load_result.image.height()),
+            image_dimensions,
             was_resized: load_result.was_resized,
+            hash_size_bits: hash_result.hash_size_bits,
+            modified_time_ms: ProcessingMetadata::modified_time_ms_of(&file_metadata),
+            content_hash,
+            dihedral_hashes,
+            segment_hashes,
+            ensemble_hashes,
+            ensemble_dihedral_hashes,
+            color_signature,
         };
 
         anyhow::Result::<(String, String, u64, ProcessingMetadata)>::Ok((
@@ -61,3 +182,200 @@ where
         },
     }
 }
+
+/// 画像をデコードせず内容ハッシュのみを計算（知覚ハッシュは空になる）
+async fn hash_content_only(
+    file_path: &str,
+    options: &WorkerOptions,
+    start_time: Instant,
+) -> anyhow::Result<(String, String, u64, ProcessingMetadata)> {
+    let path = Path::new(file_path);
+    let file_metadata = std::fs::metadata(path)?;
+    let content_hash = content_hash_of(path, options.content_hash.unwrap_or_default()).await?;
+
+    let metadata = ProcessingMetadata {
+        file_size: file_metadata.len(),
+        processing_time_ms: start_time.elapsed().as_millis().min(u64::MAX as u128) as u64,
+        // デコードしないため寸法は不明（オリジナル選択では画像ヘッダーから補完する）
+        image_dimensions: (0, 0),
+        was_resized: false,
+        hash_size_bits: 0,
+        modified_time_ms: ProcessingMetadata::modified_time_ms_of(&file_metadata),
+        content_hash: Some(content_hash),
+        dihedral_hashes: None,
+        segment_hashes: None,
+        ensemble_hashes: None,
+        ensemble_dihedral_hashes: None,
+        color_signature: None,
+    };
+
+    Ok((String::new(), EXACT_ONLY_ALGORITHM.to_string(), 0, metadata))
+}
+
+/// ファイル全体を読むため、内容ハッシュはブロッキングスレッドで計算する
+async fn content_hash_of(path: &Path, algorithm: ContentHashAlgorithm) -> anyhow::Result<String> {
+    tokio::task::spawn_blocking({
+        let path = path.to_path_buf();
+        move || compute_content_hash(&path, algorithm)
+    })
+    .await
+    .context("Failed to spawn blocking task for content hashing")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_loader::standard::StandardImageLoader;
+    use crate::perceptual_hash::dct_hash::DctHasher;
+    use crate::perceptual_hash::ensemble::EnsembleHasher;
+    use crate::perceptual_hash::factory::create_hasher;
+    use tempfile::TempDir;
+
+    const MINIMAL_PNG_DATA: &[u8] = &[
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F,
+        0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0A, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x00,
+        0x01, 0x00, 0x00, 0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00, 0x00, 0x00, 0x00, 0x49,
+        0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+    ];
+
+    async fn process(path: &Path, options: WorkerOptions) -> ProcessingOutcome {
+        process_single_file(
+            &StandardImageLoader::new(),
+            &DctHasher::new(8),
+            path.to_str().unwrap(),
+            0,
+            &options,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_content_hash_is_optional() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.png");
+        std::fs::write(&path, MINIMAL_PNG_DATA).unwrap();
+
+        let ProcessingOutcome::Success { metadata, .. } =
+            process(&path, WorkerOptions::default()).await
+        else {
+            unreachable!("Expected success");
+        };
+        assert_eq!(metadata.content_hash, None);
+
+        let options = WorkerOptions {
+            content_hash: Some(ContentHashAlgorithm::Sha256),
+            ..WorkerOptions::default()
+        };
+        let ProcessingOutcome::Success { hash, metadata, .. } = process(&path, options).await
+        else {
+            unreachable!("Expected success");
+        };
+        assert!(!hash.is_empty());
+        assert!(metadata.content_hash.unwrap().starts_with("sha256:"));
+    }
+
+    #[tokio::test]
+    async fn test_exact_only_skips_decoding() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("broken.jpg");
+        std::fs::write(&path, b"not a valid image").unwrap();
+
+        // デコードしないため、画像として読み込めないファイルも処理できる
+        let options = WorkerOptions {
+            exact_only: true,
+            ..WorkerOptions::default()
+        };
+        match process(&path, options).await {
+            ProcessingOutcome::Success {
+                hash,
+                algorithm,
+                metadata,
+                ..
+            } => {
+                assert!(hash.is_empty());
+                assert_eq!(algorithm, EXACT_ONLY_ALGORITHM);
+                assert_eq!(metadata.file_size, 17);
+                assert_eq!(
+                    metadata.content_hash,
+                    Some(format!(
+                        "blake3:{}",
+                        blake3::hash(b"not a valid image").to_hex()
+                    ))
+                );
+            }
+            ProcessingOutcome::Error { error, .. } => unreachable!("Expected success: {error}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotation_invariant_records_dihedral_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let image = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(48, 32, |x, y| {
+            image::Rgb([(x * 5) as u8, (y * 7) as u8, ((x * y) % 256) as u8])
+        }));
+        let original = temp_dir.path().join("original.png");
+        let rotated = temp_dir.path().join("rotated.png");
+        image.save(&original).unwrap();
+        image.rotate90().save(&rotated).unwrap();
+
+        let options = WorkerOptions {
+            rotation_invariant: true,
+            ..WorkerOptions::default()
+        };
+        let ProcessingOutcome::Success { hash, metadata, .. } = process(&original, options).await
+        else {
+            unreachable!("Expected success");
+        };
+        let variants = metadata.dihedral_hashes.unwrap();
+        assert_eq!(variants.len(), 8);
+        assert_eq!(variants[0], hash);
+
+        // 90度回転した画像のハッシュは元画像のRotate90バリアントと一致する
+        let ProcessingOutcome::Success { hash, .. } =
+            process(&rotated, WorkerOptions::default()).await
+        else {
+            unreachable!("Expected success");
+        };
+        assert_eq!(variants[1], hash);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_invariant_ensemble_records_every_variant() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("image.png");
+        image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(48, 32, |x, y| {
+            image::Rgb([(x * 5) as u8, (y * 7) as u8, ((x * y) % 256) as u8])
+        }))
+        .save(&path)
+        .unwrap();
+        let hasher = EnsembleHasher::new(vec![
+            ("dct".to_string(), create_hasher("dct").unwrap()),
+            ("average".to_string(), create_hasher("average").unwrap()),
+        ])
+        .unwrap();
+
+        let options = WorkerOptions {
+            rotation_invariant: true,
+            ..WorkerOptions::default()
+        };
+        let ProcessingOutcome::Success { metadata, .. } = process_single_file(
+            &StandardImageLoader::new(),
+            &hasher,
+            path.to_str().unwrap(),
+            0,
+            &options,
+        )
+        .await
+        else {
+            unreachable!("Expected success");
+        };
+
+        let ensemble = metadata.ensemble_hashes.unwrap();
+        let variants = metadata.ensemble_dihedral_hashes.unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants["dct"], metadata.dihedral_hashes.unwrap());
+        assert_eq!(variants["average"].len(), 8);
+        assert_eq!(variants["average"][0], ensemble["average"]);
+    }
+}
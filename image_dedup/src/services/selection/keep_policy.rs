@@ -0,0 +1,352 @@
+// KeepPolicy - 重複グループ内で残すファイル（オリジナル）の選択基準
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// オリジナルとして残すファイルの選択基準
+///
+/// 複数指定した場合は先頭から順に比較し、同順位の場合だけ次の基準で決める
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepPolicy {
+    /// ファイルサイズが最も大きい
+    Largest,
+    /// 解像度（幅×高さ）が最も高い
+    HighestResolution,
+    /// 更新日時が最も新しい
+    Newest,
+    /// 更新日時が最も古い
+    Oldest,
+    /// パスが最も短い
+    ShortestPath,
+    /// 指定ディレクトリ配下のファイルを優先
+    PreferDirectory(String),
+    /// 指定形式（拡張子）のファイルを優先
+    PreferFormat(String),
+}
+
+impl FromStr for KeepPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(prefix) = s.strip_prefix("dir:") {
+            if prefix.is_empty() {
+                return Err("dir: requires a directory prefix (e.g. dir:/photos/originals)".into());
+            }
+            return Ok(Self::PreferDirectory(prefix.to_string()));
+        }
+
+        if let Some(format) = s.strip_prefix("format:") {
+            let format = format.trim_start_matches('.').to_lowercase();
+            if format.is_empty() {
+                return Err("format: requires a file extension (e.g. format:png)".into());
+            }
+            return Ok(Self::PreferFormat(format));
+        }
+
+        match s {
+            "largest" => Ok(Self::Largest),
+            "resolution" => Ok(Self::HighestResolution),
+            "newest" => Ok(Self::Newest),
+            "oldest" => Ok(Self::Oldest),
+            "shortest-path" => Ok(Self::ShortestPath),
+            _ => Err(format!(
+                "Unknown keep policy '{s}'. Available: largest, resolution, newest, oldest, shortest-path, dir:<prefix>, format:<ext>"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for KeepPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Largest => write!(f, "largest"),
+            Self::HighestResolution => write!(f, "resolution"),
+            Self::Newest => write!(f, "newest"),
+            Self::Oldest => write!(f, "oldest"),
+            Self::ShortestPath => write!(f, "shortest-path"),
+            Self::PreferDirectory(prefix) => write!(f, "dir:{prefix}"),
+            Self::PreferFormat(format) => write!(f, "format:{format}"),
+        }
+    }
+}
+
+/// 選択の候補となるファイルの情報
+///
+/// 不明な項目は `None` とし、その基準では最も低い順位として扱う
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeepCandidate {
+    pub path: String,
+    pub file_size: Option<u64>,
+    pub dimensions: Option<(u32, u32)>,
+    pub modified_time_ms: Option<u64>,
+}
+
+impl KeepCandidate {
+    /// パスのみの候補を作成
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// ハッシュデータベースのメタデータ（JSON）から作成
+    ///
+    /// `file_size`・`image_dimensions`・`modified_time_ms` のうち記録されているものを使う。
+    /// `scan --exact-only` は画像をデコードしないため寸法を `(0, 0)` で記録しており、これは不明として扱う
+    pub fn from_metadata(path: impl Into<String>, metadata: Option<&serde_json::Value>) -> Self {
+        let field = |name: &str| metadata.and_then(|metadata| metadata.get(name));
+
+        Self {
+            path: path.into(),
+            file_size: field("file_size").and_then(|v| v.as_u64()),
+            dimensions: field("image_dimensions")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .filter(|&dimensions| dimensions != (0, 0)),
+            modified_time_ms: field("modified_time_ms").and_then(|v| v.as_u64()),
+        }
+    }
+
+    /// 不明な項目をファイルシステムから補完
+    ///
+    /// 解像度は画像ヘッダーのみを読み込んで取得する
+    pub fn fill_from_filesystem(mut self) -> Self {
+        if self.file_size.is_none() || self.modified_time_ms.is_none() {
+            if let Ok(metadata) = std::fs::metadata(&self.path) {
+                self.file_size.get_or_insert(metadata.len());
+                if self.modified_time_ms.is_none() {
+                    self.modified_time_ms =
+                        crate::core::ProcessingMetadata::modified_time_ms_of(&metadata);
+                }
+            }
+        }
+
+        if self.dimensions.is_none() {
+            self.dimensions = image::image_dimensions(&self.path).ok();
+        }
+
+        self
+    }
+
+    fn resolution(&self) -> Option<u64> {
+        self.dimensions
+            .map(|(width, height)| u64::from(width) * u64::from(height))
+    }
+
+    fn extension(&self) -> Option<String> {
+        Path::new(&self.path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+    }
+}
+
+impl KeepPolicy {
+    /// 2つの候補を比較（`Greater` の方が残す候補として優先）
+    fn compare(&self, a: &KeepCandidate, b: &KeepCandidate) -> Ordering {
+        match self {
+            Self::Largest => a.file_size.cmp(&b.file_size),
+            Self::HighestResolution => a.resolution().cmp(&b.resolution()),
+            Self::Newest => a.modified_time_ms.cmp(&b.modified_time_ms),
+            // 不明な日時は最も低い順位にするため、Someの中だけ逆順に比較
+            Self::Oldest => match (a.modified_time_ms, b.modified_time_ms) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            },
+            Self::ShortestPath => b.path.len().cmp(&a.path.len()),
+            Self::PreferDirectory(prefix) => {
+                let in_dir = |c: &KeepCandidate| Path::new(&c.path).starts_with(prefix);
+                in_dir(a).cmp(&in_dir(b))
+            }
+            Self::PreferFormat(format) => {
+                let matches = |c: &KeepCandidate| c.extension().as_deref() == Some(format.as_str());
+                matches(a).cmp(&matches(b))
+            }
+        }
+    }
+
+    /// 基準がファイルシステムからの補完を必要とするか
+    pub fn needs_file_info(&self) -> bool {
+        matches!(
+            self,
+            Self::Largest | Self::HighestResolution | Self::Newest | Self::Oldest
+        )
+    }
+}
+
+/// 選択基準に従って残すファイルのインデックスを決定
+///
+/// すべての基準で同順位の場合は先頭の候補を選ぶ。候補が空の場合は `None`
+pub fn select_keeper(candidates: &[KeepCandidate], policies: &[KeepPolicy]) -> Option<usize> {
+    let compare = |a: &KeepCandidate, b: &KeepCandidate| {
+        policies
+            .iter()
+            .map(|policy| policy.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    };
+
+    let mut best: Option<usize> = None;
+    for (i, candidate) in candidates.iter().enumerate() {
+        match best {
+            Some(current) if compare(candidate, &candidates[current]).is_le() => {}
+            _ => best = Some(i),
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(
+        path: &str,
+        file_size: Option<u64>,
+        dimensions: Option<(u32, u32)>,
+        modified_time_ms: Option<u64>,
+    ) -> KeepCandidate {
+        KeepCandidate {
+            path: path.to_string(),
+            file_size,
+            dimensions,
+            modified_time_ms,
+        }
+    }
+
+    fn parse(policies: &str) -> Vec<KeepPolicy> {
+        policies.split(',').map(|p| p.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_keep_policies() {
+        assert_eq!(
+            parse("largest,resolution,newest,oldest,shortest-path"),
+            vec![
+                KeepPolicy::Largest,
+                KeepPolicy::HighestResolution,
+                KeepPolicy::Newest,
+                KeepPolicy::Oldest,
+                KeepPolicy::ShortestPath,
+            ]
+        );
+        assert_eq!(
+            parse("dir:/photos/originals,format:.PNG"),
+            vec![
+                KeepPolicy::PreferDirectory("/photos/originals".to_string()),
+                KeepPolicy::PreferFormat("png".to_string()),
+            ]
+        );
+        assert!("biggest".parse::<KeepPolicy>().is_err());
+        assert!("dir:".parse::<KeepPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for policy in parse("largest,resolution,newest,oldest,shortest-path,dir:/a,format:png") {
+            assert_eq!(policy.to_string().parse::<KeepPolicy>().unwrap(), policy);
+        }
+    }
+
+    #[test]
+    fn test_select_keeper_single_policies() {
+        let candidates = vec![
+            candidate("/a/long/path/one.jpg", Some(100), Some((100, 100)), Some(3)),
+            candidate("/b/two.png", Some(300), Some((50, 50)), Some(1)),
+            candidate("/a/three.jpg", Some(200), Some((200, 200)), Some(2)),
+        ];
+
+        let select = |policies: &str| select_keeper(&candidates, &parse(policies));
+
+        assert_eq!(select("largest"), Some(1));
+        assert_eq!(select("resolution"), Some(2));
+        assert_eq!(select("newest"), Some(0));
+        assert_eq!(select("oldest"), Some(1));
+        assert_eq!(select("shortest-path"), Some(1));
+        assert_eq!(select("dir:/a"), Some(0));
+        assert_eq!(select("format:png"), Some(1));
+    }
+
+    #[test]
+    fn test_select_keeper_chains_tie_breakers() {
+        let candidates = vec![
+            candidate("/photos/a.jpg", Some(100), None, Some(1)),
+            candidate("/photos/b.png", Some(100), None, Some(2)),
+            candidate("/backup/c.png", Some(100), None, Some(3)),
+        ];
+
+        // サイズは同じ → 形式でPNGに絞り → ディレクトリで/photosを優先
+        assert_eq!(
+            select_keeper(&candidates, &parse("largest,format:png,dir:/photos")),
+            Some(1)
+        );
+        // 基準の順序を変えると結果も変わる
+        assert_eq!(
+            select_keeper(&candidates, &parse("largest,dir:/photos,oldest")),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_select_keeper_unknown_values_rank_lowest() {
+        let candidates = vec![
+            candidate("/a.jpg", None, None, None),
+            candidate("/b.jpg", Some(1), Some((1, 1)), Some(10)),
+        ];
+
+        assert_eq!(select_keeper(&candidates, &parse("largest")), Some(1));
+        assert_eq!(select_keeper(&candidates, &parse("resolution")), Some(1));
+        assert_eq!(select_keeper(&candidates, &parse("oldest")), Some(1));
+    }
+
+    #[test]
+    fn test_select_keeper_ties_keep_first() {
+        let candidates = vec![
+            candidate("/a.jpg", None, None, None),
+            candidate("/b.jpg", None, None, None),
+        ];
+
+        assert_eq!(select_keeper(&candidates, &[]), Some(0));
+        assert_eq!(select_keeper(&candidates, &parse("largest")), Some(0));
+        assert_eq!(select_keeper(&[], &parse("largest")), None);
+    }
+
+    #[test]
+    fn test_from_metadata() {
+        let metadata = serde_json::json!({
+            "file_size": 1000,
+            "image_dimensions": [640, 480],
+            "modified_time_ms": 42
+        });
+
+        assert_eq!(
+            KeepCandidate::from_metadata("/a.jpg", Some(&metadata)),
+            candidate("/a.jpg", Some(1000), Some((640, 480)), Some(42))
+        );
+        assert_eq!(
+            KeepCandidate::from_metadata("/a.jpg", None),
+            KeepCandidate::new("/a.jpg")
+        );
+
+        // 画像をデコードしていないエントリの寸法は不明
+        let exact_only = serde_json::json!({"file_size": 1000, "image_dimensions": [0, 0]});
+        assert_eq!(
+            KeepCandidate::from_metadata("/a.jpg", Some(&exact_only)),
+            candidate("/a.jpg", Some(1000), None, None)
+        );
+    }
+
+    #[test]
+    fn test_fill_from_filesystem() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("image.png");
+        image::RgbImage::new(20, 10).save(&path).unwrap();
+
+        let filled = KeepCandidate::new(path.to_string_lossy()).fill_from_filesystem();
+
+        assert!(filled.file_size.unwrap() > 0);
+        assert!(filled.modified_time_ms.is_some());
+        assert_eq!(filled.dimensions, Some((20, 10)));
+    }
+}
@@ -0,0 +1,7 @@
+// オリジナル画像の選択
+// 重複グループ内で残すファイルを選択基準（サイズ・解像度・日時・パス・形式）で決定
+
+pub mod keep_policy;
+
+// 公開API
+pub use keep_policy::{select_keeper, KeepCandidate, KeepPolicy};
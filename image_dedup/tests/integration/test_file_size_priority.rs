@@ -82,6 +82,7 @@ async fn test_file_size_priority_end_to_end() -> Result<()> {
         hash_db.clone(),
         None,
         false,
+        false, // update
         "dct".to_string(),
         8,
         None,
@@ -94,6 +95,8 @@ async fn test_file_size_priority_end_to_end() -> Result<()> {
         hash_db,
         duplicates_file.clone(),
         64, // 高い閾値で全ファイルを同じグループにする
+        image_dedup::cli::GroupingMode::Star,
+        &[],
     )
     .await?;
 
@@ -182,6 +185,8 @@ async fn test_backward_compatibility_integration() -> Result<()> {
         hash_db,
         duplicates_file.clone(),
         5,
+        image_dedup::cli::GroupingMode::Star,
+        &[],
     )
     .await?;
 
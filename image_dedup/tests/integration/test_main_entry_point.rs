@@ -321,6 +321,7 @@ mod main_unit_tests {
             hash_database: temp_dir.path().join("hashes.json"),
             output: temp_dir.path().join("dups.json"),
             threshold: 5,
+            grouping: image_dedup::cli::GroupingMode::Star,
         };
 
         let process_cmd = Commands::Process {
@@ -375,6 +376,7 @@ mod main_unit_tests {
             output.clone(),
             Some(1),
             true,
+            false, // update
         ).await;
 
         assert!(result.is_ok());
@@ -386,6 +388,8 @@ mod main_unit_tests {
             output,
             dup_output.clone(),
             5,
+            image_dedup::cli::GroupingMode::Star,
+            &[],
         ).await;
 
         assert!(find_result.is_ok());